@@ -0,0 +1,113 @@
+use num::Float;
+
+use vec3::Vector3;
+use mat4::Matrix4;
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb<T> {
+    pub min: Vector3<T>,
+    pub max: Vector3<T>,
+}
+
+impl<T> Aabb<T>
+where
+    T: Float,
+{
+    pub fn new(min: Vector3<T>, max: Vector3<T>) -> Aabb<T> {
+        Aabb { min, max }
+    }
+
+    pub fn from_points(points: &[Vector3<T>]) -> Option<Aabb<T>> {
+        let (&first, rest) = points.split_first()?;
+        Some(rest.iter().fold(
+            Aabb {
+                min: first,
+                max: first,
+            },
+            |aabb, &p| {
+                Aabb {
+                    min: aabb.min.min(p),
+                    max: aabb.max.max(p),
+                }
+            },
+        ))
+    }
+
+    pub fn center(&self) -> Vector3<T> {
+        (self.min + self.max) / T::from(2).unwrap()
+    }
+
+    pub fn size(&self) -> Vector3<T> {
+        self.max - self.min
+    }
+
+    pub fn half_extents(&self) -> Vector3<T> {
+        self.size() / T::from(2).unwrap()
+    }
+
+    /// A box is empty when its minimum is above its maximum on some axis.  A box around a single
+    /// point is not empty.
+    pub fn is_empty(&self) -> bool {
+        !(self.min.x <= self.max.x && self.min.y <= self.max.y && self.min.z <= self.max.z)
+    }
+
+    pub fn corners(&self) -> [Vector3<T>; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vector3::new(a.x, a.y, a.z),
+            Vector3::new(b.x, a.y, a.z),
+            Vector3::new(a.x, b.y, a.z),
+            Vector3::new(b.x, b.y, a.z),
+            Vector3::new(a.x, a.y, b.z),
+            Vector3::new(b.x, a.y, b.z),
+            Vector3::new(a.x, b.y, b.z),
+            Vector3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    pub fn union(&self, rhs: &Aabb<T>) -> Aabb<T> {
+        Aabb {
+            min: self.min.min(rhs.min),
+            max: self.max.max(rhs.max),
+        }
+    }
+
+    pub fn intersection(&self, rhs: &Aabb<T>) -> Aabb<T> {
+        Aabb {
+            min: self.min.max(rhs.min),
+            max: self.max.min(rhs.max),
+        }
+    }
+
+    pub fn contains_point(&self, point: Vector3<T>) -> bool {
+        self.min.x <= point.x && point.x <= self.max.x && self.min.y <= point.y &&
+            point.y <= self.max.y && self.min.z <= point.z && point.z <= self.max.z
+    }
+
+    pub fn contains_aabb(&self, rhs: &Aabb<T>) -> bool {
+        self.contains_point(rhs.min) && self.contains_point(rhs.max)
+    }
+
+    /// Boxes that only touch count as intersecting.
+    pub fn intersects_aabb(&self, rhs: &Aabb<T>) -> bool {
+        !self.intersection(rhs).is_empty()
+    }
+
+    /// The point in the box closest to `point`.
+    pub fn closest_point(&self, point: Vector3<T>) -> Vector3<T> {
+        point.clamp(self.min, self.max)
+    }
+
+    /// The box around the transformed box, the transformation must be affine.
+    pub fn transform(&self, m: &Matrix4<T>) -> Aabb<T> {
+        let center = m.transform(self.center());
+        let e = self.half_extents();
+        let row = |r: usize| m[(r, 0)].abs() * e.x + m[(r, 1)].abs() * e.y + m[(r, 2)].abs() * e.z;
+        let extents = Vector3::new(row(0), row(1), row(2));
+        Aabb {
+            min: center - extents,
+            max: center + extents,
+        }
+    }
+}
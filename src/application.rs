@@ -1,534 +1,159 @@
-use std::f32;
+use std::rc::Rc;
 
-use vec3::Vector3;
-use vec4::Vector4;
 use mat4::Matrix4;
 use color::Color;
-use renderer::{Vertex, Triangle, Renderer};
+use image::Image;
+use renderer::{Triangle, Renderer, RenderStats, ColorFormat};
+use scene::{Scene, Model};
+use scene_format::{LoadedScene, parse_scene};
+use clock::{Clock, SimulatedClock};
 
-const TRIANGLES: [Triangle; 12] = [
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: 1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 1.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: -1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: 1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 1.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-    },
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: 1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 1.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: -1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 0.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: -1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-    },
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: -1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 0.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: -1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 0.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-    },
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: -1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 0.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 1.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-    },
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: -1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: -1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 0.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 1.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-    },
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: -1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 1.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: 1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 1.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-    },
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: -1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 0.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: -1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 0.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-    },
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: -1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 0.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: 1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 1.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-    },
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: 1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 1.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 1.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-    },
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: 1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 1.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: 1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 1.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-    },
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: -1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 0.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: -1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 0.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: -1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-    },
-    Triangle {
-        a: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: -1.0,
-                z: 1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 0.0,
-                z: 1.0,
-                w: 1.0,
-            },
-        },
-        b: Vertex {
-            position: Vector3 {
-                x: -1.0,
-                y: -1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-        c: Vertex {
-            position: Vector3 {
-                x: 1.0,
-                y: -1.0,
-                z: -1.0,
-            },
-            color: Vector4 {
-                x: 1.0,
-                y: 0.0,
-                z: 0.0,
-                w: 1.0,
-            },
-        },
-    },
-];
+/// The scene shown when no scene file is given: a spinning, vertex colored cube.
+pub const DEFAULT_SCENE: &str = r#"
+[render]
+clear_color = [0, 0, 0]
+
+[[node]]
+name = "camera"
+camera = { fovy = 60, near = 0.1, far = 100 }
+
+[[node]]
+name = "cube"
+primitive = "cube"
+translation = [0, 0, -5]
+spin = [150, 300, 100]
+"#;
+
+/// The frame time of the default clock.
+pub const FRAME_TIME: f64 = 1.0 / 60.0;
 
 pub struct Application {
     renderer: Renderer,
-    rotation: f32,
+    // The tone mapped frame, when the renderer draws to a floating point color buffer.
+    output: Option<Image<Color>>,
+    scene: LoadedScene,
+    clock: Box<dyn Clock>,
+    time: f64,
+    frame_time: f64,
+    paused: bool,
 }
 
 impl Application {
+    /// Shows `DEFAULT_SCENE` at the given size.
     pub fn new(width: u32, height: u32) -> Application {
+        let mut description = parse_scene(DEFAULT_SCENE, None).unwrap();
+        description.render.width = width;
+        description.render.height = height;
+        Application::with_scene(description.instantiate().unwrap())
+    }
+
+    /// Shows a loaded scene, at the size given by its render settings.  The application starts
+    /// with a `SimulatedClock` advancing by `FRAME_TIME` every frame, so that frames are
+    /// reproducible until another clock is set.
+    pub fn with_scene(mut scene: LoadedScene) -> Application {
+        scene.scene.update();
         Application {
-            renderer: Renderer::new(width, height, f32::consts::PI / 3.0),
-            rotation: 0.0,
+            renderer: scene.renderer(),
+            output: None,
+            scene,
+            clock: Box::new(SimulatedClock::new(FRAME_TIME)),
+            time: 0.0,
+            frame_time: 0.0,
+            paused: false,
+        }
+    }
+
+    /// Replaces the clock that advances the animation every frame.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Seconds of animation shown by the last frame, not counting time spent paused.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Seconds between the last two frames, according to the clock, even when paused.
+    pub fn frame_time(&self) -> f64 {
+        self.frame_time
+    }
+
+    /// Replaces the model of every node that has one.
+    pub fn set_triangles(&mut self, triangles: Vec<Triangle>) {
+        let model = Rc::new(Model::new(triangles));
+        let ids: Vec<_> = self.scene
+            .scene
+            .nodes()
+            .filter(|&(_, n)| n.model.is_some())
+            .map(|(id, _)| id)
+            .collect();
+        for id in ids {
+            self.scene.scene.node_mut(id).model = Some(model.clone());
+        }
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene.scene
+    }
+
+    /// The world transform of the camera as of the last frame.
+    pub fn camera_transform(&self) -> Matrix4<f32> {
+        self.scene.scene.world_transform(self.scene.camera)
+    }
+
+    /// Moves the camera for the following frames, overriding its transform from the scene.
+    pub fn set_camera_transform(&mut self, transform: Matrix4<f32>) {
+        self.scene.scene.set_world_transform(self.scene.camera, transform);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stops or resumes the scene's animation.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Changes the size of the rendered image.  The camera keeps its vertical field of view.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.renderer.resize(width, height);
+        if self.output.is_some() {
+            self.output = Some(self.renderer.resolve());
         }
     }
 
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.renderer.dimensions()
+    }
+
     pub fn render(&mut self) {
-        self.rotation += 0.09;
-        let transformation = Matrix4::translation(Vector3::new(0.0, 0.0, -5.0)) *
-            Matrix4::rotation(Vector3::new(
-                self.rotation / 2.0,
-                self.rotation,
-                self.rotation / 3.0,
-            ));
+        self.frame_time = self.clock.tick();
+        if !self.paused {
+            self.time += self.frame_time;
+            self.scene.set_time(self.time as f32);
+        }
+        self.scene.render(&mut self.renderer);
+        self.output = match self.renderer.bound_framebuffer().attachment(0).format() {
+            ColorFormat::Rgba8 => None,
+            ColorFormat::Rgba16F | ColorFormat::Rgba32F => Some(self.renderer.resolve()),
+        };
+    }
+
+    pub fn stats(&self) -> RenderStats {
+        self.renderer.stats()
+    }
 
-        self.renderer.clear(Color(0, 0, 0, 255));
-        self.renderer.render(transformation, &TRIANGLES);
+    /// The last frame, tone mapped if the scene renders in high dynamic range.
+    pub fn framebuffer(&self) -> &Image<Color> {
+        match self.output {
+            Some(ref output) => output,
+            None => self.renderer.framebuffer(),
+        }
     }
 
     pub fn get_pixel(&self, x: u32, y: u32) -> (u8, u8, u8) {
-        let Color(r, g, b, _) = self.renderer.get_pixel(x, y);
+        let Color(r, g, b, _) = self.framebuffer().get_pixel(x, y);
         (r, g, b)
     }
 }
@@ -0,0 +1,44 @@
+// Renders a scene file without opening a window and saves the result as an image.
+//
+//     render <scene.toml> <output image> [time in seconds]
+
+extern crate rrasterizer;
+
+use std::env;
+use std::process;
+
+use rrasterizer::image_format;
+use rrasterizer::scene_format;
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 || args.len() > 4 {
+        eprintln!("usage: {} <scene.toml> <output image> [time in seconds]", args[0]);
+        process::exit(2);
+    }
+    let time = match args.get(3).map(|t| t.parse::<f32>()) {
+        None => 0.0,
+        Some(Ok(time)) => time,
+        Some(Err(_)) => {
+            eprintln!("invalid time '{}'", args[3]);
+            process::exit(2);
+        }
+    };
+
+    let mut scene = match scene_format::load_scene(&args[1]).and_then(|s| s.instantiate()) {
+        Ok(scene) => scene,
+        Err(err) => {
+            eprintln!("could not load {}: {}", args[1], err);
+            process::exit(1);
+        }
+    };
+    let mut renderer = scene.renderer();
+    scene.set_time(time);
+    scene.render(&mut renderer);
+
+    // Floating point color buffers are tone mapped.
+    if let Err(err) = image_format::save_image(&args[2], &renderer.resolve()) {
+        eprintln!("could not save {}: {}", args[2], err);
+        process::exit(1);
+    }
+}
@@ -1,3 +1,7 @@
+use std::ops::{Add, Sub, Mul};
+
+use num::{Float, NumCast, PrimInt};
+
 use vec2::Vector2;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -82,3 +86,196 @@ impl<T> BoundRect<T> {
         !(self.min.x < self.max.x && self.min.y < self.max.y)
     }
 }
+
+impl<T> BoundRect<T>
+where
+    T: Copy + PartialOrd,
+{
+    pub fn union(self, rhs: BoundRect<T>) -> BoundRect<T> {
+        BoundRect::from_bounds(
+            min(self.min.x, rhs.min.x),
+            min(self.min.y, rhs.min.y),
+            max(self.max.x, rhs.max.x),
+            max(self.max.y, rhs.max.y),
+        )
+    }
+
+    /// The rectangle includes its minimum edges but not its maximum edges, so that adjacent
+    /// rectangles do not overlap.
+    pub fn contains_point(&self, p: Vector2<T>) -> bool {
+        self.min.x <= p.x && p.x < self.max.x && self.min.y <= p.y && p.y < self.max.y
+    }
+
+    pub fn contains_rect(&self, rhs: &BoundRect<T>) -> bool {
+        self.min.x <= rhs.min.x && rhs.max.x <= self.max.x && self.min.y <= rhs.min.y &&
+            rhs.max.y <= self.max.y
+    }
+}
+
+impl<T> BoundRect<T>
+where
+    T: Copy + Add<T, Output = T> + Sub<T, Output = T> + Mul<T, Output = T>,
+{
+    /// Grows the rectangle by `amount` on every side, or shrinks it for a negative amount.
+    pub fn expand(self, amount: T) -> BoundRect<T> {
+        BoundRect::from_bounds(
+            self.min.x - amount,
+            self.min.y - amount,
+            self.max.x + amount,
+            self.max.y + amount,
+        )
+    }
+
+    pub fn width(&self) -> T {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> T {
+        self.max.y - self.min.y
+    }
+
+    pub fn area(&self) -> T {
+        self.width() * self.height()
+    }
+}
+
+impl<T> BoundRect<T>
+where
+    T: Float,
+{
+    pub fn center(&self) -> Vector2<T> {
+        (self.min + self.max) / T::from(2).unwrap()
+    }
+
+    /// The smallest rectangle with integer bounds containing this one, or `None` if the bounds do
+    /// not fit in `U`.
+    pub fn round_out<U>(&self) -> Option<BoundRect<U>>
+    where
+        U: NumCast,
+    {
+        BoundRect::from_bounds(
+            self.min.x.floor(),
+            self.min.y.floor(),
+            self.max.x.ceil(),
+            self.max.y.ceil(),
+        ).cast()
+    }
+
+    /// The largest rectangle with integer bounds contained in this one, or `None` if the bounds
+    /// do not fit in `U`.
+    pub fn round_in<U>(&self) -> Option<BoundRect<U>>
+    where
+        U: NumCast,
+    {
+        BoundRect::from_bounds(
+            self.min.x.ceil(),
+            self.min.y.ceil(),
+            self.max.x.floor(),
+            self.max.y.floor(),
+        ).cast()
+    }
+
+    /// Rounds every bound to the nearest integer, or returns `None` if the bounds do not fit in
+    /// `U`.
+    pub fn round<U>(&self) -> Option<BoundRect<U>>
+    where
+        U: NumCast,
+    {
+        BoundRect::from_bounds(
+            self.min.x.round(),
+            self.min.y.round(),
+            self.max.x.round(),
+            self.max.y.round(),
+        ).cast()
+    }
+}
+
+impl<T> BoundRect<T>
+where
+    T: Copy + NumCast,
+{
+    /// Converts the bounds with `NumCast`, which truncates floats towards zero.  Returns `None`
+    /// if a bound does not fit in `U`.
+    pub fn cast<U>(&self) -> Option<BoundRect<U>>
+    where
+        U: NumCast,
+    {
+        Some(BoundRect::from_bounds(
+            U::from(self.min.x)?,
+            U::from(self.min.y)?,
+            U::from(self.max.x)?,
+            U::from(self.max.y)?,
+        ))
+    }
+}
+
+impl<T> BoundRect<T>
+where
+    T: PrimInt,
+{
+    /// Iterates over the coordinates of every pixel in the rectangle, row by row, excluding the
+    /// maximum edges.
+    pub fn pixels(&self) -> Pixels<T> {
+        Pixels {
+            rect: *self,
+            next: if self.is_empty() { None } else { Some(self.min) },
+        }
+    }
+}
+
+/// An iterator over the pixels of a `BoundRect`, created by `BoundRect::pixels`.
+#[derive(Debug, Clone)]
+pub struct Pixels<T> {
+    rect: BoundRect<T>,
+    next: Option<Vector2<T>>,
+}
+
+impl<T> Iterator for Pixels<T>
+where
+    T: PrimInt,
+{
+    type Item = Vector2<T>;
+
+    fn next(&mut self) -> Option<Vector2<T>> {
+        let p = self.next?;
+        let mut n = Vector2::new(p.x + T::one(), p.y);
+        if n.x >= self.rect.max.x {
+            n = Vector2::new(self.rect.min.x, p.y + T::one());
+        }
+        self.next = if n.y < self.rect.max.y { Some(n) } else { None };
+        Some(p)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let p = match self.next {
+            Some(p) => p,
+            None => return (0, Some(0)),
+        };
+        let width = (self.rect.max.x - self.rect.min.x).to_usize();
+        let rows = (self.rect.max.y - p.y).to_usize();
+        let done = (p.x - self.rect.min.x).to_usize();
+        match (width, rows, done) {
+            (Some(width), Some(rows), Some(done)) => {
+                match rows.checked_mul(width) {
+                    Some(n) => (n - done, Some(n - done)),
+                    None => (usize::MAX, None),
+                }
+            }
+            _ => (0, None),
+        }
+    }
+}
+
+fn min<T>(a: T, b: T) -> T
+where
+    T: PartialOrd,
+{
+    if b < a { b } else { a }
+}
+
+fn max<T>(a: T, b: T) -> T
+where
+    T: PartialOrd,
+{
+    if b > a { b } else { a }
+}
@@ -0,0 +1,243 @@
+use std::f32;
+
+use vec2::Vector2;
+use vec3::Vector3;
+use mat4::Matrix4;
+use quaternion::Quaternion;
+
+/// Each zoom step changes the orbit distance by this factor.
+const ZOOM_FACTOR: f32 = 1.1;
+const MIN_DISTANCE: f32 = 1e-3;
+/// Radians the fly camera turns for a drag across half the window.
+const LOOK_SPEED: f32 = 1.5;
+/// Keeps the fly camera from looking straight up or down, where yaw is undefined.
+const MAX_PITCH: f32 = 89.0 * f32::consts::PI / 180.0;
+
+/// A camera circling a target point.  Dragging turns the scene like a ball under the mouse
+/// (Shoemake's arcball).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OrbitCamera {
+    pub target: Vector3<f32>,
+    pub distance: f32,
+    pub rotation: Quaternion<f32>,
+}
+
+impl OrbitCamera {
+    /// A camera with the given transform, orbiting the point `distance` in front of it.  The
+    /// transform must not scale.
+    pub fn from_transform(transform: &Matrix4<f32>, distance: f32) -> OrbitCamera {
+        let rotation = Quaternion::from_matrix(transform);
+        let position = transform.transform(Vector3::splat(0.0));
+        OrbitCamera {
+            target: position + rotation.rotate(Vector3::new(0.0, 0.0, -distance)),
+            distance,
+            rotation,
+        }
+    }
+
+    pub fn position(&self) -> Vector3<f32> {
+        self.target + self.rotation.rotate(Vector3::new(0.0, 0.0, self.distance))
+    }
+
+    pub fn transform(&self) -> Matrix4<f32> {
+        Matrix4::translation(self.position()) * self.rotation.to_matrix()
+    }
+
+    /// Drags the arcball from `from` to `to`, both in normalized device coordinates.
+    pub fn rotate(&mut self, from: Vector2<f32>, to: Vector2<f32>) {
+        let (a, b) = (arcball_point(from), arcball_point(to));
+        let axis = a.cross(b);
+        if axis.magnitude_squared() < 1e-12 {
+            return;
+        }
+        let angle = a.dot(b).clamp(-1.0, 1.0).acos();
+        // The scene turns with the mouse, so the camera turns the other way around the axis,
+        // which is in camera space.
+        self.rotation = (self.rotation * Quaternion::from_axis_angle(axis, -angle)).normalize();
+    }
+
+    /// Moves the target so the scene follows a drag of `delta` in normalized device
+    /// coordinates.
+    pub fn pan(&mut self, delta: Vector2<f32>) {
+        self.target -= self.rotation.rotate(Vector3::new(delta.x, delta.y, 0.0)) * self.distance;
+    }
+
+    /// Moves towards the target for positive `steps` and away from it for negative ones.
+    pub fn zoom(&mut self, steps: f32) {
+        self.distance = (self.distance * ZOOM_FACTOR.powf(-steps)).max(MIN_DISTANCE);
+    }
+}
+
+/// A first person camera that never rolls.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FlyCamera {
+    pub position: Vector3<f32>,
+    /// Radians counterclockwise around the y axis, zero looks down the negative z axis.
+    pub yaw: f32,
+    /// Radians above the horizon.
+    pub pitch: f32,
+}
+
+impl FlyCamera {
+    /// A camera at the position of `transform`, looking the same way.  Any roll is dropped.
+    pub fn from_transform(transform: &Matrix4<f32>) -> FlyCamera {
+        let forward = transform.transform_vector(Vector3::new(0.0, 0.0, -1.0)).normalize();
+        FlyCamera {
+            position: transform.transform(Vector3::splat(0.0)),
+            yaw: (-forward.x).atan2(-forward.z),
+            pitch: forward.y.clamp(-1.0, 1.0).asin().clamp(-MAX_PITCH, MAX_PITCH),
+        }
+    }
+
+    pub fn transform(&self) -> Matrix4<f32> {
+        Matrix4::translation(self.position) *
+            Matrix4::rotation(Vector3::new(self.pitch, self.yaw, 0.0))
+    }
+
+    /// Turns left by `delta.x` and up by `delta.y` radians.
+    pub fn look(&mut self, delta: Vector2<f32>) {
+        self.yaw = (self.yaw + delta.x) % (2.0 * f32::consts::PI);
+        self.pitch = (self.pitch + delta.y).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Moves by `offset` in camera space, where x is right, y is up and negative z is forward.
+    pub fn travel(&mut self, offset: Vector3<f32>) {
+        self.position += self.transform().transform_vector(offset);
+    }
+}
+
+// Projects a point in normalized device coordinates onto the unit hemisphere facing the viewer,
+// or onto its rim for points outside it.
+fn arcball_point(p: Vector2<f32>) -> Vector3<f32> {
+    let d = p.magnitude_squared();
+    if d <= 1.0 {
+        Vector3::new(p.x, p.y, (1.0 - d).sqrt())
+    } else {
+        Vector3::new(p.x, p.y, 0.0) / d.sqrt()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CameraMode {
+    Orbit,
+    Fly,
+}
+
+/// Turns mouse and keyboard input into a camera transform, in either mode.  Switching modes keeps
+/// the camera's position and view direction.
+#[derive(Debug, Clone)]
+pub struct CameraController {
+    mode: CameraMode,
+    orbit: OrbitCamera,
+    fly: FlyCamera,
+    /// Units moved per second by `travel`.
+    pub speed: f32,
+    initial: (CameraMode, OrbitCamera, FlyCamera, f32),
+}
+
+impl CameraController {
+    /// Starts in orbit mode with the camera at `transform`, orbiting the point `distance` in
+    /// front of it.  The travel speed crosses that distance in a second.
+    pub fn new(transform: &Matrix4<f32>, distance: f32) -> CameraController {
+        let orbit = OrbitCamera::from_transform(transform, distance);
+        let fly = FlyCamera::from_transform(transform);
+        CameraController {
+            mode: CameraMode::Orbit,
+            orbit,
+            fly,
+            speed: distance,
+            initial: (CameraMode::Orbit, orbit, fly, distance),
+        }
+    }
+
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        if mode == self.mode {
+            return;
+        }
+        match mode {
+            CameraMode::Orbit => {
+                let distance = self.orbit.distance;
+                self.orbit = OrbitCamera::from_transform(&self.fly.transform(), distance);
+            }
+            CameraMode::Fly => self.fly = FlyCamera::from_transform(&self.orbit.transform()),
+        }
+        self.mode = mode;
+    }
+
+    pub fn toggle_mode(&mut self) {
+        let mode = match self.mode {
+            CameraMode::Orbit => CameraMode::Fly,
+            CameraMode::Fly => CameraMode::Orbit,
+        };
+        self.set_mode(mode);
+    }
+
+    /// Returns to the view, mode and speed the controller was created with.
+    pub fn reset(&mut self) {
+        let (mode, orbit, fly, speed) = self.initial;
+        self.mode = mode;
+        self.orbit = orbit;
+        self.fly = fly;
+        self.speed = speed;
+    }
+
+    pub fn orbit(&self) -> &OrbitCamera {
+        &self.orbit
+    }
+
+    pub fn fly(&self) -> &FlyCamera {
+        &self.fly
+    }
+
+    pub fn transform(&self) -> Matrix4<f32> {
+        match self.mode {
+            CameraMode::Orbit => self.orbit.transform(),
+            CameraMode::Fly => self.fly.transform(),
+        }
+    }
+
+    /// A drag with the rotate button from `from` to `to`, in normalized device coordinates.
+    pub fn drag(&mut self, from: Vector2<f32>, to: Vector2<f32>) {
+        match self.mode {
+            CameraMode::Orbit => self.orbit.rotate(from, to),
+            CameraMode::Fly => {
+                let delta = to - from;
+                self.fly.look(Vector2::new(-delta.x, delta.y) * LOOK_SPEED);
+            }
+        }
+    }
+
+    /// A drag with the pan button by `delta`, in normalized device coordinates.
+    pub fn pan(&mut self, delta: Vector2<f32>) {
+        match self.mode {
+            CameraMode::Orbit => self.orbit.pan(delta),
+            CameraMode::Fly => self.fly.travel(-delta.extend(0.0) * self.speed),
+        }
+    }
+
+    /// Mouse wheel steps, positive to zoom in.  Flying, a step moves forward a tenth of a
+    /// second's travel.
+    pub fn zoom(&mut self, steps: f32) {
+        match self.mode {
+            CameraMode::Orbit => self.orbit.zoom(steps),
+            CameraMode::Fly => self.fly.travel(Vector3::new(0.0, 0.0, -steps * self.speed * 0.1)),
+        }
+    }
+
+    /// Moves for `seconds` at `speed` in `direction`, in camera space.  Orbiting, the target
+    /// moves along with the camera.
+    pub fn travel(&mut self, direction: Vector3<f32>, seconds: f32) {
+        if direction.magnitude_squared() == 0.0 {
+            return;
+        }
+        let offset = direction.normalize() * self.speed * seconds;
+        match self.mode {
+            CameraMode::Orbit => self.orbit.target += self.orbit.rotation.rotate(offset),
+            CameraMode::Fly => self.fly.travel(offset),
+        }
+    }
+}
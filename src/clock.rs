@@ -0,0 +1,105 @@
+use std::time::Instant;
+
+/// Longest frame a `RealTimeClock` reports, so that a stall such as a window being dragged does
+/// not make the animation jump.
+pub const MAX_FRAME_TIME: f64 = 0.25;
+
+/// A source of frame times.
+pub trait Clock {
+    /// Advances to the next frame and returns the seconds since the previous one.
+    fn tick(&mut self) -> f64;
+}
+
+/// Measures the time between ticks with the system's monotonic clock.  The first tick measures
+/// from the clock's creation.
+#[derive(Debug, Clone)]
+pub struct RealTimeClock {
+    last: Instant,
+}
+
+impl RealTimeClock {
+    pub fn new() -> RealTimeClock {
+        RealTimeClock { last: Instant::now() }
+    }
+}
+
+impl Default for RealTimeClock {
+    fn default() -> RealTimeClock {
+        RealTimeClock::new()
+    }
+}
+
+impl Clock for RealTimeClock {
+    fn tick(&mut self) -> f64 {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last);
+        self.last = now;
+        let seconds = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9;
+        seconds.min(MAX_FRAME_TIME)
+    }
+}
+
+/// Advances by exactly `step` seconds every tick, for deterministic tests and offline rendering.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SimulatedClock {
+    pub step: f64,
+}
+
+impl SimulatedClock {
+    pub fn new(step: f64) -> SimulatedClock {
+        SimulatedClock { step }
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn tick(&mut self) -> f64 {
+        self.step
+    }
+}
+
+/// Quantizes another clock to whole multiples of `step`, carrying the remainder over to later
+/// ticks.  Over many ticks the total matches the source clock, but each tick only ever advances
+/// by a whole number of steps, possibly zero.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep<C> {
+    source: C,
+    step: f64,
+    accumulator: f64,
+}
+
+impl<C> FixedTimestep<C>
+where
+    C: Clock,
+{
+    pub fn new(source: C, step: f64) -> FixedTimestep<C> {
+        assert!(step > 0.0, "the timestep must be positive");
+        FixedTimestep {
+            source,
+            step,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn step(&self) -> f64 {
+        self.step
+    }
+
+    /// How far into the next step the source clock is, from 0 to 1.  Useful for interpolating
+    /// between the last two steps.
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.step
+    }
+}
+
+impl<C> Clock for FixedTimestep<C>
+where
+    C: Clock,
+{
+    fn tick(&mut self) -> f64 {
+        self.accumulator += self.source.tick();
+        let steps = (self.accumulator / self.step).floor();
+        // Rounding could otherwise leave a tiny negative remainder.
+        self.accumulator = (self.accumulator - steps * self.step).max(0.0);
+        steps * self.step
+    }
+}
@@ -5,6 +5,22 @@ use vec4::Vector4;
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct Color(pub u8, pub u8, pub u8, pub u8);
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Color16(pub u16, pub u16, pub u16, pub u16);
+
+impl Color16 {
+    pub fn to_color(self) -> Color {
+        let c = |v: u16| ((v as u32 * 255 + 32767) / 65535) as u8;
+        Color(c(self.0), c(self.1), c(self.2), c(self.3))
+    }
+}
+
+impl From<Color> for Color16 {
+    fn from(Color(r, g, b, a): Color) -> Color16 {
+        Color16(r as u16 * 257, g as u16 * 257, b as u16 * 257, a as u16 * 257)
+    }
+}
+
 pub fn vec4_to_color(Vector4 { x, y, z, w }: Vector4<f32>) -> Color {
     Color(
         num::clamp(x * 255.0, 0.0, 255.0) as u8,
@@ -13,3 +29,58 @@ pub fn vec4_to_color(Vector4 { x, y, z, w }: Vector4<f32>) -> Color {
         num::clamp(w * 255.0, 0.0, 255.0) as u8,
     )
 }
+
+pub fn color_to_vec4(Color(r, g, b, a): Color) -> Vector4<f32> {
+    Vector4::new(r as f32, g as f32, b as f32, a as f32) / 255.0
+}
+
+/// Rounds to the nearest half precision float, returning its bits.  Values too large for half
+/// precision become infinities.
+pub fn f32_to_half(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        // Infinities stay infinities and NaNs stay NaNs.
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    // Rounds away the low `shift` bits of `bits`, breaking ties towards even.
+    let round = |bits: u32, shift: u32| {
+        let kept = bits >> shift;
+        let rest = bits & ((1 << shift) - 1);
+        let half = 1 << (shift - 1);
+        if rest > half || (rest == half && kept & 1 == 1) {
+            kept + 1
+        } else {
+            kept
+        }
+    };
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        // Subnormal, with the implicit leading bit made explicit.
+        if exponent < -10 {
+            return sign;
+        }
+        sign | round(mantissa | 0x80_0000, (14 - exponent) as u32) as u16
+    } else {
+        // A carry out of the mantissa correctly bumps the exponent, up to infinity.
+        sign | round(((exponent as u32) << 23) | mantissa, 13) as u16
+    }
+}
+
+pub fn half_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+    sign *
+        match exponent {
+            0 => mantissa * 2f32.powi(-24),
+            0x1f if mantissa == 0.0 => f32::INFINITY,
+            0x1f => f32::NAN,
+            _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+        }
+}
@@ -0,0 +1,89 @@
+use num::Float;
+
+use vec3::Vector3;
+use mat4::Matrix4;
+use plane::Plane;
+use sphere::Sphere;
+use aabb::Aabb;
+
+/// The volume visible through a projection, bounded by six planes facing inwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum<T> {
+    /// Left, right, bottom, top, near and far, in that order.
+    pub planes: [Plane<T>; 6],
+}
+
+impl<T> Frustum<T>
+where
+    T: Float,
+{
+    /// Extracts the planes from a view-projection matrix, giving the frustum in world space.  A
+    /// projection matrix alone gives the frustum in view space.  The clip volume is assumed to be
+    /// -w <= z <= w, as produced by `Matrix4::perspective_fov` and `Matrix4::orthographic`.  For
+    /// projections with 0 <= z <= w, such as the reverse-Z ones, the extracted frustum is
+    /// slightly too large, which is still correct for culling.  The far plane of an infinite
+    /// projection contains everything.
+    pub fn from_matrix(m: &Matrix4<T>) -> Frustum<T> {
+        let (x, y, z, w) = (m.row(0), m.row(1), m.row(2), m.row(3));
+        let plane = |v| Plane::from_coefficients(v).normalize();
+        Frustum {
+            planes: [
+                plane(w + x),
+                plane(w - x),
+                plane(w + y),
+                plane(w - y),
+                plane(w + z),
+                plane(w - z),
+            ],
+        }
+    }
+
+    pub fn contains_point(&self, point: Vector3<T>) -> bool {
+        self.planes.iter().all(|p| p.signed_distance(point) >= T::zero())
+    }
+
+    /// Conservative: a sphere near a corner of the frustum may be reported as intersecting even
+    /// though it is just outside.
+    pub fn intersects_sphere(&self, sphere: &Sphere<T>) -> bool {
+        self.planes.iter().all(|p| p.signed_distance(sphere.center) >= -sphere.radius)
+    }
+
+    /// Conservative in the same way as `intersects_sphere`.
+    pub fn intersects_aabb(&self, aabb: &Aabb<T>) -> bool {
+        self.planes.iter().all(|p| p.signed_distance(positive_vertex(aabb, p)) >= T::zero())
+    }
+
+    pub fn contains_sphere(&self, sphere: &Sphere<T>) -> bool {
+        self.planes.iter().all(|p| p.signed_distance(sphere.center) >= sphere.radius)
+    }
+
+    pub fn contains_aabb(&self, aabb: &Aabb<T>) -> bool {
+        self.planes.iter().all(|p| p.signed_distance(negative_vertex(aabb, p)) >= T::zero())
+    }
+}
+
+// The corner of the box furthest along the plane normal.
+fn positive_vertex<T>(aabb: &Aabb<T>, plane: &Plane<T>) -> Vector3<T>
+where
+    T: Float,
+{
+    let n = plane.normal;
+    Vector3::new(
+        if n.x >= T::zero() { aabb.max.x } else { aabb.min.x },
+        if n.y >= T::zero() { aabb.max.y } else { aabb.min.y },
+        if n.z >= T::zero() { aabb.max.z } else { aabb.min.z },
+    )
+}
+
+// The corner of the box furthest against the plane normal.
+fn negative_vertex<T>(aabb: &Aabb<T>, plane: &Plane<T>) -> Vector3<T>
+where
+    T: Float,
+{
+    let n = plane.normal;
+    Vector3::new(
+        if n.x >= T::zero() { aabb.min.x } else { aabb.max.x },
+        if n.y >= T::zero() { aabb.min.y } else { aabb.max.y },
+        if n.z >= T::zero() { aabb.min.z } else { aabb.max.z },
+    )
+}
@@ -0,0 +1,128 @@
+use std::ops::{Index, IndexMut};
+
+/// A two dimensional buffer of pixels.
+///
+/// Rows are stored starting from the *bottom* row of the image, which is the same layout that the
+/// `Renderer` framebuffer uses.  Image encoders and decoders flip rows as necessary for file
+/// formats that are stored top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image<T> {
+    width: u32,
+    height: u32,
+    pixels: Vec<T>,
+}
+
+impl<T> Image<T> {
+    pub fn new(width: u32, height: u32, fill: T) -> Image<T>
+    where
+        T: Clone,
+    {
+        Image {
+            width,
+            height,
+            pixels: vec![fill; width as usize * height as usize],
+        }
+    }
+
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<T>) -> Image<T> {
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize,
+            "pixel count does not match image dimensions"
+        );
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn pixels(&self) -> &[T] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [T] {
+        &mut self.pixels
+    }
+
+    pub fn into_pixels(self) -> Vec<T> {
+        self.pixels
+    }
+
+    pub fn row(&self, y: u32) -> &[T] {
+        let start = y as usize * self.width as usize;
+        &self.pixels[start..start + self.width as usize]
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> T
+    where
+        T: Copy,
+    {
+        self[(x, y)]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, p: T) {
+        self[(x, y)] = p;
+    }
+
+    pub fn fill(&mut self, p: T)
+    where
+        T: Clone,
+    {
+        for d in &mut self.pixels {
+            *d = p.clone();
+        }
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Image<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Image {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(f).collect(),
+        }
+    }
+
+    pub fn flip_vertical(&mut self) {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        for y in 0..height / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((height - y - 1) * width);
+            top[y * width..(y + 1) * width].swap_with_slice(&mut bottom[..width]);
+        }
+    }
+
+    fn index_of(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+impl<T> Index<(u32, u32)> for Image<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (u32, u32)) -> &T {
+        &self.pixels[self.index_of(x, y)]
+    }
+}
+
+impl<T> IndexMut<(u32, u32)> for Image<T> {
+    fn index_mut(&mut self, (x, y): (u32, u32)) -> &mut T {
+        let i = self.index_of(x, y);
+        &mut self.pixels[i]
+    }
+}
@@ -0,0 +1,343 @@
+use std::io::{self, Write};
+
+use color::Color;
+use image::Image;
+use super::{ImageError, malformed, unsupported};
+
+const FILE_HEADER_SIZE: u32 = 14;
+const INFO_HEADER_SIZE: u32 = 40;
+
+/// Writes an uncompressed 24-bit BMP file.  BMP rows are stored bottom to top and padded to a
+/// multiple of four bytes.
+pub fn encode<W: Write>(writer: &mut W, image: &Image<Color>) -> io::Result<()> {
+    let (width, height) = image.dimensions();
+    let row_size = (width * 3 + 3) & !3;
+    let data_size = row_size * height;
+    let data_offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+
+    let mut header = Vec::with_capacity(data_offset as usize);
+    header.extend_from_slice(b"BM");
+    put_u32(&mut header, data_offset + data_size);
+    put_u32(&mut header, 0);
+    put_u32(&mut header, data_offset);
+
+    put_u32(&mut header, INFO_HEADER_SIZE);
+    put_u32(&mut header, width);
+    put_u32(&mut header, height);
+    // Planes and bits per pixel
+    put_u16(&mut header, 1);
+    put_u16(&mut header, 24);
+    // BI_RGB, no compression
+    put_u32(&mut header, 0);
+    put_u32(&mut header, data_size);
+    // 72 DPI
+    put_u32(&mut header, 2835);
+    put_u32(&mut header, 2835);
+    put_u32(&mut header, 0);
+    put_u32(&mut header, 0);
+    writer.write_all(&header)?;
+
+    let mut row_data = Vec::with_capacity(row_size as usize);
+    for y in 0..height {
+        row_data.clear();
+        for &Color(r, g, b, _) in image.row(y) {
+            row_data.extend_from_slice(&[b, g, r]);
+        }
+        row_data.resize(row_size as usize, 0);
+        writer.write_all(&row_data)?;
+    }
+    Ok(())
+}
+
+/// Reads an uncompressed, bit field or RLE compressed BMP file with any of the standard header
+/// versions.
+pub fn decode(data: &[u8]) -> Result<Image<Color>, ImageError> {
+    if data.len() < 18 || !data.starts_with(b"BM") {
+        return malformed("missing BMP signature");
+    }
+    let data_offset = read_u32(&data[10..]) as usize;
+    let header_size = read_u32(&data[14..]) as usize;
+    if data.len() < FILE_HEADER_SIZE as usize + header_size {
+        return malformed("BMP header is truncated");
+    }
+    let info = &data[FILE_HEADER_SIZE as usize..];
+
+    let (width, height, bpp, compression, colors_used) = match header_size {
+        12 => (
+            read_u16(&info[4..]) as i32,
+            read_u16(&info[6..]) as i32,
+            read_u16(&info[10..]),
+            BI_RGB,
+            0,
+        ),
+        40 | 52 | 56 | 108 | 124 => (
+            read_u32(&info[4..]) as i32,
+            read_u32(&info[8..]) as i32,
+            read_u16(&info[14..]),
+            read_u32(&info[16..]),
+            read_u32(&info[32..]) as usize,
+        ),
+        s => return unsupported(format!("BMP header size {}", s)),
+    };
+
+    if width <= 0 || height == 0 {
+        return malformed("BMP image has invalid dimensions");
+    }
+    let top_down = height < 0;
+    let width = width as u32;
+    let height = height.unsigned_abs();
+
+    // Bit field masks follow a 40 byte header, later header versions contain them.
+    let mut table_offset = FILE_HEADER_SIZE as usize + header_size;
+    let masks = match compression {
+        BI_BITFIELDS | BI_ALPHABITFIELDS => {
+            let mask_count = if compression == BI_ALPHABITFIELDS { 4 } else { 3 };
+            let mask_data = if header_size == 40 {
+                table_offset += mask_count * 4;
+                if data.len() < table_offset {
+                    return malformed("BMP bit field masks are truncated");
+                }
+                &data[table_offset - mask_count * 4..table_offset]
+            } else {
+                &info[40..40 + mask_count * 4]
+            };
+            let alpha = if mask_count == 4 || header_size >= 56 {
+                if mask_count == 4 {
+                    read_u32(&mask_data[12..])
+                } else {
+                    read_u32(&info[52..])
+                }
+            } else {
+                0
+            };
+            Some([
+                read_u32(&mask_data[0..]),
+                read_u32(&mask_data[4..]),
+                read_u32(&mask_data[8..]),
+                alpha,
+            ])
+        }
+        BI_RGB | BI_RLE8 | BI_RLE4 => None,
+        c => return unsupported(format!("BMP compression type {}", c)),
+    };
+
+    let mut palette = Vec::new();
+    if bpp <= 8 {
+        let entry_size = if header_size == 12 { 3 } else { 4 };
+        let count = if colors_used == 0 {
+            1 << bpp
+        } else {
+            colors_used.min(256)
+        };
+        let end = table_offset + count * entry_size;
+        if data.len() < end {
+            return malformed("BMP palette is truncated");
+        }
+        for entry in data[table_offset..end].chunks(entry_size) {
+            palette.push(Color(entry[2], entry[1], entry[0], 255));
+        }
+    }
+
+    if data_offset > data.len() {
+        return malformed("BMP pixel data offset is past the end of the file");
+    }
+    let pixel_data = &data[data_offset..];
+
+    let mut image = match compression {
+        BI_RLE8 | BI_RLE4 => {
+            if top_down {
+                return malformed("RLE compressed BMP images cannot be top down");
+            }
+            let bits = if compression == BI_RLE8 { 8 } else { 4 };
+            if bpp != bits {
+                return malformed("BMP RLE compression does not match bit depth");
+            }
+            decode_rle(pixel_data, width, height, bits, &palette)?
+        }
+        _ => decode_uncompressed(pixel_data, width, height, bpp, masks, &palette)?,
+    };
+
+    if top_down {
+        image.flip_vertical();
+    }
+    Ok(image)
+}
+
+const BI_RGB: u32 = 0;
+const BI_RLE8: u32 = 1;
+const BI_RLE4: u32 = 2;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+// Keeps a corrupt header on a run length encoded image from exhausting memory.
+const MAX_RLE_PIXELS: u64 = 1 << 26;
+
+fn decode_uncompressed(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    bpp: u16,
+    masks: Option<[u32; 4]>,
+    palette: &[Color],
+) -> Result<Image<Color>, ImageError> {
+    let masks = match (masks, bpp) {
+        (Some(masks), 16) | (Some(masks), 32) => masks,
+        (Some(_), _) => return malformed("BMP bit fields require 16 or 32 bits per pixel"),
+        (None, 16) => [0x7c00, 0x03e0, 0x001f, 0],
+        (None, _) => [0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0],
+    };
+
+    let row_size = (width as usize * bpp as usize).div_ceil(32) * 4;
+    match row_size.checked_mul(height as usize) {
+        Some(size) if size <= data.len() => {}
+        _ => return malformed("BMP pixel data is truncated"),
+    }
+
+    let mut image = Image::new(width, height, Color(0, 0, 0, 255));
+    for y in 0..height {
+        let row = &data[y as usize * row_size..(y as usize + 1) * row_size];
+        for x in 0..width {
+            let x = x as usize;
+            let color = match bpp {
+                1 | 4 | 8 => {
+                    let bit = x * bpp as usize;
+                    let shift = 8 - bpp as usize - bit % 8;
+                    let index = ((row[bit / 8] >> shift) & ((1u16 << bpp) - 1) as u8) as usize;
+                    match palette.get(index) {
+                        Some(&c) => c,
+                        None => return malformed(format!("BMP palette index {} out of range", index)),
+                    }
+                }
+                24 => Color(row[x * 3 + 2], row[x * 3 + 1], row[x * 3], 255),
+                16 => {
+                    let v = read_u16(&row[x * 2..]) as u32;
+                    masked_color(v, masks)
+                }
+                32 => masked_color(read_u32(&row[x * 4..]), masks),
+                b => return unsupported(format!("{} bits per pixel BMP", b)),
+            };
+            image.set_pixel(x as u32, y, color);
+        }
+    }
+    Ok(image)
+}
+
+fn masked_color(v: u32, masks: [u32; 4]) -> Color {
+    let channel = |mask: u32| -> u8 {
+        if mask == 0 {
+            return 0;
+        }
+        let shift = mask.trailing_zeros();
+        let max = mask >> shift;
+        super::scale_to_u8((v & mask) >> shift, max)
+    };
+    let alpha = if masks[3] == 0 { 255 } else { channel(masks[3]) };
+    Color(channel(masks[0]), channel(masks[1]), channel(masks[2]), alpha)
+}
+
+fn decode_rle(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    bits: u16,
+    palette: &[Color],
+) -> Result<Image<Color>, ImageError> {
+    let lookup = |index: u8| -> Result<Color, ImageError> {
+        match palette.get(index as usize) {
+            Some(&c) => Ok(c),
+            None => malformed(format!("BMP palette index {} out of range", index)),
+        }
+    };
+
+    // Run length encoded data can skip over pixels, so its length does not bound the image size.
+    if width as u64 * height as u64 > MAX_RLE_PIXELS {
+        return unsupported(format!("RLE compressed BMP images of {}x{} pixels", width, height));
+    }
+
+    // Skipped pixels are left transparent.
+    let mut image = Image::new(width, height, Color(0, 0, 0, 0));
+    let (mut x, mut y) = (0u32, 0u32);
+    let mut pos = 0;
+    let put = |image: &mut Image<Color>, x: &mut u32, y: u32, c: Color| {
+        if *x < width && y < height {
+            image.set_pixel(*x, y, c);
+        }
+        *x += 1;
+    };
+
+    loop {
+        if data.len() < pos + 2 {
+            return malformed("BMP RLE data is truncated");
+        }
+        let (count, value) = (data[pos], data[pos + 1]);
+        pos += 2;
+
+        if count > 0 {
+            for i in 0..count {
+                let index = if bits == 8 {
+                    value
+                } else if i % 2 == 0 {
+                    value >> 4
+                } else {
+                    value & 0x0f
+                };
+                put(&mut image, &mut x, y, lookup(index)?);
+            }
+            continue;
+        }
+
+        match value {
+            0 => {
+                x = 0;
+                y += 1;
+            }
+            1 => break,
+            2 => {
+                if data.len() < pos + 2 {
+                    return malformed("BMP RLE delta is truncated");
+                }
+                x += data[pos] as u32;
+                y += data[pos + 1] as u32;
+                pos += 2;
+            }
+            n => {
+                let n = n as usize;
+                let bytes = if bits == 8 { n } else { n.div_ceil(2) };
+                // Absolute runs are padded to a 16-bit boundary
+                let padded = (bytes + 1) & !1;
+                if data.len() < pos + padded {
+                    return malformed("BMP RLE absolute run is truncated");
+                }
+                for i in 0..n {
+                    let index = if bits == 8 {
+                        data[pos + i]
+                    } else if i % 2 == 0 {
+                        data[pos + i / 2] >> 4
+                    } else {
+                        data[pos + i / 2] & 0x0f
+                    };
+                    put(&mut image, &mut x, y, lookup(index)?);
+                }
+                pos += padded;
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+fn read_u16(data: &[u8]) -> u16 {
+    data[0] as u16 | (data[1] as u16) << 8
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    data[0] as u32 | (data[1] as u32) << 8 | (data[2] as u32) << 16 | (data[3] as u32) << 24
+}
+
+fn put_u16(out: &mut Vec<u8>, v: u16) {
+    out.extend_from_slice(&[v as u8, (v >> 8) as u8]);
+}
+
+fn put_u32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&[v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8]);
+}
@@ -0,0 +1,224 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+use color::{Color, Color16};
+use image::Image;
+
+pub mod ppm;
+pub mod tga;
+pub mod bmp;
+pub mod png;
+mod zlib;
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    Malformed(String),
+    Unsupported(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ImageError::Io(ref err) => write!(f, "i/o error reading image: {}", err),
+            ImageError::Malformed(ref msg) => write!(f, "malformed image: {}", msg),
+            ImageError::Unsupported(ref msg) => write!(f, "unsupported image: {}", msg),
+        }
+    }
+}
+
+impl Error for ImageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ImageError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImageError {
+    fn from(err: io::Error) -> ImageError {
+        ImageError::Io(err)
+    }
+}
+
+/// A decoded image, keeping 16-bit channels when the source file has them.  Palette, grayscale
+/// and RGB images are always expanded to RGBA.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedImage {
+    Rgba8(Image<Color>),
+    Rgba16(Image<Color16>),
+}
+
+impl DecodedImage {
+    pub fn dimensions(&self) -> (u32, u32) {
+        match *self {
+            DecodedImage::Rgba8(ref image) => image.dimensions(),
+            DecodedImage::Rgba16(ref image) => image.dimensions(),
+        }
+    }
+
+    pub fn into_rgba8(self) -> Image<Color> {
+        match self {
+            DecodedImage::Rgba8(image) => image,
+            DecodedImage::Rgba16(image) => image.map(|&c| c.to_color()),
+        }
+    }
+
+    pub fn into_rgba16(self) -> Image<Color16> {
+        match self {
+            DecodedImage::Rgba8(image) => image.map(|&c| Color16::from(c)),
+            DecodedImage::Rgba16(image) => image,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ImageFormat {
+    Ppm,
+    Pam,
+    Tga,
+    Bmp,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn from_extension(extension: &str) -> Option<ImageFormat> {
+        match extension.to_ascii_lowercase().as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "pam" => Some(ImageFormat::Pam),
+            "tga" => Some(ImageFormat::Tga),
+            "bmp" => Some(ImageFormat::Bmp),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the first bytes of a file.  TGA files have no signature, so they can
+    /// only be recognized by extension.
+    pub fn from_magic(data: &[u8]) -> Option<ImageFormat> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if data.starts_with(b"BM") {
+            Some(ImageFormat::Bmp)
+        } else if data.starts_with(b"P7") {
+            Some(ImageFormat::Pam)
+        } else if data.len() >= 2 && data[0] == b'P' && data[1] >= b'1' && data[1] <= b'6' {
+            Some(ImageFormat::Ppm)
+        } else {
+            None
+        }
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<ImageFormat> {
+        path.as_ref()
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(ImageFormat::from_extension)
+    }
+}
+
+pub fn encode_image<W: Write>(
+    writer: &mut W,
+    image: &Image<Color>,
+    format: ImageFormat,
+) -> io::Result<()> {
+    match format {
+        ImageFormat::Ppm => ppm::encode(writer, image),
+        ImageFormat::Pam => ppm::encode_pam(writer, image),
+        ImageFormat::Tga => tga::encode(writer, image),
+        ImageFormat::Bmp => bmp::encode(writer, image),
+        ImageFormat::Png => png::encode(writer, image),
+    }
+}
+
+/// Saves an image to the given path, picking the file format from the path's extension.
+pub fn save_image<P: AsRef<Path>>(path: P, image: &Image<Color>) -> io::Result<()> {
+    let path = path.as_ref();
+    let format = ImageFormat::from_path(path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unrecognized image extension for {}", path.display()),
+        )
+    })?;
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    encode_image(&mut writer, image, format)?;
+    writer.flush()
+}
+
+pub fn decode_image(data: &[u8], format: ImageFormat) -> Result<DecodedImage, ImageError> {
+    match format {
+        ImageFormat::Ppm | ImageFormat::Pam => ppm::decode(data),
+        ImageFormat::Tga => tga::decode(data).map(DecodedImage::Rgba8),
+        ImageFormat::Bmp => bmp::decode(data).map(DecodedImage::Rgba8),
+        ImageFormat::Png => png::decode(data),
+    }
+}
+
+/// Loads an image from the given path, detecting the format from the file contents and falling
+/// back to the path's extension.
+pub fn load_image<P: AsRef<Path>>(path: P) -> Result<DecodedImage, ImageError> {
+    let path = path.as_ref();
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+
+    let format = ImageFormat::from_magic(&data)
+        .or_else(|| ImageFormat::from_path(path))
+        .ok_or_else(|| {
+            ImageError::Unsupported(format!("unrecognized image format for {}", path.display()))
+        })?;
+    decode_image(&data, format)
+}
+
+/// Converts a depth buffer into a displayable grayscale image, mapping the nearest finite depth to
+/// white and the farthest to black.  Non-finite depths (such as cleared depth buffer entries) are
+/// drawn black.
+pub fn depth_to_grayscale(depth: &Image<f32>) -> Image<Color> {
+    let mut min = f32::INFINITY;
+    let mut max = f32::NEG_INFINITY;
+    for &d in depth.pixels() {
+        if d.is_finite() {
+            min = min.min(d);
+            max = max.max(d);
+        }
+    }
+
+    let range = max - min;
+    depth.map(|&d| if d.is_finite() {
+        let v = if range > 0.0 { (max - d) / range } else { 1.0 };
+        let v = (v * 255.0).round() as u8;
+        Color(v, v, v, 255)
+    } else {
+        Color(0, 0, 0, 255)
+    })
+}
+
+fn malformed<T, S: Into<String>>(msg: S) -> Result<T, ImageError> {
+    Err(ImageError::Malformed(msg.into()))
+}
+
+fn unsupported<T, S: Into<String>>(msg: S) -> Result<T, ImageError> {
+    Err(ImageError::Unsupported(msg.into()))
+}
+
+// Scales a sample in the range [0, max] to the range [0, 255].
+fn scale_to_u8(v: u32, max: u32) -> u8 {
+    if max == 255 {
+        v as u8
+    } else {
+        ((v.min(max) as u64 * 255 + max as u64 / 2) / max as u64) as u8
+    }
+}
+
+// Scales a sample in the range [0, max] to the range [0, 65535].
+fn scale_to_u16(v: u32, max: u32) -> u16 {
+    if max == 65535 {
+        v as u16
+    } else {
+        ((v.min(max) as u64 * 65535 + max as u64 / 2) / max as u64) as u16
+    }
+}
@@ -0,0 +1,458 @@
+use std::io::{self, Write};
+
+use color::{Color, Color16};
+use image::Image;
+use super::{zlib, DecodedImage, ImageError, malformed, unsupported};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Writes an 8-bit RGBA PNG file.  Each row is filtered with whichever PNG filter type produces
+/// the smallest sum of absolute differences, and the result is compressed with `zlib::compress`.
+pub fn encode<W: Write>(writer: &mut W, image: &Image<Color>) -> io::Result<()> {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 || width > 0x7fff_ffff || height > 0x7fff_ffff {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid dimensions for PNG image",
+        ));
+    }
+
+    writer.write_all(&SIGNATURE)?;
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&be_u32(width));
+    ihdr.extend_from_slice(&be_u32(height));
+    // Bit depth 8, color type 6 (RGBA), deflate compression, adaptive filtering, no interlace
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+    write_chunk(writer, b"IHDR", &ihdr)?;
+
+    let stride = width as usize * 4;
+    let mut filtered = Vec::with_capacity((stride + 1) * height as usize);
+    let mut prev_row = vec![0u8; stride];
+    let mut row = Vec::with_capacity(stride);
+    let mut candidate = vec![0u8; stride];
+    let mut best = vec![0u8; stride];
+
+    for y in (0..height).rev() {
+        row.clear();
+        for &Color(r, g, b, a) in image.row(y) {
+            row.extend_from_slice(&[r, g, b, a]);
+        }
+
+        let mut best_filter = 0;
+        let mut best_score = u64::MAX;
+        for filter in 0..5 {
+            apply_filter(filter, &row, &prev_row, &mut candidate);
+            let score = candidate
+                .iter()
+                .map(|&d| (d as i8 as i16).unsigned_abs() as u64)
+                .sum::<u64>();
+            if score < best_score {
+                best_score = score;
+                best_filter = filter;
+                best.copy_from_slice(&candidate);
+            }
+        }
+
+        filtered.push(best_filter);
+        filtered.extend_from_slice(&best);
+        prev_row.copy_from_slice(&row);
+    }
+
+    write_chunk(writer, b"IDAT", &zlib::compress(&filtered))?;
+    write_chunk(writer, b"IEND", &[])
+}
+
+/// Reads a PNG file of any standard color type and bit depth, including interlaced images.
+/// Images with 16-bit channels are decoded as `DecodedImage::Rgba16`, everything else as
+/// `DecodedImage::Rgba8`.
+pub fn decode(data: &[u8]) -> Result<DecodedImage, ImageError> {
+    if !data.starts_with(&SIGNATURE) {
+        return malformed("missing PNG signature");
+    }
+
+    let mut header = None;
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut transparency: Option<Vec<u8>> = None;
+    let mut compressed = Vec::new();
+    let mut seen_end = false;
+
+    let mut pos = SIGNATURE.len();
+    while pos < data.len() {
+        if data.len() - pos < 12 {
+            return malformed("truncated PNG chunk header");
+        }
+        let len = read_be_u32(&data[pos..]) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        if data.len() - pos - 12 < len {
+            return malformed(format!(
+                "PNG chunk {} is truncated",
+                String::from_utf8_lossy(kind)
+            ));
+        }
+        let body = &data[pos + 8..pos + 8 + len];
+        let crc = read_be_u32(&data[pos + 8 + len..]);
+        if crc != crc32(&[kind, body]) {
+            return malformed(format!(
+                "CRC mismatch in PNG chunk {}",
+                String::from_utf8_lossy(kind)
+            ));
+        }
+        pos += 12 + len;
+
+        match kind {
+            b"IHDR" => header = Some(Header::parse(body)?),
+            b"PLTE" => {
+                if !len.is_multiple_of(3) || len == 0 || len > 256 * 3 {
+                    return malformed("invalid PNG palette size");
+                }
+                palette = body.chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+            }
+            b"tRNS" => transparency = Some(body.to_vec()),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => {
+                seen_end = true;
+                break;
+            }
+            _ => {
+                // Ancillary chunks have a lowercase first letter and are safe to ignore.
+                if kind[0] & 0x20 == 0 {
+                    return unsupported(format!(
+                        "unknown critical PNG chunk {}",
+                        String::from_utf8_lossy(kind)
+                    ));
+                }
+            }
+        }
+    }
+
+    let header = match header {
+        Some(header) => header,
+        None => return malformed("PNG file has no IHDR chunk"),
+    };
+    if !seen_end {
+        return malformed("PNG file has no IEND chunk");
+    }
+    if header.color_type == COLOR_PALETTE && palette.is_empty() {
+        return malformed("palette PNG file has no PLTE chunk");
+    }
+
+    let raw = zlib::decompress(&compressed).map_err(|e| {
+        ImageError::Malformed(format!("PNG image data: {}", e))
+    })?;
+
+    let channels = header.channels();
+    let bits_per_pixel = channels * header.bit_depth as usize;
+    let filter_bpp = (bits_per_pixel / 8).max(1);
+    let max_value = (1u32 << header.bit_depth) - 1;
+
+    let width = header.width as usize;
+    let height = header.height as usize;
+    // Every pixel takes up its bits in the decompressed data, so checking them first keeps a
+    // corrupt header from reserving more memory than the file could fill.
+    let bits = width
+        .checked_mul(height)
+        .and_then(|p| p.checked_mul(bits_per_pixel));
+    match bits {
+        Some(bits) if bits.div_ceil(8) <= raw.len() => {}
+        _ => return malformed("PNG image data is too short"),
+    }
+    let mut samples = vec![[0u16; 4]; width * height];
+
+    let passes: &[(usize, usize, usize, usize)] = if header.interlaced {
+        &[
+            (0, 0, 8, 8),
+            (4, 0, 8, 8),
+            (0, 4, 4, 8),
+            (2, 0, 4, 4),
+            (0, 2, 2, 4),
+            (1, 0, 2, 2),
+            (0, 1, 1, 2),
+        ]
+    } else {
+        &[(0, 0, 1, 1)]
+    };
+
+    let mut pos = 0;
+    for &(x0, y0, dx, dy) in passes {
+        if x0 >= width || y0 >= height {
+            continue;
+        }
+        let pass_width = (width - x0).div_ceil(dx);
+        let pass_height = (height - y0).div_ceil(dy);
+        let stride = (pass_width * bits_per_pixel).div_ceil(8);
+
+        let mut prev_row = vec![0u8; stride];
+        let mut row = vec![0u8; stride];
+        for py in 0..pass_height {
+            if raw.len() - pos < stride + 1 {
+                return malformed("PNG image data is too short");
+            }
+            let filter = raw[pos];
+            row.copy_from_slice(&raw[pos + 1..pos + 1 + stride]);
+            pos += stride + 1;
+            unfilter(filter, filter_bpp, &mut row, &prev_row)?;
+
+            let y = y0 + py * dy;
+            for px in 0..pass_width {
+                let x = x0 + px * dx;
+                let mut pixel = [0u16; 4];
+                for (c, p) in pixel.iter_mut().enumerate().take(channels) {
+                    *p = sample(&row, px * channels + c, header.bit_depth);
+                }
+                samples[y * width + x] = header.expand(pixel, &palette, &transparency, max_value)?;
+            }
+
+            ::std::mem::swap(&mut row, &mut prev_row);
+        }
+    }
+
+    // PNG rows are stored top to bottom, images are stored bottom to top.
+    let mut image = if header.bit_depth == 16 {
+        DecodedImage::Rgba16(Image::from_pixels(
+            header.width,
+            header.height,
+            samples
+                .iter()
+                .map(|s| Color16(s[0], s[1], s[2], s[3]))
+                .collect(),
+        ))
+    } else {
+        let max = if header.color_type == COLOR_PALETTE {
+            255
+        } else {
+            max_value
+        };
+        let scale = |v: u16| super::scale_to_u8(v as u32, max);
+        DecodedImage::Rgba8(Image::from_pixels(
+            header.width,
+            header.height,
+            samples
+                .iter()
+                .map(|s| Color(scale(s[0]), scale(s[1]), scale(s[2]), scale(s[3])))
+                .collect(),
+        ))
+    };
+    match image {
+        DecodedImage::Rgba8(ref mut image) => image.flip_vertical(),
+        DecodedImage::Rgba16(ref mut image) => image.flip_vertical(),
+    }
+    Ok(image)
+}
+
+const COLOR_GRAY: u8 = 0;
+const COLOR_RGB: u8 = 2;
+const COLOR_PALETTE: u8 = 3;
+const COLOR_GRAY_ALPHA: u8 = 4;
+const COLOR_RGBA: u8 = 6;
+
+struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn parse(body: &[u8]) -> Result<Header, ImageError> {
+        if body.len() != 13 {
+            return malformed("invalid PNG IHDR chunk size");
+        }
+        let header = Header {
+            width: read_be_u32(&body[0..]),
+            height: read_be_u32(&body[4..]),
+            bit_depth: body[8],
+            color_type: body[9],
+            interlaced: match body[12] {
+                0 => false,
+                1 => true,
+                m => return unsupported(format!("unknown PNG interlace method {}", m)),
+            },
+        };
+
+        if header.width == 0 || header.height == 0 {
+            return malformed("PNG image has zero width or height");
+        }
+        if body[10] != 0 {
+            return unsupported(format!("unknown PNG compression method {}", body[10]));
+        }
+        if body[11] != 0 {
+            return unsupported(format!("unknown PNG filter method {}", body[11]));
+        }
+
+        let valid_depth = match header.color_type {
+            COLOR_GRAY => [1, 2, 4, 8, 16].contains(&header.bit_depth),
+            COLOR_PALETTE => [1, 2, 4, 8].contains(&header.bit_depth),
+            COLOR_RGB | COLOR_GRAY_ALPHA | COLOR_RGBA => [8, 16].contains(&header.bit_depth),
+            t => return unsupported(format!("unknown PNG color type {}", t)),
+        };
+        if !valid_depth {
+            return malformed(format!(
+                "bit depth {} is not allowed for PNG color type {}",
+                header.bit_depth,
+                header.color_type
+            ));
+        }
+
+        Ok(header)
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            COLOR_GRAY | COLOR_PALETTE => 1,
+            COLOR_GRAY_ALPHA => 2,
+            COLOR_RGB => 3,
+            _ => 4,
+        }
+    }
+
+    // Expands raw samples to RGBA.  Palette entries are already 8-bit, every other color type
+    // stays in the range [0, max_value].
+    fn expand(
+        &self,
+        s: [u16; 4],
+        palette: &[[u8; 3]],
+        transparency: &Option<Vec<u8>>,
+        max_value: u32,
+    ) -> Result<[u16; 4], ImageError> {
+        let opaque = max_value as u16;
+        let trns_u16 = |i: usize| -> Option<u16> {
+            transparency.as_ref().and_then(|t| if t.len() >= i * 2 + 2 {
+                Some((t[i * 2] as u16) << 8 | t[i * 2 + 1] as u16)
+            } else {
+                None
+            })
+        };
+
+        Ok(match self.color_type {
+            COLOR_GRAY => {
+                let alpha = if trns_u16(0) == Some(s[0]) { 0 } else { opaque };
+                [s[0], s[0], s[0], alpha]
+            }
+            COLOR_RGB => {
+                let key = [trns_u16(0), trns_u16(1), trns_u16(2)];
+                let alpha = if key == [Some(s[0]), Some(s[1]), Some(s[2])] {
+                    0
+                } else {
+                    opaque
+                };
+                [s[0], s[1], s[2], alpha]
+            }
+            COLOR_PALETTE => {
+                let index = s[0] as usize;
+                let entry = match palette.get(index) {
+                    Some(entry) => entry,
+                    None => return malformed(format!("PNG palette index {} out of range", index)),
+                };
+                let alpha = transparency
+                    .as_ref()
+                    .and_then(|t| t.get(index).cloned())
+                    .unwrap_or(255);
+                [
+                    entry[0] as u16,
+                    entry[1] as u16,
+                    entry[2] as u16,
+                    alpha as u16,
+                ]
+            }
+            COLOR_GRAY_ALPHA => [s[0], s[0], s[0], s[1]],
+            _ => s,
+        })
+    }
+}
+
+fn sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    match bit_depth {
+        16 => (row[index * 2] as u16) << 8 | row[index * 2 + 1] as u16,
+        8 => row[index] as u16,
+        _ => {
+            let bit = index * bit_depth as usize;
+            let shift = 8 - bit_depth as usize - bit % 8;
+            ((row[bit / 8] >> shift) & ((1 << bit_depth) - 1)) as u16
+        }
+    }
+}
+
+fn unfilter(filter: u8, bpp: usize, row: &mut [u8], prev: &[u8]) -> Result<(), ImageError> {
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prev[i];
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        let predictor = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            4 => paeth(a, b, c),
+            f => return malformed(format!("invalid PNG filter type {}", f)),
+        };
+        row[i] = row[i].wrapping_add(predictor);
+    }
+    Ok(())
+}
+
+fn apply_filter(filter: u8, row: &[u8], prev: &[u8], out: &mut [u8]) {
+    const BPP: usize = 4;
+    for i in 0..row.len() {
+        let a = if i >= BPP { row[i - BPP] } else { 0 };
+        let b = prev[i];
+        let c = if i >= BPP { prev[i - BPP] } else { 0 };
+        let predictor = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            _ => paeth(a, b, c),
+        };
+        out[i] = row[i].wrapping_sub(predictor);
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&be_u32(data.len() as u32))?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    writer.write_all(&be_u32(crc32(&[kind, data])))
+}
+
+fn read_be_u32(data: &[u8]) -> u32 {
+    (data[0] as u32) << 24 | (data[1] as u32) << 16 | (data[2] as u32) << 8 | data[3] as u32
+}
+
+fn be_u32(v: u32) -> [u8; 4] {
+    [(v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8]
+}
+
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+        }
+        *entry = c;
+    }
+
+    let mut crc = 0xffff_ffff;
+    for part in parts {
+        for &d in part.iter() {
+            crc = table[((crc ^ d as u32) & 0xff) as usize] ^ (crc >> 8);
+        }
+    }
+    crc ^ 0xffff_ffff
+}
@@ -0,0 +1,300 @@
+use std::io::{self, Write};
+
+use color::{Color, Color16};
+use image::Image;
+use super::{DecodedImage, ImageError, malformed, unsupported};
+
+/// Writes a binary (P6) PPM file, discarding the alpha channel.
+pub fn encode<W: Write>(writer: &mut W, image: &Image<Color>) -> io::Result<()> {
+    let (width, height) = image.dimensions();
+    write!(writer, "P6\n{} {}\n255\n", width, height)?;
+
+    let mut row_data = Vec::with_capacity(width as usize * 3);
+    for y in (0..height).rev() {
+        row_data.clear();
+        for &Color(r, g, b, _) in image.row(y) {
+            row_data.extend_from_slice(&[r, g, b]);
+        }
+        writer.write_all(&row_data)?;
+    }
+    Ok(())
+}
+
+/// Writes a PAM (P7) file with an RGB_ALPHA tuple type.
+pub fn encode_pam<W: Write>(writer: &mut W, image: &Image<Color>) -> io::Result<()> {
+    let (width, height) = image.dimensions();
+    write!(
+        writer,
+        "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+        width,
+        height
+    )?;
+
+    let mut row_data = Vec::with_capacity(width as usize * 4);
+    for y in (0..height).rev() {
+        row_data.clear();
+        for &Color(r, g, b, a) in image.row(y) {
+            row_data.extend_from_slice(&[r, g, b, a]);
+        }
+        writer.write_all(&row_data)?;
+    }
+    Ok(())
+}
+
+/// Reads any of the netpbm formats: ASCII and binary PBM, PGM and PPM (P1 to P6), as well as PAM
+/// (P7).  Images with a maximum value above 255 are decoded with 16-bit channels.
+pub fn decode(data: &[u8]) -> Result<DecodedImage, ImageError> {
+    if data.len() < 2 || data[0] != b'P' {
+        return malformed("missing netpbm magic number");
+    }
+    let magic = data[1];
+    let mut parser = Parser { data, pos: 2 };
+
+    let (width, height, depth, max_value) = match magic {
+        b'1' | b'4' => {
+            let width = parser.header_number()?;
+            let height = parser.header_number()?;
+            (width, height, 1, 1)
+        }
+        b'2' | b'3' | b'5' | b'6' => {
+            let width = parser.header_number()?;
+            let height = parser.header_number()?;
+            let max_value = parser.header_number()?;
+            let depth = if magic == b'3' || magic == b'6' { 3 } else { 1 };
+            (width, height, depth, max_value)
+        }
+        b'7' => parser.pam_header()?,
+        m => return unsupported(format!("netpbm format P{}", m as char)),
+    };
+
+    if width == 0 || height == 0 {
+        return malformed("netpbm image has zero width or height");
+    }
+    if max_value == 0 || max_value > 65535 {
+        return malformed(format!("invalid netpbm maximum value {}", max_value));
+    }
+    if depth == 0 || depth > 4 {
+        return unsupported(format!("netpbm images with depth {}", depth));
+    }
+
+    // A single whitespace character separates the header from binary data.
+    if magic != b'1' && magic != b'2' && magic != b'3' && magic != b'7' {
+        parser.pos += 1;
+    }
+
+    // The header's dimensions are only trusted once the data is known to be long enough to hold
+    // them, so a corrupt header cannot make the decoder reserve huge amounts of memory.
+    let sample_size = if max_value > 255 { 2 } else { 1 };
+    let row_size = (width as usize).div_ceil(8);
+    let count = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|c| c.checked_mul(depth));
+    let size = match magic {
+        b'4' => row_size.checked_mul(height as usize),
+        // Every ASCII sample takes at least one character.
+        b'1' | b'2' | b'3' => count,
+        _ => count.and_then(|c| c.checked_mul(sample_size)),
+    };
+    let count = match (count, size) {
+        (Some(count), Some(size)) if size <= data.len().saturating_sub(parser.pos) => count,
+        _ => return malformed("netpbm image data is truncated"),
+    };
+
+    let mut samples = Vec::with_capacity(count);
+    match magic {
+        b'1' => {
+            for _ in 0..count {
+                samples.push(1 - parser.ascii_bit()?);
+            }
+        }
+        b'2' | b'3' => {
+            for _ in 0..count {
+                samples.push(parser.ascii_number()?);
+            }
+        }
+        b'4' => {
+            let bytes = parser.binary(row_size * height as usize)?;
+            for row in bytes.chunks(row_size) {
+                for x in 0..width as usize {
+                    samples.push(1 - ((row[x / 8] >> (7 - x % 8)) & 1) as u32);
+                }
+            }
+        }
+        _ => {
+            let bytes = parser.binary(count * sample_size)?;
+            if sample_size == 2 {
+                for s in bytes.chunks(2) {
+                    samples.push((s[0] as u32) << 8 | s[1] as u32);
+                }
+            } else {
+                samples.extend(bytes.iter().map(|&s| s as u32));
+            }
+        }
+    }
+
+    let pixel = |s: &[u32]| -> [u32; 4] {
+        match depth {
+            1 => [s[0], s[0], s[0], max_value],
+            2 => [s[0], s[0], s[0], s[1]],
+            3 => [s[0], s[1], s[2], max_value],
+            _ => [s[0], s[1], s[2], s[3]],
+        }
+    };
+
+    // Netpbm rows are stored top to bottom, images are stored bottom to top.
+    let mut image = if max_value > 255 {
+        let c = |v| super::scale_to_u16(v, max_value);
+        DecodedImage::Rgba16(Image::from_pixels(
+            width,
+            height,
+            samples
+                .chunks(depth)
+                .map(|s| {
+                    let p = pixel(s);
+                    Color16(c(p[0]), c(p[1]), c(p[2]), c(p[3]))
+                })
+                .collect(),
+        ))
+    } else {
+        let c = |v| super::scale_to_u8(v, max_value);
+        DecodedImage::Rgba8(Image::from_pixels(
+            width,
+            height,
+            samples
+                .chunks(depth)
+                .map(|s| {
+                    let p = pixel(s);
+                    Color(c(p[0]), c(p[1]), c(p[2]), c(p[3]))
+                })
+                .collect(),
+        ))
+    };
+    match image {
+        DecodedImage::Rgba8(ref mut image) => image.flip_vertical(),
+        DecodedImage::Rgba16(ref mut image) => image.flip_vertical(),
+    }
+    Ok(image)
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace_and_comments(&mut self) {
+        while self.pos < self.data.len() {
+            match self.data[self.pos] {
+                b'#' => {
+                    while self.pos < self.data.len() && self.data[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn token(&mut self) -> Result<&'a [u8], ImageError> {
+        self.skip_whitespace_and_comments();
+        let start = self.pos;
+        while self.pos < self.data.len() && !self.data[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return malformed("netpbm header is truncated");
+        }
+        Ok(&self.data[start..self.pos])
+    }
+
+    fn header_number(&mut self) -> Result<u32, ImageError> {
+        let token = self.token()?;
+        parse_number(token)
+    }
+
+    fn ascii_number(&mut self) -> Result<u32, ImageError> {
+        self.skip_whitespace_and_comments();
+        let start = self.pos;
+        while self.pos < self.data.len() && self.data[self.pos].is_ascii_digit() {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return malformed("netpbm ASCII image data is truncated or invalid");
+        }
+        parse_number(&self.data[start..self.pos])
+    }
+
+    // PBM ASCII bits may be written without any separating whitespace.
+    fn ascii_bit(&mut self) -> Result<u32, ImageError> {
+        self.skip_whitespace_and_comments();
+        match self.data.get(self.pos) {
+            Some(&b'0') => {
+                self.pos += 1;
+                Ok(0)
+            }
+            Some(&b'1') => {
+                self.pos += 1;
+                Ok(1)
+            }
+            _ => malformed("netpbm ASCII bitmap data is truncated or invalid"),
+        }
+    }
+
+    fn binary(&mut self, len: usize) -> Result<&'a [u8], ImageError> {
+        if self.data.len() < self.pos || self.data.len() - self.pos < len {
+            return malformed("netpbm image data is truncated");
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn pam_header(&mut self) -> Result<(u32, u32, usize, u32), ImageError> {
+        let mut width = None;
+        let mut height = None;
+        let mut depth = None;
+        let mut max_value = None;
+        loop {
+            let key = self.token()?;
+            match key {
+                b"ENDHDR" => break,
+                b"WIDTH" => width = Some(self.header_number()?),
+                b"HEIGHT" => height = Some(self.header_number()?),
+                b"DEPTH" => depth = Some(self.header_number()? as usize),
+                b"MAXVAL" => max_value = Some(self.header_number()?),
+                b"TUPLTYPE" => {
+                    // The tuple type is implied by the depth, so its value is not needed.
+                    while self.pos < self.data.len() && self.data[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                k => {
+                    return malformed(format!(
+                        "unknown PAM header field {}",
+                        String::from_utf8_lossy(k)
+                    ))
+                }
+            }
+        }
+        // ENDHDR is followed by exactly one newline.
+        self.pos += 1;
+
+        match (width, height, depth, max_value) {
+            (Some(w), Some(h), Some(d), Some(m)) => Ok((w, h, d, m)),
+            _ => malformed("PAM header is missing a required field"),
+        }
+    }
+}
+
+fn parse_number(token: &[u8]) -> Result<u32, ImageError> {
+    ::std::str::from_utf8(token)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| {
+            ImageError::Malformed(format!(
+                "invalid number {} in netpbm file",
+                String::from_utf8_lossy(token)
+            ))
+        })
+}
@@ -0,0 +1,212 @@
+use std::io::{self, Write};
+
+use color::Color;
+use image::Image;
+use super::{ImageError, malformed, unsupported};
+
+/// Writes an uncompressed 32-bit true color TGA file.  TGA images may be stored bottom to top, so
+/// rows are written in the same order as they are stored in the `Image`.
+pub fn encode<W: Write>(writer: &mut W, image: &Image<Color>) -> io::Result<()> {
+    let (width, height) = image.dimensions();
+    if width > 0xffff || height > 0xffff {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "image too large for TGA",
+        ));
+    }
+
+    let mut header = [0u8; 18];
+    // Uncompressed true color
+    header[2] = 2;
+    header[12] = width as u8;
+    header[13] = (width >> 8) as u8;
+    header[14] = height as u8;
+    header[15] = (height >> 8) as u8;
+    header[16] = 32;
+    // 8 alpha bits, bottom left origin
+    header[17] = 8;
+    writer.write_all(&header)?;
+
+    let mut data = Vec::with_capacity(image.pixels().len() * 4);
+    for &Color(r, g, b, a) in image.pixels() {
+        data.extend_from_slice(&[b, g, r, a]);
+    }
+    writer.write_all(&data)
+}
+
+/// Reads a color mapped, true color or grayscale TGA file, either uncompressed or run length
+/// encoded.
+pub fn decode(data: &[u8]) -> Result<Image<Color>, ImageError> {
+    if data.len() < 18 {
+        return malformed("TGA header is truncated");
+    }
+    let id_length = data[0] as usize;
+    let color_map_type = data[1];
+    let image_type = data[2];
+    let color_map_first = read_u16(&data[3..]) as usize;
+    let color_map_length = read_u16(&data[5..]) as usize;
+    let color_map_depth = data[7];
+    let width = read_u16(&data[12..]) as u32;
+    let height = read_u16(&data[14..]) as u32;
+    let pixel_depth = data[16];
+    let descriptor = data[17];
+    let alpha_bits = descriptor & 0x0f;
+
+    let rle = image_type & 8 != 0;
+    let kind = image_type & !8;
+    if kind != 1 && kind != 2 && kind != 3 {
+        return unsupported(format!("TGA image type {}", image_type));
+    }
+    if color_map_type > 1 {
+        return unsupported(format!("TGA color map type {}", color_map_type));
+    }
+    if kind == 1 && color_map_type != 1 {
+        return malformed("color mapped TGA file has no color map");
+    }
+    if width == 0 || height == 0 {
+        return malformed("TGA image has zero width or height");
+    }
+
+    let mut pos = 18 + id_length;
+
+    let mut color_map = Vec::new();
+    if color_map_type == 1 {
+        let entry_size = bytes_per_pixel(color_map_depth)?;
+        let size = color_map_length * entry_size;
+        if data.len() < pos + size {
+            return malformed("TGA color map is truncated");
+        }
+        for entry in data[pos..pos + size].chunks(entry_size) {
+            color_map.push(true_color(entry, alpha_bits));
+        }
+        pos += size;
+    }
+
+    let pixel_size = match kind {
+        1 => {
+            if pixel_depth != 8 && pixel_depth != 16 {
+                return unsupported(format!("{} bit TGA color map indices", pixel_depth));
+            }
+            pixel_depth as usize / 8
+        }
+        2 => bytes_per_pixel(pixel_depth)?,
+        _ => {
+            if pixel_depth != 8 && pixel_depth != 16 {
+                return unsupported(format!("{} bit TGA grayscale", pixel_depth));
+            }
+            pixel_depth as usize / 8
+        }
+    };
+
+    let convert = |p: &[u8]| -> Result<Color, ImageError> {
+        match kind {
+            1 => {
+                let index = if p.len() == 2 {
+                    read_u16(p) as usize
+                } else {
+                    p[0] as usize
+                };
+                match index
+                    .checked_sub(color_map_first)
+                    .and_then(|i| color_map.get(i))
+                {
+                    Some(&c) => Ok(c),
+                    None => malformed(format!("TGA color map index {} out of range", index)),
+                }
+            }
+            2 => Ok(true_color(p, alpha_bits)),
+            _ => {
+                let alpha = if p.len() == 2 { p[1] } else { 255 };
+                Ok(Color(p[0], p[0], p[0], alpha))
+            }
+        }
+    };
+
+    let count = width as usize * height as usize;
+    let remaining = data.len().saturating_sub(pos);
+    // Run length encoded data grows the pixels as packets are read, rather than reserving what
+    // the header claims up front, so a corrupt header cannot exhaust memory.
+    let mut pixels = Vec::new();
+    if rle {
+        while pixels.len() < count {
+            let packet = match data.get(pos) {
+                Some(&p) => p,
+                None => return malformed("TGA run length data is truncated"),
+            };
+            pos += 1;
+            let run = (packet & 0x7f) as usize + 1;
+            if packet & 0x80 != 0 {
+                if data.len() < pos + pixel_size {
+                    return malformed("TGA run length data is truncated");
+                }
+                let c = convert(&data[pos..pos + pixel_size])?;
+                pos += pixel_size;
+                for _ in 0..run {
+                    pixels.push(c);
+                }
+            } else {
+                if data.len() < pos + run * pixel_size {
+                    return malformed("TGA run length data is truncated");
+                }
+                for p in data[pos..pos + run * pixel_size].chunks(pixel_size) {
+                    pixels.push(convert(p)?);
+                }
+                pos += run * pixel_size;
+            }
+        }
+        pixels.truncate(count);
+    } else {
+        if count * pixel_size > remaining {
+            return malformed("TGA image data is truncated");
+        }
+        pixels.reserve(count);
+        for p in data[pos..pos + count * pixel_size].chunks(pixel_size) {
+            pixels.push(convert(p)?);
+        }
+    }
+
+    let mut image = Image::from_pixels(width, height, pixels);
+    if descriptor & 0x10 != 0 {
+        for y in 0..height as usize {
+            let start = y * width as usize;
+            image.pixels_mut()[start..start + width as usize].reverse();
+        }
+    }
+    if descriptor & 0x20 != 0 {
+        image.flip_vertical();
+    }
+    Ok(image)
+}
+
+fn bytes_per_pixel(depth: u8) -> Result<usize, ImageError> {
+    match depth {
+        15 | 16 => Ok(2),
+        24 => Ok(3),
+        32 => Ok(4),
+        d => unsupported(format!("{} bit TGA true color", d)),
+    }
+}
+
+fn true_color(p: &[u8], alpha_bits: u8) -> Color {
+    match p.len() {
+        2 => {
+            let v = read_u16(p);
+            let c = |shift: u16| (((v >> shift) & 0x1f) as u32 * 255 / 31) as u8;
+            let alpha = if alpha_bits == 1 && v & 0x8000 == 0 {
+                0
+            } else {
+                255
+            };
+            Color(c(10), c(5), c(0), alpha)
+        }
+        3 => Color(p[2], p[1], p[0], 255),
+        _ => {
+            let alpha = if alpha_bits == 0 { 255 } else { p[3] };
+            Color(p[2], p[1], p[0], alpha)
+        }
+    }
+}
+
+fn read_u16(data: &[u8]) -> u16 {
+    data[0] as u16 | (data[1] as u16) << 8
+}
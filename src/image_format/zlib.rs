@@ -0,0 +1,475 @@
+// A small zlib / DEFLATE implementation, just enough to read and write PNG image data without
+// pulling in an external compression library.
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for chunk in data.chunks(5552) {
+        for &d in chunk {
+            a += d as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+/// Compresses `data` into a zlib stream, using a single DEFLATE block with the fixed Huffman
+/// codes and greedy LZ77 matching.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+    // CMF (deflate, 32K window) and FLG (no dictionary, check bits)
+    writer.out.push(0x78);
+    writer.out.push(0x01);
+
+    // BFINAL = 1, BTYPE = 01 (fixed Huffman)
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+
+    let hash = |i: usize| -> usize {
+        let v = (data[i] as usize) << 16 | (data[i + 1] as usize) << 8 | data[i + 2] as usize;
+        (v.wrapping_mul(2654435761) >> 8) & ((1 << HASH_BITS) - 1)
+    };
+
+    let insert = |head: &mut Vec<usize>, prev: &mut Vec<usize>, i: usize| {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(i);
+            prev[i % WINDOW_SIZE] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+
+        if i + MIN_MATCH <= data.len() {
+            let max_len = (data.len() - i).min(MAX_MATCH);
+            let mut candidate = head[hash(i)];
+            let mut chain = 0;
+            while candidate != usize::MAX && i - candidate < WINDOW_SIZE &&
+                chain < MAX_CHAIN
+            {
+                let mut len = 0;
+                while len < max_len && data[candidate + len] == data[i + len] {
+                    len += 1;
+                }
+                if len > best_len {
+                    best_len = len;
+                    best_dist = i - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW_SIZE];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            writer.write_length(best_len);
+            writer.write_distance(best_dist);
+            for j in i..i + best_len {
+                insert(&mut head, &mut prev, j);
+            }
+            i += best_len;
+        } else {
+            writer.write_literal(data[i] as u16);
+            insert(&mut head, &mut prev, i);
+            i += 1;
+        }
+    }
+
+    writer.write_literal(256);
+    writer.flush();
+
+    let checksum = adler32(data);
+    writer.out.extend_from_slice(&[
+        (checksum >> 24) as u8,
+        (checksum >> 16) as u8,
+        (checksum >> 8) as u8,
+        checksum as u8,
+    ]);
+    writer.out
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter {
+            out: Vec::new(),
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn write_bits(&mut self, bits: u32, count: u32) {
+        self.bit_buffer |= bits << self.bit_count;
+        self.bit_count += count;
+        while self.bit_count >= 8 {
+            self.out.push(self.bit_buffer as u8);
+            self.bit_buffer >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    // Huffman codes are packed starting from their most significant bit.
+    fn write_code(&mut self, code: u32, len: u32) {
+        let mut reversed = 0;
+        for b in 0..len {
+            reversed |= ((code >> b) & 1) << (len - 1 - b);
+        }
+        self.write_bits(reversed, len);
+    }
+
+    fn write_literal(&mut self, lit: u16) {
+        let lit = lit as u32;
+        match lit {
+            0..=143 => self.write_code(0x30 + lit, 8),
+            144..=255 => self.write_code(0x190 + lit - 144, 9),
+            256..=279 => self.write_code(lit - 256, 7),
+            _ => self.write_code(0xc0 + lit - 280, 8),
+        }
+    }
+
+    fn write_length(&mut self, len: usize) {
+        let code = LENGTH_BASE.iter().rposition(|&b| b as usize <= len).unwrap();
+        self.write_literal(257 + code as u16);
+        let extra = LENGTH_EXTRA[code] as u32;
+        if extra > 0 {
+            self.write_bits((len - LENGTH_BASE[code] as usize) as u32, extra);
+        }
+    }
+
+    fn write_distance(&mut self, dist: usize) {
+        let code = DIST_BASE.iter().rposition(|&b| b as usize <= dist).unwrap();
+        self.write_code(code as u32, 5);
+        let extra = DIST_EXTRA[code] as u32;
+        if extra > 0 {
+            self.write_bits((dist - DIST_BASE[code] as usize) as u32, extra);
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.bit_count > 0 {
+            self.out.push(self.bit_buffer as u8);
+            self.bit_buffer = 0;
+            self.bit_count = 0;
+        }
+    }
+}
+
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+const MAX_BITS: usize = 15;
+
+/// Decompresses a complete zlib stream, verifying its header and Adler-32 checksum.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if data.len() < 6 {
+        return Err("zlib stream is truncated");
+    }
+    let cmf = data[0];
+    let flg = data[1];
+    if cmf & 0x0f != 8 {
+        return Err("zlib stream does not use deflate compression");
+    }
+    if !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err("zlib header checksum mismatch");
+    }
+    if flg & 0x20 != 0 {
+        return Err("zlib preset dictionaries are not supported");
+    }
+
+    let mut reader = BitReader::new(&data[2..]);
+    let out = inflate(&mut reader)?;
+
+    let trailer = reader.byte_position();
+    let rest = &data[2 + trailer..];
+    if rest.len() < 4 {
+        return Err("zlib stream is missing its checksum");
+    }
+    let expected =
+        (rest[0] as u32) << 24 | (rest[1] as u32) << 16 | (rest[2] as u32) << 8 | rest[3] as u32;
+    if adler32(&out) != expected {
+        return Err("zlib checksum mismatch");
+    }
+
+    Ok(out)
+}
+
+fn inflate(reader: &mut BitReader) -> Result<Vec<u8>, &'static str> {
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => inflate_stored(reader, &mut out)?,
+            1 => {
+                let (lit, dist) = fixed_tables();
+                inflate_codes(reader, &mut out, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(reader)?;
+                inflate_codes(reader, &mut out, &lit, &dist)?;
+            }
+            _ => return Err("invalid deflate block type"),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn inflate_stored(reader: &mut BitReader, out: &mut Vec<u8>) -> Result<(), &'static str> {
+    reader.align_to_byte();
+    let len = reader.bits(16)?;
+    let nlen = reader.bits(16)?;
+    if len != !nlen & 0xffff {
+        return Err("stored deflate block length mismatch");
+    }
+    for _ in 0..len {
+        out.push(reader.bits(8)? as u8);
+    }
+    Ok(())
+}
+
+fn inflate_codes(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> Result<(), &'static str> {
+    loop {
+        let symbol = reader.decode(lit)?;
+        if symbol < 256 {
+            out.push(symbol as u8);
+        } else if symbol == 256 {
+            return Ok(());
+        } else {
+            let symbol = symbol as usize - 257;
+            if symbol >= LENGTH_BASE.len() {
+                return Err("invalid deflate length code");
+            }
+            let len = LENGTH_BASE[symbol] as usize +
+                reader.bits(LENGTH_EXTRA[symbol] as u32)? as usize;
+
+            let symbol = reader.decode(dist)? as usize;
+            if symbol >= DIST_BASE.len() {
+                return Err("invalid deflate distance code");
+            }
+            let distance = DIST_BASE[symbol] as usize +
+                reader.bits(DIST_EXTRA[symbol] as u32)? as usize;
+            if distance > out.len() {
+                return Err("deflate distance is too far back");
+            }
+
+            let start = out.len() - distance;
+            for i in 0..len {
+                let b = out[start + i];
+                out.push(b);
+            }
+        }
+    }
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    for (i, l) in lengths.iter_mut().enumerate() {
+        *l = match i {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    // The fixed tables are always complete, so construction cannot fail.
+    (
+        Huffman::new(&lengths).unwrap(),
+        Huffman::new(&[5; 30]).unwrap(),
+    )
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), &'static str> {
+    let nlen = reader.bits(5)? as usize + 257;
+    let ndist = reader.bits(5)? as usize + 1;
+    let ncode = reader.bits(4)? as usize + 4;
+    if nlen > 286 || ndist > 30 {
+        return Err("too many deflate length or distance codes");
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for &i in CODE_LENGTH_ORDER[..ncode].iter() {
+        code_lengths[i] = reader.bits(3)? as u8;
+    }
+    let code_table = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; nlen + ndist];
+    let mut i = 0;
+    while i < nlen + ndist {
+        let symbol = reader.decode(&code_table)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                if i == 0 {
+                    return Err("deflate repeat code with no previous length");
+                }
+                (lengths[i - 1], 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+        if i + repeat > nlen + ndist {
+            return Err("too many deflate code lengths");
+        }
+        for l in &mut lengths[i..i + repeat] {
+            *l = value;
+        }
+        i += repeat;
+    }
+
+    if lengths[256] == 0 {
+        return Err("deflate block has no end of block code");
+    }
+
+    Ok((
+        Huffman::new(&lengths[..nlen])?,
+        Huffman::new(&lengths[nlen..])?,
+    ))
+}
+
+// Canonical Huffman decoding table, stored as the number of codes of each length along with the
+// symbols ordered by code.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman, &'static str> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &l in lengths {
+            counts[l as usize] += 1;
+        }
+
+        let mut left: i32 = 1;
+        for &count in counts[1..].iter() {
+            left <<= 1;
+            left -= count as i32;
+            if left < 0 {
+                return Err("over-subscribed deflate Huffman code");
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..MAX_BITS + 1 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &l) in lengths.iter().enumerate() {
+            if l != 0 {
+                symbols[offsets[l as usize] as usize] = symbol as u16;
+                offsets[l as usize] += 1;
+            }
+        }
+
+        Ok(Huffman { counts, symbols })
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            data,
+            position: 0,
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn bits(&mut self, count: u32) -> Result<u32, &'static str> {
+        while self.bit_count < count {
+            let byte = *self.data.get(self.position).ok_or(
+                "deflate stream is truncated",
+            )?;
+            self.position += 1;
+            self.bit_buffer |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buffer & ((1u64 << count) - 1) as u32;
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+    }
+
+    // Position of the first byte that has not been consumed, after discarding any partial byte.
+    fn byte_position(&self) -> usize {
+        self.position - (self.bit_count / 8) as usize
+    }
+
+    fn decode(&mut self, table: &Huffman) -> Result<u16, &'static str> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for len in 1..MAX_BITS + 1 {
+            code |= self.bits(1)? as i32;
+            let count = table.counts[len] as i32;
+            if code - first < count {
+                return Ok(table.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err("invalid deflate Huffman code")
+    }
+}
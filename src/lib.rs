@@ -1,10 +1,34 @@
 extern crate num;
 
+#[macro_use]
+mod vector_macros;
+
 pub mod vec2;
 pub mod vec3;
 pub mod vec4;
+pub mod mat2;
+pub mod mat3;
 pub mod mat4;
+pub mod quaternion;
 pub mod bound_rect;
+pub mod plane;
+pub mod ray;
+pub mod sphere;
+pub mod aabb;
+pub mod frustum;
 pub mod color;
+pub mod image;
+pub mod image_format;
+pub mod texture;
+pub mod shadow;
+pub mod lighting;
+pub mod pbr;
+pub mod tone_mapping;
 pub mod renderer;
+pub mod mesh;
+pub mod mesh_format;
+pub mod scene;
+pub mod scene_format;
+pub mod camera_control;
+pub mod clock;
 pub mod application;
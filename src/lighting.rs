@@ -0,0 +1,208 @@
+use std::rc::Rc;
+
+use vec3::Vector3;
+use shadow::ShadowMap;
+
+/// How light falls off with distance, as `1 / (constant + linear * d + quadratic * d²)`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Attenuation {
+    /// No falloff at all.
+    pub fn none() -> Attenuation {
+        Attenuation {
+            constant: 1.0,
+            linear: 0.0,
+            quadratic: 0.0,
+        }
+    }
+
+    /// Physically based inverse square falloff.
+    pub fn inverse_square() -> Attenuation {
+        Attenuation {
+            constant: 0.0,
+            linear: 0.0,
+            quadratic: 1.0,
+        }
+    }
+
+    pub fn factor(&self, distance: f32) -> f32 {
+        let d = self.constant + self.linear * distance + self.quadratic * distance * distance;
+        if d > 0.0 { 1.0 / d } else { 1.0 }
+    }
+}
+
+impl Default for Attenuation {
+    fn default() -> Attenuation {
+        Attenuation::none()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LightKind {
+    /// Shines along the negative z axis of its node.
+    Directional,
+    /// Shines in every direction from the origin of its node.
+    Point { attenuation: Attenuation },
+    /// Shines from the origin of its node in a cone around its negative z axis.  The angles are
+    /// measured from the axis in radians; the light fades out between `inner_angle` and
+    /// `outer_angle`.
+    Spot {
+        attenuation: Attenuation,
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: Vector3<f32>,
+    pub intensity: f32,
+}
+
+/// A light placed in the space that shading happens in, which is view space for the renderer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacedLight {
+    pub light: Light,
+    pub position: Vector3<f32>,
+    /// Normalized direction the light shines in.
+    pub direction: Vector3<f32>,
+    /// Depth maps rendered from the light, in the same space.  Without one the light reaches
+    /// everything.
+    pub shadow: Option<Rc<ShadowMap>>,
+}
+
+impl PlacedLight {
+    /// The normalized direction from `point` towards the light, and the light's color and
+    /// intensity arriving there.
+    pub fn incoming(&self, point: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+        let radiance = self.light.color * self.light.intensity;
+        let (attenuation, inner, outer) = match self.light.kind {
+            LightKind::Directional => return (-self.direction, radiance),
+            LightKind::Point { attenuation } => (attenuation, None, None),
+            LightKind::Spot {
+                attenuation,
+                inner_angle,
+                outer_angle,
+            } => (attenuation, Some(inner_angle), Some(outer_angle)),
+        };
+
+        let to_light = self.position - point;
+        let distance = to_light.magnitude();
+        if distance == 0.0 {
+            return (Vector3::splat(0.0), Vector3::splat(0.0));
+        }
+        let l = to_light / distance;
+        let mut factor = attenuation.factor(distance);
+        if let (Some(inner), Some(outer)) = (inner, outer) {
+            let cos = (-l).dot(self.direction);
+            factor *= smoothstep(outer.cos(), inner.cos(), cos);
+        }
+        (l, radiance * factor)
+    }
+
+    /// The fraction of the light that is not blocked on its way to `point`, a surface with the
+    /// given normal.
+    pub fn visibility(&self, point: Vector3<f32>, normal: Vector3<f32>) -> f32 {
+        let shadow = match self.shadow {
+            Some(ref shadow) => shadow,
+            None => return 1.0,
+        };
+        let to_light = match self.light.kind {
+            LightKind::Directional => -self.direction,
+            LightKind::Point { .. } | LightKind::Spot { .. } => {
+                (self.position - point).normalize()
+            }
+        };
+        shadow.visibility(point, normal, to_light)
+    }
+}
+
+/// The reflectance of a surface.  Ambient and diffuse colors are multiplied by the vertex color.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Material {
+    pub ambient: Vector3<f32>,
+    pub diffuse: Vector3<f32>,
+    pub specular: Vector3<f32>,
+    pub shininess: f32,
+}
+
+impl Default for Material {
+    fn default() -> Material {
+        Material {
+            ambient: Vector3::splat(1.0),
+            diffuse: Vector3::splat(1.0),
+            specular: Vector3::splat(0.5),
+            shininess: 32.0,
+        }
+    }
+}
+
+/// How the renderer colors fragments.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShadingMode {
+    /// Interpolated vertex colors, ignoring lights.
+    Unlit,
+    /// Blinn-Phong lighting evaluated at the vertices and interpolated.
+    Gouraud,
+    /// Phong lighting evaluated for every pixel.
+    Phong,
+    /// Blinn-Phong lighting evaluated for every pixel.
+    BlinnPhong,
+    /// The metallic-roughness model from the `pbr` module, evaluated for every pixel.
+    Pbr,
+}
+
+/// Lights a point with the Phong reflection model, or with Blinn's halfway vector variant if
+/// `blinn` is set.  `position` and `normal` are in the same space as the lights, with the viewer
+/// at the origin; `normal` must be normalized.
+pub fn phong(
+    material: &Material,
+    lights: &[PlacedLight],
+    ambient_light: Vector3<f32>,
+    color: Vector3<f32>,
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    blinn: bool,
+) -> Vector3<f32> {
+    let view = if position.magnitude_squared() > 0.0 {
+        -position.normalize()
+    } else {
+        normal
+    };
+
+    let mut result = ambient_light * material.ambient * color;
+    for light in lights {
+        let (l, radiance) = light.incoming(position);
+        let diffuse = normal.dot(l);
+        if diffuse <= 0.0 {
+            continue;
+        }
+        let radiance = radiance * light.visibility(position, normal);
+        let specular = if blinn {
+            let half = l + view;
+            if half.magnitude_squared() > 0.0 {
+                normal.dot(half.normalize()).max(0.0).powf(material.shininess)
+            } else {
+                0.0
+            }
+        } else {
+            (-l).reflect(normal).dot(view).max(0.0).powf(material.shininess)
+        };
+        result += radiance * (material.diffuse * color * diffuse + material.specular * specular);
+    }
+    result
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    if edge0 >= edge1 {
+        return if x >= edge1 { 1.0 } else { 0.0 };
+    }
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
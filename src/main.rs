@@ -1,23 +1,85 @@
 extern crate sdl2;
 extern crate rrasterizer;
 
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::rect::Rect;
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::video::FullscreenType;
+use sdl2::keyboard::{Keycode, Scancode};
 
 use rrasterizer::application::Application;
+use rrasterizer::image_format;
+use rrasterizer::mesh_format;
+use rrasterizer::scene_format;
+use rrasterizer::vec2::Vector2;
+use rrasterizer::vec3::Vector3;
+use rrasterizer::camera_control::{CameraController, CameraMode};
+use rrasterizer::clock::RealTimeClock;
 
 const WINDOW_WIDTH: u32 = 800;
 const WINDOW_HEIGHT: u32 = 600;
+/// Holding shift multiplies the travel speed by this.
+const FAST_TRAVEL: f32 = 4.0;
+
+// Movement keys and their directions in camera space.
+const TRAVEL_KEYS: [(Scancode, [f32; 3]); 6] = [
+    (Scancode::W, [0.0, 0.0, -1.0]),
+    (Scancode::S, [0.0, 0.0, 1.0]),
+    (Scancode::A, [-1.0, 0.0, 0.0]),
+    (Scancode::D, [1.0, 0.0, 0.0]),
+    (Scancode::Q, [0.0, -1.0, 0.0]),
+    (Scancode::E, [0.0, 1.0, 0.0]),
+];
 
 pub fn main() {
+    // Scene files set their own size, anything else is loaded as a mesh into the default scene.
+    let mut application = match env::args().nth(1) {
+        Some(ref path) if path.ends_with(".toml") => {
+            match scene_format::load_scene(path).and_then(|s| s.instantiate()) {
+                Ok(scene) => Application::with_scene(scene),
+                Err(err) => {
+                    eprintln!("could not load {}: {}", path, err);
+                    process::exit(1);
+                }
+            }
+        }
+        Some(path) => {
+            let mut application = Application::new(WINDOW_WIDTH, WINDOW_HEIGHT);
+            match mesh_format::load_triangles(&path) {
+                Ok(triangles) => application.set_triangles(triangles),
+                Err(err) => {
+                    eprintln!("could not load {}: {}", path, err);
+                    process::exit(1);
+                }
+            }
+            application
+        }
+        None => Application::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+    };
+    let (mut width, mut height) = application.dimensions();
+    application.set_clock(Box::new(RealTimeClock::new()));
+
+    // Orbit around the middle of the scene, or a point in front of the camera if it is empty.
+    let camera = application.camera_transform();
+    let distance = application
+        .scene()
+        .bounds()
+        .map(|b| (b.center() - camera.transform(Vector3::splat(0.0))).magnitude())
+        .filter(|&d| d > 0.0)
+        .unwrap_or(5.0);
+    let mut controller = CameraController::new(&camera, distance);
+
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
 
     let window = video_subsystem
-        .window("rust-sdl2 demo: Video", WINDOW_WIDTH, WINDOW_HEIGHT)
+        .window("rrasterizer: orbit", width, height)
         .position_centered()
+        .resizable()
         .opengl()
         .build()
         .unwrap();
@@ -26,30 +88,103 @@ pub fn main() {
     let texture_creator = canvas.texture_creator();
 
     let mut texture = texture_creator
-        .create_texture_streaming(PixelFormatEnum::RGB24, WINDOW_WIDTH, WINDOW_HEIGHT)
+        .create_texture_streaming(PixelFormatEnum::RGB24, width, height)
         .unwrap();
 
     let mut event_pump = sdl_context.event_pump().unwrap();
 
-    let mut application = Application::new(WINDOW_WIDTH, WINDOW_HEIGHT);
-
     'running: loop {
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit { .. } |
                 Event::KeyDown { keycode: Some(Keycode::Escape), .. } => break 'running,
+                Event::KeyDown { keycode: Some(Keycode::Tab), .. } => {
+                    controller.toggle_mode();
+                    let title = match controller.mode() {
+                        CameraMode::Orbit => "rrasterizer: orbit",
+                        CameraMode::Fly => "rrasterizer: fly",
+                    };
+                    canvas.window_mut().set_title(title).unwrap();
+                }
+                Event::KeyDown { keycode: Some(Keycode::R), .. } => controller.reset(),
+                Event::KeyDown { keycode: Some(Keycode::Space), .. } => {
+                    let paused = application.is_paused();
+                    application.set_paused(!paused);
+                }
+                // Left drags rotate, right or middle drags pan.
+                Event::MouseMotion {
+                    mousestate,
+                    x,
+                    y,
+                    xrel,
+                    yrel,
+                    ..
+                } => {
+                    let to = to_ndc(x, y, width, height);
+                    let from = to_ndc(x - xrel, y - yrel, width, height);
+                    if mousestate.right() || mousestate.middle() {
+                        controller.pan(to - from);
+                    } else if mousestate.left() {
+                        controller.drag(from, to);
+                    }
+                }
+                Event::MouseWheel { y, .. } => controller.zoom(y as f32),
+                // Minimized windows report a size of zero, which is not worth rendering.
+                Event::Window { win_event: WindowEvent::SizeChanged(w, h), .. }
+                    if w > 0 && h > 0 => {
+                    width = w as u32;
+                    height = h as u32;
+                    application.resize(width, height);
+                    texture = texture_creator
+                        .create_texture_streaming(PixelFormatEnum::RGB24, width, height)
+                        .unwrap();
+                }
+                Event::KeyDown { keycode: Some(Keycode::F11), .. } => {
+                    let window = canvas.window_mut();
+                    let fullscreen = match window.fullscreen_state() {
+                        FullscreenType::Off => FullscreenType::Desktop,
+                        _ => FullscreenType::Off,
+                    };
+                    if let Err(err) = window.set_fullscreen(fullscreen) {
+                        eprintln!("could not toggle fullscreen: {}", err);
+                    }
+                }
+                Event::KeyDown { keycode: Some(Keycode::F12), .. } => {
+                    let path = screenshot_path();
+                    match image_format::save_image(&path, application.framebuffer()) {
+                        Ok(()) => println!("saved screenshot to {}", path.display()),
+                        Err(err) => eprintln!("could not save screenshot: {}", err),
+                    }
+                }
                 _ => {}
             }
         }
 
+        let keys = event_pump.keyboard_state();
+        let mut direction = Vector3::splat(0.0);
+        for &(key, d) in &TRAVEL_KEYS {
+            if keys.is_scancode_pressed(key) {
+                direction += Vector3::from(d);
+            }
+        }
+        let fast = keys.is_scancode_pressed(Scancode::LShift) ||
+            keys.is_scancode_pressed(Scancode::RShift);
+        // Moves by the length of the previous frame, since this one has not been timed yet.
+        let seconds = application.frame_time() as f32;
+        controller.travel(
+            direction,
+            if fast { seconds * FAST_TRAVEL } else { seconds },
+        );
+
+        application.set_camera_transform(controller.transform());
         application.render();
 
         texture
             .with_lock(None, |buffer: &mut [u8], pitch: usize| for y in 0..
-                WINDOW_HEIGHT as usize
+                height as usize
             {
-                for x in 0..WINDOW_WIDTH as usize {
-                    let (r, g, b) = application.get_pixel(x as u32, WINDOW_HEIGHT - y as u32 - 1);
+                for x in 0..width as usize {
+                    let (r, g, b) = application.get_pixel(x as u32, height - y as u32 - 1);
 
                     let offset = y * pitch + x * 3;
                     buffer[offset] = r;
@@ -64,9 +199,28 @@ pub fn main() {
             .copy(
                 &texture,
                 None,
-                Some(Rect::new(0, 0, WINDOW_WIDTH, WINDOW_HEIGHT)),
+                Some(Rect::new(0, 0, width, height)),
             )
             .unwrap();
         canvas.present();
     }
 }
+
+// Converts a position in window pixels to normalized device coordinates.
+fn to_ndc(x: i32, y: i32, width: u32, height: u32) -> Vector2<f32> {
+    Vector2::new(
+        2.0 * x as f32 / width as f32 - 1.0,
+        1.0 - 2.0 * y as f32 / height as f32,
+    )
+}
+
+fn screenshot_path() -> PathBuf {
+    let mut i = 0;
+    loop {
+        let path = PathBuf::from(format!("screenshot-{:03}.png", i));
+        if !path.exists() {
+            return path;
+        }
+        i += 1;
+    }
+}
@@ -0,0 +1,153 @@
+use std::ops::{Add, Mul, MulAssign};
+
+use num::Float;
+
+use vec2::Vector2;
+use mat4::Matrix4;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Matrix2<T> {
+    e11: T,
+    e12: T,
+
+    e21: T,
+    e22: T,
+}
+
+impl<T> Matrix2<T> {
+    pub fn new(e11: T, e12: T, e21: T, e22: T) -> Matrix2<T> {
+        Matrix2 { e11, e12, e21, e22 }
+    }
+
+    pub fn transpose(self) -> Matrix2<T> {
+        Matrix2 {
+            e11: self.e11,
+            e12: self.e21,
+            e21: self.e12,
+            e22: self.e22,
+        }
+    }
+}
+
+impl<T> Matrix2<T>
+where
+    T: Float,
+{
+    pub fn identity() -> Matrix2<T> {
+        Matrix2 {
+            e11: T::one(),
+            e12: T::zero(),
+            e21: T::zero(),
+            e22: T::one(),
+        }
+    }
+
+    /// A counterclockwise rotation by `angle` radians.
+    pub fn rotation(angle: T) -> Matrix2<T> {
+        let (sin, cos) = angle.sin_cos();
+        Matrix2 {
+            e11: cos,
+            e12: -sin,
+            e21: sin,
+            e22: cos,
+        }
+    }
+
+    pub fn scaling(scale: Vector2<T>) -> Matrix2<T> {
+        Matrix2 {
+            e11: scale.x,
+            e12: T::zero(),
+            e21: T::zero(),
+            e22: scale.y,
+        }
+    }
+
+    pub fn determinant(&self) -> T {
+        self.e11 * self.e22 - self.e12 * self.e21
+    }
+
+    /// Returns the inverse, or `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Matrix2<T>> {
+        let det = self.determinant();
+        if det == T::zero() || !det.is_finite() {
+            return None;
+        }
+        let inv = T::one() / det;
+
+        Some(Matrix2 {
+            e11: self.e22 * inv,
+            e12: -self.e12 * inv,
+            e21: -self.e21 * inv,
+            e22: self.e11 * inv,
+        })
+    }
+}
+
+/// Embeds the matrix in the upper-left block of an identity matrix.
+impl<T> From<Matrix2<T>> for Matrix4<T>
+where
+    T: Float,
+{
+    fn from(m: Matrix2<T>) -> Matrix4<T> {
+        let zero = T::zero();
+        let one = T::one();
+        Matrix4::new(
+            m.e11,
+            m.e12,
+            zero,
+            zero,
+            m.e21,
+            m.e22,
+            zero,
+            zero,
+            zero,
+            zero,
+            one,
+            zero,
+            zero,
+            zero,
+            zero,
+            one,
+        )
+    }
+}
+
+impl<T> Mul<Matrix2<T>> for Matrix2<T>
+where
+    T: Copy + Add<T, Output = T> + Mul<T, Output = T>,
+{
+    type Output = Matrix2<T>;
+
+    fn mul(self, rhs: Matrix2<T>) -> Matrix2<T> {
+        Matrix2 {
+            e11: self.e11 * rhs.e11 + self.e12 * rhs.e21,
+            e12: self.e11 * rhs.e12 + self.e12 * rhs.e22,
+
+            e21: self.e21 * rhs.e11 + self.e22 * rhs.e21,
+            e22: self.e21 * rhs.e12 + self.e22 * rhs.e22,
+        }
+    }
+}
+
+impl<T> MulAssign<Matrix2<T>> for Matrix2<T>
+where
+    T: Copy + Add<T, Output = T> + Mul<T, Output = T>,
+{
+    fn mul_assign(&mut self, rhs: Matrix2<T>) {
+        *self = *self * rhs;
+    }
+}
+
+impl<T> Mul<Vector2<T>> for Matrix2<T>
+where
+    T: Copy + Add<T, Output = T> + Mul<T, Output = T>,
+{
+    type Output = Vector2<T>;
+
+    fn mul(self, rhs: Vector2<T>) -> Vector2<T> {
+        Vector2 {
+            x: self.e11 * rhs.x + self.e12 * rhs.y,
+            y: self.e21 * rhs.x + self.e22 * rhs.y,
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::ops::{Add, Mul, MulAssign};
+
+use num::Float;
+
+use vec2::Vector2;
+use vec3::Vector3;
+use mat2::Matrix2;
+use mat4::Matrix4;
+
+/// A 3x3 matrix.  Besides linear maps in 3D, it doubles as a 2D transformation in homogeneous
+/// coordinates, the same way `Matrix4` is used for 3D, which is what `translation`, `rotation`,
+/// `scaling` and `transform` construct and apply.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Matrix3<T> {
+    e11: T,
+    e12: T,
+    e13: T,
+
+    e21: T,
+    e22: T,
+    e23: T,
+
+    e31: T,
+    e32: T,
+    e33: T,
+}
+
+impl<T> Matrix3<T> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        e11: T,
+        e12: T,
+        e13: T,
+        e21: T,
+        e22: T,
+        e23: T,
+        e31: T,
+        e32: T,
+        e33: T,
+    ) -> Matrix3<T> {
+        Matrix3 {
+            e11,
+            e12,
+            e13,
+            e21,
+            e22,
+            e23,
+            e31,
+            e32,
+            e33,
+        }
+    }
+
+    pub fn transpose(self) -> Matrix3<T> {
+        Matrix3 {
+            e11: self.e11,
+            e12: self.e21,
+            e13: self.e31,
+            e21: self.e12,
+            e22: self.e22,
+            e23: self.e32,
+            e31: self.e13,
+            e32: self.e23,
+            e33: self.e33,
+        }
+    }
+}
+
+impl<T> Matrix3<T>
+where
+    T: Float,
+{
+    pub fn identity() -> Matrix3<T> {
+        Matrix3 {
+            e11: T::one(),
+            e12: T::zero(),
+            e13: T::zero(),
+            e21: T::zero(),
+            e22: T::one(),
+            e23: T::zero(),
+            e31: T::zero(),
+            e32: T::zero(),
+            e33: T::one(),
+        }
+    }
+
+    /// A 2D counterclockwise rotation by `angle` radians.
+    pub fn rotation(angle: T) -> Matrix3<T> {
+        Matrix3::from(Matrix2::rotation(angle))
+    }
+
+    pub fn scaling(scale: Vector2<T>) -> Matrix3<T> {
+        Matrix3::from(Matrix2::scaling(scale))
+    }
+
+    pub fn translation(pos: Vector2<T>) -> Matrix3<T> {
+        Matrix3 {
+            e13: pos.x,
+            e23: pos.y,
+            ..Matrix3::identity()
+        }
+    }
+
+    pub fn determinant(&self) -> T {
+        self.e11 * (self.e22 * self.e33 - self.e23 * self.e32) +
+            self.e12 * (self.e23 * self.e31 - self.e21 * self.e33) +
+            self.e13 * (self.e21 * self.e32 - self.e22 * self.e31)
+    }
+
+    /// Returns the inverse, or `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Matrix3<T>> {
+        let c11 = self.e22 * self.e33 - self.e23 * self.e32;
+        let c12 = self.e23 * self.e31 - self.e21 * self.e33;
+        let c13 = self.e21 * self.e32 - self.e22 * self.e31;
+
+        let det = self.e11 * c11 + self.e12 * c12 + self.e13 * c13;
+        if det == T::zero() || !det.is_finite() {
+            return None;
+        }
+        let inv = T::one() / det;
+
+        // The transposed cofactor matrix over the determinant.
+        Some(Matrix3 {
+            e11: c11 * inv,
+            e12: (self.e13 * self.e32 - self.e12 * self.e33) * inv,
+            e13: (self.e12 * self.e23 - self.e13 * self.e22) * inv,
+            e21: c12 * inv,
+            e22: (self.e11 * self.e33 - self.e13 * self.e31) * inv,
+            e23: (self.e13 * self.e21 - self.e11 * self.e23) * inv,
+            e31: c13 * inv,
+            e32: (self.e12 * self.e31 - self.e11 * self.e32) * inv,
+            e33: (self.e11 * self.e22 - self.e12 * self.e21) * inv,
+        })
+    }
+
+    /// Transforms a 2D direction, ignoring translation and without a perspective divide.
+    pub fn transform_vector(&self, v: Vector2<T>) -> Vector2<T> {
+        (*self * Vector3::new(v.x, v.y, T::zero())).vec2()
+    }
+
+    /// Transforms a 2D point in homogeneous coordinates.
+    pub fn transform(&self, pos: Vector2<T>) -> Vector2<T> {
+        let v = *self * Vector3::new(pos.x, pos.y, T::one());
+        v.vec2() / v.z
+    }
+}
+
+/// The upper-left 2x2 block.
+impl<T> From<Matrix3<T>> for Matrix2<T> {
+    fn from(m: Matrix3<T>) -> Matrix2<T> {
+        Matrix2::new(m.e11, m.e12, m.e21, m.e22)
+    }
+}
+
+/// Embeds the matrix in the upper-left block of an identity matrix.
+impl<T> From<Matrix2<T>> for Matrix3<T>
+where
+    T: Float,
+{
+    fn from(m: Matrix2<T>) -> Matrix3<T> {
+        let (zero, one) = (T::zero(), T::one());
+        let c1 = m * Vector2::new(one, zero);
+        let c2 = m * Vector2::new(zero, one);
+        Matrix3::new(c1.x, c2.x, zero, c1.y, c2.y, zero, zero, zero, one)
+    }
+}
+
+/// Embeds the matrix in the upper-left block of an identity matrix.
+impl<T> From<Matrix3<T>> for Matrix4<T>
+where
+    T: Float,
+{
+    fn from(m: Matrix3<T>) -> Matrix4<T> {
+        let zero = T::zero();
+        let one = T::one();
+        Matrix4::new(
+            m.e11,
+            m.e12,
+            m.e13,
+            zero,
+            m.e21,
+            m.e22,
+            m.e23,
+            zero,
+            m.e31,
+            m.e32,
+            m.e33,
+            zero,
+            zero,
+            zero,
+            zero,
+            one,
+        )
+    }
+}
+
+impl<T> Mul<Matrix3<T>> for Matrix3<T>
+where
+    T: Copy + Add<T, Output = T> + Mul<T, Output = T>,
+{
+    type Output = Matrix3<T>;
+
+    fn mul(self, rhs: Matrix3<T>) -> Matrix3<T> {
+        Matrix3 {
+            e11: self.e11 * rhs.e11 + self.e12 * rhs.e21 + self.e13 * rhs.e31,
+            e12: self.e11 * rhs.e12 + self.e12 * rhs.e22 + self.e13 * rhs.e32,
+            e13: self.e11 * rhs.e13 + self.e12 * rhs.e23 + self.e13 * rhs.e33,
+
+            e21: self.e21 * rhs.e11 + self.e22 * rhs.e21 + self.e23 * rhs.e31,
+            e22: self.e21 * rhs.e12 + self.e22 * rhs.e22 + self.e23 * rhs.e32,
+            e23: self.e21 * rhs.e13 + self.e22 * rhs.e23 + self.e23 * rhs.e33,
+
+            e31: self.e31 * rhs.e11 + self.e32 * rhs.e21 + self.e33 * rhs.e31,
+            e32: self.e31 * rhs.e12 + self.e32 * rhs.e22 + self.e33 * rhs.e32,
+            e33: self.e31 * rhs.e13 + self.e32 * rhs.e23 + self.e33 * rhs.e33,
+        }
+    }
+}
+
+impl<T> MulAssign<Matrix3<T>> for Matrix3<T>
+where
+    T: Copy + Add<T, Output = T> + Mul<T, Output = T>,
+{
+    fn mul_assign(&mut self, rhs: Matrix3<T>) {
+        *self = *self * rhs;
+    }
+}
+
+impl<T> Mul<Vector3<T>> for Matrix3<T>
+where
+    T: Copy + Add<T, Output = T> + Mul<T, Output = T>,
+{
+    type Output = Vector3<T>;
+
+    fn mul(self, rhs: Vector3<T>) -> Vector3<T> {
+        Vector3 {
+            x: self.e11 * rhs.x + self.e12 * rhs.y + self.e13 * rhs.z,
+            y: self.e21 * rhs.x + self.e22 * rhs.y + self.e23 * rhs.z,
+            z: self.e31 * rhs.x + self.e32 * rhs.y + self.e33 * rhs.z,
+        }
+    }
+}
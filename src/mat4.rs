@@ -1,9 +1,12 @@
-use std::ops::{Add, Mul, MulAssign};
+use std::fmt;
+use std::ops::{Add, Mul, MulAssign, Index, IndexMut};
 
 use num::Float;
 
 use vec3::Vector3;
 use vec4::Vector4;
+use mat2::Matrix2;
+use mat3::Matrix3;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct Matrix4<T> {
@@ -29,6 +32,8 @@ pub struct Matrix4<T> {
 }
 
 impl<T> Matrix4<T> {
+    /// Takes the elements in row-major order, which reads like the matrix when written out.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         e11: T,
         e12: T,
@@ -89,6 +94,85 @@ impl<T> Matrix4<T> {
     }
 }
 
+impl<T> Matrix4<T>
+where
+    T: Copy,
+{
+    pub fn from_rows(rows: [Vector4<T>; 4]) -> Matrix4<T> {
+        let [r1, r2, r3, r4] = rows;
+        Matrix4::new(
+            r1.x,
+            r1.y,
+            r1.z,
+            r1.w,
+            r2.x,
+            r2.y,
+            r2.z,
+            r2.w,
+            r3.x,
+            r3.y,
+            r3.z,
+            r3.w,
+            r4.x,
+            r4.y,
+            r4.z,
+            r4.w,
+        )
+    }
+
+    pub fn from_cols(cols: [Vector4<T>; 4]) -> Matrix4<T> {
+        Matrix4::from_rows(cols).transpose()
+    }
+
+    pub fn from_rows_array(e: [T; 16]) -> Matrix4<T> {
+        Matrix4::new(
+            e[0], e[1], e[2], e[3], e[4], e[5], e[6], e[7], e[8], e[9], e[10], e[11], e[12], e[13],
+            e[14], e[15],
+        )
+    }
+
+    /// The layout used by OpenGL, Vulkan and glTF.
+    pub fn from_cols_array(e: [T; 16]) -> Matrix4<T> {
+        Matrix4::from_rows_array(e).transpose()
+    }
+
+    pub fn to_rows_array(&self) -> [T; 16] {
+        [
+            self.e11,
+            self.e12,
+            self.e13,
+            self.e14,
+            self.e21,
+            self.e22,
+            self.e23,
+            self.e24,
+            self.e31,
+            self.e32,
+            self.e33,
+            self.e34,
+            self.e41,
+            self.e42,
+            self.e43,
+            self.e44,
+        ]
+    }
+
+    /// The layout used by OpenGL, Vulkan and glTF.
+    pub fn to_cols_array(&self) -> [T; 16] {
+        self.transpose().to_rows_array()
+    }
+
+    /// The row at `index`, counting from zero.
+    pub fn row(&self, index: usize) -> Vector4<T> {
+        Vector4::new(self[(index, 0)], self[(index, 1)], self[(index, 2)], self[(index, 3)])
+    }
+
+    /// The column at `index`, counting from zero.
+    pub fn col(&self, index: usize) -> Vector4<T> {
+        Vector4::new(self[(0, index)], self[(1, index)], self[(2, index)], self[(3, index)])
+    }
+}
+
 impl<T> Matrix4<T>
 where
     T: Float,
@@ -247,10 +331,378 @@ where
         }
     }
 
+    /// A right handed view matrix for a camera at `eye` looking towards `target`, mapping `eye` to
+    /// the origin and looking down the negative z axis.
+    pub fn look_at(eye: Vector3<T>, target: Vector3<T>, up: Vector3<T>) -> Matrix4<T> {
+        let f = (target - eye).normalize();
+        let s = f.cross(up).normalize();
+        let u = s.cross(f);
+        Matrix4 {
+            e11: s.x,
+            e12: s.y,
+            e13: s.z,
+            e14: -s.dot(eye),
+            e21: u.x,
+            e22: u.y,
+            e23: u.z,
+            e24: -u.dot(eye),
+            e31: -f.x,
+            e32: -f.y,
+            e33: -f.z,
+            e34: f.dot(eye),
+            e41: T::zero(),
+            e42: T::zero(),
+            e43: T::zero(),
+            e44: T::one(),
+        }
+    }
+
+    /// A perspective projection with a vertical field of view of `fovy` radians, mapping depths
+    /// between `near` and `far` to the range [-1, 1].
+    pub fn perspective_fov(fovy: T, aspect: T, near: T, far: T) -> Matrix4<T> {
+        let two = T::from(2).unwrap();
+        let f = T::one() / (fovy / two).tan();
+        Matrix4 {
+            e33: (far + near) / (near - far),
+            e34: (two * far * near) / (near - far),
+            ..Self::perspective_base(f / aspect, f)
+        }
+    }
+
+    /// Like `perspective_fov` with the far plane at infinity.
+    pub fn perspective_infinite(fovy: T, aspect: T, near: T) -> Matrix4<T> {
+        let two = T::from(2).unwrap();
+        let f = T::one() / (fovy / two).tan();
+        Matrix4 {
+            e33: -T::one(),
+            e34: -two * near,
+            ..Self::perspective_base(f / aspect, f)
+        }
+    }
+
+    /// A perspective projection which maps `near` to a depth of 1 and `far` to a depth of 0.
+    /// Floating point depth buffers are much more precise with reversed depths.
+    pub fn perspective_reverse_z(fovy: T, aspect: T, near: T, far: T) -> Matrix4<T> {
+        let two = T::from(2).unwrap();
+        let f = T::one() / (fovy / two).tan();
+        Matrix4 {
+            e33: near / (far - near),
+            e34: (far * near) / (far - near),
+            ..Self::perspective_base(f / aspect, f)
+        }
+    }
+
+    /// Like `perspective_reverse_z` with the far plane at infinity.
+    pub fn perspective_infinite_reverse_z(fovy: T, aspect: T, near: T) -> Matrix4<T> {
+        let two = T::from(2).unwrap();
+        let f = T::one() / (fovy / two).tan();
+        Matrix4 {
+            e33: T::zero(),
+            e34: near,
+            ..Self::perspective_base(f / aspect, f)
+        }
+    }
+
+    /// An orthographic projection mapping the given box to [-1, 1] on every axis, with `near` and
+    /// `far` measured along the negative z axis.
+    pub fn orthographic(left: T, right: T, bottom: T, top: T, near: T, far: T) -> Matrix4<T> {
+        let two = T::from(2).unwrap();
+        Matrix4 {
+            e11: two / (right - left),
+            e12: T::zero(),
+            e13: T::zero(),
+            e14: -(right + left) / (right - left),
+            e21: T::zero(),
+            e22: two / (top - bottom),
+            e23: T::zero(),
+            e24: -(top + bottom) / (top - bottom),
+            e31: T::zero(),
+            e32: T::zero(),
+            e33: -two / (far - near),
+            e34: -(far + near) / (far - near),
+            e41: T::zero(),
+            e42: T::zero(),
+            e43: T::zero(),
+            e44: T::one(),
+        }
+    }
+
+    pub fn determinant(&self) -> T {
+        let (s0, s1, s2, s3, s4, s5) = self.upper_minors();
+        let (c0, c1, c2, c3, c4, c5) = self.lower_minors();
+        s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0
+    }
+
+    /// Returns the inverse of a general matrix, or `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Matrix4<T>> {
+        let (s0, s1, s2, s3, s4, s5) = self.upper_minors();
+        let (c0, c1, c2, c3, c4, c5) = self.lower_minors();
+
+        let det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+        if det == T::zero() || !det.is_finite() {
+            return None;
+        }
+        let inv = T::one() / det;
+
+        Some(Matrix4 {
+            e11: (self.e22 * c5 - self.e23 * c4 + self.e24 * c3) * inv,
+            e12: (-self.e12 * c5 + self.e13 * c4 - self.e14 * c3) * inv,
+            e13: (self.e42 * s5 - self.e43 * s4 + self.e44 * s3) * inv,
+            e14: (-self.e32 * s5 + self.e33 * s4 - self.e34 * s3) * inv,
+
+            e21: (-self.e21 * c5 + self.e23 * c2 - self.e24 * c1) * inv,
+            e22: (self.e11 * c5 - self.e13 * c2 + self.e14 * c1) * inv,
+            e23: (-self.e41 * s5 + self.e43 * s2 - self.e44 * s1) * inv,
+            e24: (self.e31 * s5 - self.e33 * s2 + self.e34 * s1) * inv,
+
+            e31: (self.e21 * c4 - self.e22 * c2 + self.e24 * c0) * inv,
+            e32: (-self.e11 * c4 + self.e12 * c2 - self.e14 * c0) * inv,
+            e33: (self.e41 * s4 - self.e42 * s2 + self.e44 * s0) * inv,
+            e34: (-self.e31 * s4 + self.e32 * s2 - self.e34 * s0) * inv,
+
+            e41: (-self.e21 * c3 + self.e22 * c1 - self.e23 * c0) * inv,
+            e42: (self.e11 * c3 - self.e12 * c1 + self.e13 * c0) * inv,
+            e43: (-self.e41 * s3 + self.e42 * s1 - self.e43 * s0) * inv,
+            e44: (self.e31 * s3 - self.e32 * s1 + self.e33 * s0) * inv,
+        })
+    }
+
+    /// Inverts a matrix whose bottom row is (0, 0, 0, 1), such as any combination of
+    /// translations, rotations and scalings.  This is cheaper and more precise than `inverse`,
+    /// but gives wrong results for projective matrices.
+    pub fn affine_inverse(&self) -> Option<Matrix4<T>> {
+        let (cofactors, det) = self.upper_cofactors();
+        if det == T::zero() || !det.is_finite() {
+            return None;
+        }
+        let inv = T::one() / det;
+
+        // The inverse of the upper 3x3 is the transposed cofactor matrix over the determinant.
+        let r = cofactors.transpose();
+        let m11 = r.e11 * inv;
+        let m12 = r.e12 * inv;
+        let m13 = r.e13 * inv;
+        let m21 = r.e21 * inv;
+        let m22 = r.e22 * inv;
+        let m23 = r.e23 * inv;
+        let m31 = r.e31 * inv;
+        let m32 = r.e32 * inv;
+        let m33 = r.e33 * inv;
+        let (tx, ty, tz) = (self.e14, self.e24, self.e34);
+
+        Some(Matrix4 {
+            e11: m11,
+            e12: m12,
+            e13: m13,
+            e14: -(m11 * tx + m12 * ty + m13 * tz),
+            e21: m21,
+            e22: m22,
+            e23: m23,
+            e24: -(m21 * tx + m22 * ty + m23 * tz),
+            e31: m31,
+            e32: m32,
+            e33: m33,
+            e34: -(m31 * tx + m32 * ty + m33 * tz),
+            e41: T::zero(),
+            e42: T::zero(),
+            e43: T::zero(),
+            e44: T::one(),
+        })
+    }
+
+    /// The matrix for transforming surface normals: the inverse transpose of the upper 3x3 part,
+    /// with no translation.  Transformed normals need to be renormalized if the matrix contains
+    /// a scaling.  Returns `None` if the upper 3x3 part is singular.
+    pub fn normal_matrix(&self) -> Option<Matrix4<T>> {
+        let (cofactors, det) = self.upper_cofactors();
+        if det == T::zero() || !det.is_finite() {
+            return None;
+        }
+        let inv = T::one() / det;
+
+        Some(Matrix4 {
+            e11: cofactors.e11 * inv,
+            e12: cofactors.e12 * inv,
+            e13: cofactors.e13 * inv,
+            e21: cofactors.e21 * inv,
+            e22: cofactors.e22 * inv,
+            e23: cofactors.e23 * inv,
+            e31: cofactors.e31 * inv,
+            e32: cofactors.e32 * inv,
+            e33: cofactors.e33 * inv,
+            ..Matrix4::identity()
+        })
+    }
+
+    /// Transforms a direction, ignoring translation and without a perspective divide.
+    pub fn transform_vector(&self, v: Vector3<T>) -> Vector3<T> {
+        (*self * Vector4::new(v.x, v.y, v.z, T::zero())).vec3()
+    }
+
     pub fn transform(&self, pos: Vector3<T>) -> Vector3<T> {
         let v = *self * Vector4::new(pos.x, pos.y, pos.z, T::one());
         v.vec3() / v.w
     }
+
+    // The x, y and w rows shared by every perspective projection.
+    fn perspective_base(x_scale: T, y_scale: T) -> Matrix4<T> {
+        Matrix4 {
+            e11: x_scale,
+            e12: T::zero(),
+            e13: T::zero(),
+            e14: T::zero(),
+            e21: T::zero(),
+            e22: y_scale,
+            e23: T::zero(),
+            e24: T::zero(),
+            e31: T::zero(),
+            e32: T::zero(),
+            e33: T::zero(),
+            e34: T::zero(),
+            e41: T::zero(),
+            e42: T::zero(),
+            e43: -T::one(),
+            e44: T::zero(),
+        }
+    }
+
+    // 2x2 determinants of the top two rows.
+    fn upper_minors(&self) -> (T, T, T, T, T, T) {
+        (
+            self.e11 * self.e22 - self.e21 * self.e12,
+            self.e11 * self.e23 - self.e21 * self.e13,
+            self.e11 * self.e24 - self.e21 * self.e14,
+            self.e12 * self.e23 - self.e22 * self.e13,
+            self.e12 * self.e24 - self.e22 * self.e14,
+            self.e13 * self.e24 - self.e23 * self.e14,
+        )
+    }
+
+    // 2x2 determinants of the bottom two rows.
+    fn lower_minors(&self) -> (T, T, T, T, T, T) {
+        (
+            self.e31 * self.e42 - self.e41 * self.e32,
+            self.e31 * self.e43 - self.e41 * self.e33,
+            self.e31 * self.e44 - self.e41 * self.e34,
+            self.e32 * self.e43 - self.e42 * self.e33,
+            self.e32 * self.e44 - self.e42 * self.e34,
+            self.e33 * self.e44 - self.e43 * self.e34,
+        )
+    }
+
+    // The cofactor matrix of the upper 3x3 part, along with its determinant.
+    fn upper_cofactors(&self) -> (Matrix4<T>, T) {
+        let cofactors = Matrix4 {
+            e11: self.e22 * self.e33 - self.e23 * self.e32,
+            e12: self.e23 * self.e31 - self.e21 * self.e33,
+            e13: self.e21 * self.e32 - self.e22 * self.e31,
+            e21: self.e13 * self.e32 - self.e12 * self.e33,
+            e22: self.e11 * self.e33 - self.e13 * self.e31,
+            e23: self.e12 * self.e31 - self.e11 * self.e32,
+            e31: self.e12 * self.e23 - self.e13 * self.e22,
+            e32: self.e13 * self.e21 - self.e11 * self.e23,
+            e33: self.e11 * self.e22 - self.e12 * self.e21,
+            ..Matrix4::identity()
+        };
+        let det = self.e11 * cofactors.e11 + self.e12 * cofactors.e12 + self.e13 * cofactors.e13;
+        (cofactors, det)
+    }
+}
+
+/// Indexed by `(row, column)`, counting from zero.
+impl<T> Index<(usize, usize)> for Matrix4<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        match (row, col) {
+            (0, 0) => &self.e11,
+            (0, 1) => &self.e12,
+            (0, 2) => &self.e13,
+            (0, 3) => &self.e14,
+            (1, 0) => &self.e21,
+            (1, 1) => &self.e22,
+            (1, 2) => &self.e23,
+            (1, 3) => &self.e24,
+            (2, 0) => &self.e31,
+            (2, 1) => &self.e32,
+            (2, 2) => &self.e33,
+            (2, 3) => &self.e34,
+            (3, 0) => &self.e41,
+            (3, 1) => &self.e42,
+            (3, 2) => &self.e43,
+            (3, 3) => &self.e44,
+            _ => panic!("index ({}, {}) is out of range for a Matrix4", row, col),
+        }
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix4<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        match (row, col) {
+            (0, 0) => &mut self.e11,
+            (0, 1) => &mut self.e12,
+            (0, 2) => &mut self.e13,
+            (0, 3) => &mut self.e14,
+            (1, 0) => &mut self.e21,
+            (1, 1) => &mut self.e22,
+            (1, 2) => &mut self.e23,
+            (1, 3) => &mut self.e24,
+            (2, 0) => &mut self.e31,
+            (2, 1) => &mut self.e32,
+            (2, 2) => &mut self.e33,
+            (2, 3) => &mut self.e34,
+            (3, 0) => &mut self.e41,
+            (3, 1) => &mut self.e42,
+            (3, 2) => &mut self.e43,
+            (3, 3) => &mut self.e44,
+            _ => panic!("index ({}, {}) is out of range for a Matrix4", row, col),
+        }
+    }
+}
+
+/// Prints one row per line, with the columns aligned.
+impl<T> fmt::Display for Matrix4<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cells: Vec<String> = (0..16)
+            .map(|i| match f.precision() {
+                Some(precision) => format!("{:.*}", precision, self[(i / 4, i % 4)]),
+                None => format!("{}", self[(i / 4, i % 4)]),
+            })
+            .collect();
+        let width = cells.iter().map(|c| c.len()).max().unwrap_or(0);
+        for (row, cells) in cells.chunks(4).enumerate() {
+            if row > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "[{:>w$} {:>w$} {:>w$} {:>w$}]",
+                cells[0],
+                cells[1],
+                cells[2],
+                cells[3],
+                w = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The upper-left 3x3 block.
+impl<T> From<Matrix4<T>> for Matrix3<T> {
+    fn from(m: Matrix4<T>) -> Matrix3<T> {
+        Matrix3::new(m.e11, m.e12, m.e13, m.e21, m.e22, m.e23, m.e31, m.e32, m.e33)
+    }
+}
+
+/// The upper-left 2x2 block.
+impl<T> From<Matrix4<T>> for Matrix2<T> {
+    fn from(m: Matrix4<T>) -> Matrix2<T> {
+        Matrix2::new(m.e11, m.e12, m.e21, m.e22)
+    }
 }
 
 impl<T> Mul<Matrix4<T>> for Matrix4<T>
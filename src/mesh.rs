@@ -0,0 +1,104 @@
+use vec2::Vector2;
+use vec3::Vector3;
+use vec4::Vector4;
+use renderer::{Vertex, Triangle, Bounds};
+
+/// An indexed triangle list.
+///
+/// `positions` holds one entry per vertex, every other attribute list is either empty or has the
+/// same length as `positions`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Mesh {
+    pub positions: Vec<Vector3<f32>>,
+    pub normals: Vec<Vector3<f32>>,
+    pub tex_coords: Vec<Vector2<f32>>,
+    pub colors: Vec<Vector4<f32>>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        Mesh::default()
+    }
+
+    /// A cube spanning -1 to 1 on every axis, with each vertex colored by its position.
+    pub fn cube() -> Mesh {
+        let mut mesh = Mesh::new();
+        for i in 0..8 {
+            let p = Vector3::new(
+                if i & 1 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { -1.0 } else { 1.0 },
+                if i & 4 == 0 { -1.0 } else { 1.0 },
+            );
+            mesh.positions.push(p);
+            mesh.colors.push(((p + Vector3::splat(1.0)) / 2.0).extend(1.0));
+        }
+        mesh.indices = vec![
+            0, 2, 1, 1, 2, 3, 4, 5, 6, 5, 7, 6, 0, 1, 4, 1, 5, 4, 2, 6, 3, 3, 6, 7, 0, 4, 2, 2, 4,
+            6, 1, 3, 5, 3, 7, 5,
+        ];
+        mesh
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// The bounding volumes of the positions, or `None` for a mesh without vertices.
+    pub fn bounds(&self) -> Option<Bounds> {
+        Bounds::from_points(&self.positions)
+    }
+
+    /// Replaces the normals with smooth, area weighted vertex normals.
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vector3::new(0.0, 0.0, 0.0); self.positions.len()];
+        for t in self.indices.chunks(3) {
+            let (a, b, c) = (t[0] as usize, t[1] as usize, t[2] as usize);
+            let n = (self.positions[b] - self.positions[a])
+                .cross(self.positions[c] - self.positions[a]);
+            normals[a] += n;
+            normals[b] += n;
+            normals[c] += n;
+        }
+        for n in &mut normals {
+            if n.magnitude_squared() > 0.0 {
+                *n = n.normalize();
+            }
+        }
+        self.normals = normals;
+    }
+
+    /// Expands the mesh into triangles that can be passed to `Renderer::render`, using
+    /// `default_color` for vertices when the mesh has no colors.  Without normals the vertices
+    /// get zero normals, which the renderer lights with the face normal.
+    pub fn triangles(&self, default_color: Vector4<f32>) -> Vec<Triangle> {
+        let vertex = |i: u32| {
+            let i = i as usize;
+            Vertex {
+                position: self.positions[i],
+                color: self.colors.get(i).cloned().unwrap_or(default_color),
+                normal: self.normals.get(i).cloned().unwrap_or_else(|| Vector3::splat(0.0)),
+                tex_coord: self.tex_coords.get(i).cloned().unwrap_or(Vector2::splat(0.0)),
+            }
+        };
+
+        self.indices
+            .chunks(3)
+            .map(|t| {
+                Triangle {
+                    a: vertex(t[0]),
+                    b: vertex(t[1]),
+                    c: vertex(t[2]),
+                }
+            })
+            .collect()
+    }
+}
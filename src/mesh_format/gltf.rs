@@ -0,0 +1,1294 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::slice;
+
+use vec2::Vector2;
+use vec3::Vector3;
+use vec4::Vector4;
+use mat4::Matrix4;
+use quaternion::Quaternion;
+use mesh::Mesh;
+use renderer::{Vertex, Triangle};
+use pbr::PbrMaterial;
+use texture::Texture;
+use image_format::{self, DecodedImage, ImageError, ImageFormat};
+use super::MeshError;
+use super::json::{Json, parse_json};
+
+/// A glTF 2.0 asset loaded from a `.gltf` or `.glb` file.  Indices between the different lists
+/// are the same as in the source document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfDocument {
+    pub scenes: Vec<GltfScene>,
+    /// The scene to display by default, if the document names one.
+    pub scene: Option<usize>,
+    pub nodes: Vec<GltfNode>,
+    pub meshes: Vec<GltfMesh>,
+    pub materials: Vec<GltfMaterial>,
+    pub textures: Vec<GltfTexture>,
+    pub images: Vec<GltfImage>,
+    pub cameras: Vec<GltfCamera>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfScene {
+    pub name: Option<String>,
+    pub nodes: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfNode {
+    pub name: Option<String>,
+    pub children: Vec<usize>,
+    pub mesh: Option<usize>,
+    pub camera: Option<usize>,
+    /// Transform relative to the parent node.
+    pub transform: Matrix4<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+/// A triangle list from a mesh primitive.  Strips and fans are converted to lists, point and line
+/// primitives are skipped when loading.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfPrimitive {
+    /// Holds the POSITION, NORMAL, TEXCOORD_0 and COLOR_0 attributes.
+    pub mesh: Mesh,
+    /// The TEXCOORD_1 attribute, or empty.
+    pub tex_coords_1: Vec<Vector2<f32>>,
+    /// The TANGENT attribute, or empty.  The w component is the bitangent sign.
+    pub tangents: Vec<Vector4<f32>>,
+    pub material: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum AlphaMode {
+    Opaque,
+    Mask,
+    Blend,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GltfTextureRef {
+    pub texture: usize,
+    /// Which TEXCOORD attribute set to sample with.
+    pub tex_coord: u32,
+    /// The normal map scale or occlusion strength, 1.0 for other textures.
+    pub scale: f32,
+}
+
+/// A metallic-roughness material.  Default values are the defaults from the glTF specification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfMaterial {
+    pub name: Option<String>,
+    pub base_color: Vector4<f32>,
+    pub base_color_texture: Option<GltfTextureRef>,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// Roughness is sampled from the green channel and metalness from the blue channel.
+    pub metallic_roughness_texture: Option<GltfTextureRef>,
+    pub normal_texture: Option<GltfTextureRef>,
+    pub occlusion_texture: Option<GltfTextureRef>,
+    pub emissive: Vector3<f32>,
+    pub emissive_texture: Option<GltfTextureRef>,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+}
+
+impl Default for GltfMaterial {
+    fn default() -> GltfMaterial {
+        GltfMaterial {
+            name: None,
+            base_color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            base_color_texture: None,
+            metallic: 1.0,
+            roughness: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            occlusion_texture: None,
+            emissive: Vector3::new(0.0, 0.0, 0.0),
+            emissive_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            double_sided: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum WrapMode {
+    ClampToEdge,
+    MirroredRepeat,
+    Repeat,
+}
+
+/// Filter constants are the OpenGL enum values used by glTF, `None` if unspecified.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct GltfSampler {
+    pub mag_filter: Option<u32>,
+    pub min_filter: Option<u32>,
+    pub wrap_s: WrapMode,
+    pub wrap_t: WrapMode,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfTexture {
+    pub image: Option<usize>,
+    pub sampler: GltfSampler,
+}
+
+/// Encoded image data, read from an external file, a data URI or a buffer view.  Images are
+/// decoded on demand so that assets with images in unsupported formats can still be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfImage {
+    pub name: Option<String>,
+    pub mime_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl GltfImage {
+    pub fn decode(&self) -> Result<DecodedImage, ImageError> {
+        match ImageFormat::from_magic(&self.data) {
+            Some(format) => image_format::decode_image(&self.data, format),
+            None => Err(ImageError::Unsupported(format!(
+                "glTF image{} with mime type {}",
+                self.name
+                    .as_ref()
+                    .map(|n| format!(" '{}'", n))
+                    .unwrap_or_default(),
+                self.mime_type.as_deref().unwrap_or("unknown")
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GltfCamera {
+    Perspective {
+        aspect_ratio: Option<f32>,
+        yfov: f32,
+        znear: f32,
+        /// `None` for an infinite projection.
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        xmag: f32,
+        ymag: f32,
+        znear: f32,
+        zfar: f32,
+    },
+}
+
+impl GltfDocument {
+    /// The scene to display: the document's default scene, or the first scene if there is no
+    /// default.
+    pub fn default_scene(&self) -> Option<&GltfScene> {
+        self.scene.or(if self.scenes.is_empty() { None } else { Some(0) }).map(
+            |s| &self.scenes[s],
+        )
+    }
+
+    /// Walks the node hierarchy of a scene, returning every node along with its world transform.
+    pub fn world_transforms(&self, scene: &GltfScene) -> Vec<(usize, Matrix4<f32>)> {
+        let mut result = Vec::new();
+        let mut stack: Vec<(usize, Matrix4<f32>)> = scene
+            .nodes
+            .iter()
+            .rev()
+            .map(|&n| (n, Matrix4::identity()))
+            .collect();
+        while let Some((n, parent)) = stack.pop() {
+            let node = &self.nodes[n];
+            let world = parent * node.transform;
+            result.push((n, world));
+            for &child in node.children.iter().rev() {
+                stack.push((child, world));
+            }
+        }
+        result
+    }
+
+    /// Flattens the default scene into world space renderer triangles.  Primitives without
+    /// vertex colors use the base color of their material.
+    pub fn triangles(&self) -> Vec<Triangle> {
+        let mut triangles = Vec::new();
+        let scene = match self.default_scene() {
+            Some(scene) => scene,
+            None => return triangles,
+        };
+
+        for (n, world) in self.world_transforms(scene) {
+            let mesh = match self.nodes[n].mesh {
+                Some(mesh) => &self.meshes[mesh],
+                None => continue,
+            };
+            for primitive in &mesh.primitives {
+                let color = primitive
+                    .material
+                    .map(|m| self.materials[m].base_color)
+                    .unwrap_or_else(|| Vector4::new(1.0, 1.0, 1.0, 1.0));
+                let normal_matrix = world.normal_matrix().unwrap_or_else(Matrix4::identity);
+                let transform = |v: Vertex| {
+                    let normal = normal_matrix.transform_vector(v.normal);
+                    Vertex {
+                        position: world.transform(v.position),
+                        // glTF puts the origin of texture coordinates at the top left.
+                        tex_coord: Vector2::new(v.tex_coord.x, 1.0 - v.tex_coord.y),
+                        normal: if normal.magnitude_squared() > 0.0 {
+                            normal.normalize()
+                        } else {
+                            normal
+                        },
+                        ..v
+                    }
+                };
+                triangles.extend(primitive.mesh.triangles(color).into_iter().map(|t| {
+                    Triangle {
+                        a: transform(t.a),
+                        b: transform(t.b),
+                        c: transform(t.c),
+                    }
+                }));
+            }
+        }
+        triangles
+    }
+
+    /// Converts a material for `ShadingMode::Pbr`, decoding its textures.  Textures that use a
+    /// second set of texture coordinates are left out, since renderer vertices carry only one.
+    pub fn pbr_material(&self, material: usize) -> Result<PbrMaterial, ImageError> {
+        let material = &self.materials[material];
+        let texture = |t: Option<GltfTextureRef>, srgb| -> Result<_, ImageError> {
+            let image = match t {
+                Some(t) if t.tex_coord == 0 => self.textures[t.texture].image,
+                _ => None,
+            };
+            match image {
+                Some(image) => {
+                    let colors = self.images[image].decode()?.into_rgba8();
+                    match Texture::try_from_colors(&colors, srgb) {
+                        Some(texture) => Ok(Some(Rc::new(texture))),
+                        None => Err(ImageError::Malformed("the image has no pixels".to_string())),
+                    }
+                }
+                None => Ok(None),
+            }
+        };
+        Ok(PbrMaterial {
+            base_color: material.base_color,
+            base_color_texture: texture(material.base_color_texture, true)?,
+            metallic: material.metallic,
+            roughness: material.roughness,
+            metallic_roughness_texture: texture(material.metallic_roughness_texture, false)?,
+            emissive: material.emissive,
+            emissive_texture: texture(material.emissive_texture, true)?,
+            occlusion_texture: texture(material.occlusion_texture, false)?,
+            occlusion_strength: material.occlusion_texture.map_or(1.0, |t| t.scale),
+        })
+    }
+}
+
+/// Loads a `.gltf` or `.glb` file, along with any external buffers and images it references.
+pub fn load_gltf<P: AsRef<Path>>(path: P) -> Result<GltfDocument, MeshError> {
+    let path = path.as_ref();
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+    parse_gltf(&data, path.parent())
+}
+
+/// Parses glTF JSON or binary glTF data.  External files are resolved relative to `base_dir`,
+/// which may be `None` for self contained assets.
+pub fn parse_gltf(data: &[u8], base_dir: Option<&Path>) -> Result<GltfDocument, MeshError> {
+    let (json, bin) = if data.starts_with(b"glTF") {
+        split_glb(data)?
+    } else {
+        (data, None)
+    };
+
+    let json = match ::std::str::from_utf8(json) {
+        Ok(json) => json,
+        Err(_) => return malformed("glTF JSON is not valid UTF-8"),
+    };
+    let root = parse_json(json)?;
+    if !root.is_object() {
+        return malformed("glTF root is not an object");
+    }
+
+    let version = root.get("asset").and_then(|a| a.get("version")).and_then(
+        |v| v.as_str(),
+    );
+    match version {
+        Some(v) if v.starts_with("2.") => {}
+        Some(v) => return unsupported(format!("glTF version {}", v)),
+        None => return malformed("glTF asset has no version"),
+    }
+
+    if let Some(required) = root.get("extensionsRequired").and_then(|e| e.as_array()) {
+        if let Some(extension) = required.first().and_then(|e| e.as_str()) {
+            return unsupported(format!("required glTF extension {}", extension));
+        }
+    }
+
+    let loader = Loader {
+        root: &root,
+        base_dir,
+        buffers: load_buffers(&root, base_dir, bin)?,
+    };
+    loader.document()
+}
+
+const GLB_JSON_CHUNK: u32 = 0x4e4f_534a;
+const GLB_BIN_CHUNK: u32 = 0x004e_4942;
+
+fn split_glb(data: &[u8]) -> Result<(&[u8], Option<&[u8]>), MeshError> {
+    if data.len() < 20 {
+        return malformed("GLB header is truncated");
+    }
+    let version = read_u32(&data[4..]);
+    if version != 2 {
+        return unsupported(format!("GLB container version {}", version));
+    }
+    let length = (read_u32(&data[8..]) as usize).min(data.len());
+
+    let mut json = None;
+    let mut bin = None;
+    let mut pos = 12;
+    while pos + 8 <= length {
+        let chunk_length = read_u32(&data[pos..]) as usize;
+        let chunk_type = read_u32(&data[pos + 4..]);
+        if length - pos - 8 < chunk_length {
+            return malformed("GLB chunk is truncated");
+        }
+        let chunk = &data[pos + 8..pos + 8 + chunk_length];
+        match chunk_type {
+            GLB_JSON_CHUNK if json.is_none() => json = Some(chunk),
+            GLB_BIN_CHUNK if bin.is_none() => bin = Some(chunk),
+            _ => {}
+        }
+        // Chunks are padded to four byte boundaries
+        pos += 8 + ((chunk_length + 3) & !3);
+    }
+
+    match json {
+        Some(json) => Ok((json, bin)),
+        None => malformed("GLB file has no JSON chunk"),
+    }
+}
+
+fn load_buffers(
+    root: &Json,
+    base_dir: Option<&Path>,
+    bin: Option<&[u8]>,
+) -> Result<Vec<Vec<u8>>, MeshError> {
+    let mut buffers = Vec::new();
+    for (i, buffer) in array(root, "buffers").iter().enumerate() {
+        let context = format!("buffer {}", i);
+        let byte_length = required_usize(buffer, "byteLength", &context)?;
+        let data = match buffer.get("uri").and_then(|u| u.as_str()) {
+            Some(uri) => load_uri(uri, base_dir, &context)?,
+            None => {
+                if i != 0 {
+                    return malformed(format!("{} has no uri", context));
+                }
+                match bin {
+                    Some(bin) => bin.to_vec(),
+                    None => {
+                        return malformed(format!("{} has no uri and there is no GLB binary chunk", context))
+                    }
+                }
+            }
+        };
+        if data.len() < byte_length {
+            return malformed(format!(
+                "{} is {} bytes long, expected {}",
+                context,
+                data.len(),
+                byte_length
+            ));
+        }
+        buffers.push(data);
+    }
+    Ok(buffers)
+}
+
+fn load_uri(uri: &str, base_dir: Option<&Path>, context: &str) -> Result<Vec<u8>, MeshError> {
+    if uri.starts_with("data:") {
+        let comma = match uri.find(',') {
+            Some(comma) => comma,
+            None => return malformed(format!("{} has an invalid data URI", context)),
+        };
+        if !uri[..comma].ends_with(";base64") {
+            return unsupported(format!("{} has a data URI that is not base64", context));
+        }
+        return match decode_base64(&uri[comma + 1..]) {
+            Some(data) => Ok(data),
+            None => malformed(format!("{} has invalid base64 data", context)),
+        };
+    }
+
+    if uri.contains("://") {
+        return unsupported(format!("{} refers to a remote URI {}", context, uri));
+    }
+    let base_dir = match base_dir {
+        Some(base_dir) => base_dir,
+        None => return malformed(format!("{} refers to an external file {}", context, uri)),
+    };
+
+    let path: PathBuf = base_dir.join(percent_decode(uri));
+    let mut data = Vec::new();
+    File::open(&path)
+        .and_then(|mut f| f.read_to_end(&mut data))
+        .map_err(|err| {
+            MeshError::Malformed(format!(
+                "{}: could not read {}: {}",
+                context,
+                path.display(),
+                err
+            ))
+        })?;
+    Ok(data)
+}
+
+// The most values an accessor without a buffer view may have.
+const MAX_ZEROED_VALUES: usize = 1 << 24;
+
+// The values of an accessor, `components` to an element.
+struct Elements {
+    components: usize,
+    values: Vec<f32>,
+}
+
+impl Elements {
+    fn len(&self) -> usize {
+        self.values.len() / self.components
+    }
+
+    fn iter(&self) -> slice::Chunks<'_, f32> {
+        self.values.chunks(self.components)
+    }
+}
+
+struct Loader<'a> {
+    root: &'a Json,
+    base_dir: Option<&'a Path>,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl<'a> Loader<'a> {
+    fn document(&self) -> Result<GltfDocument, MeshError> {
+        let node_count = array(self.root, "nodes").len();
+        let mut document = GltfDocument {
+            scenes: Vec::new(),
+            scene: optional_usize(self.root, "scene", "document")?,
+            nodes: Vec::new(),
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            textures: Vec::new(),
+            images: Vec::new(),
+            cameras: Vec::new(),
+        };
+
+        for (i, scene) in array(self.root, "scenes").iter().enumerate() {
+            let context = format!("scene {}", i);
+            document.scenes.push(GltfScene {
+                name: name(scene),
+                nodes: index_list(scene, "nodes", node_count, &context)?,
+            });
+        }
+        if let Some(scene) = document.scene {
+            if scene >= document.scenes.len() {
+                return malformed(format!("default scene {} does not exist", scene));
+            }
+        }
+
+        for (i, node) in array(self.root, "nodes").iter().enumerate() {
+            document.nodes.push(self.node(node, &format!("node {}", i))?);
+        }
+        check_hierarchy(&document.nodes)?;
+
+        for (i, mesh) in array(self.root, "meshes").iter().enumerate() {
+            document.meshes.push(self.mesh(mesh, &format!("mesh {}", i))?);
+        }
+        for (i, material) in array(self.root, "materials").iter().enumerate() {
+            document.materials.push(
+                self.material(material, &format!("material {}", i))?,
+            );
+        }
+        for (i, texture) in array(self.root, "textures").iter().enumerate() {
+            document.textures.push(
+                self.texture(texture, &format!("texture {}", i))?,
+            );
+        }
+        for (i, image) in array(self.root, "images").iter().enumerate() {
+            document.images.push(self.image(image, &format!("image {}", i))?);
+        }
+        for (i, camera) in array(self.root, "cameras").iter().enumerate() {
+            document.cameras.push(
+                self.camera(camera, &format!("camera {}", i))?,
+            );
+        }
+
+        // Validate cross references now that everything is loaded.
+        for (i, node) in document.nodes.iter().enumerate() {
+            check_index(node.mesh, document.meshes.len(), "mesh", &format!("node {}", i))?;
+            check_index(node.camera, document.cameras.len(), "camera", &format!("node {}", i))?;
+        }
+        for (i, mesh) in document.meshes.iter().enumerate() {
+            for primitive in &mesh.primitives {
+                check_index(
+                    primitive.material,
+                    document.materials.len(),
+                    "material",
+                    &format!("mesh {}", i),
+                )?;
+            }
+        }
+        for (i, material) in document.materials.iter().enumerate() {
+            let textures = [
+                material.base_color_texture,
+                material.metallic_roughness_texture,
+                material.normal_texture,
+                material.occlusion_texture,
+                material.emissive_texture,
+            ];
+            for t in textures.iter().filter_map(|t| *t) {
+                check_index(
+                    Some(t.texture),
+                    document.textures.len(),
+                    "texture",
+                    &format!("material {}", i),
+                )?;
+            }
+        }
+        for (i, texture) in document.textures.iter().enumerate() {
+            check_index(
+                texture.image,
+                document.images.len(),
+                "image",
+                &format!("texture {}", i),
+            )?;
+        }
+
+        Ok(document)
+    }
+
+    fn node(&self, node: &Json, context: &str) -> Result<GltfNode, MeshError> {
+        let transform = if let Some(matrix) = node.get("matrix") {
+            let mut m = [0.0; 16];
+            m.copy_from_slice(&float_array(matrix, 16, context, "matrix")?);
+            Matrix4::from_cols_array(m)
+        } else {
+            let t = match node.get("translation") {
+                Some(t) => float_array(t, 3, context, "translation")?,
+                None => vec![0.0; 3],
+            };
+            let r = match node.get("rotation") {
+                Some(r) => float_array(r, 4, context, "rotation")?,
+                None => vec![0.0, 0.0, 0.0, 1.0],
+            };
+            let s = match node.get("scale") {
+                Some(s) => float_array(s, 3, context, "scale")?,
+                None => vec![1.0; 3],
+            };
+            Matrix4::translation(Vector3::new(t[0], t[1], t[2])) *
+                Quaternion::new(r[0], r[1], r[2], r[3]).to_matrix() *
+                Matrix4::scaling(Vector3::new(s[0], s[1], s[2]))
+        };
+
+        Ok(GltfNode {
+            name: name(node),
+            children: index_list(node, "children", array(self.root, "nodes").len(), context)?,
+            mesh: optional_usize(node, "mesh", context)?,
+            camera: optional_usize(node, "camera", context)?,
+            transform,
+        })
+    }
+
+    fn mesh(&self, mesh: &Json, context: &str) -> Result<GltfMesh, MeshError> {
+        let mut primitives = Vec::new();
+        for (i, primitive) in array(mesh, "primitives").iter().enumerate() {
+            let context = format!("{} primitive {}", context, i);
+            if let Some(primitive) = self.primitive(primitive, &context)? {
+                primitives.push(primitive);
+            }
+        }
+        Ok(GltfMesh {
+            name: name(mesh),
+            primitives,
+        })
+    }
+
+    fn primitive(&self, primitive: &Json, context: &str) -> Result<Option<GltfPrimitive>, MeshError> {
+        const TRIANGLES: usize = 4;
+        const TRIANGLE_STRIP: usize = 5;
+        const TRIANGLE_FAN: usize = 6;
+
+        let mode = optional_usize(primitive, "mode", context)?.unwrap_or(TRIANGLES);
+        if mode < TRIANGLES {
+            // Points and lines
+            return Ok(None);
+        }
+
+        let attributes = match primitive.get("attributes") {
+            Some(attributes) if attributes.is_object() => attributes,
+            _ => return malformed(format!("{} has no attributes", context)),
+        };
+        let attribute = |name: &str| -> Result<Option<Elements>, MeshError> {
+            match optional_usize(attributes, name, context)? {
+                Some(accessor) => {
+                    self.accessor(accessor, &format!("{} attribute {}", context, name))
+                        .map(Some)
+                }
+                None => Ok(None),
+            }
+        };
+
+        let positions = match attribute("POSITION")? {
+            Some(positions) => positions,
+            None => return malformed(format!("{} has no POSITION attribute", context)),
+        };
+        let count = positions.len();
+        let check_count = |values: &Elements, name: &str| if values.len() != count {
+            malformed(format!(
+                "{} attribute {} has {} elements, POSITION has {}",
+                context,
+                name,
+                values.len(),
+                count
+            ))
+        } else {
+            Ok(())
+        };
+        let component = |values: &[f32], i: usize, default: f32| {
+            values.get(i).cloned().unwrap_or(default)
+        };
+
+        let mut mesh = Mesh::new();
+        mesh.positions = positions
+            .iter()
+            .map(|v| {
+                Vector3::new(component(v, 0, 0.0), component(v, 1, 0.0), component(v, 2, 0.0))
+            })
+            .collect();
+
+        if let Some(normals) = attribute("NORMAL")? {
+            check_count(&normals, "NORMAL")?;
+            mesh.normals = normals
+                .iter()
+                .map(|v| {
+                    Vector3::new(component(v, 0, 0.0), component(v, 1, 0.0), component(v, 2, 0.0))
+                })
+                .collect();
+        }
+        let vec2s = |values: Elements| -> Vec<Vector2<f32>> {
+            values
+                .iter()
+                .map(|v| Vector2::new(component(v, 0, 0.0), component(v, 1, 0.0)))
+                .collect()
+        };
+        if let Some(tex_coords) = attribute("TEXCOORD_0")? {
+            check_count(&tex_coords, "TEXCOORD_0")?;
+            mesh.tex_coords = vec2s(tex_coords);
+        }
+        if let Some(colors) = attribute("COLOR_0")? {
+            check_count(&colors, "COLOR_0")?;
+            mesh.colors = colors
+                .iter()
+                .map(|v| {
+                    Vector4::new(
+                        component(v, 0, 1.0),
+                        component(v, 1, 1.0),
+                        component(v, 2, 1.0),
+                        component(v, 3, 1.0),
+                    )
+                })
+                .collect();
+        }
+
+        let mut tex_coords_1 = Vec::new();
+        if let Some(values) = attribute("TEXCOORD_1")? {
+            check_count(&values, "TEXCOORD_1")?;
+            tex_coords_1 = vec2s(values);
+        }
+        let mut tangents = Vec::new();
+        if let Some(values) = attribute("TANGENT")? {
+            check_count(&values, "TANGENT")?;
+            tangents = values
+                .iter()
+                .map(|v| {
+                    Vector4::new(
+                        component(v, 0, 0.0),
+                        component(v, 1, 0.0),
+                        component(v, 2, 0.0),
+                        component(v, 3, 1.0),
+                    )
+                })
+                .collect();
+        }
+
+        let indices: Vec<u32> = match optional_usize(primitive, "indices", context)? {
+            Some(accessor) => {
+                let context = format!("{} indices", context);
+                let values = self.accessor(accessor, &context)?;
+                let mut indices = Vec::with_capacity(values.len());
+                for v in values.iter() {
+                    let index = v[0] as usize;
+                    if index >= count {
+                        return malformed(format!(
+                            "{} refer to vertex {}, only {} vertices exist",
+                            context,
+                            index,
+                            count
+                        ));
+                    }
+                    indices.push(index as u32);
+                }
+                indices
+            }
+            None => (0..count as u32).collect(),
+        };
+
+        mesh.indices = match mode {
+            TRIANGLES => {
+                let mut indices = indices;
+                indices.truncate(indices.len() / 3 * 3);
+                indices
+            }
+            TRIANGLE_STRIP => {
+                let mut list = Vec::new();
+                for i in 2..indices.len() {
+                    // Every other triangle has its winding flipped back to the strip's winding.
+                    if i % 2 == 0 {
+                        list.extend_from_slice(&[indices[i - 2], indices[i - 1], indices[i]]);
+                    } else {
+                        list.extend_from_slice(&[indices[i - 1], indices[i - 2], indices[i]]);
+                    }
+                }
+                list
+            }
+            TRIANGLE_FAN => {
+                let mut list = Vec::new();
+                for i in 2..indices.len() {
+                    list.extend_from_slice(&[indices[0], indices[i - 1], indices[i]]);
+                }
+                list
+            }
+            m => return malformed(format!("{} has invalid mode {}", context, m)),
+        };
+
+        Ok(Some(GltfPrimitive {
+            mesh,
+            tex_coords_1,
+            tangents,
+            material: optional_usize(primitive, "material", context)?,
+        }))
+    }
+
+    // Reads an accessor as a list of elements, each with one value per component.  Normalized
+    // integer components are converted to the range [0, 1] or [-1, 1].
+    fn accessor(&self, index: usize, context: &str) -> Result<Elements, MeshError> {
+        let accessors = array(self.root, "accessors");
+        let accessor = match accessors.get(index) {
+            Some(accessor) => accessor,
+            None => return malformed(format!("{} refers to missing accessor {}", context, index)),
+        };
+        let context = format!("{} (accessor {})", context, index);
+
+        let count = required_usize(accessor, "count", &context)?;
+        let component_type = required_usize(accessor, "componentType", &context)?;
+        let normalized = accessor.get("normalized").and_then(|n| n.as_bool()).unwrap_or(false);
+        let components = match accessor.get("type").and_then(|t| t.as_str()) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") | Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => return malformed(format!("{} has an invalid type", context)),
+        };
+        let mut values = match optional_usize(accessor, "bufferView", &context)? {
+            Some(view) => {
+                let offset = optional_usize(accessor, "byteOffset", &context)?.unwrap_or(0);
+                self.read_elements(
+                    view,
+                    offset,
+                    count,
+                    components,
+                    component_type,
+                    normalized,
+                    &context,
+                )?
+            }
+            // Without a buffer view the elements start out as zeros, and nothing in the file
+            // bounds how many there are.
+            None => {
+                match count.checked_mul(components) {
+                    Some(size) if size <= MAX_ZEROED_VALUES => {
+                        Elements {
+                            components,
+                            values: vec![0.0; size],
+                        }
+                    }
+                    _ => {
+                        return malformed(format!(
+                            "{} has {} elements but no buffer view",
+                            context,
+                            count
+                        ))
+                    }
+                }
+            }
+        };
+
+        if let Some(sparse) = accessor.get("sparse") {
+            let sparse_count = required_usize(sparse, "count", &context)?;
+            let (indices, values_json) = match (sparse.get("indices"), sparse.get("values")) {
+                (Some(i), Some(v)) => (i, v),
+                _ => return malformed(format!("{} has incomplete sparse data", context)),
+            };
+            let sparse_indices = self.read_elements(
+                required_usize(indices, "bufferView", &context)?,
+                optional_usize(indices, "byteOffset", &context)?.unwrap_or(0),
+                sparse_count,
+                1,
+                required_usize(indices, "componentType", &context)?,
+                false,
+                &context,
+            )?;
+            let sparse_values = self.read_elements(
+                required_usize(values_json, "bufferView", &context)?,
+                optional_usize(values_json, "byteOffset", &context)?.unwrap_or(0),
+                sparse_count,
+                components,
+                component_type,
+                normalized,
+                &context,
+            )?;
+            for (i, v) in sparse_indices.iter().zip(sparse_values.iter()) {
+                let i = i[0] as usize;
+                if i >= count {
+                    return malformed(format!("{} has sparse index {} out of range", context, i));
+                }
+                values.values[i * components..(i + 1) * components].copy_from_slice(v);
+            }
+        }
+
+        Ok(values)
+    }
+
+    // Returns the bytes of a buffer view along with its stride, if it has one.
+    fn buffer_view(&self, index: usize, context: &str) -> Result<(&[u8], Option<usize>), MeshError> {
+        let view = match array(self.root, "bufferViews").get(index) {
+            Some(view) => view,
+            None => return malformed(format!("{} refers to missing buffer view {}", context, index)),
+        };
+        let context = format!("{} buffer view {}", context, index);
+        let buffer_index = required_usize(view, "buffer", &context)?;
+        let buffer = match self.buffers.get(buffer_index) {
+            Some(buffer) => buffer,
+            None => return malformed(format!("{} refers to missing buffer {}", context, buffer_index)),
+        };
+        let offset = optional_usize(view, "byteOffset", &context)?.unwrap_or(0);
+        let length = required_usize(view, "byteLength", &context)?;
+        if offset + length > buffer.len() {
+            return malformed(format!("{} extends past the end of its buffer", context));
+        }
+        let stride = optional_usize(view, "byteStride", &context)?;
+        Ok((&buffer[offset..offset + length], stride))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn read_elements(
+        &self,
+        view_index: usize,
+        offset: usize,
+        count: usize,
+        components: usize,
+        component_type: usize,
+        normalized: bool,
+        context: &str,
+    ) -> Result<Elements, MeshError> {
+        let (data, stride) = self.buffer_view(view_index, context)?;
+        let component_size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            t => return malformed(format!("{} has invalid component type {}", context, t)),
+        };
+        let element_size = components * component_size;
+        let stride = stride.unwrap_or(element_size);
+        if stride < element_size {
+            return malformed(format!(
+                "{} buffer view {} has a stride smaller than its elements",
+                context,
+                view_index
+            ));
+        }
+        if count > 0 {
+            let end = (count - 1)
+                .checked_mul(stride)
+                .and_then(|s| s.checked_add(offset))
+                .and_then(|s| s.checked_add(element_size));
+            if end.is_none_or(|end| end > data.len()) {
+                return malformed(format!("{} reads past the end of its buffer view", context));
+            }
+        }
+
+        let read = |p: &[u8]| -> f32 {
+            match component_type {
+                5120 => {
+                    let v = p[0] as i8 as f32;
+                    if normalized { (v / 127.0).max(-1.0) } else { v }
+                }
+                5121 => {
+                    let v = p[0] as f32;
+                    if normalized { v / 255.0 } else { v }
+                }
+                5122 => {
+                    let v = (p[0] as u16 | (p[1] as u16) << 8) as i16 as f32;
+                    if normalized { (v / 32767.0).max(-1.0) } else { v }
+                }
+                5123 => {
+                    let v = (p[0] as u16 | (p[1] as u16) << 8) as f32;
+                    if normalized { v / 65535.0 } else { v }
+                }
+                5125 => read_u32(p) as f32,
+                _ => f32::from_bits(read_u32(p)),
+            }
+        };
+
+        // The check above bounds `count` by the size of the buffer view.
+        let mut values = Vec::with_capacity(count * components);
+        for i in 0..count {
+            let start = offset + i * stride;
+            values.extend((0..components).map(|c| read(&data[start + c * component_size..])));
+        }
+        Ok(Elements { components, values })
+    }
+
+    fn material(&self, material: &Json, context: &str) -> Result<GltfMaterial, MeshError> {
+        let mut result = GltfMaterial {
+            name: name(material),
+            ..GltfMaterial::default()
+        };
+
+        if let Some(pbr) = material.get("pbrMetallicRoughness") {
+            if let Some(c) = pbr.get("baseColorFactor") {
+                let c = float_array(c, 4, context, "baseColorFactor")?;
+                result.base_color = Vector4::new(c[0], c[1], c[2], c[3]);
+            }
+            result.base_color_texture = texture_ref(pbr, "baseColorTexture", "", context)?;
+            if let Some(m) = optional_f32(pbr, "metallicFactor", context)? {
+                result.metallic = m;
+            }
+            if let Some(r) = optional_f32(pbr, "roughnessFactor", context)? {
+                result.roughness = r;
+            }
+            result.metallic_roughness_texture =
+                texture_ref(pbr, "metallicRoughnessTexture", "", context)?;
+        }
+
+        result.normal_texture = texture_ref(material, "normalTexture", "scale", context)?;
+        result.occlusion_texture = texture_ref(material, "occlusionTexture", "strength", context)?;
+        result.emissive_texture = texture_ref(material, "emissiveTexture", "", context)?;
+        if let Some(e) = material.get("emissiveFactor") {
+            let e = float_array(e, 3, context, "emissiveFactor")?;
+            result.emissive = Vector3::new(e[0], e[1], e[2]);
+        }
+
+        result.alpha_mode = match material.get("alphaMode").and_then(|m| m.as_str()) {
+            None | Some("OPAQUE") => AlphaMode::Opaque,
+            Some("MASK") => AlphaMode::Mask,
+            Some("BLEND") => AlphaMode::Blend,
+            Some(m) => return malformed(format!("{} has invalid alpha mode {}", context, m)),
+        };
+        if let Some(cutoff) = optional_f32(material, "alphaCutoff", context)? {
+            result.alpha_cutoff = cutoff;
+        }
+        result.double_sided = material.get("doubleSided").and_then(|d| d.as_bool()).unwrap_or(
+            false,
+        );
+
+        Ok(result)
+    }
+
+    fn texture(&self, texture: &Json, context: &str) -> Result<GltfTexture, MeshError> {
+        let mut sampler = GltfSampler {
+            mag_filter: None,
+            min_filter: None,
+            wrap_s: WrapMode::Repeat,
+            wrap_t: WrapMode::Repeat,
+        };
+        if let Some(index) = optional_usize(texture, "sampler", context)? {
+            let s = match array(self.root, "samplers").get(index) {
+                Some(s) => s,
+                None => return malformed(format!("{} refers to missing sampler {}", context, index)),
+            };
+            let wrap = |key: &str| -> Result<WrapMode, MeshError> {
+                match optional_usize(s, key, context)? {
+                    None | Some(10497) => Ok(WrapMode::Repeat),
+                    Some(33071) => Ok(WrapMode::ClampToEdge),
+                    Some(33648) => Ok(WrapMode::MirroredRepeat),
+                    Some(w) => malformed(format!("{} has invalid wrap mode {}", context, w)),
+                }
+            };
+            sampler = GltfSampler {
+                mag_filter: optional_usize(s, "magFilter", context)?.map(|f| f as u32),
+                min_filter: optional_usize(s, "minFilter", context)?.map(|f| f as u32),
+                wrap_s: wrap("wrapS")?,
+                wrap_t: wrap("wrapT")?,
+            };
+        }
+
+        Ok(GltfTexture {
+            image: optional_usize(texture, "source", context)?,
+            sampler,
+        })
+    }
+
+    fn image(&self, image: &Json, context: &str) -> Result<GltfImage, MeshError> {
+        let mime_type = image.get("mimeType").and_then(|m| m.as_str()).map(
+            |m| m.to_string(),
+        );
+        let data = if let Some(uri) = image.get("uri").and_then(|u| u.as_str()) {
+            load_uri(uri, self.base_dir, context)?
+        } else if let Some(view) = optional_usize(image, "bufferView", context)? {
+            self.buffer_view(view, context)?.0.to_vec()
+        } else {
+            return malformed(format!("{} has neither a uri nor a buffer view", context));
+        };
+
+        Ok(GltfImage {
+            name: name(image),
+            mime_type,
+            data,
+        })
+    }
+
+    fn camera(&self, camera: &Json, context: &str) -> Result<GltfCamera, MeshError> {
+        match camera.get("type").and_then(|t| t.as_str()) {
+            Some("perspective") => {
+                let p = match camera.get("perspective") {
+                    Some(p) => p,
+                    None => return malformed(format!("{} has no perspective properties", context)),
+                };
+                Ok(GltfCamera::Perspective {
+                    aspect_ratio: optional_f32(p, "aspectRatio", context)?,
+                    yfov: required_f32(p, "yfov", context)?,
+                    znear: required_f32(p, "znear", context)?,
+                    zfar: optional_f32(p, "zfar", context)?,
+                })
+            }
+            Some("orthographic") => {
+                let o = match camera.get("orthographic") {
+                    Some(o) => o,
+                    None => return malformed(format!("{} has no orthographic properties", context)),
+                };
+                Ok(GltfCamera::Orthographic {
+                    xmag: required_f32(o, "xmag", context)?,
+                    ymag: required_f32(o, "ymag", context)?,
+                    znear: required_f32(o, "znear", context)?,
+                    zfar: required_f32(o, "zfar", context)?,
+                })
+            }
+            _ => malformed(format!("{} has an invalid type", context)),
+        }
+    }
+}
+
+// Rejects node graphs where a node has more than one parent or is its own ancestor, since they
+// cannot be walked as a tree.
+fn check_hierarchy(nodes: &[GltfNode]) -> Result<(), MeshError> {
+    let mut parent = vec![None; nodes.len()];
+    for (i, node) in nodes.iter().enumerate() {
+        for &child in &node.children {
+            if parent[child].is_some() {
+                return malformed(format!("node {} has more than one parent", child));
+            }
+            parent[child] = Some(i);
+        }
+    }
+    for start in 0..nodes.len() {
+        let mut n = start;
+        let mut steps = 0;
+        while let Some(p) = parent[n] {
+            n = p;
+            steps += 1;
+            if n == start || steps > nodes.len() {
+                return malformed(format!("node {} is its own ancestor", start));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn texture_ref(
+    parent: &Json,
+    key: &str,
+    scale_key: &str,
+    context: &str,
+) -> Result<Option<GltfTextureRef>, MeshError> {
+    let info = match parent.get(key) {
+        Some(info) => info,
+        None => return Ok(None),
+    };
+    let context = format!("{} {}", context, key);
+    Ok(Some(GltfTextureRef {
+        texture: required_usize(info, "index", &context)?,
+        tex_coord: optional_usize(info, "texCoord", &context)?.unwrap_or(0) as u32,
+        scale: if scale_key.is_empty() {
+            1.0
+        } else {
+            optional_f32(info, scale_key, &context)?.unwrap_or(1.0)
+        },
+    }))
+}
+
+fn check_index(
+    index: Option<usize>,
+    len: usize,
+    kind: &str,
+    context: &str,
+) -> Result<(), MeshError> {
+    match index {
+        Some(i) if i >= len => malformed(format!("{} refers to missing {} {}", context, kind, i)),
+        _ => Ok(()),
+    }
+}
+
+fn array<'a>(parent: &'a Json, key: &str) -> &'a [Json] {
+    parent.get(key).and_then(|a| a.as_array()).unwrap_or(&[])
+}
+
+fn name(object: &Json) -> Option<String> {
+    object.get("name").and_then(|n| n.as_str()).map(|n| n.to_string())
+}
+
+fn optional_usize(object: &Json, key: &str, context: &str) -> Result<Option<usize>, MeshError> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(v) => match v.as_f64() {
+            Some(n) if n >= 0.0 && n.fract() == 0.0 && n <= u32::MAX as f64 => {
+                Ok(Some(n as usize))
+            }
+            _ => malformed(format!("{} has an invalid '{}'", context, key)),
+        },
+    }
+}
+
+fn required_usize(object: &Json, key: &str, context: &str) -> Result<usize, MeshError> {
+    match optional_usize(object, key, context)? {
+        Some(v) => Ok(v),
+        None => malformed(format!("{} is missing '{}'", context, key)),
+    }
+}
+
+fn optional_f32(object: &Json, key: &str, context: &str) -> Result<Option<f32>, MeshError> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(v) => match v.as_f64() {
+            Some(n) => Ok(Some(n as f32)),
+            None => malformed(format!("{} has an invalid '{}'", context, key)),
+        },
+    }
+}
+
+fn required_f32(object: &Json, key: &str, context: &str) -> Result<f32, MeshError> {
+    match optional_f32(object, key, context)? {
+        Some(v) => Ok(v),
+        None => malformed(format!("{} is missing '{}'", context, key)),
+    }
+}
+
+fn float_array(value: &Json, len: usize, context: &str, key: &str) -> Result<Vec<f32>, MeshError> {
+    let values: Option<Vec<f32>> = value.as_array().and_then(|a| if a.len() == len {
+        a.iter().map(|v| v.as_f64().map(|v| v as f32)).collect()
+    } else {
+        None
+    });
+    match values {
+        Some(values) => Ok(values),
+        None => malformed(format!("{} '{}' must be an array of {} numbers", context, key, len)),
+    }
+}
+
+fn index_list(
+    object: &Json,
+    key: &str,
+    len: usize,
+    context: &str,
+) -> Result<Vec<usize>, MeshError> {
+    let mut result = Vec::new();
+    for v in array(object, key) {
+        match v.as_f64() {
+            Some(n) if n >= 0.0 && n.fract() == 0.0 && (n as usize) < len => {
+                result.push(n as usize)
+            }
+            _ => return malformed(format!("{} has an invalid index in '{}'", context, key)),
+        }
+    }
+    Ok(result)
+}
+
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in input.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            b' ' | b'\n' | b'\r' | b'\t' => continue,
+            _ => return None,
+        };
+        buffer = buffer << 6 | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = ::std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(v) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(v);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    data[0] as u32 | (data[1] as u32) << 8 | (data[2] as u32) << 16 | (data[3] as u32) << 24
+}
+
+fn malformed<T, S: Into<String>>(msg: S) -> Result<T, MeshError> {
+    Err(MeshError::Malformed(msg.into()))
+}
+
+fn unsupported<T, S: Into<String>>(msg: S) -> Result<T, MeshError> {
+    Err(MeshError::Unsupported(msg.into()))
+}
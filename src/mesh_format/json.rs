@@ -0,0 +1,276 @@
+// A minimal JSON parser for reading glTF documents.
+
+use std::collections::BTreeMap;
+
+use super::MeshError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl Json {
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Json::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Json::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match *self {
+            Json::Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(*self, Json::Object(_))
+    }
+}
+
+pub fn parse_json(source: &str) -> Result<Json, MeshError> {
+    let mut parser = Parser {
+        source: source.as_bytes(),
+        pos: 0,
+    };
+    parser.skip_whitespace();
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.pos != parser.source.len() {
+        return parser.error("unexpected characters after JSON value");
+    }
+    Ok(value)
+}
+
+// Guards against stack overflows from maliciously nested documents.
+const MAX_DEPTH: usize = 512;
+
+struct Parser<'a> {
+    source: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: &str) -> Result<T, MeshError> {
+        let line = self.source[..self.pos.min(self.source.len())]
+            .iter()
+            .filter(|&&c| c == b'\n')
+            .count() + 1;
+        Err(MeshError::Parse {
+            line,
+            message: format!("JSON: {}", message),
+        })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.source.get(self.pos).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == b' ' || c == b'\t' || c == b'\n' || c == b'\r' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &str, value: Json) -> Result<Json, MeshError> {
+        if self.source[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(value)
+        } else {
+            self.error("invalid literal")
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, MeshError> {
+        if depth > MAX_DEPTH {
+            return self.error("document is nested too deeply");
+        }
+
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut map = BTreeMap::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(map));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != Some(b'"') {
+                        return self.error("expected object key");
+                    }
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    if self.peek() != Some(b':') {
+                        return self.error("expected ':' after object key");
+                    }
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    let value = self.value(depth + 1)?;
+                    map.insert(key, value);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Json::Object(map));
+                        }
+                        _ => return self.error("expected ',' or '}' in object"),
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut array = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(array));
+                }
+                loop {
+                    self.skip_whitespace();
+                    array.push(self.value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Json::Array(array));
+                        }
+                        _ => return self.error("expected ',' or ']' in array"),
+                    }
+                }
+            }
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.expect_literal("true", Json::Bool(true)),
+            Some(b'f') => self.expect_literal("false", Json::Bool(false)),
+            Some(b'n') => self.expect_literal("null", Json::Null),
+            Some(c) if c == b'-' || c.is_ascii_digit() => self.number(),
+            Some(_) => self.error("unexpected character"),
+            None => self.error("unexpected end of document"),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, MeshError> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || c == b'-' || c == b'+' || c == b'.' || c == b'e' || c == b'E' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        // The source came from a &str and the number contains only ASCII, so this cannot fail.
+        let text = ::std::str::from_utf8(&self.source[start..self.pos]).unwrap();
+        match text.parse() {
+            Ok(n) => Ok(Json::Number(n)),
+            Err(_) => self.error(&format!("invalid number '{}'", text)),
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, MeshError> {
+        if self.source.len() - self.pos < 4 {
+            return self.error("truncated unicode escape");
+        }
+        let text = ::std::str::from_utf8(&self.source[self.pos..self.pos + 4]).ok();
+        match text.and_then(|t| u32::from_str_radix(t, 16).ok()) {
+            Some(v) => {
+                self.pos += 4;
+                Ok(v)
+            }
+            None => self.error("invalid unicode escape"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, MeshError> {
+        // Skip the opening quote
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                None => return self.error("unterminated string"),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escape = match self.peek() {
+                        Some(c) => c,
+                        None => return self.error("unterminated string"),
+                    };
+                    self.pos += 1;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            if (0xd800..0xdc00).contains(&code) {
+                                if !self.source[self.pos..].starts_with(b"\\u") {
+                                    return self.error("unpaired surrogate in string");
+                                }
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return self.error("invalid surrogate pair in string");
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            match ::std::char::from_u32(code) {
+                                Some(c) => c,
+                                None => return self.error("invalid unicode escape"),
+                            }
+                        }
+                        _ => return self.error("invalid escape in string"),
+                    };
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                Some(c) => {
+                    bytes.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        // Unescaped bytes were copied from valid UTF-8 and escapes were encoded as UTF-8.
+        Ok(String::from_utf8(bytes).unwrap())
+    }
+}
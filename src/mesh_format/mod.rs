@@ -0,0 +1,231 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use vec2::Vector2;
+use vec3::Vector3;
+use vec4::Vector4;
+use renderer::Triangle;
+
+pub mod obj;
+pub mod mtl;
+pub mod gltf;
+pub mod stl;
+pub mod ply;
+
+mod json;
+
+#[derive(Debug)]
+pub enum MeshError {
+    Io(io::Error),
+    /// An error in a line based text format, `line` starts at 1.
+    Parse { line: usize, message: String },
+    Malformed(String),
+    Unsupported(String),
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MeshError::Io(ref err) => write!(f, "i/o error reading mesh: {}", err),
+            MeshError::Parse { line, ref message } => write!(f, "line {}: {}", line, message),
+            MeshError::Malformed(ref msg) => write!(f, "malformed mesh: {}", msg),
+            MeshError::Unsupported(ref msg) => write!(f, "unsupported mesh: {}", msg),
+        }
+    }
+}
+
+impl Error for MeshError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            MeshError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MeshError {
+    fn from(err: io::Error) -> MeshError {
+        MeshError::Io(err)
+    }
+}
+
+/// Loads the triangles of a mesh in any supported format, chosen by the file extension.  Formats
+/// without colors or materials are colored white.
+pub fn load_triangles<P: AsRef<Path>>(path: P) -> Result<Vec<Triangle>, MeshError> {
+    let path = path.as_ref();
+    let extension = path.extension().and_then(|e| e.to_str()).map(
+        |e| e.to_lowercase(),
+    );
+    let white = Vector4::new(1.0, 1.0, 1.0, 1.0);
+    match extension.as_deref() {
+        Some("gltf") | Some("glb") => Ok(gltf::load_gltf(path)?.triangles()),
+        Some("stl") => Ok(stl::load_stl(path)?.triangles(white)),
+        Some("ply") => Ok(ply::load_ply(path)?.triangles(white)),
+        _ => Ok(obj::load_obj(path)?.triangles()),
+    }
+}
+
+// Iterates over the statements of a line based text format such as OBJ or MTL, skipping blank
+// lines and comments, joining lines ending in a backslash, and yielding the line number of each
+// statement along with its whitespace separated tokens.
+struct Statements<'a> {
+    lines: ::std::iter::Enumerate<::std::str::Lines<'a>>,
+}
+
+impl<'a> Statements<'a> {
+    fn new(source: &'a str) -> Statements<'a> {
+        Statements { lines: source.lines().enumerate() }
+    }
+}
+
+impl<'a> Iterator for Statements<'a> {
+    type Item = (usize, Vec<&'a str>);
+
+    fn next(&mut self) -> Option<(usize, Vec<&'a str>)> {
+        let mut tokens = Vec::new();
+        let mut start_line = None;
+        for (i, line) in &mut self.lines {
+            let line = match line.find('#') {
+                Some(comment) => &line[..comment],
+                None => line,
+            };
+            let (line, continued) = match line.trim_end().strip_suffix('\\') {
+                Some(line) => (line, true),
+                None => (line, false),
+            };
+
+            if start_line.is_none() {
+                start_line = Some(i + 1);
+            }
+            tokens.extend(line.split_whitespace());
+
+            if continued {
+                continue;
+            }
+            if tokens.is_empty() {
+                start_line = None;
+                continue;
+            }
+            return Some((start_line.unwrap(), tokens));
+        }
+
+        start_line.and_then(|line| if tokens.is_empty() {
+            None
+        } else {
+            Some((line, tokens))
+        })
+    }
+}
+
+fn parse_error<T, S: Into<String>>(line: usize, message: S) -> Result<T, MeshError> {
+    Err(MeshError::Parse {
+        line,
+        message: message.into(),
+    })
+}
+
+fn parse_f32(line: usize, token: &str) -> Result<f32, MeshError> {
+    token.parse().or_else(|_| {
+        parse_error(line, format!("invalid number '{}'", token))
+    })
+}
+
+// Parses the tokens after the keyword as exactly `N` numbers, or between `min` and `N` numbers
+// with the remainder taken from `defaults`.
+fn parse_floats(
+    line: usize,
+    tokens: &[&str],
+    min: usize,
+    defaults: &[f32],
+) -> Result<Vec<f32>, MeshError> {
+    let max = defaults.len();
+    let args = &tokens[1..];
+    if args.len() < min || args.len() > max {
+        return parse_error(
+            line,
+            if min == max {
+                format!("'{}' expects {} values, found {}", tokens[0], max, args.len())
+            } else {
+                format!(
+                    "'{}' expects {} to {} values, found {}",
+                    tokens[0],
+                    min,
+                    max,
+                    args.len()
+                )
+            },
+        );
+    }
+    let mut values = defaults.to_vec();
+    for (v, t) in values.iter_mut().zip(args) {
+        *v = parse_f32(line, t)?;
+    }
+    Ok(values)
+}
+
+// Splits a polygon into triangles by ear clipping, so that concave faces are handled correctly.
+// The polygon is projected onto the plane of its largest normal component, and if it is
+// degenerate or self intersecting, falls back to a triangle fan.
+fn triangulate(points: &[Vector3<f32>]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    if n == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    let fan = || (1..n - 1).map(|i| [0, i, i + 1]).collect();
+
+    // Newell's method
+    let mut normal = Vector3::new(0.0, 0.0, 0.0);
+    for i in 0..n {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        normal.x += (a.y - b.y) * (a.z + b.z);
+        normal.y += (a.z - b.z) * (a.x + b.x);
+        normal.z += (a.x - b.x) * (a.y + b.y);
+    }
+    let (ax, ay, az) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+    let projected: Vec<Vector2<f32>> = points
+        .iter()
+        .map(|p| if az >= ax && az >= ay {
+            Vector2::new(p.x * normal.z.signum(), p.y)
+        } else if ax >= ay {
+            Vector2::new(p.y * normal.x.signum(), p.z)
+        } else {
+            Vector2::new(p.z * normal.y.signum(), p.x)
+        })
+        .collect();
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let ear = (0..m).find(|&i| {
+            let p = remaining[(i + m - 1) % m];
+            let c = remaining[i];
+            let q = remaining[(i + 1) % m];
+            let (a, b, d) = (projected[p], projected[c], projected[q]);
+            if (b - a).cross(d - a) <= 0.0 {
+                return false;
+            }
+            remaining.iter().all(|&o| {
+                if o == p || o == c || o == q {
+                    return true;
+                }
+                let x = projected[o];
+                !((b - a).cross(x - a) >= 0.0 && (d - b).cross(x - b) >= 0.0 &&
+                      (a - d).cross(x - d) >= 0.0)
+            })
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push([remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]]);
+                remaining.remove(i);
+            }
+            None => return fan(),
+        }
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
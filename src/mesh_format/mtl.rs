@@ -0,0 +1,211 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use vec3::Vector3;
+use super::{MeshError, Statements, parse_error, parse_f32, parse_floats};
+
+/// A material from a Wavefront MTL library.  Texture map paths are stored as written in the
+/// file, or resolved relative to the library when loaded with `load_mtl`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    pub ambient: Vector3<f32>,
+    pub diffuse: Vector3<f32>,
+    pub specular: Vector3<f32>,
+    pub emissive: Vector3<f32>,
+    pub shininess: f32,
+    pub optical_density: f32,
+    pub dissolve: f32,
+    pub illumination_model: u32,
+    pub ambient_map: Option<PathBuf>,
+    pub diffuse_map: Option<PathBuf>,
+    pub specular_map: Option<PathBuf>,
+    pub emissive_map: Option<PathBuf>,
+    pub shininess_map: Option<PathBuf>,
+    pub dissolve_map: Option<PathBuf>,
+    pub bump_map: Option<PathBuf>,
+    pub normal_map: Option<PathBuf>,
+}
+
+impl ObjMaterial {
+    pub fn new(name: String) -> ObjMaterial {
+        ObjMaterial {
+            name,
+            ambient: Vector3::new(0.0, 0.0, 0.0),
+            diffuse: Vector3::new(0.8, 0.8, 0.8),
+            specular: Vector3::new(0.0, 0.0, 0.0),
+            emissive: Vector3::new(0.0, 0.0, 0.0),
+            shininess: 0.0,
+            optical_density: 1.0,
+            dissolve: 1.0,
+            illumination_model: 2,
+            ambient_map: None,
+            diffuse_map: None,
+            specular_map: None,
+            emissive_map: None,
+            shininess_map: None,
+            dissolve_map: None,
+            bump_map: None,
+            normal_map: None,
+        }
+    }
+
+    fn texture_maps_mut(&mut self) -> [&mut Option<PathBuf>; 8] {
+        [
+            &mut self.ambient_map,
+            &mut self.diffuse_map,
+            &mut self.specular_map,
+            &mut self.emissive_map,
+            &mut self.shininess_map,
+            &mut self.dissolve_map,
+            &mut self.bump_map,
+            &mut self.normal_map,
+        ]
+    }
+}
+
+pub fn parse_mtl(source: &str) -> Result<Vec<ObjMaterial>, MeshError> {
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+
+    for (line, tokens) in Statements::new(source) {
+        let keyword = tokens[0];
+        if keyword == "newmtl" {
+            if tokens.len() < 2 {
+                return parse_error(line, "'newmtl' requires a material name");
+            }
+            materials.push(ObjMaterial::new(tokens[1..].join(" ")));
+            continue;
+        }
+
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => {
+                return parse_error(
+                    line,
+                    format!("'{}' appears before any 'newmtl' statement", keyword),
+                )
+            }
+        };
+
+        match keyword {
+            "Ka" => material.ambient = parse_color(line, &tokens)?,
+            "Kd" => material.diffuse = parse_color(line, &tokens)?,
+            "Ks" => material.specular = parse_color(line, &tokens)?,
+            "Ke" => material.emissive = parse_color(line, &tokens)?,
+            "Ns" => material.shininess = parse_floats(line, &tokens, 1, &[0.0])?[0],
+            "Ni" => material.optical_density = parse_floats(line, &tokens, 1, &[0.0])?[0],
+            "d" => material.dissolve = parse_dissolve(line, &tokens)?,
+            "Tr" => material.dissolve = 1.0 - parse_floats(line, &tokens, 1, &[0.0])?[0],
+            "illum" => {
+                material.illumination_model = match tokens.get(1).and_then(|t| t.parse().ok()) {
+                    Some(model) if tokens.len() == 2 => model,
+                    _ => return parse_error(line, "'illum' expects a single integer"),
+                }
+            }
+            "map_Ka" => material.ambient_map = Some(parse_map(line, &tokens)?),
+            "map_Kd" => material.diffuse_map = Some(parse_map(line, &tokens)?),
+            "map_Ks" => material.specular_map = Some(parse_map(line, &tokens)?),
+            "map_Ke" => material.emissive_map = Some(parse_map(line, &tokens)?),
+            "map_Ns" => material.shininess_map = Some(parse_map(line, &tokens)?),
+            "map_d" => material.dissolve_map = Some(parse_map(line, &tokens)?),
+            "map_bump" | "map_Bump" | "bump" => {
+                material.bump_map = Some(parse_map(line, &tokens)?)
+            }
+            "norm" | "map_Kn" => material.normal_map = Some(parse_map(line, &tokens)?),
+            // Other statements (reflection maps, transmission filters and various vendor
+            // extensions) do not affect rendering and are ignored.
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+/// Loads an MTL library, resolving texture map paths relative to the library's directory.
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<Vec<ObjMaterial>, MeshError> {
+    let path = path.as_ref();
+    let mut source = String::new();
+    File::open(path)?.read_to_string(&mut source)?;
+
+    let mut materials = parse_mtl(&source).map_err(|err| match err {
+        MeshError::Parse { line, message } => MeshError::Parse {
+            line,
+            message: format!("{}: {}", path.display(), message),
+        },
+        err => err,
+    })?;
+
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    for material in &mut materials {
+        for map in material.texture_maps_mut().iter_mut() {
+            if let Some(ref mut map) = **map {
+                *map = dir.join(&map);
+            }
+        }
+    }
+    Ok(materials)
+}
+
+fn parse_color(line: usize, tokens: &[&str]) -> Result<Vector3<f32>, MeshError> {
+    if tokens.get(1) == Some(&"spectral") || tokens.get(1) == Some(&"xyz") {
+        return parse_error(
+            line,
+            format!("'{} {}' colors are not supported", tokens[0], tokens[1]),
+        );
+    }
+    // A single value is used for all three channels.
+    match tokens.len() {
+        2 => {
+            let v = parse_f32(line, tokens[1])?;
+            Ok(Vector3::new(v, v, v))
+        }
+        4 => {
+            let v = parse_floats(line, tokens, 3, &[0.0; 3])?;
+            Ok(Vector3::new(v[0], v[1], v[2]))
+        }
+        n => parse_error(
+            line,
+            format!("'{}' expects 1 or 3 values, found {}", tokens[0], n - 1),
+        ),
+    }
+}
+
+fn parse_dissolve(line: usize, tokens: &[&str]) -> Result<f32, MeshError> {
+    match tokens.len() {
+        2 => parse_f32(line, tokens[1]),
+        // "d -halo factor"
+        3 if tokens[1] == "-halo" => parse_f32(line, tokens[2]),
+        _ => parse_error(line, "'d' expects a single value"),
+    }
+}
+
+// Texture map statements may have options such as "-bm 0.5" or "-s 1 1 1" before the file name.
+// Options are skipped according to their argument counts and the remaining tokens are the file
+// name, which may contain spaces.
+fn parse_map(line: usize, tokens: &[&str]) -> Result<PathBuf, MeshError> {
+    let mut i = 1;
+    while i < tokens.len() && tokens[i].starts_with('-') {
+        let args = match tokens[i] {
+            "-blendu" | "-blendv" | "-cc" | "-clamp" | "-texres" | "-bm" | "-boost" |
+            "-imfchan" | "-type" => 1,
+            "-mm" => 2,
+            "-o" | "-s" | "-t" => {
+                // Up to three numeric arguments
+                let mut n = 0;
+                while n < 3 && i + 1 + n < tokens.len() && tokens[i + 1 + n].parse::<f32>().is_ok()
+                {
+                    n += 1;
+                }
+                n
+            }
+            option => return parse_error(line, format!("unknown texture map option '{}'", option)),
+        };
+        i += 1 + args;
+    }
+
+    if i >= tokens.len() {
+        return parse_error(line, format!("'{}' requires a file name", tokens[0]));
+    }
+    Ok(PathBuf::from(tokens[i..].join(" ")))
+}
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use vec2::Vector2;
+use vec3::Vector3;
+use vec4::Vector4;
+use mesh::Mesh;
+use renderer::Triangle;
+use super::{MeshError, Statements, parse_error, parse_floats, triangulate};
+use super::mtl::{ObjMaterial, load_mtl};
+
+/// A mesh from an OBJ file, split wherever the object, group or material changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjObject {
+    /// Name from the most recent 'o' statement, empty if there was none.
+    pub object: String,
+    /// Names from the most recent 'g' statement, joined with spaces.
+    pub group: String,
+    pub material: Option<String>,
+    pub mesh: Mesh,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjModel {
+    pub objects: Vec<ObjObject>,
+    /// Files named by 'mtllib' statements, as written in the OBJ file.
+    pub material_libraries: Vec<String>,
+    pub materials: Vec<ObjMaterial>,
+}
+
+impl ObjModel {
+    pub fn material(&self, name: &str) -> Option<&ObjMaterial> {
+        self.materials.iter().find(|m| m.name == name)
+    }
+
+    /// Expands every object into renderer triangles.  Objects without vertex colors are colored
+    /// with the diffuse color of their material, or white if they have none.
+    pub fn triangles(&self) -> Vec<Triangle> {
+        let mut triangles = Vec::new();
+        for object in &self.objects {
+            let color = object
+                .material
+                .as_ref()
+                .and_then(|name| self.material(name))
+                .map(|m| Vector4::new(m.diffuse.x, m.diffuse.y, m.diffuse.z, m.dissolve))
+                .unwrap_or_else(|| Vector4::new(1.0, 1.0, 1.0, 1.0));
+            triangles.extend(object.mesh.triangles(color));
+        }
+        triangles
+    }
+}
+
+/// Parses OBJ source text.  Material libraries are recorded but not loaded, use `load_obj` to
+/// load a model along with its materials.
+pub fn parse_obj(source: &str) -> Result<ObjModel, MeshError> {
+    let mut parser = Parser::new();
+    for (line, tokens) in Statements::new(source) {
+        parser.statement(line, &tokens)?;
+    }
+    parser.finish_object();
+
+    Ok(ObjModel {
+        objects: parser.objects,
+        material_libraries: parser.material_libraries,
+        materials: Vec::new(),
+    })
+}
+
+/// Loads an OBJ file along with every MTL library it references, relative to the OBJ file's
+/// directory.
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<ObjModel, MeshError> {
+    let path = path.as_ref();
+    let mut source = String::new();
+    File::open(path)?.read_to_string(&mut source)?;
+
+    let mut model = parse_obj(&source).map_err(|err| match err {
+        MeshError::Parse { line, message } => MeshError::Parse {
+            line,
+            message: format!("{}: {}", path.display(), message),
+        },
+        err => err,
+    })?;
+
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_else(
+        PathBuf::new,
+    );
+    for library in &model.material_libraries {
+        model.materials.extend(load_mtl(dir.join(library))?);
+    }
+    Ok(model)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+struct VertexKey {
+    position: usize,
+    tex_coord: Option<usize>,
+    normal: Option<usize>,
+}
+
+struct Parser {
+    positions: Vec<Vector3<f32>>,
+    colors: Vec<Option<Vector4<f32>>>,
+    tex_coords: Vec<Vector2<f32>>,
+    normals: Vec<Vector3<f32>>,
+    material_libraries: Vec<String>,
+
+    object: String,
+    group: String,
+    material: Option<String>,
+    mesh: Mesh,
+    vertex_map: HashMap<VertexKey, u32>,
+    // Whether the current mesh uses texture coordinates, normals or colors at all
+    has_tex_coords: bool,
+    has_normals: bool,
+    has_colors: bool,
+
+    objects: Vec<ObjObject>,
+}
+
+impl Parser {
+    fn new() -> Parser {
+        Parser {
+            positions: Vec::new(),
+            colors: Vec::new(),
+            tex_coords: Vec::new(),
+            normals: Vec::new(),
+            material_libraries: Vec::new(),
+            object: String::new(),
+            group: String::new(),
+            material: None,
+            mesh: Mesh::new(),
+            vertex_map: HashMap::new(),
+            has_tex_coords: false,
+            has_normals: false,
+            has_colors: false,
+            objects: Vec::new(),
+        }
+    }
+
+    fn statement(&mut self, line: usize, tokens: &[&str]) -> Result<(), MeshError> {
+        match tokens[0] {
+            "v" => {
+                // Vertices may have a w weight, which only matters for rational curves, or an RGB
+                // color as a common extension.
+                match tokens.len() {
+                    4 | 5 => {
+                        let v = parse_floats(line, tokens, 3, &[0.0, 0.0, 0.0, 1.0])?;
+                        self.positions.push(Vector3::new(v[0], v[1], v[2]));
+                        self.colors.push(None);
+                    }
+                    7 => {
+                        let v = parse_floats(line, tokens, 6, &[0.0; 6])?;
+                        self.positions.push(Vector3::new(v[0], v[1], v[2]));
+                        self.colors.push(Some(Vector4::new(v[3], v[4], v[5], 1.0)));
+                    }
+                    n => {
+                        return parse_error(
+                            line,
+                            format!("'v' expects 3, 4 or 6 values, found {}", n - 1),
+                        )
+                    }
+                }
+            }
+            "vt" => {
+                let v = parse_floats(line, tokens, 1, &[0.0, 0.0, 0.0])?;
+                self.tex_coords.push(Vector2::new(v[0], v[1]));
+            }
+            "vn" => {
+                let v = parse_floats(line, tokens, 3, &[0.0, 0.0, 0.0])?;
+                self.normals.push(Vector3::new(v[0], v[1], v[2]));
+            }
+            "f" => self.face(line, tokens)?,
+            "o" => {
+                self.finish_object();
+                self.object = tokens[1..].join(" ");
+            }
+            "g" => {
+                self.finish_object();
+                self.group = tokens[1..].join(" ");
+            }
+            "usemtl" => {
+                if tokens.len() < 2 {
+                    return parse_error(line, "'usemtl' requires a material name");
+                }
+                self.finish_object();
+                self.material = Some(tokens[1..].join(" "));
+            }
+            "mtllib" => {
+                if tokens.len() < 2 {
+                    return parse_error(line, "'mtllib' requires a file name");
+                }
+                self.material_libraries.extend(
+                    tokens[1..].iter().map(|t| t.to_string()),
+                );
+            }
+            // Smoothing groups only matter for generating normals, points, lines, curves and
+            // surfaces cannot be rendered as triangles, and unknown statements are most likely
+            // vendor extensions.
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn face(&mut self, line: usize, tokens: &[&str]) -> Result<(), MeshError> {
+        if tokens.len() < 4 {
+            return parse_error(
+                line,
+                format!("face has {} vertices, at least 3 are required", tokens.len() - 1),
+            );
+        }
+
+        let mut keys = Vec::with_capacity(tokens.len() - 1);
+        for token in &tokens[1..] {
+            let mut parts = token.split('/');
+            let position = parts.next().unwrap_or("");
+            let tex_coord = parts.next().unwrap_or("");
+            let normal = parts.next().unwrap_or("");
+            if parts.next().is_some() {
+                return parse_error(line, format!("invalid face vertex '{}'", token));
+            }
+
+            let key = VertexKey {
+                position: resolve_index(line, position, self.positions.len(), "vertex")?,
+                tex_coord: if tex_coord.is_empty() {
+                    None
+                } else {
+                    Some(resolve_index(
+                        line,
+                        tex_coord,
+                        self.tex_coords.len(),
+                        "texture coordinate",
+                    )?)
+                },
+                normal: if normal.is_empty() {
+                    None
+                } else {
+                    Some(resolve_index(line, normal, self.normals.len(), "normal")?)
+                },
+            };
+            keys.push(key);
+        }
+
+        let points: Vec<Vector3<f32>> = keys.iter().map(|k| self.positions[k.position]).collect();
+        let indices: Vec<u32> = keys.iter().map(|&k| self.vertex(k)).collect();
+        for t in triangulate(&points) {
+            self.mesh.indices.extend_from_slice(
+                &[indices[t[0]], indices[t[1]], indices[t[2]]],
+            );
+        }
+        Ok(())
+    }
+
+    fn vertex(&mut self, key: VertexKey) -> u32 {
+        if let Some(&index) = self.vertex_map.get(&key) {
+            return index;
+        }
+
+        let index = self.mesh.positions.len() as u32;
+        self.mesh.positions.push(self.positions[key.position]);
+
+        // Attributes are kept in lockstep with positions, and back filled the first time a mesh
+        // vertex uses one.
+        let count = index as usize;
+        if let Some(t) = key.tex_coord {
+            if !self.has_tex_coords {
+                self.has_tex_coords = true;
+                self.mesh.tex_coords.resize(count, Vector2::new(0.0, 0.0));
+            }
+            self.mesh.tex_coords.push(self.tex_coords[t]);
+        } else if self.has_tex_coords {
+            self.mesh.tex_coords.push(Vector2::new(0.0, 0.0));
+        }
+
+        if let Some(n) = key.normal {
+            if !self.has_normals {
+                self.has_normals = true;
+                self.mesh.normals.resize(count, Vector3::new(0.0, 0.0, 0.0));
+            }
+            self.mesh.normals.push(self.normals[n]);
+        } else if self.has_normals {
+            self.mesh.normals.push(Vector3::new(0.0, 0.0, 0.0));
+        }
+
+        let white = Vector4::new(1.0, 1.0, 1.0, 1.0);
+        if let Some(c) = self.colors[key.position] {
+            if !self.has_colors {
+                self.has_colors = true;
+                self.mesh.colors.resize(count, white);
+            }
+            self.mesh.colors.push(c);
+        } else if self.has_colors {
+            self.mesh.colors.push(white);
+        }
+
+        self.vertex_map.insert(key, index);
+        index
+    }
+
+    fn finish_object(&mut self) {
+        if !self.mesh.is_empty() {
+            self.objects.push(ObjObject {
+                object: self.object.clone(),
+                group: self.group.clone(),
+                material: self.material.clone(),
+                mesh: ::std::mem::take(&mut self.mesh),
+            });
+        }
+        self.mesh = Mesh::new();
+        self.vertex_map.clear();
+        self.has_tex_coords = false;
+        self.has_normals = false;
+        self.has_colors = false;
+    }
+}
+
+// OBJ indices start at 1, negative indices count backwards from the most recently defined element.
+fn resolve_index(line: usize, token: &str, count: usize, kind: &str) -> Result<usize, MeshError> {
+    let index: i64 = match token.parse() {
+        Ok(index) => index,
+        Err(_) => return parse_error(line, format!("invalid {} index '{}'", kind, token)),
+    };
+
+    let resolved = if index > 0 {
+        index - 1
+    } else if index < 0 {
+        count as i64 + index
+    } else {
+        return parse_error(line, format!("{} index 0 is invalid, indices start at 1", kind));
+    };
+
+    if resolved < 0 || resolved >= count as i64 {
+        return parse_error(
+            line,
+            format!(
+                "{} index {} is out of range, only {} defined",
+                kind,
+                index,
+                count
+            ),
+        );
+    }
+    Ok(resolved as usize)
+}
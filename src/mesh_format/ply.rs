@@ -0,0 +1,579 @@
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+use vec2::Vector2;
+use vec3::Vector3;
+use vec4::Vector4;
+use mesh::Mesh;
+use super::{MeshError, parse_error, triangulate};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// Decodes a PLY file.
+///
+/// Vertex positions, normals (`nx`, `ny`, `nz`), texture coordinates (`u`/`v` or `s`/`t`) and
+/// colors (`red`, `green`, `blue` and optionally `alpha`) are read from the "vertex" element and
+/// polygons from the "face" element, other elements and properties are skipped.  Integer colors
+/// are scaled to the range [0, 1].
+pub fn decode(data: &[u8]) -> Result<Mesh, MeshError> {
+    let (header, body) = parse_header(data)?;
+    let mut reader = match header.format {
+        PlyFormat::Ascii => {
+            let body = match ::std::str::from_utf8(body) {
+                Ok(body) => body,
+                Err(_) => return malformed("ASCII PLY data is not valid UTF-8"),
+            };
+            Reader::Ascii {
+                lines: body.lines().enumerate(),
+                tokens: "".split_whitespace(),
+                line: 0,
+                first_line: header.lines,
+            }
+        }
+        format => Reader::Binary {
+            data: body,
+            pos: 0,
+            big_endian: format == PlyFormat::BinaryBigEndian,
+        },
+    };
+
+    let mut mesh = Mesh::new();
+    let mut polygons = Vec::new();
+    for element in &header.elements {
+        match element.name.as_str() {
+            "vertex" => read_vertices(&mut reader, element, &mut mesh)?,
+            "face" => polygons = read_faces(&mut reader, element)?,
+            // Elements without properties take up no data, whatever their count.
+            _ if element.properties.is_empty() => {}
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        reader.property(property)?;
+                    }
+                }
+            }
+        }
+    }
+
+    let count = mesh.positions.len();
+    for polygon in polygons {
+        if let Some(&i) = polygon.iter().find(|&&i| i as usize >= count) {
+            return malformed(format!(
+                "face refers to vertex {}, only {} vertices exist",
+                i,
+                count
+            ));
+        }
+        if polygon.len() < 3 {
+            continue;
+        }
+        let points: Vec<Vector3<f32>> = polygon
+            .iter()
+            .map(|&i| mesh.positions[i as usize])
+            .collect();
+        for t in triangulate(&points) {
+            mesh.indices.extend(t.iter().map(|&i| polygon[i]));
+        }
+    }
+    Ok(mesh)
+}
+
+pub fn load_ply<P: AsRef<Path>>(path: P) -> Result<Mesh, MeshError> {
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+    decode(&data)
+}
+
+/// Encodes a mesh as PLY.  Normals, texture coordinates and colors are written when the mesh
+/// has them, with colors stored as bytes.
+pub fn encode<W: Write>(writer: &mut W, mesh: &Mesh, format: PlyFormat) -> io::Result<()> {
+    let count = mesh.positions.len();
+    let has_normals = !mesh.normals.is_empty() && mesh.normals.len() == count;
+    let has_tex_coords = !mesh.tex_coords.is_empty() && mesh.tex_coords.len() == count;
+    let has_colors = !mesh.colors.is_empty() && mesh.colors.len() == count;
+
+    writeln!(writer, "ply")?;
+    writeln!(
+        writer,
+        "format {} 1.0",
+        match format {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+            PlyFormat::BinaryBigEndian => "binary_big_endian",
+        }
+    )?;
+    writeln!(writer, "comment written by rrasterizer")?;
+    writeln!(writer, "element vertex {}", count)?;
+    let mut names = vec!["x", "y", "z"];
+    if has_normals {
+        names.extend_from_slice(&["nx", "ny", "nz"]);
+    }
+    if has_tex_coords {
+        names.extend_from_slice(&["s", "t"]);
+    }
+    for name in names {
+        writeln!(writer, "property float {}", name)?;
+    }
+    if has_colors {
+        for name in &["red", "green", "blue", "alpha"] {
+            writeln!(writer, "property uchar {}", name)?;
+        }
+    }
+    writeln!(writer, "element face {}", mesh.triangle_count())?;
+    writeln!(writer, "property list uchar int vertex_indices")?;
+    writeln!(writer, "end_header")?;
+
+    let mut out = Writer {
+        writer: writer as &mut dyn Write,
+        format,
+        separator: "",
+    };
+    for i in 0..count {
+        let p = mesh.positions[i];
+        let mut floats = vec![p.x, p.y, p.z];
+        if has_normals {
+            let n = mesh.normals[i];
+            floats.extend_from_slice(&[n.x, n.y, n.z]);
+        }
+        if has_tex_coords {
+            let t = mesh.tex_coords[i];
+            floats.extend_from_slice(&[t.x, t.y]);
+        }
+        for f in floats {
+            out.float(f)?;
+        }
+        if has_colors {
+            let c = mesh.colors[i];
+            for v in &[c.x, c.y, c.z, c.w] {
+                out.byte((v.clamp(0.0, 1.0) * 255.0).round() as u8)?;
+            }
+        }
+        out.end_line()?;
+    }
+    for t in mesh.indices.chunks(3).filter(|t| t.len() == 3) {
+        out.byte(3)?;
+        for &i in t {
+            out.int(i as i32)?;
+        }
+        out.end_line()?;
+    }
+    Ok(())
+}
+
+pub fn save_ply<P: AsRef<Path>>(path: P, mesh: &Mesh, format: PlyFormat) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    encode(&mut writer, mesh, format)?;
+    writer.flush()
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<ScalarType> {
+        Some(match name {
+            "char" | "int8" => ScalarType::Int8,
+            "uchar" | "uint8" => ScalarType::UInt8,
+            "short" | "int16" => ScalarType::Int16,
+            "ushort" | "uint16" => ScalarType::UInt16,
+            "int" | "int32" => ScalarType::Int32,
+            "uint" | "uint32" => ScalarType::UInt32,
+            "float" | "float32" => ScalarType::Float32,
+            "double" | "float64" => ScalarType::Float64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+
+    // The value that represents full intensity when the type is used for a color channel.
+    fn color_scale(self) -> f64 {
+        match self {
+            ScalarType::Int8 => 127.0,
+            ScalarType::UInt8 => 255.0,
+            ScalarType::Int16 => 32767.0,
+            ScalarType::UInt16 => 65535.0,
+            ScalarType::Int32 => 2_147_483_647.0,
+            ScalarType::UInt32 => 4_294_967_295.0,
+            ScalarType::Float32 | ScalarType::Float64 => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    kind: ScalarType,
+    /// The type of the element count for list properties.
+    list_count: Option<ScalarType>,
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: PlyFormat,
+    elements: Vec<Element>,
+    /// Number of lines in the header, used to report line numbers in ASCII data.
+    lines: usize,
+}
+
+fn parse_header(data: &[u8]) -> Result<(Header, &[u8]), MeshError> {
+    if !data.starts_with(b"ply") {
+        return malformed("missing PLY signature");
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut pos = 0;
+    let mut line = 0;
+    loop {
+        let end = match data[pos..].iter().position(|&c| c == b'\n') {
+            Some(end) => pos + end,
+            None => return malformed("PLY header has no 'end_header' line"),
+        };
+        let text = match ::std::str::from_utf8(&data[pos..end]) {
+            Ok(text) => text,
+            Err(_) => return malformed("PLY header is not valid UTF-8"),
+        };
+        pos = end + 1;
+        line += 1;
+
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        match tokens.first().cloned() {
+            Some("ply") if line == 1 => {}
+            Some("format") => {
+                if tokens.get(2) != Some(&"1.0") {
+                    return parse_error(line, "only PLY version 1.0 is supported");
+                }
+                format = Some(match tokens.get(1).cloned() {
+                    Some("ascii") => PlyFormat::Ascii,
+                    Some("binary_little_endian") => PlyFormat::BinaryLittleEndian,
+                    Some("binary_big_endian") => PlyFormat::BinaryBigEndian,
+                    _ => return parse_error(line, "invalid PLY format"),
+                });
+            }
+            Some("element") => {
+                let count = match tokens.get(2).and_then(|c| c.parse().ok()) {
+                    Some(count) if tokens.len() == 3 => count,
+                    _ => return parse_error(line, "'element' expects a name and a count"),
+                };
+                elements.push(Element {
+                    name: tokens[1].to_owned(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            Some("property") => {
+                let element = match elements.last_mut() {
+                    Some(element) => element,
+                    None => return parse_error(line, "'property' appears before any 'element'"),
+                };
+                let property = if tokens.get(1) == Some(&"list") && tokens.len() == 5 {
+                    match (ScalarType::parse(tokens[2]), ScalarType::parse(tokens[3])) {
+                        (Some(count), Some(kind)) => Property {
+                            name: tokens[4].to_owned(),
+                            kind,
+                            list_count: Some(count),
+                        },
+                        _ => return parse_error(line, "invalid list property type"),
+                    }
+                } else if tokens.len() == 3 {
+                    match ScalarType::parse(tokens[1]) {
+                        Some(kind) => Property {
+                            name: tokens[2].to_owned(),
+                            kind,
+                            list_count: None,
+                        },
+                        None => {
+                            return parse_error(
+                                line,
+                                format!("invalid property type '{}'", tokens[1]),
+                            )
+                        }
+                    }
+                } else {
+                    return parse_error(line, "'property' expects a type and a name");
+                };
+                element.properties.push(property);
+            }
+            Some("end_header") => break,
+            Some("comment") | Some("obj_info") | None => {}
+            Some(keyword) => {
+                return parse_error(line, format!("unknown header statement '{}'", keyword))
+            }
+        }
+    }
+
+    match format {
+        Some(format) => Ok((
+            Header {
+                format,
+                elements,
+                lines: line,
+            },
+            &data[pos..],
+        )),
+        None => malformed("PLY header has no 'format' line"),
+    }
+}
+
+enum Reader<'a> {
+    Ascii {
+        lines: ::std::iter::Enumerate<::std::str::Lines<'a>>,
+        tokens: ::std::str::SplitWhitespace<'a>,
+        line: usize,
+        first_line: usize,
+    },
+    Binary {
+        data: &'a [u8],
+        pos: usize,
+        big_endian: bool,
+    },
+}
+
+impl<'a> Reader<'a> {
+    fn scalar(&mut self, kind: ScalarType) -> Result<f64, MeshError> {
+        match *self {
+            Reader::Ascii {
+                ref mut lines,
+                ref mut tokens,
+                ref mut line,
+                first_line,
+            } => {
+                let token = loop {
+                    if let Some(token) = tokens.next() {
+                        break token;
+                    }
+                    match lines.next() {
+                        Some((i, text)) => {
+                            *line = first_line + i + 1;
+                            *tokens = text.split_whitespace();
+                        }
+                        None => return malformed("PLY data ends unexpectedly"),
+                    }
+                };
+                let value = match kind {
+                    ScalarType::Float32 | ScalarType::Float64 => token.parse().ok(),
+                    _ => token.parse::<i64>().ok().map(|v| v as f64),
+                };
+                match value {
+                    Some(value) => Ok(value),
+                    None => parse_error(*line, format!("invalid value '{}'", token)),
+                }
+            }
+            Reader::Binary {
+                data,
+                ref mut pos,
+                big_endian,
+            } => {
+                let size = kind.size();
+                if data.len() - *pos < size {
+                    return malformed("PLY data ends unexpectedly");
+                }
+                let mut bytes = [0; 8];
+                bytes[..size].copy_from_slice(&data[*pos..*pos + size]);
+                if big_endian {
+                    bytes[..size].reverse();
+                }
+                *pos += size;
+                Ok(match kind {
+                    ScalarType::Int8 => bytes[0] as i8 as f64,
+                    ScalarType::UInt8 => bytes[0] as f64,
+                    ScalarType::Int16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    ScalarType::UInt16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    ScalarType::Int32 => {
+                        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::UInt32 => {
+                        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::Float32 => {
+                        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::Float64 => f64::from_le_bytes(bytes),
+                })
+            }
+        }
+    }
+
+    // Reads a property, returning its values.  Scalar properties have a single value.
+    fn property(&mut self, property: &Property) -> Result<Vec<f64>, MeshError> {
+        match property.list_count {
+            Some(count_kind) => {
+                let count = self.scalar(count_kind)?;
+                if count < 0.0 {
+                    return malformed(format!("list property '{}' has a negative length", property.name));
+                }
+                // The count comes from the file, so the list grows as its values are read.
+                let mut values = Vec::new();
+                for _ in 0..count as usize {
+                    values.push(self.scalar(property.kind)?);
+                }
+                Ok(values)
+            }
+            None => Ok(vec![self.scalar(property.kind)?]),
+        }
+    }
+}
+
+fn read_vertices(reader: &mut Reader, element: &Element, mesh: &mut Mesh) -> Result<(), MeshError> {
+    let find = |names: &[&str]| {
+        element.properties.iter().position(|p| {
+            p.list_count.is_none() && names.contains(&p.name.as_str())
+        })
+    };
+    let all = |indices: &[Option<usize>]| -> Option<Vec<usize>> { indices.iter().cloned().collect() };
+
+    let position = match all(&[find(&["x"]), find(&["y"]), find(&["z"])]) {
+        Some(position) => position,
+        None => return malformed("PLY vertex element has no x, y and z properties"),
+    };
+    let normal = all(&[find(&["nx"]), find(&["ny"]), find(&["nz"])]);
+    let tex_coord = all(
+        &[
+            find(&["u", "s", "texture_u", "texture_s"]),
+            find(&["v", "t", "texture_v", "texture_t"]),
+        ],
+    );
+    let color = all(
+        &[
+            find(&["red", "diffuse_red"]),
+            find(&["green", "diffuse_green"]),
+            find(&["blue", "diffuse_blue"]),
+        ],
+    );
+    let alpha = find(&["alpha", "diffuse_alpha"]);
+    let color_scale = |i: usize| element.properties[i].kind.color_scale();
+
+    let mut values = vec![0.0; element.properties.len()];
+    for _ in 0..element.count {
+        for (value, property) in values.iter_mut().zip(&element.properties) {
+            // List properties on vertices are skipped, so only their first value is kept.
+            *value = reader.property(property)?.first().cloned().unwrap_or(0.0);
+        }
+        let get = |i: usize| values[i] as f32;
+
+        mesh.positions.push(Vector3::new(
+            get(position[0]),
+            get(position[1]),
+            get(position[2]),
+        ));
+        if let Some(ref n) = normal {
+            mesh.normals.push(Vector3::new(get(n[0]), get(n[1]), get(n[2])));
+        }
+        if let Some(ref t) = tex_coord {
+            mesh.tex_coords.push(Vector2::new(get(t[0]), get(t[1])));
+        }
+        if let Some(ref c) = color {
+            let channel = |i: usize| (values[i] / color_scale(i)) as f32;
+            mesh.colors.push(Vector4::new(
+                channel(c[0]),
+                channel(c[1]),
+                channel(c[2]),
+                alpha.map(channel).unwrap_or(1.0),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn read_faces(reader: &mut Reader, element: &Element) -> Result<Vec<Vec<u32>>, MeshError> {
+    let indices = element.properties.iter().position(|p| {
+        p.list_count.is_some() && (p.name == "vertex_indices" || p.name == "vertex_index")
+    });
+    let indices = match indices {
+        Some(indices) => indices,
+        None => return malformed("PLY face element has no vertex_indices property"),
+    };
+
+    // Not reserved from the header's count, which may be far more than the data holds.
+    let mut polygons = Vec::new();
+    for _ in 0..element.count {
+        for (i, property) in element.properties.iter().enumerate() {
+            let values = reader.property(property)?;
+            if i == indices {
+                let mut polygon = Vec::with_capacity(values.len());
+                for v in values {
+                    if v < 0.0 {
+                        return malformed(format!("face refers to negative vertex index {}", v));
+                    }
+                    polygon.push(v as u32);
+                }
+                polygons.push(polygon);
+            }
+        }
+    }
+    Ok(polygons)
+}
+
+struct Writer<'a> {
+    writer: &'a mut dyn Write,
+    format: PlyFormat,
+    separator: &'static str,
+}
+
+impl<'a> Writer<'a> {
+    fn write(&mut self, text: &dyn Display, le: &[u8], be: &[u8]) -> io::Result<()> {
+        match self.format {
+            PlyFormat::Ascii => {
+                write!(self.writer, "{}{}", self.separator, text)?;
+                self.separator = " ";
+                Ok(())
+            }
+            PlyFormat::BinaryLittleEndian => self.writer.write_all(le),
+            PlyFormat::BinaryBigEndian => self.writer.write_all(be),
+        }
+    }
+
+    fn float(&mut self, v: f32) -> io::Result<()> {
+        self.write(&v, &v.to_le_bytes(), &v.to_be_bytes())
+    }
+
+    fn int(&mut self, v: i32) -> io::Result<()> {
+        self.write(&v, &v.to_le_bytes(), &v.to_be_bytes())
+    }
+
+    fn byte(&mut self, v: u8) -> io::Result<()> {
+        self.write(&v, &[v], &[v])
+    }
+
+    fn end_line(&mut self) -> io::Result<()> {
+        self.separator = "";
+        match self.format {
+            PlyFormat::Ascii => writeln!(self.writer),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn malformed<T, S: Into<String>>(msg: S) -> Result<T, MeshError> {
+    Err(MeshError::Malformed(msg.into()))
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+use vec3::Vector3;
+use mesh::Mesh;
+use super::{MeshError, Statements, parse_error, parse_floats};
+
+/// Decodes an ASCII or binary STL file.
+///
+/// STL stores every triangle separately, so vertices with identical positions are merged to
+/// produce an indexed mesh.  Facet normals are ignored, use `Mesh::compute_normals` to generate
+/// vertex normals.
+pub fn decode(data: &[u8]) -> Result<Mesh, MeshError> {
+    // Binary files may also start with "solid", so check whether the size matches the triangle
+    // count first.
+    if data.len() >= 84 {
+        let count = read_u32(&data[80..]) as u64;
+        if 84 + 50 * count == data.len() as u64 {
+            return Ok(decode_binary(&data[84..], count as usize));
+        }
+    }
+
+    if data.starts_with(b"solid") {
+        match ::std::str::from_utf8(data) {
+            Ok(source) => decode_ascii(source),
+            Err(_) => Err(MeshError::Malformed(
+                "ASCII STL file is not valid UTF-8".to_owned(),
+            )),
+        }
+    } else if data.len() >= 84 {
+        Err(MeshError::Malformed(format!(
+            "binary STL file has {} bytes, expected {} for {} triangles",
+            data.len(),
+            84 + 50 * read_u32(&data[80..]) as u64,
+            read_u32(&data[80..])
+        )))
+    } else {
+        Err(MeshError::Malformed("STL file is truncated".to_owned()))
+    }
+}
+
+pub fn load_stl<P: AsRef<Path>>(path: P) -> Result<Mesh, MeshError> {
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+    decode(&data)
+}
+
+/// Encodes a mesh as binary STL, with facet normals computed from the triangle winding.
+pub fn encode<W: Write>(writer: &mut W, mesh: &Mesh) -> io::Result<()> {
+    let mut header = [0; 80];
+    let comment = b"binary STL written by rrasterizer";
+    header[..comment.len()].copy_from_slice(comment);
+    writer.write_all(&header)?;
+    writer.write_all(&(mesh.triangle_count() as u32).to_le_bytes())?;
+
+    for [a, b, c] in facets(mesh) {
+        let mut facet = [0; 50];
+        let values = [facet_normal(a, b, c), a, b, c];
+        for (i, v) in values.iter().enumerate() {
+            facet[i * 12..i * 12 + 4].copy_from_slice(&v.x.to_le_bytes());
+            facet[i * 12 + 4..i * 12 + 8].copy_from_slice(&v.y.to_le_bytes());
+            facet[i * 12 + 8..i * 12 + 12].copy_from_slice(&v.z.to_le_bytes());
+        }
+        writer.write_all(&facet)?;
+    }
+    Ok(())
+}
+
+pub fn encode_ascii<W: Write>(writer: &mut W, mesh: &Mesh, name: &str) -> io::Result<()> {
+    writeln!(writer, "solid {}", name)?;
+    for [a, b, c] in facets(mesh) {
+        let n = facet_normal(a, b, c);
+        writeln!(writer, "  facet normal {} {} {}", n.x, n.y, n.z)?;
+        writeln!(writer, "    outer loop")?;
+        for v in &[a, b, c] {
+            writeln!(writer, "      vertex {} {} {}", v.x, v.y, v.z)?;
+        }
+        writeln!(writer, "    endloop")?;
+        writeln!(writer, "  endfacet")?;
+    }
+    writeln!(writer, "endsolid {}", name)
+}
+
+/// Saves a mesh as binary STL.
+pub fn save_stl<P: AsRef<Path>>(path: P, mesh: &Mesh) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    encode(&mut writer, mesh)?;
+    writer.flush()
+}
+
+fn decode_binary(data: &[u8], count: usize) -> Mesh {
+    let mut builder = Welder::new();
+    for facet in data.chunks(50).take(count) {
+        for v in 1..4 {
+            let p = &facet[v * 12..];
+            builder.vertex(Vector3::new(
+                read_f32(p),
+                read_f32(&p[4..]),
+                read_f32(&p[8..]),
+            ));
+        }
+    }
+    builder.mesh
+}
+
+fn decode_ascii(source: &str) -> Result<Mesh, MeshError> {
+    let mut builder = Welder::new();
+    let mut in_loop = false;
+    let mut loop_vertices = Vec::new();
+
+    for (line, tokens) in Statements::new(source) {
+        match tokens[0] {
+            "outer" => {
+                if in_loop || tokens.get(1) != Some(&"loop") {
+                    return parse_error(line, "unexpected 'outer' statement");
+                }
+                in_loop = true;
+            }
+            "vertex" => {
+                if !in_loop {
+                    return parse_error(line, "'vertex' outside of a facet loop");
+                }
+                let v = parse_floats(line, &tokens, 3, &[0.0; 3])?;
+                loop_vertices.push(Vector3::new(v[0], v[1], v[2]));
+            }
+            "endloop" => {
+                if !in_loop {
+                    return parse_error(line, "'endloop' without a matching 'outer loop'");
+                }
+                if loop_vertices.len() < 3 {
+                    return parse_error(
+                        line,
+                        format!("facet has {} vertices, expected 3", loop_vertices.len()),
+                    );
+                }
+                // Some exporters write polygons with more than three vertices.
+                for i in 1..loop_vertices.len() - 1 {
+                    builder.vertex(loop_vertices[0]);
+                    builder.vertex(loop_vertices[i]);
+                    builder.vertex(loop_vertices[i + 1]);
+                }
+                loop_vertices.clear();
+                in_loop = false;
+            }
+            "solid" | "endsolid" | "facet" | "endfacet" => {}
+            keyword => return parse_error(line, format!("unknown statement '{}'", keyword)),
+        }
+    }
+
+    if in_loop {
+        return Err(MeshError::Malformed(
+            "ASCII STL file ends inside a facet".to_owned(),
+        ));
+    }
+    Ok(builder.mesh)
+}
+
+// Builds an indexed mesh from a triangle soup, merging vertices with bitwise equal positions.
+struct Welder {
+    mesh: Mesh,
+    vertex_map: HashMap<[u32; 3], u32>,
+}
+
+impl Welder {
+    fn new() -> Welder {
+        Welder {
+            mesh: Mesh::new(),
+            vertex_map: HashMap::new(),
+        }
+    }
+
+    fn vertex(&mut self, p: Vector3<f32>) {
+        // Adding zero turns negative zero into positive zero, so both are merged.
+        let key = [(p.x + 0.0).to_bits(), (p.y + 0.0).to_bits(), (p.z + 0.0).to_bits()];
+        let mesh = &mut self.mesh;
+        let index = *self.vertex_map.entry(key).or_insert_with(|| {
+            mesh.positions.push(p);
+            mesh.positions.len() as u32 - 1
+        });
+        mesh.indices.push(index);
+    }
+}
+
+fn facets<'a>(mesh: &'a Mesh) -> impl Iterator<Item = [Vector3<f32>; 3]> + 'a {
+    mesh.indices.chunks(3).filter(|t| t.len() == 3).map(move |t| {
+        [
+            mesh.positions[t[0] as usize],
+            mesh.positions[t[1] as usize],
+            mesh.positions[t[2] as usize],
+        ]
+    })
+}
+
+fn facet_normal(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> Vector3<f32> {
+    let n = (b - a).cross(c - a);
+    if n.magnitude_squared() > 0.0 {
+        n.normalize()
+    } else {
+        n
+    }
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    data[0] as u32 | (data[1] as u32) << 8 | (data[2] as u32) << 16 | (data[3] as u32) << 24
+}
+
+fn read_f32(data: &[u8]) -> f32 {
+    f32::from_bits(read_u32(data))
+}
@@ -0,0 +1,167 @@
+use std::f32;
+use std::rc::Rc;
+
+use vec2::Vector2;
+use vec3::Vector3;
+use vec4::Vector4;
+use lighting::PlacedLight;
+use texture::Texture;
+
+/// Reflectance of dielectrics at normal incidence, used for every non-metal.
+pub const DIELECTRIC_F0: f32 = 0.04;
+
+/// A metallic-roughness material, as in glTF.  Every input is a constant factor, multiplied by
+/// a texture when there is one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PbrMaterial {
+    /// Linear RGBA, multiplied by the vertex color.
+    pub base_color: Vector4<f32>,
+    pub base_color_texture: Option<Rc<Texture>>,
+    pub metallic: f32,
+    /// Perceptual roughness, squared to get the GGX alpha.
+    pub roughness: f32,
+    /// Roughness is sampled from the green channel and metalness from the blue channel.
+    pub metallic_roughness_texture: Option<Rc<Texture>>,
+    pub emissive: Vector3<f32>,
+    pub emissive_texture: Option<Rc<Texture>>,
+    /// Ambient occlusion is sampled from the red channel.  It only darkens ambient light.
+    pub occlusion_texture: Option<Rc<Texture>>,
+    pub occlusion_strength: f32,
+}
+
+impl Default for PbrMaterial {
+    fn default() -> PbrMaterial {
+        PbrMaterial {
+            base_color: Vector4::splat(1.0),
+            base_color_texture: None,
+            metallic: 0.0,
+            roughness: 0.5,
+            metallic_roughness_texture: None,
+            emissive: Vector3::splat(0.0),
+            emissive_texture: None,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+        }
+    }
+}
+
+impl PbrMaterial {
+    /// Evaluates the material's inputs at a point with the given vertex color and texture
+    /// coordinates.
+    pub fn surface(&self, color: Vector4<f32>, tex_coord: Vector2<f32>) -> Surface {
+        let sample = |texture: &Option<Rc<Texture>>| {
+            texture.as_ref().map_or(Vector4::splat(1.0), |t| t.sample(tex_coord))
+        };
+        let base_color = self.base_color * color * sample(&self.base_color_texture);
+        let metallic_roughness = sample(&self.metallic_roughness_texture);
+        let occlusion = match self.occlusion_texture {
+            Some(ref texture) => {
+                1.0 + self.occlusion_strength * (texture.sample(tex_coord).x - 1.0)
+            }
+            None => 1.0,
+        };
+        Surface {
+            base_color,
+            metallic: (self.metallic * metallic_roughness.z).clamp(0.0, 1.0),
+            roughness: (self.roughness * metallic_roughness.y).clamp(0.0, 1.0),
+            emissive: self.emissive * sample(&self.emissive_texture).vec3(),
+            occlusion,
+        }
+    }
+}
+
+/// The material inputs at a single point.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Surface {
+    pub base_color: Vector4<f32>,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: Vector3<f32>,
+    pub occlusion: f32,
+}
+
+// Keeps perfectly smooth surfaces from having an infinitely sharp highlight, which punctual
+// lights would never hit.
+const MIN_ALPHA: f32 = 1e-3;
+
+/// The GGX (Trowbridge-Reitz) normal distribution function.
+pub fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let alpha = (roughness * roughness).max(MIN_ALPHA);
+    let alpha2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (f32::consts::PI * d * d)
+}
+
+/// The height-correlated Smith visibility term for GGX, which is the geometric shadowing term
+/// divided by `4 (n·l) (n·v)`.
+pub fn visibility_smith_ggx(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let alpha = (roughness * roughness).max(MIN_ALPHA);
+    let alpha2 = alpha * alpha;
+    let v = n_dot_l * (n_dot_v * n_dot_v * (1.0 - alpha2) + alpha2).sqrt();
+    let l = n_dot_v * (n_dot_l * n_dot_l * (1.0 - alpha2) + alpha2).sqrt();
+    if v + l > 0.0 { 0.5 / (v + l) } else { 0.0 }
+}
+
+/// Schlick's approximation of the Fresnel reflectance.
+pub fn fresnel_schlick(cos_theta: f32, f0: Vector3<f32>) -> Vector3<f32> {
+    let t = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    f0 + (Vector3::splat(1.0) - f0) * t
+}
+
+/// The Cook-Torrance BRDF with a Lambertian diffuse lobe, for normalized `normal`, `view` and
+/// `light` directions all pointing away from the surface.  Multiplied by the incoming radiance
+/// and `n·l` it gives the reflected radiance.
+pub fn brdf(
+    surface: &Surface,
+    normal: Vector3<f32>,
+    view: Vector3<f32>,
+    light: Vector3<f32>,
+) -> Vector3<f32> {
+    let n_dot_l = normal.dot(light);
+    let n_dot_v = normal.dot(view).max(1e-4);
+    if n_dot_l <= 0.0 {
+        return Vector3::splat(0.0);
+    }
+    let half = (light + view).normalize();
+    let n_dot_h = normal.dot(half).max(0.0);
+    let v_dot_h = view.dot(half).max(0.0);
+
+    let base_color = surface.base_color.vec3();
+    let f0 = Vector3::splat(DIELECTRIC_F0).lerp(base_color, surface.metallic);
+    let fresnel = fresnel_schlick(v_dot_h, f0);
+    let specular = fresnel * distribution_ggx(n_dot_h, surface.roughness) *
+        visibility_smith_ggx(n_dot_v, n_dot_l, surface.roughness);
+    let diffuse = (Vector3::splat(1.0) - fresnel) * base_color * (1.0 - surface.metallic) /
+        f32::consts::PI;
+    diffuse + specular
+}
+
+/// Lights a point with punctual lights, whose color times intensity is the irradiance they
+/// deliver to a surface facing them, plus ambient light scaled by the occlusion and the emission.
+/// `position` and `normal` are in the same space as the lights, with the viewer at the origin;
+/// `normal` must be normalized.  The alpha of the base color is returned unchanged.
+pub fn shade(
+    surface: &Surface,
+    lights: &[PlacedLight],
+    ambient_light: Vector3<f32>,
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+) -> Vector4<f32> {
+    let view = if position.magnitude_squared() > 0.0 {
+        -position.normalize()
+    } else {
+        normal
+    };
+
+    let base_color = surface.base_color.vec3();
+    let mut result = ambient_light * base_color * surface.occlusion + surface.emissive;
+    for light in lights {
+        let (l, radiance) = light.incoming(position);
+        let n_dot_l = normal.dot(l);
+        if n_dot_l > 0.0 {
+            let radiance = radiance * light.visibility(position, normal);
+            result += brdf(surface, normal, view, l) * radiance * n_dot_l;
+        }
+    }
+    result.extend(surface.base_color.w)
+}
@@ -0,0 +1,70 @@
+use num::Float;
+
+use vec3::Vector3;
+use vec4::Vector4;
+
+/// The plane of points `p` where `normal.dot(p) + distance == 0`.  The normal points to the
+/// front of the plane, where signed distances are positive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane<T> {
+    pub normal: Vector3<T>,
+    pub distance: T,
+}
+
+impl<T> Plane<T>
+where
+    T: Float,
+{
+    pub fn new(normal: Vector3<T>, distance: T) -> Plane<T> {
+        Plane { normal, distance }
+    }
+
+    pub fn from_point_normal(point: Vector3<T>, normal: Vector3<T>) -> Plane<T> {
+        let normal = normal.normalize();
+        Plane {
+            normal,
+            distance: -normal.dot(point),
+        }
+    }
+
+    /// The plane through three points, facing the side from which they appear counterclockwise.
+    /// Returns `None` if the points are collinear.
+    pub fn from_points(a: Vector3<T>, b: Vector3<T>, c: Vector3<T>) -> Option<Plane<T>> {
+        let normal = (b - a).cross(c - a);
+        if normal.magnitude_squared() == T::zero() {
+            return None;
+        }
+        Some(Plane::from_point_normal(a, normal))
+    }
+
+    /// The plane `(x, y, z) . normal + w = 0` given by the coefficients of a vector.
+    pub fn from_coefficients(v: Vector4<T>) -> Plane<T> {
+        Plane {
+            normal: v.vec3(),
+            distance: v.w,
+        }
+    }
+
+    /// Scales the plane equation so that the normal has unit length, which is needed for
+    /// `signed_distance` to return actual distances.  A plane with a zero normal is returned
+    /// unchanged.
+    pub fn normalize(&self) -> Plane<T> {
+        let magnitude = self.normal.magnitude();
+        if magnitude == T::zero() {
+            return *self;
+        }
+        Plane {
+            normal: self.normal / magnitude,
+            distance: self.distance / magnitude,
+        }
+    }
+
+    pub fn signed_distance(&self, point: Vector3<T>) -> T {
+        self.normal.dot(point) + self.distance
+    }
+
+    /// The closest point on the plane.
+    pub fn project(&self, point: Vector3<T>) -> Vector3<T> {
+        point - self.normal * self.signed_distance(point)
+    }
+}
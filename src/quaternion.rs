@@ -0,0 +1,302 @@
+use std::ops::{Add, Sub, Mul, MulAssign, Neg};
+
+use num::Float;
+
+use vec3::Vector3;
+use mat4::Matrix4;
+
+/// A rotation quaternion, `w` is the scalar part.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Quaternion<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub w: T,
+}
+
+impl<T> Quaternion<T> {
+    pub fn new(x: T, y: T, z: T, w: T) -> Quaternion<T> {
+        Quaternion { x, y, z, w }
+    }
+
+    pub fn vector(self) -> Vector3<T> {
+        Vector3 {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+        }
+    }
+}
+
+impl<T> Quaternion<T>
+where
+    T: Float,
+{
+    pub fn identity() -> Quaternion<T> {
+        Quaternion::new(T::zero(), T::zero(), T::zero(), T::one())
+    }
+
+    /// A rotation of `angle` radians counterclockwise around `axis`, which does not need to be
+    /// normalized.
+    pub fn from_axis_angle(axis: Vector3<T>, angle: T) -> Quaternion<T> {
+        let half = angle / T::from(2).unwrap();
+        let v = axis.normalize() * half.sin();
+        Quaternion::new(v.x, v.y, v.z, half.cos())
+    }
+
+    /// Returns the normalized rotation axis and the angle in radians, in the range [0, 2π].  The
+    /// axis of an identity rotation is arbitrary.
+    pub fn to_axis_angle(&self) -> (Vector3<T>, T) {
+        let q = self.normalize();
+        let angle = T::from(2).unwrap() * q.w.max(-T::one()).min(T::one()).acos();
+        let s = (T::one() - q.w * q.w).sqrt();
+        if s > T::epsilon() {
+            (q.vector() / s, angle)
+        } else {
+            (Vector3::new(T::one(), T::zero(), T::zero()), angle)
+        }
+    }
+
+    /// The same rotation as `Matrix4::rotation`, rotating around x, then y, then z.
+    pub fn from_euler(rotation: Vector3<T>) -> Quaternion<T> {
+        let (zero, one) = (T::zero(), T::one());
+        let x = Quaternion::from_axis_angle(Vector3::new(one, zero, zero), rotation.x);
+        let y = Quaternion::from_axis_angle(Vector3::new(zero, one, zero), rotation.y);
+        let z = Quaternion::from_axis_angle(Vector3::new(zero, zero, one), rotation.z);
+        z * y * x
+    }
+
+    /// The inverse of `from_euler`.  The y angle is in the range [-π/2, π/2].
+    pub fn to_euler(&self) -> Vector3<T> {
+        let q = self.normalize();
+        let one = T::one();
+        let two = T::from(2).unwrap();
+
+        let sin_y = -two * (q.x * q.z - q.w * q.y);
+        let y = sin_y.max(-one).min(one).asin();
+        if sin_y.abs() > one - T::from(1e-6).unwrap() {
+            // Gimbal lock: the x and z rotations are around the same axis, so put all of it in z.
+            let z = (-two * (q.x * q.y - q.w * q.z)).atan2(one - two * (q.x * q.x + q.z * q.z));
+            return Vector3::new(T::zero(), y, z);
+        }
+
+        let x = (two * (q.y * q.z + q.w * q.x)).atan2(one - two * (q.x * q.x + q.y * q.y));
+        let z = (two * (q.x * q.y + q.w * q.z)).atan2(one - two * (q.y * q.y + q.z * q.z));
+        Vector3::new(x, y, z)
+    }
+
+    /// Extracts the rotation from a matrix.  The upper 3x3 part of the matrix must be a pure
+    /// rotation, without scaling or shearing.
+    pub fn from_matrix(m: &Matrix4<T>) -> Quaternion<T> {
+        let zero = T::zero();
+        let one = T::one();
+        let two = T::from(2).unwrap();
+        let four = T::from(4).unwrap();
+
+        let (e11, e12, e13) = (m[(0, 0)], m[(0, 1)], m[(0, 2)]);
+        let (e21, e22, e23) = (m[(1, 0)], m[(1, 1)], m[(1, 2)]);
+        let (e31, e32, e33) = (m[(2, 0)], m[(2, 1)], m[(2, 2)]);
+
+        // Divide by the largest of the four possible terms for precision.
+        let trace = e11 + e22 + e33;
+        let q = if trace > zero {
+            let s = (trace + one).sqrt() * two;
+            Quaternion::new((e32 - e23) / s, (e13 - e31) / s, (e21 - e12) / s, s / four)
+        } else if e11 > e22 && e11 > e33 {
+            let s = (one + e11 - e22 - e33).sqrt() * two;
+            Quaternion::new(s / four, (e12 + e21) / s, (e13 + e31) / s, (e32 - e23) / s)
+        } else if e22 > e33 {
+            let s = (one + e22 - e11 - e33).sqrt() * two;
+            Quaternion::new((e12 + e21) / s, s / four, (e23 + e32) / s, (e13 - e31) / s)
+        } else {
+            let s = (one + e33 - e11 - e22).sqrt() * two;
+            Quaternion::new((e13 + e31) / s, (e23 + e32) / s, s / four, (e21 - e12) / s)
+        };
+        q.normalize()
+    }
+
+    pub fn to_matrix(&self) -> Matrix4<T> {
+        let Quaternion { x, y, z, w } = *self;
+        let zero = T::zero();
+        let one = T::one();
+        let two = T::from(2).unwrap();
+        Matrix4::new(
+            one - two * (y * y + z * z),
+            two * (x * y - z * w),
+            two * (x * z + y * w),
+            zero,
+            two * (x * y + z * w),
+            one - two * (x * x + z * z),
+            two * (y * z - x * w),
+            zero,
+            two * (x * z - y * w),
+            two * (y * z + x * w),
+            one - two * (x * x + y * y),
+            zero,
+            zero,
+            zero,
+            zero,
+            one,
+        )
+    }
+
+    pub fn dot(&self, rhs: Quaternion<T>) -> T {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+
+    pub fn magnitude_squared(&self) -> T {
+        self.dot(*self)
+    }
+
+    pub fn magnitude(&self) -> T {
+        self.magnitude_squared().sqrt()
+    }
+
+    pub fn normalize(&self) -> Quaternion<T> {
+        *self * (T::one() / self.magnitude())
+    }
+
+    pub fn conjugate(&self) -> Quaternion<T> {
+        Quaternion::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    /// For unit quaternions this is the same as `conjugate`.
+    pub fn inverse(&self) -> Quaternion<T> {
+        self.conjugate() * (T::one() / self.magnitude_squared())
+    }
+
+    /// Rotates a vector, the quaternion must be normalized.
+    pub fn rotate(&self, v: Vector3<T>) -> Vector3<T> {
+        let two = T::from(2).unwrap();
+        let q = self.vector();
+        let t = q.cross(v) * two;
+        v + t * self.w + q.cross(t)
+    }
+
+    /// Normalized linear interpolation along the shortest path.  Cheaper than `slerp`, but the
+    /// angular velocity is not constant.
+    pub fn nlerp(&self, other: Quaternion<T>, t: T) -> Quaternion<T> {
+        let other = if self.dot(other) < T::zero() {
+            -other
+        } else {
+            other
+        };
+        (*self * (T::one() - t) + other * t).normalize()
+    }
+
+    /// Spherical linear interpolation along the shortest path, with a constant angular velocity.
+    pub fn slerp(&self, other: Quaternion<T>, t: T) -> Quaternion<T> {
+        let mut cos = self.dot(other);
+        let other = if cos < T::zero() {
+            cos = -cos;
+            -other
+        } else {
+            other
+        };
+
+        // Nearly parallel quaternions would divide by almost zero.
+        if cos > T::from(0.9995).unwrap() {
+            return self.nlerp(other, t);
+        }
+
+        let theta = cos.acos();
+        let sin = theta.sin();
+        let a = ((T::one() - t) * theta).sin() / sin;
+        let b = (t * theta).sin() / sin;
+        (*self * a + other * b).normalize()
+    }
+}
+
+impl<T> From<Quaternion<T>> for Matrix4<T>
+where
+    T: Float,
+{
+    fn from(q: Quaternion<T>) -> Matrix4<T> {
+        q.to_matrix()
+    }
+}
+
+impl<T> Add<Quaternion<T>> for Quaternion<T>
+where
+    T: Add<T, Output = T>,
+{
+    type Output = Quaternion<T>;
+
+    fn add(self, rhs: Quaternion<T>) -> Quaternion<T> {
+        Quaternion {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+            w: self.w + rhs.w,
+        }
+    }
+}
+
+impl<T> Mul<T> for Quaternion<T>
+where
+    T: Copy + Mul<T, Output = T>,
+{
+    type Output = Quaternion<T>;
+
+    fn mul(self, rhs: T) -> Quaternion<T> {
+        Quaternion {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+            w: self.w * rhs,
+        }
+    }
+}
+
+/// The Hamilton product, `a * b` rotates by `b` and then by `a`.
+impl<T> Mul<Quaternion<T>> for Quaternion<T>
+where
+    T: Copy + Add<T, Output = T> + Sub<T, Output = T> + Mul<T, Output = T>,
+{
+    type Output = Quaternion<T>;
+
+    fn mul(self, rhs: Quaternion<T>) -> Quaternion<T> {
+        Quaternion {
+            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        }
+    }
+}
+
+impl<T> MulAssign<Quaternion<T>> for Quaternion<T>
+where
+    T: Copy + Add<T, Output = T> + Sub<T, Output = T> + Mul<T, Output = T>,
+{
+    fn mul_assign(&mut self, rhs: Quaternion<T>) {
+        *self = *self * rhs;
+    }
+}
+
+impl<T> Mul<Vector3<T>> for Quaternion<T>
+where
+    T: Float,
+{
+    type Output = Vector3<T>;
+
+    fn mul(self, rhs: Vector3<T>) -> Vector3<T> {
+        self.rotate(rhs)
+    }
+}
+
+impl<T> Neg for Quaternion<T>
+where
+    T: Neg<Output = T>,
+{
+    type Output = Quaternion<T>;
+
+    fn neg(self) -> Self::Output {
+        Quaternion {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+            w: -self.w,
+        }
+    }
+}
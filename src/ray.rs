@@ -0,0 +1,117 @@
+use num::Float;
+
+use vec2::Vector2;
+use vec3::Vector3;
+use mat4::Matrix4;
+use plane::Plane;
+use sphere::Sphere;
+use aabb::Aabb;
+
+/// A half-line starting at `origin`.  The direction is normalized, so the parameters returned by
+/// the intersection tests are distances from the origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray<T> {
+    pub origin: Vector3<T>,
+    pub direction: Vector3<T>,
+}
+
+impl<T> Ray<T>
+where
+    T: Float,
+{
+    pub fn new(origin: Vector3<T>, direction: Vector3<T>) -> Ray<T> {
+        Ray {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// The ray from the camera through a point in normalized device coordinates, for picking.
+    /// `inverse_view_projection` is the inverse of a projection that maps the near plane to
+    /// z = -1, such as `Matrix4::perspective_fov` or `Matrix4::orthographic`, times the view
+    /// matrix.  The ray starts on the near plane.
+    pub fn from_ndc(inverse_view_projection: &Matrix4<T>, ndc: Vector2<T>) -> Ray<T> {
+        let near = inverse_view_projection.transform(Vector3::new(ndc.x, ndc.y, -T::one()));
+        let mid = inverse_view_projection.transform(Vector3::new(ndc.x, ndc.y, T::zero()));
+        Ray::new(near, mid - near)
+    }
+
+    pub fn at(&self, t: T) -> Vector3<T> {
+        self.origin + self.direction * t
+    }
+
+    /// The distance to where the ray crosses the plane, from either side.
+    pub fn intersect_plane(&self, plane: &Plane<T>) -> Option<T> {
+        let denominator = plane.normal.dot(self.direction);
+        if denominator == T::zero() {
+            return None;
+        }
+        let t = -plane.signed_distance(self.origin) / denominator;
+        if t >= T::zero() { Some(t) } else { None }
+    }
+
+    /// The distance to the first point where the ray hits the surface of the sphere.  For a ray
+    /// starting inside the sphere, that is where it leaves.
+    pub fn intersect_sphere(&self, sphere: &Sphere<T>) -> Option<T> {
+        let offset = self.origin - sphere.center;
+        let b = offset.dot(self.direction);
+        let c = offset.magnitude_squared() - sphere.radius * sphere.radius;
+        let discriminant = b * b - c;
+        if discriminant < T::zero() {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        let (near, far) = (-b - root, -b + root);
+        if near >= T::zero() {
+            Some(near)
+        } else if far >= T::zero() {
+            Some(far)
+        } else {
+            None
+        }
+    }
+
+    /// The distance to the first point where the ray hits the surface of the box.  For a ray
+    /// starting inside the box, that is where it leaves.
+    pub fn intersect_aabb(&self, aabb: &Aabb<T>) -> Option<T> {
+        // Slab test.  Division by a zero component gives infinities with the right signs, but
+        // 0 * infinity is NaN, which `max` and `min` ignore.
+        let inverse = Vector3::splat(T::one()) / self.direction;
+        let t1 = (aabb.min - self.origin) * inverse;
+        let t2 = (aabb.max - self.origin) * inverse;
+        let (low, high) = (t1.min(t2), t1.max(t2));
+        let near = low.x.max(low.y).max(low.z);
+        let far = high.x.min(high.y).min(high.z);
+        if near > far || far < T::zero() {
+            None
+        } else if near >= T::zero() {
+            Some(near)
+        } else {
+            Some(far)
+        }
+    }
+
+    /// The distance to where the ray hits the triangle, from either side.
+    pub fn intersect_triangle(&self, a: Vector3<T>, b: Vector3<T>, c: Vector3<T>) -> Option<T> {
+        // Möller-Trumbore
+        let (ab, ac) = (b - a, c - a);
+        let p = self.direction.cross(ac);
+        let det = ab.dot(p);
+        if det.abs() <= T::epsilon() {
+            return None;
+        }
+        let inv = T::one() / det;
+        let s = self.origin - a;
+        let u = s.dot(p) * inv;
+        if u < T::zero() || u > T::one() {
+            return None;
+        }
+        let q = s.cross(ab);
+        let v = self.direction.dot(q) * inv;
+        if v < T::zero() || u + v > T::one() {
+            return None;
+        }
+        let t = ac.dot(q) * inv;
+        if t >= T::zero() { Some(t) } else { None }
+    }
+}
@@ -1,14 +1,28 @@
+use std::mem;
+
 use vec2::Vector2;
 use vec3::Vector3;
 use vec4::Vector4;
 use mat4::Matrix4;
 use bound_rect::BoundRect;
-use color::{Color, vec4_to_color};
+use aabb::Aabb;
+use sphere::Sphere;
+use frustum::Frustum;
+use color::{Color, vec4_to_color, color_to_vec4, f32_to_half, half_to_f32};
+use image::Image;
+use texture::Texture;
+use lighting::{self, Material, PlacedLight, ShadingMode};
+use pbr::{self, PbrMaterial};
+use tone_mapping::ToneMapping;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Vertex {
     pub position: Vector3<f32>,
     pub color: Vector4<f32>,
+    /// Used for lighting.  A zero normal stands for the normal of the triangle's face.
+    pub normal: Vector3<f32>,
+    /// Where textures are sampled, see `Texture`.
+    pub tex_coord: Vector2<f32>,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -18,107 +32,671 @@ pub struct Triangle {
     pub c: Vertex,
 }
 
+/// Bounding volumes of an object, in the same space as its vertices.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bounds {
+    pub aabb: Aabb<f32>,
+    pub sphere: Sphere<f32>,
+}
+
+impl Bounds {
+    pub fn from_points(points: &[Vector3<f32>]) -> Option<Bounds> {
+        Some(Bounds {
+            aabb: Aabb::from_points(points)?,
+            sphere: Sphere::from_points(points)?,
+        })
+    }
+
+    pub fn from_triangles(triangles: &[Triangle]) -> Option<Bounds> {
+        let points: Vec<_> = triangles
+            .iter()
+            .flat_map(|t| [t.a.position, t.b.position, t.c.position])
+            .collect();
+        Bounds::from_points(&points)
+    }
+}
+
+/// Counts of the objects passed to the renderer since the last `clear`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct RenderStats {
+    pub objects_drawn: u32,
+    pub objects_culled: u32,
+}
+
+/// How a fragment's depth is compared against the depth buffer.  Depth is the normalized device z
+/// coordinate, so `Less` suits projections that map the near plane to -1 and `Greater` suits the
+/// reverse-Z projections.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DepthTest {
+    Less,
+    Greater,
+    /// Every fragment is drawn in submission order and the depth buffer is not written.
+    Disabled,
+}
+
+/// What a fragment writes to a color attachment of a `Framebuffer`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FragmentOutput {
+    /// The final, shaded color.
+    Color,
+    /// The color before lighting: the vertex color, times the base color of the material with
+    /// `ShadingMode::Pbr`.
+    Albedo,
+    /// The view space normal, with each component mapped from [-1, 1] to [0, 1].
+    Normal,
+}
+
+/// How a color attachment stores its pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorFormat {
+    /// 8-bit channels, clamped to [0, 1].
+    Rgba8,
+    /// Half precision floats, which keep values above 1 for tone mapping.
+    Rgba16F,
+    /// Single precision floats.
+    Rgba32F,
+}
+
+/// The pixels of a color attachment, in one of the `ColorFormat`s.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorBuffer {
+    Rgba8(Image<Color>),
+    /// The bits of each channel, see `f32_to_half`.
+    Rgba16F(Image<[u16; 4]>),
+    Rgba32F(Image<Vector4<f32>>),
+}
+
+impl ColorBuffer {
+    /// Creates a buffer filled with opaque black.
+    pub fn new(width: u32, height: u32, format: ColorFormat) -> ColorBuffer {
+        match format {
+            ColorFormat::Rgba8 => {
+                ColorBuffer::Rgba8(Image::new(width, height, Color(0, 0, 0, 255)))
+            }
+            ColorFormat::Rgba16F => {
+                let one = f32_to_half(1.0);
+                ColorBuffer::Rgba16F(Image::new(width, height, [0, 0, 0, one]))
+            }
+            ColorFormat::Rgba32F => {
+                ColorBuffer::Rgba32F(Image::new(width, height, Vector4::new(0.0, 0.0, 0.0, 1.0)))
+            }
+        }
+    }
+
+    pub fn format(&self) -> ColorFormat {
+        match *self {
+            ColorBuffer::Rgba8(_) => ColorFormat::Rgba8,
+            ColorBuffer::Rgba16F(_) => ColorFormat::Rgba16F,
+            ColorBuffer::Rgba32F(_) => ColorFormat::Rgba32F,
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        match *self {
+            ColorBuffer::Rgba8(ref image) => image.dimensions(),
+            ColorBuffer::Rgba16F(ref image) => image.dimensions(),
+            ColorBuffer::Rgba32F(ref image) => image.dimensions(),
+        }
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Vector4<f32> {
+        match *self {
+            ColorBuffer::Rgba8(ref image) => color_to_vec4(image[(x, y)]),
+            ColorBuffer::Rgba16F(ref image) => half_to_vec4(image[(x, y)]),
+            ColorBuffer::Rgba32F(ref image) => image[(x, y)],
+        }
+    }
+
+    /// Stores a color, rounded to the precision of the format.
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Vector4<f32>) {
+        match *self {
+            ColorBuffer::Rgba8(ref mut image) => image[(x, y)] = vec4_to_color(color),
+            ColorBuffer::Rgba16F(ref mut image) => image[(x, y)] = vec4_to_half(color),
+            ColorBuffer::Rgba32F(ref mut image) => image[(x, y)] = color,
+        }
+    }
+
+    pub fn fill(&mut self, color: Vector4<f32>) {
+        match *self {
+            ColorBuffer::Rgba8(ref mut image) => image.fill(vec4_to_color(color)),
+            ColorBuffer::Rgba16F(ref mut image) => image.fill(vec4_to_half(color)),
+            ColorBuffer::Rgba32F(ref mut image) => image.fill(color),
+        }
+    }
+
+    /// Converts every pixel, as read by `get_pixel`.
+    pub fn map<T, F>(&self, mut f: F) -> Image<T>
+    where
+        F: FnMut(Vector4<f32>) -> T,
+    {
+        match *self {
+            ColorBuffer::Rgba8(ref image) => image.map(|&c| f(color_to_vec4(c))),
+            ColorBuffer::Rgba16F(ref image) => image.map(|&c| f(half_to_vec4(c))),
+            ColorBuffer::Rgba32F(ref image) => image.map(|&c| f(c)),
+        }
+    }
+}
+
+fn vec4_to_half(Vector4 { x, y, z, w }: Vector4<f32>) -> [u16; 4] {
+    [f32_to_half(x), f32_to_half(y), f32_to_half(z), f32_to_half(w)]
+}
+
+fn half_to_vec4(c: [u16; 4]) -> Vector4<f32> {
+    Vector4::new(half_to_f32(c[0]), half_to_f32(c[1]), half_to_f32(c[2]), half_to_f32(c[3]))
+}
+
+/// A set of images to render into: any number of color attachments, each written with its own
+/// `FragmentOutput` in the same pass, and a depth buffer.  Framebuffers are created on their own
+/// and bound to a `Renderer`, after which their attachments can be read as images or sampled as
+/// textures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Framebuffer {
+    outputs: Vec<FragmentOutput>,
+    colors: Vec<ColorBuffer>,
+    depth: Image<f32>,
+}
+
+impl Framebuffer {
+    /// Creates a framebuffer with one 8-bit color attachment per output, cleared to black and the
+    /// far depth of `DepthTest::Less`.  With no outputs only depth is rendered.
+    pub fn new(width: u32, height: u32, outputs: &[FragmentOutput]) -> Framebuffer {
+        let attachments: Vec<_> = outputs.iter().map(|&o| (o, ColorFormat::Rgba8)).collect();
+        Framebuffer::with_formats(width, height, &attachments)
+    }
+
+    /// Like `new`, with the format of each attachment given alongside its output.
+    pub fn with_formats(
+        width: u32,
+        height: u32,
+        attachments: &[(FragmentOutput, ColorFormat)],
+    ) -> Framebuffer {
+        Framebuffer {
+            outputs: attachments.iter().map(|&(output, _)| output).collect(),
+            colors: attachments
+                .iter()
+                .map(|&(_, format)| ColorBuffer::new(width, height, format))
+                .collect(),
+            depth: Image::new(width, height, f32::INFINITY),
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.depth.dimensions()
+    }
+
+    pub fn outputs(&self) -> &[FragmentOutput] {
+        &self.outputs
+    }
+
+    /// Panics if there is no attachment at `index`.
+    pub fn attachment(&self, index: usize) -> &ColorBuffer {
+        &self.colors[index]
+    }
+
+    /// The 8-bit color attachment at `index`.  Panics if there is no attachment at `index` or it
+    /// has a floating point format, which needs `attachment` or tone mapping instead.
+    pub fn color(&self, index: usize) -> &Image<Color> {
+        match self.colors[index] {
+            ColorBuffer::Rgba8(ref image) => image,
+            ref other => panic!("attachment {} is {:?}, not 8-bit", index, other.format()),
+        }
+    }
+
+    pub fn depth(&self) -> &Image<f32> {
+        &self.depth
+    }
+
+    /// The color attachment at `index` as a texture, for sampling in a later pass.  Attachments
+    /// hold linear values, so no sRGB decoding is done.
+    pub fn texture(&self, index: usize) -> Texture {
+        Texture::new(self.colors[index].map(|c| c))
+    }
+
+    /// Hands over the color attachments, in the order of the outputs.
+    pub fn into_attachments(self) -> Vec<ColorBuffer> {
+        self.colors
+    }
+
+    fn clear(&mut self, color: Color, depth: f32) {
+        for buffer in &mut self.colors {
+            buffer.fill(color_to_vec4(color));
+        }
+        self.depth.fill(depth);
+    }
+}
+
 pub struct Renderer {
-    dimensions: (u32, u32),
-    framebuffer: Vec<Color>,
-    perspective: Matrix4<f32>,
+    target: Framebuffer,
+    depth_test: DepthTest,
+    color_write: bool,
+    projection: Matrix4<f32>,
+    // The aspect ratio the projection was last fitted to, if the renderer has not always been
+    // empty.
+    aspect: Option<f32>,
+    shading: ShadingMode,
+    lights: Vec<PlacedLight>,
+    ambient_light: Vector3<f32>,
+    material: Material,
+    pbr_material: PbrMaterial,
+    tone_mapping: ToneMapping,
+    stats: RenderStats,
 }
 
 impl Renderer {
-    pub fn new(width: u32, height: u32, fov: f32) -> Renderer {
-        let scale = (fov * 0.5).tan();
-        let perspective = Matrix4::perspective(scale * 2.0, scale * 2.0, -1.0, 1.0);
+    pub fn new(width: u32, height: u32, projection: Matrix4<f32>) -> Renderer {
         Renderer {
-            dimensions: (width, height),
-            framebuffer: vec![Color(0, 0, 0, 255); (width * height) as usize],
-            perspective,
+            target: Framebuffer::new(width, height, &[FragmentOutput::Color]),
+            depth_test: DepthTest::Less,
+            color_write: true,
+            projection,
+            aspect: aspect_ratio(width, height),
+            shading: ShadingMode::Unlit,
+            lights: Vec::new(),
+            ambient_light: Vector3::splat(0.0),
+            material: Material::default(),
+            pbr_material: PbrMaterial::default(),
+            tone_mapping: ToneMapping::default(),
+            stats: RenderStats::default(),
         }
     }
 
+    /// The dimensions of the bound framebuffer.
     pub fn dimensions(&self) -> (u32, u32) {
-        self.dimensions
+        self.target.dimensions()
+    }
+
+    /// Reallocates the bound framebuffer for new dimensions, clearing it to black and the far
+    /// depth; resizing to the current dimensions does nothing and keeps the contents.  The
+    /// projection is scaled horizontally to keep its vertical field of view at the new aspect
+    /// ratio.  Empty dimensions leave the projection alone, and the next non-empty ones are
+    /// compared with the last non-empty ones.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) == self.dimensions() {
+            return;
+        }
+
+        if let Some(aspect) = aspect_ratio(width, height) {
+            if let Some(old_aspect) = self.aspect {
+                self.projection = Matrix4::scaling(Vector3::new(old_aspect / aspect, 1.0, 1.0)) *
+                    self.projection;
+            }
+            self.aspect = Some(aspect);
+        }
+
+        let attachments: Vec<_> = self.target
+            .outputs
+            .iter()
+            .zip(&self.target.colors)
+            .map(|(&output, buffer)| (output, buffer.format()))
+            .collect();
+        let mut target = Framebuffer::with_formats(width, height, &attachments);
+        target.depth.fill(self.far_depth());
+        self.target = target;
+    }
+
+    pub fn projection(&self) -> Matrix4<f32> {
+        self.projection
     }
 
+    pub fn set_projection(&mut self, projection: Matrix4<f32>) {
+        self.projection = projection;
+    }
+
+    /// The first color attachment of the bound framebuffer.  Panics if it has none.
+    pub fn framebuffer(&self) -> &Image<Color> {
+        self.target.color(0)
+    }
+
+    pub fn depth_buffer(&self) -> &Image<f32> {
+        &self.target.depth
+    }
+
+    pub fn bound_framebuffer(&self) -> &Framebuffer {
+        &self.target
+    }
+
+    /// Makes `framebuffer` the target of the following draws and returns the one bound before,
+    /// which can be bound again later.  The projection is left alone, so it may need to change
+    /// with the aspect ratio.
+    pub fn bind_framebuffer(&mut self, framebuffer: Framebuffer) -> Framebuffer {
+        mem::replace(&mut self.target, framebuffer)
+    }
+
+    pub fn depth_test(&self) -> DepthTest {
+        self.depth_test
+    }
+
+    /// Takes effect at the next `clear`, which resets the depth buffer to the far value for the
+    /// new test.
+    pub fn set_depth_test(&mut self, depth_test: DepthTest) {
+        self.depth_test = depth_test;
+    }
+
+    pub fn shading(&self) -> ShadingMode {
+        self.shading
+    }
+
+    pub fn set_shading(&mut self, shading: ShadingMode) {
+        self.shading = shading;
+    }
+
+    pub fn lights(&self) -> &[PlacedLight] {
+        &self.lights
+    }
+
+    /// The lights are in view space, where the camera sits at the origin looking down the
+    /// negative z axis.
+    pub fn set_lights(&mut self, lights: Vec<PlacedLight>) {
+        self.lights = lights;
+    }
+
+    pub fn ambient_light(&self) -> Vector3<f32> {
+        self.ambient_light
+    }
+
+    pub fn set_ambient_light(&mut self, ambient_light: Vector3<f32>) {
+        self.ambient_light = ambient_light;
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// The material for the following `render` calls with Phong-style shading.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    pub fn pbr_material(&self) -> &PbrMaterial {
+        &self.pbr_material
+    }
+
+    /// The material for the following `render` calls with `ShadingMode::Pbr`.
+    pub fn set_pbr_material(&mut self, material: PbrMaterial) {
+        self.pbr_material = material;
+    }
+
+    pub fn color_write(&self) -> bool {
+        self.color_write
+    }
+
+    /// With color writes off, rendering only updates the depth buffer and skips shading, for
+    /// depth-only passes such as shadow maps.
+    pub fn set_color_write(&mut self, color_write: bool) {
+        self.color_write = color_write;
+    }
+
+    pub fn tone_mapping(&self) -> ToneMapping {
+        self.tone_mapping
+    }
+
+    /// How `resolve` converts floating point color attachments for display.
+    pub fn set_tone_mapping(&mut self, tone_mapping: ToneMapping) {
+        self.tone_mapping = tone_mapping;
+    }
+
+    /// The first color attachment of the bound framebuffer as a displayable image.  8-bit
+    /// attachments are already clamped and are copied as they are, while floating point ones are
+    /// tone mapped.  Panics if there is no color attachment.
+    pub fn resolve(&self) -> Image<Color> {
+        match *self.target.attachment(0) {
+            ColorBuffer::Rgba8(ref image) => image.clone(),
+            ref buffer => self.tone_mapping.resolve(buffer),
+        }
+    }
+
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    /// Fills every color attachment of the bound framebuffer, resets its depth buffer and resets
+    /// the statistics for a new frame.
     pub fn clear(&mut self, color: Color) {
-        for p in &mut self.framebuffer {
-            *p = color;
+        let far = self.far_depth();
+        self.target.clear(color, far);
+        self.stats = RenderStats::default();
+    }
+
+    // The depth every fragment passes against.
+    fn far_depth(&self) -> f32 {
+        match self.depth_test {
+            DepthTest::Greater => f32::NEG_INFINITY,
+            DepthTest::Less | DepthTest::Disabled => f32::INFINITY,
         }
     }
 
+    /// Renders the triangles unless `bounds` lie entirely outside the view frustum, in which case
+    /// nothing is transformed and the object is counted as culled.  Returns whether the object
+    /// was drawn.
+    pub fn render_culled(
+        &mut self,
+        transformation: Matrix4<f32>,
+        bounds: &Bounds,
+        triangles: &[Triangle],
+    ) -> bool {
+        // Planes extracted from the full matrix are in object space, so the bounds can be tested
+        // as they are.
+        let frustum = Frustum::from_matrix(&(self.projection * transformation));
+        if !frustum.intersects_sphere(&bounds.sphere) || !frustum.intersects_aabb(&bounds.aabb) {
+            self.stats.objects_culled += 1;
+            return false;
+        }
+        self.render(transformation, triangles);
+        true
+    }
+
     pub fn render(&mut self, transformation: Matrix4<f32>, triangles: &[Triangle]) {
-        let screen_bounds =
-            BoundRect::from_bounds(0.0, 0.0, self.dimensions.0 as f32, self.dimensions.1 as f32);
-
-        let transformation = self.perspective * transformation;
-        let (width, height) = self.dimensions;
-        let screen_transform = move |v| {
-            let v = transformation.transform(v);
-            Vector3::new(
-                (v.x + 1.0) / 2.0 * width as f32,
-                (v.y + 1.0) / 2.0 * height as f32,
-                v.z,
-            )
+        self.stats.objects_drawn += 1;
+
+        let (width, height) = self.dimensions();
+        let screen_bounds = BoundRect::from_bounds(0.0, 0.0, width as f32, height as f32);
+
+        let model_view = transformation;
+        let normal_matrix = model_view.normal_matrix().unwrap_or_else(Matrix4::identity);
+        let shading = if self.color_write {
+            self.shading
+        } else {
+            ShadingMode::Unlit
+        };
+        let outputs = if self.color_write {
+            self.target.outputs.clone()
+        } else {
+            Vec::new()
+        };
+        let needs_normals = shading != ShadingMode::Unlit ||
+            outputs.contains(&FragmentOutput::Normal);
+        let needs_albedo = outputs.contains(&FragmentOutput::Albedo);
+        let transformation = self.projection * transformation;
+        // Returns the screen position, 1 / w and depth of a vertex, or None if the vertex is
+        // behind the camera.
+        let screen_transform = move |p: Vector3<f32>| {
+            let v = transformation * Vector4::new(p.x, p.y, p.z, 1.0);
+            if v.w <= 0.0 {
+                return None;
+            }
+            let inv_w = 1.0 / v.w;
+            Some((
+                Vector2::new(
+                    (v.x * inv_w + 1.0) / 2.0 * width as f32,
+                    (v.y * inv_w + 1.0) / 2.0 * height as f32,
+                ),
+                inv_w,
+                v.z * inv_w,
+            ))
         };
 
         for triangle in triangles {
-            let a = screen_transform(triangle.a.position);
-            let b = screen_transform(triangle.b.position);
-            let c = screen_transform(triangle.c.position);
-
-            let az = a.z;
-            let bz = b.z;
-            let cz = c.z;
-
-            let a = a.vec2();
-            let b = b.vec2();
-            let c = c.vec2();
-
-            if (b - a).cross(c - a) > 0.0 {
-                if let Some(bb) = BoundRect::from_points(&[a, b, c]).map(|bb| {
-                    bb.intersection(screen_bounds)
-                })
-                {
-                    if !bb.is_empty() {
-                        let screen_xmin = bb.min.x.floor() as u32;
-                        let screen_ymin = bb.min.y.floor() as u32;
-                        let screen_xmax = bb.max.x.ceil() as u32;
-                        let screen_ymax = bb.max.y.ceil() as u32;
-
-                        let s1 = b - a;
-                        let s2 = c - b;
-                        let s3 = a - c;
-
-                        for y in screen_ymin..screen_ymax {
-                            for x in screen_xmin..screen_xmax {
-                                let p = Vector2::new(x as f32 + 0.5, y as f32 + 0.5);
-                                let mut apart = s2.cross(p - b) / 2.0 * az;
-                                let mut bpart = s3.cross(p - c) / 2.0 * bz;
-                                let mut cpart = s1.cross(p - a) / 2.0 * cz;
-
-                                let sum = apart + bpart + cpart;
-                                apart /= sum;
-                                bpart /= sum;
-                                cpart /= sum;
-
-                                if apart >= 0.0 && bpart >= 0.0 && cpart >= 0.0 {
-                                    let color = triangle.a.color * apart +
-                                        triangle.b.color * bpart +
-                                        triangle.c.color * cpart;
-                                    self.set_pixel(x, y, vec4_to_color(color));
-                                }
-                            }
+            // Triangles crossing behind the camera are skipped entirely, since there is no
+            // clipping.
+            let ((a, aw, az), (b, bw, bz), (c, cw, cz)) = match (
+                screen_transform(triangle.a.position),
+                screen_transform(triangle.b.position),
+                screen_transform(triangle.c.position),
+            ) {
+                (Some(a), Some(b), Some(c)) => (a, b, c),
+                _ => continue,
+            };
+
+            if (b - a).cross(c - a) <= 0.0 {
+                continue;
+            }
+            let bounds = match BoundRect::from_points(&[a, b, c]) {
+                Some(bounds) => bounds.intersection(screen_bounds),
+                None => continue,
+            };
+            if bounds.is_empty() {
+                continue;
+            }
+            let pixels: BoundRect<u32> = match bounds.round_out() {
+                Some(pixels) => pixels,
+                None => continue,
+            };
+
+            // Lighting happens in view space.
+            let albedos = [triangle.a.color, triangle.b.color, triangle.c.color];
+            let mut colors = albedos;
+            let mut positions = [Vector3::splat(0.0); 3];
+            let mut normals = [Vector3::splat(0.0); 3];
+            if needs_normals {
+                let vertices = [triangle.a, triangle.b, triangle.c];
+                positions = vertices.map(|v| model_view.transform(v.position));
+                let face = (positions[1] - positions[0]).cross(positions[2] - positions[0]);
+                let face = normalize_or_zero(face);
+                normals = vertices.map(|v| {
+                    let normal = normalize_or_zero(normal_matrix.transform_vector(v.normal));
+                    if normal.magnitude_squared() > 0.0 { normal } else { face }
+                });
+                if shading == ShadingMode::Gouraud {
+                    colors = [0, 1, 2].map(|i| {
+                        self.shade(colors[i], positions[i], normals[i], true)
+                    });
+                }
+            }
+
+            let s1 = b - a;
+            let s2 = c - b;
+            let s3 = a - c;
+
+            for pixel in pixels.pixels() {
+                let p = Vector2::new(pixel.x as f32 + 0.5, pixel.y as f32 + 0.5);
+                let apart = s2.cross(p - b);
+                let bpart = s3.cross(p - c);
+                let cpart = s1.cross(p - a);
+
+                if apart >= 0.0 && bpart >= 0.0 && cpart >= 0.0 {
+                    // Depth is linear in screen space, so it uses the unweighted coordinates.
+                    let depth = (az * apart + bz * bpart + cz * cpart) / (apart + bpart + cpart);
+                    let stored = self.target.depth[(pixel.x, pixel.y)];
+                    let passed = match self.depth_test {
+                        DepthTest::Less => depth < stored,
+                        DepthTest::Greater => depth > stored,
+                        DepthTest::Disabled => true,
+                    };
+                    if !passed {
+                        continue;
+                    }
+                    if self.depth_test != DepthTest::Disabled {
+                        self.target.depth[(pixel.x, pixel.y)] = depth;
+                    }
+                    if outputs.is_empty() {
+                        continue;
+                    }
+
+                    // Weighting by 1 / w makes the interpolation perspective correct.
+                    let apart = apart * aw;
+                    let bpart = bpart * bw;
+                    let cpart = cpart * cw;
+                    let sum = apart + bpart + cpart;
+
+                    let color = (colors[0] * apart + colors[1] * bpart + colors[2] * cpart) / sum;
+                    let mut albedo = if needs_albedo {
+                        (albedos[0] * apart + albedos[1] * bpart + albedos[2] * cpart) / sum
+                    } else {
+                        color
+                    };
+                    let (position, normal) = if needs_normals {
+                        let position = (positions[0] * apart + positions[1] * bpart +
+                                            positions[2] * cpart) / sum;
+                        let normal = normals[0] * apart + normals[1] * bpart + normals[2] * cpart;
+                        (position, normalize_or_zero(normal))
+                    } else {
+                        (Vector3::splat(0.0), Vector3::splat(0.0))
+                    };
+                    let color = match shading {
+                        ShadingMode::Unlit | ShadingMode::Gouraud => color,
+                        ShadingMode::Phong | ShadingMode::BlinnPhong => {
+                            let blinn = shading == ShadingMode::BlinnPhong;
+                            self.shade(color, position, normal, blinn)
+                        }
+                        ShadingMode::Pbr => {
+                            let tex_coord = (triangle.a.tex_coord * apart +
+                                                 triangle.b.tex_coord * bpart +
+                                                 triangle.c.tex_coord * cpart) /
+                                sum;
+                            let surface = self.pbr_material.surface(color, tex_coord);
+                            albedo = surface.base_color;
+                            pbr::shade(&surface, &self.lights, self.ambient_light, position, normal)
                         }
+                    };
+
+                    for (output, buffer) in outputs.iter().zip(&mut self.target.colors) {
+                        let value = match *output {
+                            FragmentOutput::Color => color,
+                            FragmentOutput::Albedo => albedo,
+                            FragmentOutput::Normal => {
+                                (normal * 0.5 + Vector3::splat(0.5)).extend(1.0)
+                            }
+                        };
+                        buffer.set_pixel(pixel.x, pixel.y, value);
                     }
                 }
             }
         }
     }
 
+    // Lights a color, leaving its alpha alone.
+    fn shade(
+        &self,
+        color: Vector4<f32>,
+        position: Vector3<f32>,
+        normal: Vector3<f32>,
+        blinn: bool,
+    ) -> Vector4<f32> {
+        lighting::phong(
+            &self.material,
+            &self.lights,
+            self.ambient_light,
+            color.vec3(),
+            position,
+            normal,
+            blinn,
+        ).extend(color.w)
+    }
+
+    /// Reads the first color attachment of the bound framebuffer.
     pub fn get_pixel(&self, x: u32, y: u32) -> Color {
-        self.framebuffer[(y * self.dimensions.0 + x) as usize]
+        self.framebuffer().get_pixel(x, y)
     }
 
+    /// Writes the first color attachment of the bound framebuffer.  Panics if it has none.
     pub fn set_pixel(&mut self, x: u32, y: u32, c: Color) {
-        self.framebuffer[(y * self.dimensions.0 + x) as usize] = c;
+        self.target.colors[0].set_pixel(x, y, color_to_vec4(c));
+    }
+}
+
+// The width over the height, or `None` for empty dimensions.
+fn aspect_ratio(width: u32, height: u32) -> Option<f32> {
+    if width > 0 && height > 0 {
+        Some(width as f32 / height as f32)
+    } else {
+        None
+    }
+}
+
+fn normalize_or_zero(v: Vector3<f32>) -> Vector3<f32> {
+    if v.magnitude_squared() > 0.0 {
+        v.normalize()
+    } else {
+        v
     }
 }
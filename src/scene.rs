@@ -0,0 +1,463 @@
+use std::f32;
+use std::rc::Rc;
+
+use vec3::Vector3;
+use mat4::Matrix4;
+use aabb::Aabb;
+use renderer::{Triangle, Bounds, Renderer};
+use lighting::{Light, LightKind, Material, PlacedLight};
+use pbr::PbrMaterial;
+use shadow::{self, ShadowCascade, ShadowMap, ShadowSettings};
+
+/// Identifies a node within the `Scene` that created it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// Triangles that can be shared between nodes, with their bounds precomputed for culling.
+#[derive(Debug, Clone)]
+pub struct Model {
+    pub triangles: Vec<Triangle>,
+    pub bounds: Option<Bounds>,
+    pub material: Material,
+    pub pbr_material: PbrMaterial,
+}
+
+impl Model {
+    pub fn new(triangles: Vec<Triangle>) -> Model {
+        Model {
+            bounds: Bounds::from_triangles(&triangles),
+            triangles,
+            material: Material::default(),
+            pbr_material: PbrMaterial::default(),
+        }
+    }
+
+    pub fn with_material(mut self, material: Material) -> Model {
+        self.material = material;
+        self
+    }
+
+    pub fn with_pbr_material(mut self, material: PbrMaterial) -> Model {
+        self.pbr_material = material;
+        self
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Projection {
+    Perspective { fovy: f32, near: f32, far: f32 },
+    /// `height` is the extent of the view along the y axis; the width follows from the aspect
+    /// ratio.
+    Orthographic { height: f32, near: f32, far: f32 },
+}
+
+impl Projection {
+    pub fn near(&self) -> f32 {
+        match *self {
+            Projection::Perspective { near, .. } | Projection::Orthographic { near, .. } => near,
+        }
+    }
+
+    pub fn far(&self) -> f32 {
+        match *self {
+            Projection::Perspective { far, .. } | Projection::Orthographic { far, .. } => far,
+        }
+    }
+
+    pub fn matrix(&self, aspect: f32) -> Matrix4<f32> {
+        match *self {
+            Projection::Perspective { fovy, near, far } => {
+                Matrix4::perspective_fov(fovy, aspect, near, far)
+            }
+            Projection::Orthographic { height, near, far } => {
+                let (x, y) = (height * aspect / 2.0, height / 2.0);
+                Matrix4::orthographic(-x, x, -y, y, near, far)
+            }
+        }
+    }
+}
+
+/// A camera looks down the negative z axis of its node.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera {
+    pub projection: Projection,
+}
+
+/// A node of a `Scene`.  Its transform is relative to its parent, and anything attached to it is
+/// placed by its world transform.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub model: Option<Rc<Model>>,
+    pub camera: Option<Camera>,
+    pub light: Option<Light>,
+    /// Makes the node's light cast shadows.  Point lights never do.
+    pub shadow: Option<ShadowSettings>,
+    local: Matrix4<f32>,
+    world: Matrix4<f32>,
+    dirty: bool,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl Node {
+    pub fn new<S>(name: S) -> Node
+    where
+        S: Into<String>,
+    {
+        Node {
+            name: name.into(),
+            model: None,
+            camera: None,
+            light: None,
+            shadow: None,
+            local: Matrix4::identity(),
+            world: Matrix4::identity(),
+            dirty: true,
+            parent: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_transform(mut self, transform: Matrix4<f32>) -> Node {
+        self.local = transform;
+        self
+    }
+
+    pub fn with_model(mut self, model: Rc<Model>) -> Node {
+        self.model = Some(model);
+        self
+    }
+
+    pub fn with_camera(mut self, camera: Camera) -> Node {
+        self.camera = Some(camera);
+        self
+    }
+
+    pub fn with_light(mut self, light: Light) -> Node {
+        self.light = Some(light);
+        self
+    }
+
+    pub fn with_shadow(mut self, shadow: ShadowSettings) -> Node {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    pub fn transform(&self) -> Matrix4<f32> {
+        self.local
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+/// A hierarchy of nodes.  World transforms are cached and only recomputed by `update` for nodes
+/// whose transform or ancestry changed.
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    nodes: Vec<Node>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds a node as a child of `parent`, or as a root if `parent` is `None`.
+    pub fn add(&mut self, parent: Option<NodeId>, node: Node) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            parent: None,
+            children: Vec::new(),
+            dirty: true,
+            ..node
+        });
+        if parent.is_some() {
+            self.set_parent(id, parent);
+        }
+        id
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    /// Transforms and hierarchy can only be changed through the scene, so that world transforms
+    /// stay up to date.
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+
+    /// The first node with the given name.
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|n| n.name == name).map(NodeId)
+    }
+
+    pub fn nodes<'a>(&'a self) -> Nodes<'a> {
+        Nodes {
+            scene: self,
+            index: 0,
+        }
+    }
+
+    pub fn roots(&self) -> Vec<NodeId> {
+        self.nodes().filter(|&(_, n)| n.parent.is_none()).map(|(id, _)| id).collect()
+    }
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Matrix4<f32>) {
+        let node = &mut self.nodes[id.0];
+        node.local = transform;
+        node.dirty = true;
+    }
+
+    /// Moves a node, with its descendants, below `parent`, or makes it a root if `parent` is
+    /// `None`.  Panics if `parent` is the node itself or one of its descendants.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        if let Some(parent) = parent {
+            assert!(!self.is_ancestor(id, parent), "a node cannot be its own ancestor");
+        }
+
+        if let Some(old) = self.nodes[id.0].parent {
+            self.nodes[old.0].children.retain(|&c| c != id);
+        }
+        if let Some(parent) = parent {
+            self.nodes[parent.0].children.push(id);
+        }
+        let node = &mut self.nodes[id.0];
+        node.parent = parent;
+        node.dirty = true;
+    }
+
+    /// Whether `ancestor` is `id` itself or one of its ancestors.
+    pub fn is_ancestor(&self, ancestor: NodeId, id: NodeId) -> bool {
+        let mut node = Some(id);
+        while let Some(n) = node {
+            if n == ancestor {
+                return true;
+            }
+            node = self.nodes[n.0].parent;
+        }
+        false
+    }
+
+    /// Recomputes the world transforms of every node that changed since the last update, and of
+    /// their descendants.
+    pub fn update(&mut self) {
+        for root in self.roots() {
+            self.update_node(root, Matrix4::identity(), false);
+        }
+    }
+
+    fn update_node(&mut self, id: NodeId, parent_world: Matrix4<f32>, parent_dirty: bool) {
+        let (world, dirty) = {
+            let node = &mut self.nodes[id.0];
+            let dirty = node.dirty || parent_dirty;
+            if dirty {
+                node.world = parent_world * node.local;
+                node.dirty = false;
+            }
+            (node.world, dirty)
+        };
+        for i in 0..self.nodes[id.0].children.len() {
+            let child = self.nodes[id.0].children[i];
+            self.update_node(child, world, dirty);
+        }
+    }
+
+    /// The transform from the node's space to world space, as of the last `update`.
+    pub fn world_transform(&self, id: NodeId) -> Matrix4<f32> {
+        self.nodes[id.0].world
+    }
+
+    /// Sets the local transform of a node so that its world transform becomes `world`, given the
+    /// world transform of its parent as of the last `update`.  Does nothing if the parent's
+    /// transform cannot be inverted.
+    pub fn set_world_transform(&mut self, id: NodeId, world: Matrix4<f32>) {
+        let local = match self.nodes[id.0].parent {
+            Some(parent) => {
+                match self.nodes[parent.0].world.inverse() {
+                    Some(inverse) => inverse * world,
+                    None => return,
+                }
+            }
+            None => world,
+        };
+        self.set_transform(id, local);
+    }
+
+    /// The world space bounding box of every model, as of the last `update`.
+    pub fn bounds(&self) -> Option<Aabb<f32>> {
+        self.nodes
+            .iter()
+            .filter_map(|n| {
+                let bounds = n.model.as_ref()?.bounds?;
+                Some(bounds.aabb.transform(&n.world))
+            })
+            .fold(None, |acc, b| Some(acc.map_or(b, |a: Aabb<f32>| a.union(&b))))
+    }
+
+    /// Updates the scene and renders every model as seen by the camera attached to `camera`,
+    /// replacing the renderer's projection, lights and materials.  Lights with shadow settings
+    /// first render their shadow maps.  Panics if the node has no camera.
+    pub fn render(&mut self, renderer: &mut Renderer, camera: NodeId) {
+        self.update();
+
+        let projection = self.nodes[camera.0].camera.expect("node has no camera").projection;
+        let (width, height) = renderer.dimensions();
+        let projection_matrix = projection.matrix(width as f32 / height as f32);
+        renderer.set_projection(projection_matrix);
+        let camera_world = self.world_transform(camera);
+        let view = camera_world.affine_inverse().unwrap_or_else(Matrix4::identity);
+
+        let lights = self.nodes
+            .iter()
+            .filter_map(|n| {
+                let light = n.light?;
+                let transform = view * n.world;
+                let shadow = n.shadow.and_then(|settings| {
+                    let (near, far) = (projection.near(), projection.far());
+                    let frustum = (camera_world, projection_matrix, near, far);
+                    self.shadow_map(&light, n.world, &settings, frustum)
+                });
+                Some(PlacedLight {
+                    light,
+                    position: transform.transform(Vector3::splat(0.0)),
+                    direction: transform.transform_vector(Vector3::new(0.0, 0.0, -1.0)).normalize(),
+                    shadow: shadow.map(Rc::new),
+                })
+            })
+            .collect();
+        renderer.set_lights(lights);
+
+        self.render_models(renderer, view);
+    }
+
+    fn render_models(&self, renderer: &mut Renderer, view: Matrix4<f32>) {
+        for node in &self.nodes {
+            let model = match node.model {
+                Some(ref model) => model,
+                None => continue,
+            };
+            renderer.set_material(model.material);
+            renderer.set_pbr_material(model.pbr_material.clone());
+            let transformation = view * node.world;
+            match model.bounds {
+                Some(ref bounds) => {
+                    renderer.render_culled(transformation, bounds, &model.triangles);
+                }
+                None => renderer.render(transformation, &model.triangles),
+            }
+        }
+    }
+
+    // Renders the depth maps of a light for a camera given by its world transform, projection
+    // and near and far distances.  Returns `None` if the light casts no shadow on anything.
+    fn shadow_map(
+        &self,
+        light: &Light,
+        light_world: Matrix4<f32>,
+        settings: &ShadowSettings,
+        (camera_world, camera_projection, near, far): (Matrix4<f32>, Matrix4<f32>, f32, f32),
+    ) -> Option<ShadowMap> {
+        let light_view = light_world.affine_inverse()?;
+        let casters = self.bounds()?.transform(&light_view);
+
+        // Each projection with the range of view distances it covers.
+        let projections = match light.kind {
+            LightKind::Directional => {
+                let far = far.min(settings.distance);
+                if far <= near {
+                    return None;
+                }
+                let splits = shadow::cascade_splits(
+                    near,
+                    far,
+                    settings.cascades,
+                    settings.split_lambda,
+                );
+                let camera_to_light = light_view * camera_world;
+                let mut projections = Vec::new();
+                for range in splits.windows(2) {
+                    let corners = shadow::frustum_slice(&camera_projection, range[0], range[1])?;
+                    let points: Vec<_> =
+                        corners.iter().map(|&c| camera_to_light.transform(c)).collect();
+                    let projection =
+                        shadow::fit_orthographic(&points, &casters, settings.resolution)?;
+                    projections.push((projection, range[0], range[1]));
+                }
+                projections
+            }
+            LightKind::Spot { outer_angle, .. } => {
+                // Padded so receivers at the far end of the casters stay inside the map.
+                let far = -casters.min.z * 1.01;
+                if far <= 0.0 {
+                    return None;
+                }
+                // As far out as the casters allow, since depth precision falls with the ratio
+                // of the far and near planes.
+                let near = (-casters.max.z / 2.0).max(far * 1e-2);
+                let fovy = (2.0 * outer_angle).min(MAX_SPOT_SHADOW_ANGLE);
+                let projection = Matrix4::perspective_fov(fovy, 1.0, near, far);
+                vec![(projection, 0.0, f32::INFINITY)]
+            }
+            LightKind::Point { .. } => return None,
+        };
+
+        let cascades = projections
+            .into_iter()
+            .map(|(projection, near, far)| {
+                let mut pass = Renderer::new(settings.resolution, settings.resolution, projection);
+                pass.set_color_write(false);
+                self.render_models(&mut pass, light_view);
+                ShadowCascade {
+                    depth: pass.depth_buffer().clone(),
+                    transform: projection * light_view * camera_world,
+                    near,
+                    far,
+                }
+            })
+            .collect();
+        Some(ShadowMap {
+            cascades,
+            bias: settings.bias,
+            slope_bias: settings.slope_bias,
+            pcf_radius: settings.pcf_radius,
+        })
+    }
+}
+
+// Spot light shadow maps cover at most this field of view, since a perspective projection
+// cannot cover a half space.
+const MAX_SPOT_SHADOW_ANGLE: f32 = 170.0 * f32::consts::PI / 180.0;
+
+/// An iterator over the nodes of a `Scene` in the order they were added, created by
+/// `Scene::nodes`.
+pub struct Nodes<'a> {
+    scene: &'a Scene,
+    index: usize,
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = (NodeId, &'a Node);
+
+    fn next(&mut self) -> Option<(NodeId, &'a Node)> {
+        let node = self.scene.nodes.get(self.index)?;
+        let id = NodeId(self.index);
+        self.index += 1;
+        Some((id, node))
+    }
+}
@@ -0,0 +1,832 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use vec3::Vector3;
+use vec4::Vector4;
+use mat4::Matrix4;
+use color::vec4_to_color;
+use mesh::Mesh;
+use renderer::{Renderer, DepthTest, Framebuffer, FragmentOutput, ColorFormat};
+use scene::{Scene, Node, NodeId, Model, Camera, Projection};
+use lighting::{Attenuation, Light, LightKind, Material, ShadingMode};
+use pbr::PbrMaterial;
+use tone_mapping::{ToneMapping, ToneMapOperator};
+use shadow::ShadowSettings;
+use texture::Texture;
+use image_format::{self, ImageError};
+use mesh_format::{self, MeshError};
+
+mod toml;
+
+use self::toml::{Toml, parse_toml};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    /// A syntax error in the scene file, `line` starts at 1.
+    Parse { line: usize, message: String },
+    /// The file is valid TOML but does not describe a valid scene.
+    Invalid(String),
+    Mesh { path: PathBuf, error: MeshError },
+    Image { path: PathBuf, error: ImageError },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SceneError::Io(ref err) => write!(f, "i/o error reading scene: {}", err),
+            SceneError::Parse { line, ref message } => write!(f, "line {}: {}", line, message),
+            SceneError::Invalid(ref msg) => write!(f, "invalid scene: {}", msg),
+            SceneError::Mesh { ref path, ref error } => {
+                write!(f, "could not load {}: {}", path.display(), error)
+            }
+            SceneError::Image { ref path, ref error } => {
+                write!(f, "could not load {}: {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl Error for SceneError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            SceneError::Io(ref err) => Some(err),
+            SceneError::Mesh { ref error, .. } => Some(error),
+            SceneError::Image { ref error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(err: io::Error) -> SceneError {
+        SceneError::Io(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    pub clear_color: Vector4<f32>,
+    pub depth_test: DepthTest,
+    pub shading: ShadingMode,
+    pub ambient_light: Vector3<f32>,
+    /// The format of the color buffer.  Floating point formats are tone mapped for display.
+    pub format: ColorFormat,
+    pub tone_mapping: ToneMapping,
+    /// Name of the node to render from, or the first node with a camera if `None`.
+    pub camera: Option<String>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings {
+            width: 800,
+            height: 600,
+            clear_color: Vector4::new(0.0, 0.0, 0.0, 1.0),
+            depth_test: DepthTest::Less,
+            shading: ShadingMode::Unlit,
+            ambient_light: Vector3::splat(0.0),
+            format: ColorFormat::Rgba8,
+            tone_mapping: ToneMapping::default(),
+            camera: None,
+        }
+    }
+}
+
+/// A material multiplies the vertex colors of the meshes using it, and gives them its
+/// reflectance for lighting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialDescription {
+    pub name: String,
+    pub color: Vector4<f32>,
+    pub material: Material,
+    /// The inputs for `ShadingMode::Pbr`, apart from the textures.  `color` serves as the base
+    /// color.
+    pub pbr: PbrMaterial,
+    pub textures: PbrTextures,
+}
+
+/// Paths of the images used by a metallic-roughness material, loaded by
+/// `SceneDescription::instantiate`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PbrTextures {
+    pub base_color: Option<PathBuf>,
+    pub metallic_roughness: Option<PathBuf>,
+    pub emissive: Option<PathBuf>,
+    pub occlusion: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MeshSource {
+    File(PathBuf),
+    /// `Mesh::cube`
+    Cube,
+}
+
+/// A node as written in a scene file.  Angles are in radians, although scene files give them in
+/// degrees.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeDescription {
+    pub name: String,
+    pub parent: Option<String>,
+    pub translation: Vector3<f32>,
+    /// Euler angles, applied as by `Matrix4::rotation`.
+    pub rotation: Vector3<f32>,
+    pub scale: Vector3<f32>,
+    /// Added to `rotation` every second.
+    pub spin: Vector3<f32>,
+    pub mesh: Option<MeshSource>,
+    pub material: Option<String>,
+    pub camera: Option<Camera>,
+    pub light: Option<Light>,
+    pub shadow: Option<ShadowSettings>,
+}
+
+impl NodeDescription {
+    pub fn new(name: String) -> NodeDescription {
+        NodeDescription {
+            name,
+            parent: None,
+            translation: Vector3::splat(0.0),
+            rotation: Vector3::splat(0.0),
+            scale: Vector3::splat(1.0),
+            spin: Vector3::splat(0.0),
+            mesh: None,
+            material: None,
+            camera: None,
+            light: None,
+            shadow: None,
+        }
+    }
+
+    /// The local transform after `time` seconds.
+    pub fn transform_at(&self, time: f32) -> Matrix4<f32> {
+        let rotation = self.rotation + self.spin * time;
+        Matrix4::translation(self.translation) * Matrix4::rotation(rotation) *
+            Matrix4::scaling(self.scale)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SceneDescription {
+    pub render: RenderSettings,
+    pub materials: Vec<MaterialDescription>,
+    pub nodes: Vec<NodeDescription>,
+}
+
+impl SceneDescription {
+    pub fn material(&self, name: &str) -> Option<&MaterialDescription> {
+        self.materials.iter().find(|m| m.name == name)
+    }
+
+    /// Loads the meshes and builds the scene graph.  Nodes sharing a mesh and material share one
+    /// `Model`.
+    pub fn instantiate(&self) -> Result<LoadedScene, SceneError> {
+        let mut scene = Scene::new();
+        let mut ids: HashMap<&str, NodeId> = HashMap::new();
+        let mut models: HashMap<(&MeshSource, Option<&str>), Rc<Model>> = HashMap::new();
+        let mut textures = HashMap::new();
+
+        for desc in &self.nodes {
+            let mut node = Node::new(desc.name.clone()).with_transform(desc.transform_at(0.0));
+            node.camera = desc.camera;
+            node.light = desc.light;
+            node.shadow = desc.shadow;
+
+            if let Some(ref source) = desc.mesh {
+                let key = (source, desc.material.as_deref());
+                let model = match models.get(&key) {
+                    Some(model) => model.clone(),
+                    None => {
+                        let model = Rc::new(self.load_model(source, key.1, &mut textures)?);
+                        models.insert(key, model.clone());
+                        model
+                    }
+                };
+                node.model = Some(model);
+            } else if desc.material.is_some() {
+                return invalid(format!("node '{}' has a material but no mesh", desc.name));
+            }
+
+            let id = scene.add(None, node);
+            if ids.insert(&desc.name, id).is_some() {
+                return invalid(format!("more than one node is named '{}'", desc.name));
+            }
+        }
+
+        for desc in &self.nodes {
+            if let Some(ref parent) = desc.parent {
+                let id = ids[desc.name.as_str()];
+                let parent = match ids.get(parent.as_str()) {
+                    Some(&parent) => parent,
+                    None => return invalid(format!("unknown parent node '{}'", parent)),
+                };
+                if scene.is_ancestor(id, parent) {
+                    return invalid(format!("node '{}' is its own ancestor", desc.name));
+                }
+                scene.set_parent(id, Some(parent));
+            }
+        }
+
+        let camera = match self.render.camera {
+            Some(ref name) => {
+                match ids.get(name.as_str()) {
+                    Some(&id) if scene.node(id).camera.is_some() => id,
+                    Some(_) => return invalid(format!("node '{}' has no camera", name)),
+                    None => return invalid(format!("unknown camera node '{}'", name)),
+                }
+            }
+            None => {
+                match scene.nodes().find(|&(_, n)| n.camera.is_some()) {
+                    Some((id, _)) => id,
+                    None => return invalid("the scene has no camera"),
+                }
+            }
+        };
+
+        let animated = self.nodes
+            .iter()
+            .filter(|n| n.spin != Vector3::splat(0.0))
+            .map(|n| (ids[n.name.as_str()], n.clone()))
+            .collect();
+
+        Ok(LoadedScene {
+            settings: self.render.clone(),
+            scene,
+            camera,
+            animated,
+        })
+    }
+
+    // Textures are shared between models through `textures`, keyed by their path and whether
+    // they hold sRGB colors.
+    fn load_model(
+        &self,
+        source: &MeshSource,
+        material: Option<&str>,
+        textures: &mut HashMap<(PathBuf, bool), Rc<Texture>>,
+    ) -> Result<Model, SceneError> {
+        let mut triangles = match *source {
+            MeshSource::File(ref path) => {
+                mesh_format::load_triangles(path).map_err(|error| {
+                    SceneError::Mesh {
+                        path: path.clone(),
+                        error,
+                    }
+                })?
+            }
+            MeshSource::Cube => Mesh::cube().triangles(Vector4::splat(1.0)),
+        };
+
+        let material = match material {
+            Some(name) => {
+                match self.material(name) {
+                    Some(material) => material,
+                    None => return invalid(format!("unknown material '{}'", name)),
+                }
+            }
+            None => return Ok(Model::new(triangles)),
+        };
+        for t in &mut triangles {
+            for v in &mut [&mut t.a, &mut t.b, &mut t.c] {
+                v.color *= material.color;
+            }
+        }
+
+        let mut texture = |path: &Option<PathBuf>, srgb| -> Result<_, SceneError> {
+            let path = match *path {
+                Some(ref path) => path,
+                None => return Ok(None),
+            };
+            let key = (path.clone(), srgb);
+            if let Some(texture) = textures.get(&key) {
+                return Ok(Some(texture.clone()));
+            }
+            let image_error = |error| {
+                SceneError::Image {
+                    path: path.clone(),
+                    error,
+                }
+            };
+            let image = image_format::load_image(path).map_err(&image_error)?;
+            let texture = match Texture::try_from_colors(&image.into_rgba8(), srgb) {
+                Some(texture) => Rc::new(texture),
+                None => {
+                    let error = ImageError::Malformed("the image has no pixels".to_string());
+                    return Err(image_error(error));
+                }
+            };
+            textures.insert(key, texture.clone());
+            Ok(Some(texture))
+        };
+        let pbr = PbrMaterial {
+            base_color_texture: texture(&material.textures.base_color, true)?,
+            metallic_roughness_texture: texture(&material.textures.metallic_roughness, false)?,
+            emissive_texture: texture(&material.textures.emissive, true)?,
+            occlusion_texture: texture(&material.textures.occlusion, false)?,
+            ..material.pbr.clone()
+        };
+
+        Ok(Model::new(triangles).with_material(material.material).with_pbr_material(pbr))
+    }
+}
+
+/// A scene ready to render, created by `SceneDescription::instantiate`.
+#[derive(Debug, Clone)]
+pub struct LoadedScene {
+    pub settings: RenderSettings,
+    pub scene: Scene,
+    pub camera: NodeId,
+    animated: Vec<(NodeId, NodeDescription)>,
+}
+
+impl LoadedScene {
+    /// A renderer with the dimensions, color format, depth test, lighting and tone mapping from
+    /// the render settings.
+    pub fn renderer(&self) -> Renderer {
+        let (width, height) = (self.settings.width, self.settings.height);
+        let mut renderer = Renderer::new(width, height, Matrix4::identity());
+        if self.settings.format != ColorFormat::Rgba8 {
+            let attachments = [(FragmentOutput::Color, self.settings.format)];
+            renderer.bind_framebuffer(Framebuffer::with_formats(width, height, &attachments));
+        }
+        renderer.set_depth_test(self.settings.depth_test);
+        renderer.set_shading(self.settings.shading);
+        renderer.set_ambient_light(self.settings.ambient_light);
+        renderer.set_tone_mapping(self.settings.tone_mapping);
+        renderer
+    }
+
+    /// Moves spinning nodes to where they are `time` seconds after the start of the scene.
+    pub fn set_time(&mut self, time: f32) {
+        for &(id, ref desc) in &self.animated {
+            self.scene.set_transform(id, desc.transform_at(time));
+        }
+    }
+
+    /// Clears the renderer with the clear color and renders the scene from its camera.
+    pub fn render(&mut self, renderer: &mut Renderer) {
+        renderer.set_depth_test(self.settings.depth_test);
+        renderer.set_shading(self.settings.shading);
+        renderer.set_ambient_light(self.settings.ambient_light);
+        renderer.set_tone_mapping(self.settings.tone_mapping);
+        renderer.clear(vec4_to_color(self.settings.clear_color));
+        self.scene.render(renderer, self.camera);
+    }
+}
+
+/// Parses a scene file.  Relative mesh and texture paths are resolved against `base_dir` when it
+/// is given.
+pub fn parse_scene(source: &str, base_dir: Option<&Path>) -> Result<SceneDescription, SceneError> {
+    let resolve = |path: &str| match base_dir {
+        Some(dir) => dir.join(path),
+        None => PathBuf::from(path),
+    };
+    let document = parse_toml(source)?;
+    let root = Table::new("the scene", &document)?;
+    root.check_keys(&["render", "material", "node"])?;
+
+    let mut description = SceneDescription::default();
+
+    if let Some(render) = root.table("render")? {
+        render.check_keys(
+            &[
+                "width",
+                "height",
+                "clear_color",
+                "depth_test",
+                "shading",
+                "ambient_light",
+                "format",
+                "tone_mapping",
+                "exposure",
+                "camera",
+            ],
+        )?;
+        let settings = &mut description.render;
+        settings.width = render.u32_or("width", settings.width, 1, MAX_IMAGE_SIZE)?;
+        settings.height = render.u32_or("height", settings.height, 1, MAX_IMAGE_SIZE)?;
+        settings.clear_color = render.color_or("clear_color", settings.clear_color)?;
+        settings.depth_test = match render.str("depth_test")? {
+            None | Some("less") => DepthTest::Less,
+            Some("greater") => DepthTest::Greater,
+            Some("disabled") => DepthTest::Disabled,
+            Some(other) => return invalid(format!("unknown depth test '{}'", other)),
+        };
+        settings.shading = match render.str("shading")? {
+            None | Some("unlit") => ShadingMode::Unlit,
+            Some("gouraud") => ShadingMode::Gouraud,
+            Some("phong") => ShadingMode::Phong,
+            Some("blinn-phong") => ShadingMode::BlinnPhong,
+            Some("pbr") => ShadingMode::Pbr,
+            Some(other) => return invalid(format!("unknown shading mode '{}'", other)),
+        };
+        settings.ambient_light = render
+            .color_or("ambient_light", settings.ambient_light.extend(1.0))?
+            .vec3();
+        settings.format = match render.str("format")? {
+            None | Some("rgba8") => ColorFormat::Rgba8,
+            Some("rgba16f") => ColorFormat::Rgba16F,
+            Some("rgba32f") => ColorFormat::Rgba32F,
+            Some(other) => return invalid(format!("unknown color format '{}'", other)),
+        };
+        settings.tone_mapping.operator = match render.str("tone_mapping")? {
+            None | Some("clamp") => ToneMapOperator::Clamp,
+            Some("reinhard") => ToneMapOperator::Reinhard,
+            Some("aces") => ToneMapOperator::AcesFilmic,
+            Some(other) => return invalid(format!("unknown tone mapping '{}'", other)),
+        };
+        settings.tone_mapping.exposure = render.f32_or("exposure", 0.0)?;
+        settings.camera = render.str("camera")?.map(|s| s.to_string());
+    }
+
+    for material in root.tables("material")? {
+        material.check_keys(
+            &[
+                "name",
+                "color",
+                "ambient",
+                "diffuse",
+                "specular",
+                "shininess",
+                "metallic",
+                "roughness",
+                "emissive",
+                "occlusion_strength",
+                "base_color_texture",
+                "metallic_roughness_texture",
+                "emissive_texture",
+                "occlusion_texture",
+            ],
+        )?;
+        let name = material.required_str("name")?.to_string();
+        if description.material(&name).is_some() {
+            return invalid(format!("more than one material is named '{}'", name));
+        }
+        let default = Material::default();
+        let reflectance = Material {
+            ambient: material.color_or("ambient", default.ambient.extend(1.0))?.vec3(),
+            diffuse: material.color_or("diffuse", default.diffuse.extend(1.0))?.vec3(),
+            specular: material.color_or("specular", default.specular.extend(1.0))?.vec3(),
+            shininess: material.f32_or("shininess", default.shininess)?,
+        };
+        if reflectance.shininess < 0.0 {
+            return material.invalid("has a negative shininess");
+        }
+        let default = PbrMaterial::default();
+        let pbr = PbrMaterial {
+            metallic: material.f32_or("metallic", default.metallic)?,
+            roughness: material.f32_or("roughness", default.roughness)?,
+            emissive: material.color_or("emissive", default.emissive.extend(1.0))?.vec3(),
+            occlusion_strength: material.f32_or("occlusion_strength", 1.0)?,
+            ..default
+        };
+        for &(key, value) in
+            &[
+                ("metallic", pbr.metallic),
+                ("roughness", pbr.roughness),
+                ("occlusion_strength", pbr.occlusion_strength),
+            ]
+        {
+            if !(0.0..=1.0).contains(&value) {
+                return material.invalid(&format!("has '{}' outside 0 to 1", key));
+            }
+        }
+        let texture = |key| -> Result<_, SceneError> { Ok(material.str(key)?.map(&resolve)) };
+        let textures = PbrTextures {
+            base_color: texture("base_color_texture")?,
+            metallic_roughness: texture("metallic_roughness_texture")?,
+            emissive: texture("emissive_texture")?,
+            occlusion: texture("occlusion_texture")?,
+        };
+        description.materials.push(MaterialDescription {
+            color: material.color_or("color", Vector4::splat(1.0))?,
+            material: reflectance,
+            pbr,
+            textures,
+            name,
+        });
+    }
+
+    for node in root.tables("node")? {
+        node.check_keys(
+            &[
+                "name",
+                "parent",
+                "translation",
+                "rotation",
+                "scale",
+                "spin",
+                "mesh",
+                "primitive",
+                "material",
+                "camera",
+                "light",
+                "shadow",
+            ],
+        )?;
+        let mut desc = NodeDescription::new(node.required_str("name")?.to_string());
+        let node = Table {
+            context: format!("node '{}'", desc.name),
+            ..node
+        };
+
+        desc.parent = node.str("parent")?.map(|s| s.to_string());
+        desc.translation = node.vector3_or("translation", desc.translation)?;
+        desc.rotation = node.vector3_or("rotation", desc.rotation)? * DEGREES;
+        desc.scale = match node.get("scale") {
+            Some(&Toml::Integer(_)) | Some(&Toml::Float(_)) => {
+                Vector3::splat(node.f32_or("scale", 1.0)?)
+            }
+            _ => node.vector3_or("scale", desc.scale)?,
+        };
+        desc.spin = node.vector3_or("spin", desc.spin)? * DEGREES;
+
+        desc.mesh = match (node.str("mesh")?, node.str("primitive")?) {
+            (Some(_), Some(_)) => return node.invalid("has both a mesh and a primitive"),
+            (Some(path), None) => Some(MeshSource::File(resolve(path))),
+            (None, Some("cube")) => Some(MeshSource::Cube),
+            (None, Some(other)) => return node.invalid(&format!("unknown primitive '{}'", other)),
+            (None, None) => None,
+        };
+        desc.material = node.str("material")?.map(|s| s.to_string());
+
+        if let Some(camera) = node.table("camera")? {
+            camera.check_keys(&["projection", "fovy", "height", "near", "far"])?;
+            let near = camera.f32_or("near", 0.1)?;
+            let far = camera.f32_or("far", 100.0)?;
+            let projection = match camera.str("projection")? {
+                None | Some("perspective") => {
+                    Projection::Perspective {
+                        fovy: camera.f32_or("fovy", 60.0)? * DEGREES,
+                        near,
+                        far,
+                    }
+                }
+                Some("orthographic") => {
+                    Projection::Orthographic {
+                        height: camera.f32_or("height", 2.0)?,
+                        near,
+                        far,
+                    }
+                }
+                Some(other) => return camera.invalid(&format!("unknown projection '{}'", other)),
+            };
+            desc.camera = Some(Camera { projection });
+        }
+
+        if let Some(light) = node.table("light")? {
+            light.check_keys(
+                &[
+                    "kind",
+                    "color",
+                    "intensity",
+                    "attenuation",
+                    "inner_angle",
+                    "outer_angle",
+                ],
+            )?;
+            let kind = light.str("kind")?;
+            let positional = kind == Some("point") || kind == Some("spot");
+            if !positional && light.get("attenuation").is_some() {
+                return light.invalid("has an attenuation but is not a point or spot light");
+            }
+            if kind != Some("spot") &&
+                (light.get("inner_angle").is_some() || light.get("outer_angle").is_some())
+            {
+                return light.invalid("has a cone angle but is not a spot light");
+            }
+            let attenuation = {
+                let a = light.vector3_or("attenuation", Vector3::new(1.0, 0.0, 0.0))?;
+                Attenuation {
+                    constant: a.x,
+                    linear: a.y,
+                    quadratic: a.z,
+                }
+            };
+            let kind = match kind {
+                None | Some("directional") => LightKind::Directional,
+                Some("point") => LightKind::Point { attenuation },
+                Some("spot") => {
+                    let outer_angle = light.f32_or("outer_angle", 30.0)? * DEGREES;
+                    let inner_angle = light.f32_or("inner_angle", 0.0)? * DEGREES;
+                    if inner_angle > outer_angle {
+                        return light.invalid("has an inner angle wider than its outer angle");
+                    }
+                    LightKind::Spot {
+                        attenuation,
+                        inner_angle,
+                        outer_angle,
+                    }
+                }
+                Some(other) => return light.invalid(&format!("unknown light kind '{}'", other)),
+            };
+            desc.light = Some(Light {
+                kind,
+                color: light.color_or("color", Vector4::splat(1.0))?.vec3(),
+                intensity: light.f32_or("intensity", 1.0)?,
+            });
+        }
+
+        if let Some(shadow) = node.table("shadow")? {
+            shadow.check_keys(
+                &[
+                    "resolution",
+                    "bias",
+                    "slope_bias",
+                    "pcf_radius",
+                    "cascades",
+                    "distance",
+                    "split_lambda",
+                ],
+            )?;
+            match desc.light {
+                Some(Light { kind: LightKind::Point { .. }, .. }) => {
+                    return node.invalid("has a shadow but point lights cannot cast shadows");
+                }
+                Some(_) => {}
+                None => return node.invalid("has a shadow but no light"),
+            }
+            let default = ShadowSettings::default();
+            let settings = ShadowSettings {
+                resolution: shadow.u32_or(
+                    "resolution",
+                    default.resolution,
+                    1,
+                    MAX_SHADOW_RESOLUTION,
+                )?,
+                bias: shadow.f32_or("bias", default.bias)?,
+                slope_bias: shadow.f32_or("slope_bias", default.slope_bias)?,
+                pcf_radius: shadow.u32_or("pcf_radius", default.pcf_radius, 0, MAX_PCF_RADIUS)?,
+                cascades: shadow.u32_or("cascades", default.cascades, 1, MAX_CASCADES)?,
+                distance: shadow.f32_or("distance", default.distance)?,
+                split_lambda: shadow.f32_or("split_lambda", default.split_lambda)?,
+            };
+            desc.shadow = Some(settings);
+        }
+
+        description.nodes.push(desc);
+    }
+
+    Ok(description)
+}
+
+/// Loads a scene file, resolving mesh paths relative to the directory containing it.  The meshes
+/// themselves are loaded by `SceneDescription::instantiate`.
+pub fn load_scene<P: AsRef<Path>>(path: P) -> Result<SceneDescription, SceneError> {
+    let path = path.as_ref();
+    let mut source = String::new();
+    File::open(path)?.read_to_string(&mut source)?;
+    parse_scene(&source, path.parent())
+}
+
+// The largest image width or height a scene may ask for.
+const MAX_IMAGE_SIZE: u32 = 16384;
+
+// Limits on shadow settings, which each map or cascade multiplies the memory and time of.
+const MAX_SHADOW_RESOLUTION: u32 = 8192;
+const MAX_PCF_RADIUS: u32 = 8;
+const MAX_CASCADES: u32 = 8;
+
+const DEGREES: f32 = ::std::f32::consts::PI / 180.0;
+
+fn invalid<T, S: Into<String>>(message: S) -> Result<T, SceneError> {
+    Err(SceneError::Invalid(message.into()))
+}
+
+// A TOML table along with a description of where it is, for error messages.
+#[derive(Clone)]
+struct Table<'a> {
+    context: String,
+    table: &'a BTreeMap<String, Toml>,
+}
+
+impl<'a> Table<'a> {
+    fn new<S: Into<String>>(context: S, value: &'a Toml) -> Result<Table<'a>, SceneError> {
+        let context = context.into();
+        match value.as_table() {
+            Some(table) => Ok(Table { context, table }),
+            None => invalid(format!("{} must be a table", context)),
+        }
+    }
+
+    fn invalid<T>(&self, message: &str) -> Result<T, SceneError> {
+        invalid(format!("{} {}", self.context, message))
+    }
+
+    fn wrong_type<T>(&self, key: &str, expected: &str) -> Result<T, SceneError> {
+        self.invalid(&format!("has '{}' of the wrong type, expected {}", key, expected))
+    }
+
+    fn check_keys(&self, known: &[&str]) -> Result<(), SceneError> {
+        match self.table.keys().find(|k| !known.contains(&k.as_str())) {
+            Some(key) => self.invalid(&format!("has unknown key '{}'", key)),
+            None => Ok(()),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&'a Toml> {
+        self.table.get(key)
+    }
+
+    fn table(&self, key: &str) -> Result<Option<Table<'a>>, SceneError> {
+        match self.get(key) {
+            Some(value) => Table::new(format!("{} {}", self.context, key), value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn tables(&self, key: &str) -> Result<Vec<Table<'a>>, SceneError> {
+        let array = match self.get(key) {
+            Some(value) => {
+                match value.as_array() {
+                    Some(array) => array,
+                    None => return self.wrong_type(key, &format!("[[{}]] tables", key)),
+                }
+            }
+            None => return Ok(Vec::new()),
+        };
+        array
+            .iter()
+            .enumerate()
+            .map(|(i, v)| Table::new(format!("{} {}", key, i + 1), v))
+            .collect()
+    }
+
+    fn str(&self, key: &str) -> Result<Option<&'a str>, SceneError> {
+        match self.get(key) {
+            Some(value) => {
+                match value.as_str() {
+                    Some(s) => Ok(Some(s)),
+                    None => self.wrong_type(key, "a string"),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn required_str(&self, key: &str) -> Result<&'a str, SceneError> {
+        match self.str(key)? {
+            Some(s) => Ok(s),
+            None => self.invalid(&format!("is missing '{}'", key)),
+        }
+    }
+
+    fn f32_or(&self, key: &str, default: f32) -> Result<f32, SceneError> {
+        match self.get(key) {
+            Some(value) => {
+                match value.as_f64() {
+                    Some(n) => Ok(n as f32),
+                    None => self.wrong_type(key, "a number"),
+                }
+            }
+            None => Ok(default),
+        }
+    }
+
+    // Integers from `min` to `max`.
+    fn u32_or(&self, key: &str, default: u32, min: u32, max: u32) -> Result<u32, SceneError> {
+        match self.get(key) {
+            Some(value) => {
+                match value.as_integer() {
+                    Some(n) if n >= min as i64 && n <= max as i64 => Ok(n as u32),
+                    _ => self.wrong_type(key, &format!("an integer from {} to {}", min, max)),
+                }
+            }
+            None => Ok(default),
+        }
+    }
+
+    fn floats(&self, key: &str, min: usize, max: usize) -> Result<Option<Vec<f32>>, SceneError> {
+        let value = match self.get(key) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let floats: Option<Vec<f32>> = value.as_array().and_then(|a| {
+            a.iter().map(|v| v.as_f64().map(|n| n as f32)).collect()
+        });
+        match floats {
+            Some(ref f) if f.len() >= min && f.len() <= max => Ok(floats),
+            _ if min == max => self.wrong_type(key, &format!("{} numbers", min)),
+            _ => self.wrong_type(key, &format!("{} to {} numbers", min, max)),
+        }
+    }
+
+    fn vector3_or(&self, key: &str, default: Vector3<f32>) -> Result<Vector3<f32>, SceneError> {
+        Ok(self.floats(key, 3, 3)?.map_or(default, |f| Vector3::new(f[0], f[1], f[2])))
+    }
+
+    // Colors are RGB or RGBA, with components from 0 to 1.
+    fn color_or(&self, key: &str, default: Vector4<f32>) -> Result<Vector4<f32>, SceneError> {
+        Ok(self.floats(key, 3, 4)?.map_or(default, |f| {
+            Vector4::new(f[0], f[1], f[2], f.get(3).cloned().unwrap_or(1.0))
+        }))
+    }
+}
@@ -0,0 +1,407 @@
+// A parser for the subset of TOML used by scene files.  Tables, arrays of tables, inline tables,
+// arrays, strings, numbers and booleans are supported; dotted keys, multi-line strings and dates
+// are not.
+
+use std::collections::BTreeMap;
+
+use super::SceneError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Toml {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Toml>),
+    Table(BTreeMap<String, Toml>),
+}
+
+impl Toml {
+    /// Integers are converted, since TOML writes whole numbers without a decimal point.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Toml::Float(n) => Some(n),
+            Toml::Integer(n) => Some(n as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match *self {
+            Toml::Integer(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Toml::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Toml]> {
+        match *self {
+            Toml::Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&BTreeMap<String, Toml>> {
+        match *self {
+            Toml::Table(ref t) => Some(t),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_toml(source: &str) -> Result<Toml, SceneError> {
+    let mut parser = Parser {
+        source: source.as_bytes(),
+        pos: 0,
+    };
+    let mut root = BTreeMap::new();
+    // The header that key/value pairs are currently added to, `None` for the root table.
+    let mut current: Option<(String, bool)> = None;
+
+    loop {
+        parser.skip_blank_lines();
+        match parser.peek() {
+            None => break,
+            Some(b'[') => {
+                parser.pos += 1;
+                let array = parser.peek() == Some(b'[');
+                if array {
+                    parser.pos += 1;
+                }
+                parser.skip_whitespace();
+                let name = parser.key()?;
+                parser.skip_whitespace();
+                let close: &[u8] = if array { b"]]" } else { b"]" };
+                if !parser.source[parser.pos..].starts_with(close) {
+                    return parser.error(if parser.peek() == Some(b'.') {
+                        "dotted table names are not supported"
+                    } else {
+                        "expected ']' after table name"
+                    });
+                }
+                parser.pos += close.len();
+                parser.end_of_line()?;
+
+                if array {
+                    match *root.entry(name.clone()).or_insert_with(|| Toml::Array(Vec::new())) {
+                        Toml::Array(ref mut tables) => tables.push(Toml::Table(BTreeMap::new())),
+                        _ => return parser.error(&format!("'{}' is already defined", name)),
+                    }
+                } else if root.contains_key(&name) {
+                    return parser.error(&format!("'{}' is already defined", name));
+                } else {
+                    root.insert(name.clone(), Toml::Table(BTreeMap::new()));
+                }
+                current = Some((name, array));
+            }
+            Some(_) => {
+                let (key, value) = parser.key_value(0)?;
+                parser.end_of_line()?;
+                let table = match current {
+                    None => &mut root,
+                    Some((ref name, array)) => {
+                        let value = root.get_mut(name);
+                        let table = if array {
+                            value.and_then(|a| match *a {
+                                Toml::Array(ref mut tables) => tables.last_mut(),
+                                _ => None,
+                            })
+                        } else {
+                            value
+                        };
+                        match table {
+                            Some(&mut Toml::Table(ref mut table)) => table,
+                            // Headers always insert the table they refer to.
+                            _ => unreachable!(),
+                        }
+                    }
+                };
+                if table.contains_key(&key) {
+                    return parser.error(&format!("duplicate key '{}'", key));
+                }
+                table.insert(key, value);
+            }
+        }
+    }
+
+    Ok(Toml::Table(root))
+}
+
+// Guards against stack overflows from maliciously nested documents.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    source: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: &str) -> Result<T, SceneError> {
+        let line = self.source[..self.pos.min(self.source.len())]
+            .iter()
+            .filter(|&&c| c == b'\n')
+            .count() + 1;
+        Err(SceneError::Parse {
+            line,
+            message: message.to_string(),
+        })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.source.get(self.pos).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some(b'#') {
+            while let Some(c) = self.peek() {
+                if c == b'\n' {
+                    break;
+                }
+                self.pos += 1;
+            }
+        }
+    }
+
+    // Also used between the elements of arrays, which may span lines.
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_whitespace();
+            self.skip_comment();
+            match self.peek() {
+                Some(b'\n') | Some(b'\r') => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), SceneError> {
+        self.skip_whitespace();
+        self.skip_comment();
+        match self.peek() {
+            None | Some(b'\n') => Ok(()),
+            Some(b'\r') if self.source.get(self.pos + 1) == Some(&b'\n') => Ok(()),
+            Some(_) => self.error("expected the end of the line"),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, SceneError> {
+        match self.peek() {
+            Some(b'"') => self.basic_string(),
+            Some(b'\'') => self.literal_string(),
+            _ => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    if c.is_ascii_alphanumeric() || c == b'_' || c == b'-' {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                if start == self.pos {
+                    return self.error("expected a key");
+                }
+                // Bare keys contain only ASCII.
+                Ok(String::from_utf8(self.source[start..self.pos].to_vec()).unwrap())
+            }
+        }
+    }
+
+    fn key_value(&mut self, depth: usize) -> Result<(String, Toml), SceneError> {
+        let key = self.key()?;
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'=') => self.pos += 1,
+            Some(b'.') => return self.error("dotted keys are not supported"),
+            _ => return self.error("expected '=' after key"),
+        }
+        self.skip_whitespace();
+        let value = self.value(depth)?;
+        Ok((key, value))
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Toml, SceneError> {
+        if depth > MAX_DEPTH {
+            return self.error("document is nested too deeply");
+        }
+
+        match self.peek() {
+            Some(b'[') => {
+                self.pos += 1;
+                let mut array = Vec::new();
+                loop {
+                    self.skip_blank_lines();
+                    if self.peek() == Some(b']') {
+                        self.pos += 1;
+                        return Ok(Toml::Array(array));
+                    }
+                    array.push(self.value(depth + 1)?);
+                    self.skip_blank_lines();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Toml::Array(array));
+                        }
+                        _ => return self.error("expected ',' or ']' in array"),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut table = BTreeMap::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Toml::Table(table));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let (key, value) = self.key_value(depth + 1)?;
+                    if table.contains_key(&key) {
+                        return self.error(&format!("duplicate key '{}'", key));
+                    }
+                    table.insert(key, value);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Toml::Table(table));
+                        }
+                        _ => return self.error("expected ',' or '}' in inline table"),
+                    }
+                }
+            }
+            Some(b'"') => self.basic_string().map(Toml::String),
+            Some(b'\'') => self.literal_string().map(Toml::String),
+            Some(b't') => self.expect_literal("true", Toml::Boolean(true)),
+            Some(b'f') => self.expect_literal("false", Toml::Boolean(false)),
+            Some(c) if c == b'-' || c == b'+' || c.is_ascii_digit() => self.number(),
+            Some(b'\n') | None => self.error("expected a value"),
+            Some(_) => self.error("unexpected character"),
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &str, value: Toml) -> Result<Toml, SceneError> {
+        if self.source[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(value)
+        } else {
+            self.error("invalid literal")
+        }
+    }
+
+    fn number(&mut self) -> Result<Toml, SceneError> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == b'-' || c == b'+' || c == b'.' || c == b'_' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        // The number contains only ASCII.
+        let text = ::std::str::from_utf8(&self.source[start..self.pos]).unwrap();
+        let digits: String = text.chars().filter(|&c| c != '_').collect();
+        let is_float = digits.contains(&['.', 'e', 'E'][..]);
+        let valid_underscores = !text.starts_with('_') && !text.ends_with('_') &&
+            !text.contains("__");
+        let value = if !valid_underscores {
+            None
+        } else if is_float {
+            digits.parse().ok().map(Toml::Float)
+        } else {
+            digits.parse().ok().map(Toml::Integer)
+        };
+        match value {
+            Some(value) => Ok(value),
+            None => self.error(&format!("invalid number '{}'", text)),
+        }
+    }
+
+    fn hex(&mut self, len: usize) -> Result<char, SceneError> {
+        let text = self.source
+            .get(self.pos..self.pos + len)
+            .and_then(|t| ::std::str::from_utf8(t).ok());
+        match text.and_then(|t| u32::from_str_radix(t, 16).ok()).and_then(
+            ::std::char::from_u32,
+        ) {
+            Some(c) => {
+                self.pos += len;
+                Ok(c)
+            }
+            None => self.error("invalid unicode escape"),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, SceneError> {
+        // Skip the opening quote
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => return self.error("unterminated string"),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escape = match self.peek() {
+                        Some(c) => c,
+                        None => return self.error("unterminated string"),
+                    };
+                    self.pos += 1;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.hex(4)?,
+                        b'U' => self.hex(8)?,
+                        _ => return self.error("invalid escape in string"),
+                    };
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                Some(c) => {
+                    bytes.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        // Unescaped bytes were copied from valid UTF-8 and escapes were encoded as UTF-8.
+        Ok(String::from_utf8(bytes).unwrap())
+    }
+
+    fn literal_string(&mut self) -> Result<String, SceneError> {
+        // Skip the opening quote
+        self.pos += 1;
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => return self.error("unterminated string"),
+                Some(b'\'') => break,
+                Some(_) => self.pos += 1,
+            }
+        }
+        let s = String::from_utf8(self.source[start..self.pos].to_vec()).unwrap();
+        self.pos += 1;
+        Ok(s)
+    }
+}
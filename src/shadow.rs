@@ -0,0 +1,178 @@
+use vec3::Vector3;
+use vec4::Vector4;
+use mat4::Matrix4;
+use aabb::Aabb;
+use image::Image;
+
+/// How a light renders and samples its shadow map.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ShadowSettings {
+    /// Width and height of each depth map.
+    pub resolution: u32,
+    /// Constant depth offset, in the normalized device depth of the light, that keeps surfaces
+    /// from shadowing themselves.
+    pub bias: f32,
+    /// Additional offset, multiplied by the tangent of the angle between the light and the
+    /// surface normal, for surfaces the light grazes.
+    pub slope_bias: f32,
+    /// Percentage closer filtering averages the depth test over a square of `2 * pcf_radius + 1`
+    /// texels on a side.  Zero gives hard shadows.
+    pub pcf_radius: u32,
+    /// Number of cascades for directional lights, which split the view frustum by distance so
+    /// nearby shadows get more texels.  Other lights always use a single map.
+    pub cascades: u32,
+    /// How far from the camera directional light shadows reach, at most the far plane.
+    pub distance: f32,
+    /// Blends cascade splits between uniform (0) and logarithmic (1) spacing.
+    pub split_lambda: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> ShadowSettings {
+        ShadowSettings {
+            resolution: 1024,
+            bias: 0.002,
+            slope_bias: 0.004,
+            pcf_radius: 1,
+            cascades: 3,
+            distance: 50.0,
+            split_lambda: 0.75,
+        }
+    }
+}
+
+/// One depth map of a shadow, covering the view from `near` to `far`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowCascade {
+    /// Normalized device depth as seen from the light, as written by a `DepthTest::Less` pass.
+    pub depth: Image<f32>,
+    /// Maps points from the space shading happens in to the light's clip space.
+    pub transform: Matrix4<f32>,
+    /// Distance along the view direction where the cascade starts.
+    pub near: f32,
+    /// Distance along the view direction where the cascade ends.
+    pub far: f32,
+}
+
+/// The depth maps a light renders to find what it cannot reach.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowMap {
+    /// Ordered from nearest to farthest.
+    pub cascades: Vec<ShadowCascade>,
+    pub bias: f32,
+    pub slope_bias: f32,
+    pub pcf_radius: u32,
+}
+
+impl ShadowMap {
+    /// The fraction of light reaching `position`, from 0 in full shadow to 1 when fully lit.
+    /// `position`, the normalized `normal` and the normalized direction `to_light` are in the
+    /// space shading happens in, with the viewer at the origin looking down the negative z axis.
+    /// Points outside every cascade are lit.
+    pub fn visibility(
+        &self,
+        position: Vector3<f32>,
+        normal: Vector3<f32>,
+        to_light: Vector3<f32>,
+    ) -> f32 {
+        let distance = -position.z;
+        let cascade = match self.cascades.iter().find(|c| distance <= c.far) {
+            Some(cascade) => cascade,
+            None => return 1.0,
+        };
+
+        let clip = cascade.transform * position.extend(1.0);
+        if clip.w <= 0.0 {
+            return 1.0;
+        }
+        let ndc = clip.vec3() / clip.w;
+        if ndc.z > 1.0 {
+            return 1.0;
+        }
+
+        let cos = normal.dot(to_light).clamp(1e-3, 1.0);
+        let tan = ((1.0 - cos * cos).sqrt() / cos).min(10.0);
+        let depth = ndc.z - self.bias - self.slope_bias * tan;
+
+        let (width, height) = cascade.depth.dimensions();
+        let x = ((ndc.x + 1.0) / 2.0 * width as f32).floor() as i64;
+        let y = ((ndc.y + 1.0) / 2.0 * height as f32).floor() as i64;
+        let radius = self.pcf_radius as i64;
+        let mut lit = 0;
+        for dy in -radius..radius + 1 {
+            for dx in -radius..radius + 1 {
+                let (sx, sy) = (x + dx, y + dy);
+                // Anything outside the map is outside the light's view, and so lit.
+                if sx < 0 || sy < 0 || sx >= width as i64 || sy >= height as i64 ||
+                    depth <= cascade.depth[(sx as u32, sy as u32)]
+                {
+                    lit += 1;
+                }
+            }
+        }
+        lit as f32 / ((2 * radius + 1) * (2 * radius + 1)) as f32
+    }
+}
+
+/// Distances that split the range from `near` to `far` into `count` cascades, blending between
+/// uniform and logarithmic spacing by `lambda`.  Returns `count + 1` distances, starting with
+/// `near` and ending with `far`.
+pub fn cascade_splits(near: f32, far: f32, count: u32, lambda: f32) -> Vec<f32> {
+    let count = count.max(1);
+    (0..count + 1)
+        .map(|i| {
+            let t = i as f32 / count as f32;
+            let logarithmic = near * (far / near).powf(t);
+            let uniform = near + (far - near) * t;
+            lambda * logarithmic + (1.0 - lambda) * uniform
+        })
+        .collect()
+}
+
+/// The eight corners of the part of a view frustum between the distances `near` and `far` along
+/// the view direction, in view space.  `projection` must be invertible.
+pub fn frustum_slice(projection: &Matrix4<f32>, near: f32, far: f32) -> Option<[Vector3<f32>; 8]> {
+    let inverse = projection.inverse()?;
+    let mut corners = [Vector3::splat(0.0); 8];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let x = if i & 1 == 0 { -1.0 } else { 1.0 };
+        let y = if i & 2 == 0 { -1.0 } else { 1.0 };
+        let unproject = |z| {
+            let v = inverse * Vector4::new(x, y, z, 1.0);
+            v.vec3() / v.w
+        };
+        // Points along the ray through the near and far plane corners.
+        let (a, b) = (unproject(-1.0), unproject(1.0));
+        let distance = if i & 4 == 0 { near } else { far };
+        let t = (distance + a.z) / (a.z - b.z);
+        *corner = a.lerp(b, t);
+    }
+    Some(corners)
+}
+
+/// An orthographic projection for a directional light covering `points` across and every shadow
+/// caster in `casters` in depth, both in the light's view space.  The bounds are snapped to
+/// whole texels of a `resolution` sized map, so shadows do not shimmer as the points move.
+pub fn fit_orthographic(
+    points: &[Vector3<f32>],
+    casters: &Aabb<f32>,
+    resolution: u32,
+) -> Option<Matrix4<f32>> {
+    let bounds = Aabb::from_points(points)?;
+    // A square that fits the points in any orientation keeps the texel size constant.
+    let size = bounds.size().magnitude().max(1e-6);
+    let texel = size / resolution.max(1) as f32;
+    let center = bounds.center();
+    let (x, y) = (
+        (center.x / texel).floor() * texel,
+        (center.y / texel).floor() * texel,
+    );
+    let half = size / 2.0;
+    // Casters between the light and the points shadow them too.
+    let near = -casters.max.z.max(bounds.max.z);
+    let far = -casters.min.z.min(bounds.min.z);
+    if near >= far {
+        return None;
+    }
+    Some(Matrix4::orthographic(x - half, x + half, y - half, y + half, near, far))
+}
@@ -0,0 +1,46 @@
+use num::Float;
+
+use vec3::Vector3;
+use aabb::Aabb;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere<T> {
+    pub center: Vector3<T>,
+    pub radius: T,
+}
+
+impl<T> Sphere<T>
+where
+    T: Float,
+{
+    pub fn new(center: Vector3<T>, radius: T) -> Sphere<T> {
+        Sphere { center, radius }
+    }
+
+    /// A sphere containing every point, centered on their bounding box.  This is not the
+    /// smallest such sphere, but it is cheap and close enough for culling.
+    pub fn from_points(points: &[Vector3<T>]) -> Option<Sphere<T>> {
+        let center = Aabb::from_points(points)?.center();
+        let radius = points
+            .iter()
+            .map(|&p| p.distance_squared(center))
+            .fold(T::zero(), T::max)
+            .sqrt();
+        // Grow by one rounding step so the furthest point is still contained.
+        let radius = radius + radius * T::epsilon();
+        Some(Sphere { center, radius })
+    }
+
+    pub fn contains_point(&self, point: Vector3<T>) -> bool {
+        point.distance_squared(self.center) <= self.radius * self.radius
+    }
+
+    pub fn intersects_sphere(&self, rhs: &Sphere<T>) -> bool {
+        let radius = self.radius + rhs.radius;
+        self.center.distance_squared(rhs.center) <= radius * radius
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb<T>) -> bool {
+        self.contains_point(aabb.closest_point(self.center))
+    }
+}
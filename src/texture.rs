@@ -0,0 +1,84 @@
+use vec2::Vector2;
+use vec4::Vector4;
+use color::{Color, color_to_vec4};
+use image::Image;
+
+/// An image that can be sampled with texture coordinates, holding linear RGBA values.
+///
+/// Texture coordinates run from (0, 0) at the bottom left of the image to (1, 1) at the top
+/// right, and repeat outside that range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Texture {
+    image: Image<Vector4<f32>>,
+}
+
+impl Texture {
+    /// Panics if the image is empty.
+    pub fn new(image: Image<Vector4<f32>>) -> Texture {
+        Texture::try_new(image).expect("a texture needs at least one pixel")
+    }
+
+    /// Returns `None` if the image is empty.
+    pub fn try_new(image: Image<Vector4<f32>>) -> Option<Texture> {
+        if image.width() == 0 || image.height() == 0 {
+            return None;
+        }
+        Some(Texture { image })
+    }
+
+    /// Converts 8-bit colors to linear values.  Images of colors, such as base color maps, are
+    /// stored in sRGB and need `srgb` set; data such as roughness or occlusion is stored as is.
+    /// Alpha is always linear.
+    ///
+    /// Panics if the image is empty.
+    pub fn from_colors(image: &Image<Color>, srgb: bool) -> Texture {
+        Texture::new(linearize(image, srgb))
+    }
+
+    /// Like `from_colors`, but returns `None` if the image is empty.
+    pub fn try_from_colors(image: &Image<Color>, srgb: bool) -> Option<Texture> {
+        Texture::try_new(linearize(image, srgb))
+    }
+
+    pub fn image(&self) -> &Image<Vector4<f32>> {
+        &self.image
+    }
+
+    /// Bilinearly filters the four texels around `uv`.
+    pub fn sample(&self, uv: Vector2<f32>) -> Vector4<f32> {
+        let (width, height) = self.image.dimensions();
+        // Texel centers are at half-integer coordinates.
+        let x = uv.x * width as f32 - 0.5;
+        let y = uv.y * height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let texel = |x: f32, y: f32| {
+            let x = (x as i64).rem_euclid(width as i64) as u32;
+            let y = (y as i64).rem_euclid(height as i64) as u32;
+            self.image[(x, y)]
+        };
+        let bottom = texel(x0, y0).lerp(texel(x0 + 1.0, y0), fx);
+        let top = texel(x0, y0 + 1.0).lerp(texel(x0 + 1.0, y0 + 1.0), fx);
+        bottom.lerp(top, fy)
+    }
+}
+
+fn linearize(image: &Image<Color>, srgb: bool) -> Image<Vector4<f32>> {
+    image.map(|&c| {
+        let v = color_to_vec4(c);
+        if srgb {
+            Vector4::new(srgb_to_linear(v.x), srgb_to_linear(v.y), srgb_to_linear(v.z), v.w)
+        } else {
+            v
+        }
+    })
+}
+
+/// Decodes an sRGB encoded channel from 0 to 1.
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
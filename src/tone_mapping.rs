@@ -0,0 +1,60 @@
+use vec3::Vector3;
+use vec4::Vector4;
+use color::{Color, vec4_to_color};
+use image::Image;
+use renderer::ColorBuffer;
+
+/// How high dynamic range colors are compressed into the displayable range from 0 to 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ToneMapOperator {
+    /// Clips every channel to 1, as 8-bit render targets do.
+    Clamp,
+    /// `c / (1 + c)` per channel, which never quite reaches white.
+    Reinhard,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve, with a toe and a shoulder for more
+    /// contrast than Reinhard.
+    AcesFilmic,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ToneMapping {
+    pub operator: ToneMapOperator,
+    /// Exposure compensation in stops: colors are scaled by two to this power before the operator
+    /// is applied.
+    pub exposure: f32,
+}
+
+impl Default for ToneMapping {
+    fn default() -> ToneMapping {
+        ToneMapping {
+            operator: ToneMapOperator::Clamp,
+            exposure: 0.0,
+        }
+    }
+}
+
+impl ToneMapping {
+    /// Maps a linear color to the range from 0 to 1.
+    pub fn apply(&self, color: Vector3<f32>) -> Vector3<f32> {
+        let color = (color * 2f32.powf(self.exposure)).max(Vector3::splat(0.0));
+        let map = |c: f32| match self.operator {
+            ToneMapOperator::Clamp => c,
+            ToneMapOperator::Reinhard => c / (1.0 + c),
+            ToneMapOperator::AcesFilmic => {
+                (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14)
+            }
+        };
+        Vector3::new(map(color.x), map(color.y), map(color.z))
+            .clamp(Vector3::splat(0.0), Vector3::splat(1.0))
+    }
+
+    /// Converts a color to 8 bits, leaving its alpha alone apart from clamping it.
+    pub fn to_color(&self, color: Vector4<f32>) -> Color {
+        vec4_to_color(self.apply(color.vec3()).extend(color.w))
+    }
+
+    /// Converts a whole render target to a displayable image.
+    pub fn resolve(&self, buffer: &ColorBuffer) -> Image<Color> {
+        buffer.map(|color| self.to_color(color))
+    }
+}
@@ -1,8 +1,7 @@
-use std::ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div, DivAssign, Neg};
-
 use num::Float;
 
 use vec2::Vector2;
+use vec4::Vector4;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct Vector3<T> {
@@ -12,34 +11,22 @@ pub struct Vector3<T> {
 }
 
 impl<T> Vector3<T> {
-    pub fn new(x: T, y: T, z: T) -> Vector3<T> {
-        Vector3 { x, y, z }
-    }
-
     pub fn vec2(self) -> Vector2<T> {
         Vector2 {
             x: self.x,
             y: self.y,
         }
     }
+
+    pub fn extend(self, w: T) -> Vector4<T> {
+        Vector4::new(self.x, self.y, self.z, w)
+    }
 }
 
 impl<T> Vector3<T>
 where
     T: Float,
 {
-    pub fn magnitude_squared(&self) -> T {
-        self.x * self.x + self.y * self.y + self.z * self.z
-    }
-
-    pub fn magnitude(&self) -> T {
-        self.magnitude_squared().sqrt()
-    }
-
-    pub fn normalize(&self) -> Vector3<T> {
-        *self / self.magnitude()
-    }
-
     pub fn cross(&self, rhs: Vector3<T>) -> Vector3<T> {
         Vector3 {
             x: self.y * rhs.z - self.z * rhs.y,
@@ -47,157 +34,38 @@ where
             z: self.x * rhs.y - self.y * rhs.x,
         }
     }
-
-    pub fn dot(&self, rhs: Vector3<T>) -> T {
-        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
-    }
-}
-
-impl<T> Add<Vector3<T>> for Vector3<T>
-where
-    T: Add<T, Output = T>,
-{
-    type Output = Vector3<T>;
-
-    fn add(self, rhs: Vector3<T>) -> Vector3<T> {
-        Vector3 {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-            z: self.z + rhs.z,
-        }
-    }
-}
-
-impl<T> AddAssign<Vector3<T>> for Vector3<T>
-where
-    T: AddAssign<T>,
-{
-    fn add_assign(&mut self, rhs: Vector3<T>) {
-        self.x += rhs.x;
-        self.y += rhs.y;
-        self.z += rhs.z;
-    }
-}
-
-impl<T> Sub<Vector3<T>> for Vector3<T>
-where
-    T: Sub<T, Output = T>,
-{
-    type Output = Vector3<T>;
-
-    fn sub(self, rhs: Vector3<T>) -> Vector3<T> {
-        Vector3 {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-            z: self.z - rhs.z,
-        }
-    }
-}
-
-impl<T> SubAssign<Vector3<T>> for Vector3<T>
-where
-    T: SubAssign<T>,
-{
-    fn sub_assign(&mut self, rhs: Vector3<T>) {
-        self.x -= rhs.x;
-        self.y -= rhs.y;
-        self.z -= rhs.z;
-    }
-}
-
-impl<T> Mul<T> for Vector3<T>
-where
-    T: Copy + Mul<T, Output = T>,
-{
-    type Output = Vector3<T>;
-
-    fn mul(self, rhs: T) -> Vector3<T> {
-        Vector3 {
-            x: self.x * rhs,
-            y: self.y * rhs,
-            z: self.z * rhs,
-        }
-    }
-}
-
-impl<T> Mul<Vector3<T>> for Vector3<T>
-where
-    T: Mul<T, Output = T>,
-{
-    type Output = Vector3<T>;
-
-    fn mul(self, rhs: Vector3<T>) -> Vector3<T> {
-        Vector3 {
-            x: self.x * rhs.x,
-            y: self.y * rhs.y,
-            z: self.z * rhs.z,
-        }
-    }
-}
-
-impl<T> MulAssign<Vector3<T>> for Vector3<T>
-where
-    T: MulAssign<T>,
-{
-    fn mul_assign(&mut self, rhs: Vector3<T>) {
-        self.x *= rhs.x;
-        self.y *= rhs.y;
-        self.z *= rhs.z;
-    }
-}
-
-impl<T> Div<T> for Vector3<T>
-where
-    T: Copy + Div<T, Output = T>,
-{
-    type Output = Vector3<T>;
-
-    fn div(self, rhs: T) -> Vector3<T> {
-        Vector3 {
-            x: self.x / rhs,
-            y: self.y / rhs,
-            z: self.z / rhs,
-        }
-    }
-}
-
-impl<T> Div<Vector3<T>> for Vector3<T>
-where
-    T: Div<T, Output = T>,
-{
-    type Output = Vector3<T>;
-
-    fn div(self, rhs: Vector3<T>) -> Vector3<T> {
-        Vector3 {
-            x: self.x / rhs.x,
-            y: self.y / rhs.y,
-            z: self.z / rhs.z,
-        }
-    }
-}
-
-impl<T> DivAssign<Vector3<T>> for Vector3<T>
-where
-    T: DivAssign<T>,
-{
-    fn div_assign(&mut self, rhs: Vector3<T>) {
-        self.x /= rhs.x;
-        self.y /= rhs.y;
-        self.z /= rhs.z;
-    }
 }
 
-impl<T> Neg for Vector3<T>
-where
-    T: Neg<Output = T>,
-{
-    type Output = Vector3<T>;
-
-    fn neg(self) -> Self::Output {
-        Vector3 {
-            x: -self.x,
-            y: -self.y,
-            z: -self.z,
-        }
-    }
-}
+impl_vector!(Vector3, 3, { 0 => x, 1 => y, 2 => z });
+
+impl_swizzles!(Vector3 => Vector2:
+    xx(x, x), xy(x, y), xz(x, z), yx(y, x), yy(y, y), yz(y, z), zx(z, x), zy(z, y), zz(z, z)
+);
+
+impl_swizzles!(Vector3 => Vector3:
+    xxx(x, x, x), xxy(x, x, y), xxz(x, x, z), xyx(x, y, x), xyy(x, y, y), xyz(x, y, z),
+    xzx(x, z, x), xzy(x, z, y), xzz(x, z, z), yxx(y, x, x), yxy(y, x, y), yxz(y, x, z),
+    yyx(y, y, x), yyy(y, y, y), yyz(y, y, z), yzx(y, z, x), yzy(y, z, y), yzz(y, z, z),
+    zxx(z, x, x), zxy(z, x, y), zxz(z, x, z), zyx(z, y, x), zyy(z, y, y), zyz(z, y, z),
+    zzx(z, z, x), zzy(z, z, y), zzz(z, z, z)
+);
+
+impl_swizzles!(Vector3 => Vector4:
+    xxxx(x, x, x, x), xxxy(x, x, x, y), xxxz(x, x, x, z), xxyx(x, x, y, x), xxyy(x, x, y, y),
+    xxyz(x, x, y, z), xxzx(x, x, z, x), xxzy(x, x, z, y), xxzz(x, x, z, z), xyxx(x, y, x, x),
+    xyxy(x, y, x, y), xyxz(x, y, x, z), xyyx(x, y, y, x), xyyy(x, y, y, y), xyyz(x, y, y, z),
+    xyzx(x, y, z, x), xyzy(x, y, z, y), xyzz(x, y, z, z), xzxx(x, z, x, x), xzxy(x, z, x, y),
+    xzxz(x, z, x, z), xzyx(x, z, y, x), xzyy(x, z, y, y), xzyz(x, z, y, z), xzzx(x, z, z, x),
+    xzzy(x, z, z, y), xzzz(x, z, z, z), yxxx(y, x, x, x), yxxy(y, x, x, y), yxxz(y, x, x, z),
+    yxyx(y, x, y, x), yxyy(y, x, y, y), yxyz(y, x, y, z), yxzx(y, x, z, x), yxzy(y, x, z, y),
+    yxzz(y, x, z, z), yyxx(y, y, x, x), yyxy(y, y, x, y), yyxz(y, y, x, z), yyyx(y, y, y, x),
+    yyyy(y, y, y, y), yyyz(y, y, y, z), yyzx(y, y, z, x), yyzy(y, y, z, y), yyzz(y, y, z, z),
+    yzxx(y, z, x, x), yzxy(y, z, x, y), yzxz(y, z, x, z), yzyx(y, z, y, x), yzyy(y, z, y, y),
+    yzyz(y, z, y, z), yzzx(y, z, z, x), yzzy(y, z, z, y), yzzz(y, z, z, z), zxxx(z, x, x, x),
+    zxxy(z, x, x, y), zxxz(z, x, x, z), zxyx(z, x, y, x), zxyy(z, x, y, y), zxyz(z, x, y, z),
+    zxzx(z, x, z, x), zxzy(z, x, z, y), zxzz(z, x, z, z), zyxx(z, y, x, x), zyxy(z, y, x, y),
+    zyxz(z, y, x, z), zyyx(z, y, y, x), zyyy(z, y, y, y), zyyz(z, y, y, z), zyzx(z, y, z, x),
+    zyzy(z, y, z, y), zyzz(z, y, z, z), zzxx(z, z, x, x), zzxy(z, z, x, y), zzxz(z, z, x, z),
+    zzyx(z, z, y, x), zzyy(z, z, y, y), zzyz(z, z, y, z), zzzx(z, z, z, x), zzzy(z, z, z, y),
+    zzzz(z, z, z, z)
+);
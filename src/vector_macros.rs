@@ -0,0 +1,337 @@
+// The API shared by `Vector2`, `Vector3` and `Vector4`, written once so that the three types stay
+// consistent.  Each component is listed with its index, e.g.
+//
+//     impl_vector!(Vector3, 3, { 0 => x, 1 => y, 2 => z });
+
+macro_rules! replace_with_t {
+    ($_field:ident) => { T }
+}
+
+macro_rules! impl_vector {
+    ($Vector:ident, $n:expr, { $($i:tt => $field:ident),+ }) => {
+        impl<T> $Vector<T> {
+            pub fn new($($field: T),+) -> $Vector<T> {
+                $Vector { $($field),+ }
+            }
+        }
+
+        impl<T> $Vector<T>
+        where
+            T: Copy,
+        {
+            /// A vector with every component set to `v`.
+            pub fn splat(v: T) -> $Vector<T> {
+                $Vector { $($field: v),+ }
+            }
+        }
+
+        impl<T> $Vector<T>
+        where
+            T: ::num::Float,
+        {
+            pub fn dot(&self, rhs: $Vector<T>) -> T {
+                T::zero() $(+ self.$field * rhs.$field)+
+            }
+
+            pub fn magnitude_squared(&self) -> T {
+                self.dot(*self)
+            }
+
+            pub fn magnitude(&self) -> T {
+                self.magnitude_squared().sqrt()
+            }
+
+            pub fn normalize(&self) -> $Vector<T> {
+                *self / self.magnitude()
+            }
+
+            pub fn distance_squared(&self, rhs: $Vector<T>) -> T {
+                (*self - rhs).magnitude_squared()
+            }
+
+            pub fn distance(&self, rhs: $Vector<T>) -> T {
+                (*self - rhs).magnitude()
+            }
+
+            /// Linear interpolation, `t = 0` gives `self` and `t = 1` gives `rhs`.
+            pub fn lerp(&self, rhs: $Vector<T>, t: T) -> $Vector<T> {
+                *self + (rhs - *self) * t
+            }
+
+            /// The component-wise minimum.
+            pub fn min(&self, rhs: $Vector<T>) -> $Vector<T> {
+                $Vector { $($field: self.$field.min(rhs.$field)),+ }
+            }
+
+            /// The component-wise maximum.
+            pub fn max(&self, rhs: $Vector<T>) -> $Vector<T> {
+                $Vector { $($field: self.$field.max(rhs.$field)),+ }
+            }
+
+            /// Clamps every component between the corresponding components of `min` and `max`.
+            pub fn clamp(&self, min: $Vector<T>, max: $Vector<T>) -> $Vector<T> {
+                self.max(min).min(max)
+            }
+
+            pub fn abs(&self) -> $Vector<T> {
+                $Vector { $($field: self.$field.abs()),+ }
+            }
+
+            pub fn floor(&self) -> $Vector<T> {
+                $Vector { $($field: self.$field.floor()),+ }
+            }
+
+            pub fn ceil(&self) -> $Vector<T> {
+                $Vector { $($field: self.$field.ceil()),+ }
+            }
+
+            pub fn round(&self) -> $Vector<T> {
+                $Vector { $($field: self.$field.round()),+ }
+            }
+
+            /// Reflects an incident direction off a surface with the normalized `normal`.
+            pub fn reflect(&self, normal: $Vector<T>) -> $Vector<T> {
+                *self - normal * (T::from(2).unwrap() * normal.dot(*self))
+            }
+
+            /// Refracts a normalized incident direction through a surface with the normalized
+            /// `normal`, where `eta` is the ratio of the refractive indices.  Returns `None` on
+            /// total internal reflection.
+            pub fn refract(&self, normal: $Vector<T>, eta: T) -> Option<$Vector<T>> {
+                let cos = normal.dot(*self);
+                let k = T::one() - eta * eta * (T::one() - cos * cos);
+                if k < T::zero() {
+                    return None;
+                }
+                Some(*self * eta - normal * (eta * cos + k.sqrt()))
+            }
+        }
+
+        impl<T> ::std::ops::Index<usize> for $Vector<T> {
+            type Output = T;
+
+            fn index(&self, index: usize) -> &T {
+                match index {
+                    $($i => &self.$field,)+
+                    _ => panic!("index {} is out of range for a {}", index, stringify!($Vector)),
+                }
+            }
+        }
+
+        impl<T> ::std::ops::IndexMut<usize> for $Vector<T> {
+            fn index_mut(&mut self, index: usize) -> &mut T {
+                match index {
+                    $($i => &mut self.$field,)+
+                    _ => panic!("index {} is out of range for a {}", index, stringify!($Vector)),
+                }
+            }
+        }
+
+        impl<T> From<[T; $n]> for $Vector<T> {
+            fn from(array: [T; $n]) -> $Vector<T> {
+                let [$($field),+] = array;
+                $Vector { $($field),+ }
+            }
+        }
+
+        impl<T> From<$Vector<T>> for [T; $n] {
+            fn from(v: $Vector<T>) -> [T; $n] {
+                [$(v.$field),+]
+            }
+        }
+
+        impl<T> From<($(replace_with_t!($field)),+)> for $Vector<T> {
+            fn from(tuple: ($(replace_with_t!($field)),+)) -> $Vector<T> {
+                let ($($field),+) = tuple;
+                $Vector { $($field),+ }
+            }
+        }
+
+        impl<T> From<$Vector<T>> for ($(replace_with_t!($field)),+) {
+            fn from(v: $Vector<T>) -> ($(replace_with_t!($field)),+) {
+                ($(v.$field),+)
+            }
+        }
+
+        impl<T> ::std::iter::Sum for $Vector<T>
+        where
+            T: ::num::Zero + ::std::ops::Add<T, Output = T>,
+        {
+            fn sum<I: Iterator<Item = $Vector<T>>>(iter: I) -> $Vector<T> {
+                iter.fold($Vector { $($field: T::zero()),+ }, |a, b| a + b)
+            }
+        }
+
+        impl<'a, T> ::std::iter::Sum<&'a $Vector<T>> for $Vector<T>
+        where
+            T: 'a + Copy + ::num::Zero + ::std::ops::Add<T, Output = T>,
+        {
+            fn sum<I: Iterator<Item = &'a $Vector<T>>>(iter: I) -> $Vector<T> {
+                iter.cloned().sum()
+            }
+        }
+
+        impl<T> ::std::ops::Add<$Vector<T>> for $Vector<T>
+        where
+            T: ::std::ops::Add<T, Output = T>,
+        {
+            type Output = $Vector<T>;
+
+            fn add(self, rhs: $Vector<T>) -> $Vector<T> {
+                $Vector { $($field: self.$field + rhs.$field),+ }
+            }
+        }
+
+        impl<T> ::std::ops::AddAssign<$Vector<T>> for $Vector<T>
+        where
+            T: ::std::ops::AddAssign<T>,
+        {
+            fn add_assign(&mut self, rhs: $Vector<T>) {
+                $(self.$field += rhs.$field;)+
+            }
+        }
+
+        impl<T> ::std::ops::Sub<$Vector<T>> for $Vector<T>
+        where
+            T: ::std::ops::Sub<T, Output = T>,
+        {
+            type Output = $Vector<T>;
+
+            fn sub(self, rhs: $Vector<T>) -> $Vector<T> {
+                $Vector { $($field: self.$field - rhs.$field),+ }
+            }
+        }
+
+        impl<T> ::std::ops::SubAssign<$Vector<T>> for $Vector<T>
+        where
+            T: ::std::ops::SubAssign<T>,
+        {
+            fn sub_assign(&mut self, rhs: $Vector<T>) {
+                $(self.$field -= rhs.$field;)+
+            }
+        }
+
+        impl<T> ::std::ops::Mul<T> for $Vector<T>
+        where
+            T: Copy + ::std::ops::Mul<T, Output = T>,
+        {
+            type Output = $Vector<T>;
+
+            fn mul(self, rhs: T) -> $Vector<T> {
+                $Vector { $($field: self.$field * rhs),+ }
+            }
+        }
+
+        impl<T> ::std::ops::Mul<$Vector<T>> for $Vector<T>
+        where
+            T: ::std::ops::Mul<T, Output = T>,
+        {
+            type Output = $Vector<T>;
+
+            fn mul(self, rhs: $Vector<T>) -> $Vector<T> {
+                $Vector { $($field: self.$field * rhs.$field),+ }
+            }
+        }
+
+        impl<T> ::std::ops::MulAssign<T> for $Vector<T>
+        where
+            T: Copy + ::std::ops::MulAssign<T>,
+        {
+            fn mul_assign(&mut self, rhs: T) {
+                $(self.$field *= rhs;)+
+            }
+        }
+
+        impl<T> ::std::ops::MulAssign<$Vector<T>> for $Vector<T>
+        where
+            T: ::std::ops::MulAssign<T>,
+        {
+            fn mul_assign(&mut self, rhs: $Vector<T>) {
+                $(self.$field *= rhs.$field;)+
+            }
+        }
+
+        impl<T> ::std::ops::Div<T> for $Vector<T>
+        where
+            T: Copy + ::std::ops::Div<T, Output = T>,
+        {
+            type Output = $Vector<T>;
+
+            fn div(self, rhs: T) -> $Vector<T> {
+                $Vector { $($field: self.$field / rhs),+ }
+            }
+        }
+
+        impl<T> ::std::ops::Div<$Vector<T>> for $Vector<T>
+        where
+            T: ::std::ops::Div<T, Output = T>,
+        {
+            type Output = $Vector<T>;
+
+            fn div(self, rhs: $Vector<T>) -> $Vector<T> {
+                $Vector { $($field: self.$field / rhs.$field),+ }
+            }
+        }
+
+        impl<T> ::std::ops::DivAssign<T> for $Vector<T>
+        where
+            T: Copy + ::std::ops::DivAssign<T>,
+        {
+            fn div_assign(&mut self, rhs: T) {
+                $(self.$field /= rhs;)+
+            }
+        }
+
+        impl<T> ::std::ops::DivAssign<$Vector<T>> for $Vector<T>
+        where
+            T: ::std::ops::DivAssign<T>,
+        {
+            fn div_assign(&mut self, rhs: $Vector<T>) {
+                $(self.$field /= rhs.$field;)+
+            }
+        }
+
+        impl<T> ::std::ops::Neg for $Vector<T>
+        where
+            T: ::std::ops::Neg<Output = T>,
+        {
+            type Output = $Vector<T>;
+
+            fn neg(self) -> $Vector<T> {
+                $Vector { $($field: -self.$field),+ }
+            }
+        }
+
+        impl_scalar_mul!($Vector, f32, { $($field),+ });
+        impl_scalar_mul!($Vector, f64, { $($field),+ });
+    };
+}
+
+// `scalar * vector`, which cannot be implemented generically.
+macro_rules! impl_scalar_mul {
+    ($Vector:ident, $T:ty, { $($field:ident),+ }) => {
+        impl ::std::ops::Mul<$Vector<$T>> for $T {
+            type Output = $Vector<$T>;
+
+            fn mul(self, rhs: $Vector<$T>) -> $Vector<$T> {
+                $Vector { $($field: self * rhs.$field),+ }
+            }
+        }
+    };
+}
+
+// Swizzles such as `v.zyx()`, each listed with the components it reads.
+macro_rules! impl_swizzles {
+    ($Vector:ident => $Output:ident: $($name:ident($($field:ident),+)),+) => {
+        impl<T> $Vector<T>
+        where
+            T: Copy,
+        {
+            $(
+                pub fn $name(&self) -> $Output<T> {
+                    $Output::new($(self.$field),+)
+                }
+            )+
+        }
+    };
+}
@@ -0,0 +1,90 @@
+extern crate rrasterizer;
+
+use rrasterizer::vec2::Vector2;
+use rrasterizer::bound_rect::BoundRect;
+
+#[test]
+fn measurements() {
+    let r = BoundRect::from_bounds(1.0, 2.0, 5.0, 3.5);
+    assert_eq!(r.width(), 4.0);
+    assert_eq!(r.height(), 1.5);
+    assert_eq!(r.area(), 6.0);
+    assert_eq!(r.center(), Vector2::new(3.0, 2.75));
+    assert_eq!(r.expand(1.0), BoundRect::from_bounds(0.0, 1.0, 6.0, 4.5));
+    assert_eq!(r.expand(-0.5), BoundRect::from_bounds(1.5, 2.5, 4.5, 3.0));
+    assert!(r.expand(-1.0).is_empty());
+
+    let pixels = BoundRect::from_bounds(2u32, 3, 10, 7);
+    assert_eq!(pixels.area(), 32);
+}
+
+#[test]
+fn union_and_containment() {
+    let a = BoundRect::from_bounds(0, 0, 4, 4);
+    let b = BoundRect::from_bounds(2, -1, 6, 3);
+    assert_eq!(a.union(b), BoundRect::from_bounds(0, -1, 6, 4));
+    assert_eq!(a.intersection(b), BoundRect::from_bounds(2, 0, 4, 3));
+    assert!(a.union(b).contains_rect(&a));
+    assert!(a.union(b).contains_rect(&b));
+    assert!(!a.contains_rect(&b));
+    assert!(a.contains_rect(&a));
+
+    // The maximum edges are excluded.
+    assert!(a.contains_point(Vector2::new(0, 0)));
+    assert!(a.contains_point(Vector2::new(3, 3)));
+    assert!(!a.contains_point(Vector2::new(4, 3)));
+    assert!(!a.contains_point(Vector2::new(3, 4)));
+    assert!(!a.contains_point(Vector2::new(-1, 0)));
+}
+
+#[test]
+fn rounding() {
+    let r = BoundRect::from_bounds(-1.5f32, 0.25, 2.5, 3.75);
+    assert_eq!(r.round_out(), Some(BoundRect::from_bounds(-2, 0, 3, 4)));
+    assert_eq!(r.round_in(), Some(BoundRect::from_bounds(-1, 1, 2, 3)));
+    assert_eq!(r.round(), Some(BoundRect::from_bounds(-2, 0, 3, 4)));
+    assert_eq!(r.cast(), Some(BoundRect::from_bounds(-1, 0, 2, 3)));
+
+    // Negative bounds do not fit in an unsigned rectangle.
+    assert_eq!(r.round_out::<u32>(), None);
+    assert_eq!(
+        BoundRect::from_bounds(0.5f32, 0.5, 1.5, 1.5).round_out::<u32>(),
+        Some(BoundRect::from_bounds(0, 0, 2, 2))
+    );
+    assert_eq!(
+        BoundRect::from_bounds(f32::NAN, 0.0, 1.0, 1.0).round_out::<u32>(),
+        None
+    );
+
+    let back = BoundRect::from_bounds(1u32, 2, 3, 4).cast::<f32>().unwrap();
+    assert_eq!(back, BoundRect::from_bounds(1.0, 2.0, 3.0, 4.0));
+}
+
+#[test]
+fn pixel_iteration() {
+    let r = BoundRect::from_bounds(1u32, 2, 4, 4);
+    let pixels: Vec<_> = r.pixels().map(|p| (p.x, p.y)).collect();
+    assert_eq!(
+        pixels,
+        vec![(1, 2), (2, 2), (3, 2), (1, 3), (2, 3), (3, 3)]
+    );
+
+    let mut iter = r.pixels();
+    assert_eq!(iter.size_hint(), (6, Some(6)));
+    iter.next();
+    iter.next();
+    assert_eq!(iter.size_hint(), (4, Some(4)));
+    assert_eq!(iter.count(), 4);
+
+    assert_eq!(BoundRect::from_bounds(0, 0, 0, 5).pixels().count(), 0);
+    assert_eq!(BoundRect::from_bounds(3, 3, 1, 5).pixels().count(), 0);
+    assert_eq!(BoundRect::from_bounds(-2i32, -1, 0, 0).pixels().count(), 2);
+
+    // Every pixel of a rectangle is contained in it.
+    for p in r.pixels() {
+        assert!(r.contains_point(p));
+    }
+    // Iterating up to the largest value does not overflow.
+    let edge = BoundRect::from_bounds(254u8, 254, 255, 255);
+    assert_eq!(edge.pixels().count(), 1);
+}
@@ -0,0 +1,138 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod approx;
+}
+
+use std::f32;
+
+use rrasterizer::vec2::Vector2;
+use rrasterizer::vec3::Vector3;
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::camera_control::{OrbitCamera, FlyCamera, CameraController, CameraMode};
+
+use common::approx::assert_close;
+
+fn origin(m: &Matrix4<f32>) -> Vector3<f32> {
+    m.transform(Vector3::splat(0.0))
+}
+
+fn forward(m: &Matrix4<f32>) -> Vector3<f32> {
+    m.transform_vector(Vector3::new(0.0, 0.0, -1.0))
+}
+
+#[test]
+fn orbit_from_transform() {
+    let transform = Matrix4::translation(Vector3::new(1.0, 2.0, 3.0)) *
+        Matrix4::rotation(Vector3::new(0.3, -0.5, 0.0));
+    let orbit = OrbitCamera::from_transform(&transform, 4.0);
+    assert_close(orbit.transform(), transform, 1e-4);
+    assert_close(orbit.target, origin(&transform) + forward(&transform) * 4.0, 1e-4);
+    assert_close(orbit.position(), origin(&transform), 1e-4);
+}
+
+#[test]
+fn orbit_rotation() {
+    let mut orbit = OrbitCamera::from_transform(&Matrix4::identity(), 5.0);
+    let target = orbit.target;
+
+    // Dragging right turns the scene right, so the camera circles to the left.
+    orbit.rotate(Vector2::new(0.0, 0.0), Vector2::new(0.5, 0.0));
+    let transform = orbit.transform();
+    assert!(origin(&transform).x < -1.0);
+    assert_close(orbit.target, target, 1e-4);
+    assert!(((origin(&transform) - target).magnitude() - 5.0).abs() < 1e-4);
+    // The camera keeps looking at the target.
+    assert_close(origin(&transform) + forward(&transform) * 5.0, target, 1e-4);
+
+    // Dragging back undoes the rotation.
+    orbit.rotate(Vector2::new(0.5, 0.0), Vector2::new(0.0, 0.0));
+    assert_close(orbit.transform(), Matrix4::identity(), 1e-4);
+
+    // Dragging up moves the camera below the target.
+    orbit.rotate(Vector2::new(0.0, -0.3), Vector2::new(0.0, 0.3));
+    assert!(orbit.position().y < -1.0);
+
+    // Drags outside the ball and empty drags are handled.
+    orbit.rotate(Vector2::new(-3.0, 0.0), Vector2::new(0.0, 3.0));
+    orbit.rotate(Vector2::new(0.2, 0.2), Vector2::new(0.2, 0.2));
+    assert!((orbit.rotation.magnitude() - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn orbit_zoom_and_pan() {
+    let mut orbit = OrbitCamera::from_transform(&Matrix4::identity(), 5.0);
+    orbit.zoom(2.0);
+    assert!(orbit.distance < 5.0);
+    orbit.zoom(-2.0);
+    assert!((orbit.distance - 5.0).abs() < 1e-4);
+    orbit.zoom(1000.0);
+    assert!(orbit.distance > 0.0);
+
+    let mut orbit = OrbitCamera::from_transform(&Matrix4::identity(), 5.0);
+    orbit.pan(Vector2::new(0.2, -0.1));
+    assert_close(orbit.target, Vector3::new(-1.0, 0.5, -5.0), 1e-4);
+    assert_close(orbit.position(), Vector3::new(-1.0, 0.5, 0.0), 1e-4);
+}
+
+#[test]
+fn fly_camera() {
+    let transform = Matrix4::translation(Vector3::new(1.0, 2.0, 3.0)) *
+        Matrix4::rotation(Vector3::new(0.3, 2.5, 0.0));
+    let mut fly = FlyCamera::from_transform(&transform);
+    assert_close(fly.transform(), transform, 1e-4);
+
+    fly.travel(Vector3::new(0.0, 0.0, -2.0));
+    assert_close(fly.position, origin(&transform) + forward(&transform) * 2.0, 1e-4);
+
+    // Turning left from looking down -z looks towards -x.
+    let mut fly = FlyCamera::from_transform(&Matrix4::identity());
+    fly.look(Vector2::new(f32::consts::PI / 2.0, 0.0));
+    assert_close(forward(&fly.transform()), Vector3::new(-1.0, 0.0, 0.0), 1e-4);
+
+    // Looking up stops short of vertical.
+    fly.look(Vector2::new(0.0, 10.0));
+    let f = forward(&fly.transform());
+    assert!(f.y > 0.99 && f.y < 1.0);
+    fly.look(Vector2::new(0.0, -20.0));
+    assert!(forward(&fly.transform()).y < -0.99);
+}
+
+#[test]
+fn controller() {
+    let start = Matrix4::translation(Vector3::new(0.0, 1.0, 0.0));
+    let mut controller = CameraController::new(&start, 5.0);
+    assert_eq!(controller.mode(), CameraMode::Orbit);
+    assert_close(controller.transform(), start, 1e-4);
+
+    controller.drag(Vector2::new(0.0, 0.0), Vector2::new(0.4, 0.1));
+    controller.zoom(1.0);
+    let orbiting = controller.transform();
+
+    // Switching modes keeps the position and view direction, dropping the roll.
+    controller.toggle_mode();
+    assert_eq!(controller.mode(), CameraMode::Fly);
+    assert_close(origin(&controller.transform()), origin(&orbiting), 1e-4);
+    assert_close(forward(&controller.transform()), forward(&orbiting), 1e-4);
+
+    controller.travel(Vector3::new(0.0, 0.0, -1.0), 0.5);
+    let flying = controller.transform();
+    assert_close(origin(&flying), origin(&orbiting) + forward(&orbiting) * 2.5, 1e-4);
+    controller.drag(Vector2::new(0.0, 0.0), Vector2::new(0.1, 0.0));
+    controller.set_mode(CameraMode::Orbit);
+    let f = forward(&controller.transform());
+    assert_close(controller.orbit().target, origin(&controller.transform()) + f * 4.545454, 1e-4);
+
+    // Orbiting, travelling moves the target too.
+    let target = controller.orbit().target;
+    let right = controller.transform().transform_vector(Vector3::new(1.0, 0.0, 0.0));
+    controller.travel(Vector3::new(2.0, 0.0, 0.0), 1.0);
+    assert_close(controller.orbit().target, target + right * 5.0, 1e-4);
+    controller.travel(Vector3::splat(0.0), 1.0);
+
+    controller.toggle_mode();
+    controller.reset();
+    assert_eq!(controller.mode(), CameraMode::Orbit);
+    assert_close(controller.transform(), start, 1e-4);
+    assert_close(controller.orbit().target, Vector3::new(0.0, 1.0, -5.0), 1e-4);
+}
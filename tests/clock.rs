@@ -0,0 +1,98 @@
+extern crate rrasterizer;
+
+use std::thread;
+use std::time::Duration;
+
+use rrasterizer::application::{Application, FRAME_TIME};
+use rrasterizer::clock::{Clock, RealTimeClock, SimulatedClock, FixedTimestep, MAX_FRAME_TIME};
+
+// Replays a fixed list of frame times.
+struct Replay(Vec<f64>);
+
+impl Clock for Replay {
+    fn tick(&mut self) -> f64 {
+        self.0.remove(0)
+    }
+}
+
+#[test]
+fn simulated_clock() {
+    let mut clock = SimulatedClock::new(0.5);
+    assert_eq!(clock.tick(), 0.5);
+    assert_eq!(clock.tick(), 0.5);
+    clock.step = 0.25;
+    assert_eq!(clock.tick(), 0.25);
+}
+
+#[test]
+fn real_time_clock() {
+    let mut clock = RealTimeClock::new();
+    thread::sleep(Duration::from_millis(10));
+    let t = clock.tick();
+    assert!((0.01..=MAX_FRAME_TIME).contains(&t));
+
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(clock.tick(), MAX_FRAME_TIME);
+}
+
+#[test]
+fn fixed_timestep() {
+    let mut clock = FixedTimestep::new(Replay(vec![0.3, 0.3, 0.05, 0.6, 0.0]), 0.25);
+    assert_eq!(clock.tick(), 0.25);
+    assert!((clock.alpha() - 0.2).abs() < 1e-9);
+    assert_eq!(clock.tick(), 0.25);
+    assert_eq!(clock.tick(), 0.0);
+    assert!((clock.alpha() - 0.6).abs() < 1e-9);
+    assert_eq!(clock.tick(), 0.75);
+    assert_eq!(clock.tick(), 0.0);
+    assert!(clock.alpha() < 1e-9);
+    assert_eq!(clock.step(), 0.25);
+}
+
+#[test]
+fn frames_are_reproducible() {
+    let mut a = Application::new(80, 60);
+    let mut b = Application::new(80, 60);
+    for _ in 0..10 {
+        a.render();
+        b.render();
+        assert_eq!(a.framebuffer(), b.framebuffer());
+    }
+    assert!((a.time() - 10.0 * FRAME_TIME).abs() < 1e-9);
+    assert_eq!(a.frame_time(), FRAME_TIME);
+}
+
+#[test]
+fn animation_is_frame_rate_independent() {
+    let mut fast = Application::new(80, 60);
+    fast.set_clock(Box::new(SimulatedClock::new(0.0125)));
+    let mut slow = Application::new(80, 60);
+    slow.set_clock(Box::new(SimulatedClock::new(0.05)));
+
+    for _ in 0..4 {
+        fast.render();
+    }
+    slow.render();
+    assert_eq!(fast.time(), slow.time());
+    assert_eq!(fast.framebuffer(), slow.framebuffer());
+}
+
+#[test]
+fn pausing_stops_the_animation() {
+    let mut application = Application::new(80, 60);
+    application.set_clock(Box::new(SimulatedClock::new(0.1)));
+    application.render();
+    let frame = application.framebuffer().clone();
+
+    application.set_paused(true);
+    application.render();
+    application.render();
+    assert_eq!(application.time(), 0.1);
+    assert_eq!(application.frame_time(), 0.1);
+    assert_eq!(application.framebuffer(), &frame);
+
+    application.set_paused(false);
+    application.render();
+    assert_eq!(application.time(), 0.2);
+    assert!(application.framebuffer() != &frame);
+}
@@ -0,0 +1,66 @@
+// Comparisons that allow for rounding errors, shared by the integration tests.
+
+use std::fmt::Debug;
+
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::mat4::Matrix4;
+
+/// Values with a distance between them.
+pub trait Approx: Copy + Debug {
+    type Distance: PartialOrd + Debug;
+
+    fn distance(self, other: Self) -> Self::Distance;
+}
+
+macro_rules! impl_approx {
+    ($t:ident) => {
+        impl Approx for $t {
+            type Distance = $t;
+
+            fn distance(self, other: $t) -> $t {
+                (self - other).abs()
+            }
+        }
+
+        impl Approx for Vector3<$t> {
+            type Distance = $t;
+
+            fn distance(self, other: Vector3<$t>) -> $t {
+                (self - other).magnitude()
+            }
+        }
+
+        impl Approx for Matrix4<$t> {
+            type Distance = $t;
+
+            /// The largest distance between corresponding columns.
+            fn distance(self, other: Matrix4<$t>) -> $t {
+                let mut distance: $t = 0.0;
+                for &v in &[
+                    Vector4::new(1.0, 0.0, 0.0, 0.0),
+                    Vector4::new(0.0, 1.0, 0.0, 0.0),
+                    Vector4::new(0.0, 0.0, 1.0, 0.0),
+                    Vector4::new(0.0, 0.0, 0.0, 1.0),
+                ]
+                {
+                    distance = distance.max((self * v - other * v).magnitude());
+                }
+                distance
+            }
+        }
+    };
+}
+
+impl_approx!(f32);
+impl_approx!(f64);
+
+pub fn assert_close<T: Approx>(actual: T, expected: T, tolerance: T::Distance) {
+    assert!(
+        actual.distance(expected) <= tolerance,
+        "{:?} != {:?} (tolerance {:?})",
+        actual,
+        expected,
+        tolerance
+    );
+}
@@ -0,0 +1,13 @@
+// A light fixture shared by the integration tests.
+
+use rrasterizer::vec3::Vector3;
+use rrasterizer::lighting::{Light, LightKind};
+
+/// A white light of unit intensity.
+pub fn white_light(kind: LightKind) -> Light {
+    Light {
+        kind,
+        color: Vector3::splat(1.0),
+        intensity: 1.0,
+    }
+}
@@ -0,0 +1,31 @@
+// A mesh fixture shared by the integration tests.
+
+use rrasterizer::vec2::Vector2;
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::renderer::{Vertex, Triangle};
+
+/// A square facing the camera, filling [-1, 1] on the x and y axes, with vertex normals given by
+/// `normal` and texture coordinates covering [0, 1].
+pub fn square(z: f32, color: Vector4<f32>, normal: Vector3<f32>) -> Vec<Triangle> {
+    let vertex = |x: f32, y: f32| {
+        Vertex {
+            position: Vector3::new(x, y, z),
+            color,
+            normal,
+            tex_coord: Vector2::new((x + 1.0) / 2.0, (y + 1.0) / 2.0),
+        }
+    };
+    vec![
+        Triangle {
+            a: vertex(-1.0, -1.0),
+            b: vertex(1.0, -1.0),
+            c: vertex(1.0, 1.0),
+        },
+        Triangle {
+            a: vertex(-1.0, -1.0),
+            b: vertex(1.0, 1.0),
+            c: vertex(-1.0, 1.0),
+        },
+    ]
+}
@@ -0,0 +1,15 @@
+// A renderer fixture shared by the integration tests.
+
+use std::f32;
+
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::renderer::Renderer;
+
+/// The width and height of `renderer`.
+pub const SIZE: u32 = 64;
+
+/// A `SIZE` by `SIZE` renderer with a 60 degree field of view.
+pub fn renderer() -> Renderer {
+    let projection = Matrix4::perspective_fov(f32::consts::PI / 3.0, 1.0, 0.1, 100.0);
+    Renderer::new(SIZE, SIZE, projection)
+}
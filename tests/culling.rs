@@ -0,0 +1,136 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod render;
+}
+
+use std::f32;
+
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::color::Color;
+use rrasterizer::mesh::Mesh;
+use rrasterizer::renderer::{Bounds, Renderer, RenderStats};
+
+const BLACK: Color = Color(0, 0, 0, 255);
+
+fn cube() -> Mesh {
+    let mut mesh = Mesh::new();
+    for i in 0..8 {
+        mesh.positions.push(Vector3::new(
+            if i & 1 == 0 { -1.0 } else { 1.0 },
+            if i & 2 == 0 { -1.0 } else { 1.0 },
+            if i & 4 == 0 { -1.0 } else { 1.0 },
+        ));
+    }
+    mesh.indices = vec![
+        0, 2, 1, 1, 2, 3, 4, 5, 6, 5, 7, 6, 0, 1, 4, 1, 5, 4, 2, 6, 3, 3, 6, 7, 0, 4, 2, 2, 4,
+        6, 1, 3, 5, 3, 7, 5,
+    ];
+    mesh
+}
+
+fn renderer() -> Renderer {
+    let mut renderer = common::render::renderer();
+    renderer.clear(BLACK);
+    renderer
+}
+
+fn is_blank(renderer: &Renderer) -> bool {
+    renderer.framebuffer().pixels().iter().all(|&p| p == BLACK)
+}
+
+#[test]
+fn mesh_bounds() {
+    let bounds = cube().bounds().unwrap();
+    assert_eq!(bounds.aabb.min, Vector3::splat(-1.0));
+    assert_eq!(bounds.aabb.max, Vector3::splat(1.0));
+    assert_eq!(bounds.sphere.center, Vector3::splat(0.0));
+    assert!((bounds.sphere.radius - 3.0f32.sqrt()).abs() < 1e-5);
+    assert_eq!(Mesh::new().bounds(), None);
+
+    let triangles = cube().triangles(Vector4::splat(1.0));
+    assert_eq!(Bounds::from_triangles(&triangles), cube().bounds());
+}
+
+#[test]
+fn visible_objects_are_drawn() {
+    let mesh = cube();
+    let bounds = mesh.bounds().unwrap();
+    let triangles = mesh.triangles(Vector4::splat(1.0));
+    let mut renderer = renderer();
+
+    let in_front = Matrix4::translation(Vector3::new(0.0, 0.0, -5.0));
+    assert!(renderer.render_culled(in_front, &bounds, &triangles));
+    assert!(!is_blank(&renderer));
+
+    // Only a corner pokes into the view, but the object must still be drawn.
+    let edge = Matrix4::translation(Vector3::new(3.5, 0.0, -5.0));
+    assert!(renderer.render_culled(edge, &bounds, &triangles));
+    assert_eq!(
+        renderer.stats(),
+        RenderStats {
+            objects_drawn: 2,
+            objects_culled: 0,
+        }
+    );
+}
+
+#[test]
+fn invisible_objects_are_culled() {
+    let mesh = cube();
+    let bounds = mesh.bounds().unwrap();
+    let triangles = mesh.triangles(Vector4::splat(1.0));
+    let mut renderer = renderer();
+
+    let placements = [
+        Vector3::new(0.0, 0.0, 5.0),
+        Vector3::new(20.0, 0.0, -5.0),
+        Vector3::new(0.0, -20.0, -5.0),
+        Vector3::new(0.0, 0.0, -200.0),
+    ];
+    for &p in &placements {
+        assert!(!renderer.render_culled(Matrix4::translation(p), &bounds, &triangles));
+    }
+    // Scaled down, the cube fits between the camera and the near plane.
+    let tiny = Matrix4::translation(Vector3::new(0.0, 0.0, -0.05)) *
+        Matrix4::scaling(Vector3::splat(0.01));
+    assert!(!renderer.render_culled(tiny, &bounds, &triangles));
+
+    assert!(is_blank(&renderer));
+    assert_eq!(
+        renderer.stats(),
+        RenderStats {
+            objects_drawn: 0,
+            objects_culled: 5,
+        }
+    );
+
+    // Statistics are per frame.
+    renderer.clear(BLACK);
+    assert_eq!(renderer.stats(), RenderStats::default());
+}
+
+#[test]
+fn culling_matches_rendering() {
+    // Culling is conservative, so anything culled must be invisible when rendered anyway.
+    let mesh = cube();
+    let bounds = mesh.bounds().unwrap();
+    let triangles = mesh.triangles(Vector4::splat(1.0));
+
+    for i in 0..64 {
+        let angle = i as f32 * f32::consts::PI / 32.0;
+        let position = Vector3::new(angle.sin() * 8.0, (i % 5) as f32 - 2.0, angle.cos() * 8.0);
+        let transformation = Matrix4::translation(position);
+
+        let mut culled = renderer();
+        let drawn = culled.render_culled(transformation, &bounds, &triangles);
+        let mut reference = renderer();
+        reference.render(transformation, &triangles);
+
+        if !drawn {
+            assert!(is_blank(&reference), "{:?} was culled but is visible", position);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod approx;
+}
+
+use std::f64::consts::PI;
+
+use rrasterizer::vec2::Vector2;
+use rrasterizer::vec3::Vector3;
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::plane::Plane;
+use rrasterizer::ray::Ray;
+use rrasterizer::sphere::Sphere;
+use rrasterizer::aabb::Aabb;
+use rrasterizer::frustum::Frustum;
+
+use common::approx::assert_close;
+
+const EPSILON: f64 = 1e-9;
+
+fn unit_box() -> Aabb<f64> {
+    Aabb::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0))
+}
+
+#[test]
+fn planes() {
+    let plane = Plane::from_point_normal(Vector3::new(0.0, 2.0, 0.0), Vector3::new(0.0, 3.0, 0.0));
+    assert_eq!(plane.normal, Vector3::new(0.0, 1.0, 0.0));
+    assert_eq!(plane.signed_distance(Vector3::new(5.0, 5.0, 5.0)), 3.0);
+    assert_eq!(plane.signed_distance(Vector3::new(0.0, -1.0, 0.0)), -3.0);
+    assert_close(
+        plane.project(Vector3::new(1.0, 7.0, -2.0)),
+        Vector3::new(1.0, 2.0, -2.0),
+        EPSILON,
+    );
+
+    let ccw = Plane::from_points(
+        Vector3::new(0.0, 0.0, 1.0),
+        Vector3::new(1.0, 0.0, 1.0),
+        Vector3::new(0.0, 1.0, 1.0),
+    ).unwrap();
+    assert_close(ccw.normal, Vector3::new(0.0, 0.0, 1.0), EPSILON);
+    assert_close(ccw.distance, -1.0, EPSILON);
+    assert_eq!(
+        Plane::from_points(
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 1.0, 1.0),
+            Vector3::new(2.0, 2.0, 2.0),
+        ),
+        None
+    );
+
+    let scaled = Plane::new(Vector3::new(0.0, 0.0, 2.0), 4.0).normalize();
+    assert_eq!(scaled, Plane::new(Vector3::new(0.0, 0.0, 1.0), 2.0));
+}
+
+#[test]
+fn ray_intersections() {
+    let ray = Ray::new(Vector3::new(0.0, 0.0, 10.0), Vector3::new(0.0, 0.0, -2.0));
+    assert_eq!(ray.direction, Vector3::new(0.0, 0.0, -1.0));
+    assert_eq!(ray.at(3.0), Vector3::new(0.0, 0.0, 7.0));
+
+    let plane = Plane::new(Vector3::new(0.0, 0.0, 1.0), 0.0);
+    assert_eq!(ray.intersect_plane(&plane), Some(10.0));
+    let away = Ray::new(Vector3::new(0.0, 0.0, 10.0), Vector3::new(0.0, 0.0, 1.0));
+    assert_eq!(away.intersect_plane(&plane), None);
+    let parallel = Ray::new(Vector3::new(0.0, 0.0, 10.0), Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(parallel.intersect_plane(&plane), None);
+
+    let sphere = Sphere::new(Vector3::new(0.0, 0.0, 0.0), 2.0);
+    assert_eq!(ray.intersect_sphere(&sphere), Some(8.0));
+    let inside = Ray::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(inside.intersect_sphere(&sphere), Some(2.0));
+    assert_eq!(away.intersect_sphere(&sphere), None);
+    assert_eq!(parallel.intersect_sphere(&sphere), None);
+
+    assert_eq!(ray.intersect_aabb(&unit_box()), Some(9.0));
+    assert_eq!(inside.intersect_aabb(&unit_box()), Some(1.0));
+    assert_eq!(away.intersect_aabb(&unit_box()), None);
+    let diagonal = Ray::new(Vector3::new(-3.0, -3.0, 0.0), Vector3::new(1.0, 1.0, 0.0));
+    let t = diagonal.intersect_aabb(&unit_box()).unwrap();
+    assert_close(diagonal.at(t), Vector3::new(-1.0, -1.0, 0.0), EPSILON);
+    let miss = Ray::new(Vector3::new(-3.0, 0.0, 0.0), Vector3::new(1.0, 2.0, 0.0));
+    assert_eq!(miss.intersect_aabb(&unit_box()), None);
+}
+
+#[test]
+fn ray_triangle_intersection() {
+    let (a, b, c) = (
+        Vector3::new(0.0f64, 0.0, 0.0),
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::new(0.0, 2.0, 0.0),
+    );
+    let down = Ray::new(Vector3::new(0.5, 0.5, 3.0), Vector3::new(0.0, 0.0, -1.0));
+    assert_close(down.intersect_triangle(a, b, c).unwrap(), 3.0, EPSILON);
+    // Both sides are hit.
+    let up = Ray::new(Vector3::new(0.5, 0.5, -3.0), Vector3::new(0.0, 0.0, 1.0));
+    assert_close(up.intersect_triangle(a, b, c).unwrap(), 3.0, EPSILON);
+
+    let outside = Ray::new(Vector3::new(1.5, 1.5, 3.0), Vector3::new(0.0, 0.0, -1.0));
+    assert_eq!(outside.intersect_triangle(a, b, c), None);
+    let behind = Ray::new(Vector3::new(0.5, 0.5, 3.0), Vector3::new(0.0, 0.0, 1.0));
+    assert_eq!(behind.intersect_triangle(a, b, c), None);
+}
+
+#[test]
+fn picking_ray() {
+    let eye = Vector3::new(0.0, 0.0, 5.0);
+    let view = Matrix4::look_at(eye, Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+    let projection = Matrix4::perspective_fov(PI / 2.0, 1.0, 0.1, 100.0);
+    let inverse = (projection * view).inverse().unwrap();
+
+    let center = Ray::from_ndc(&inverse, Vector2::new(0.0, 0.0));
+    assert_close(center.direction, Vector3::new(0.0, 0.0, -1.0), EPSILON);
+    assert_close(center.origin, Vector3::new(0.0, 0.0, 4.9), EPSILON);
+
+    // With a 90 degree field of view the right edge of the screen is at 45 degrees.
+    let right = Ray::from_ndc(&inverse, Vector2::new(1.0, 0.0));
+    assert_close(right.direction, Vector3::new(1.0, 0.0, -1.0).normalize(), EPSILON);
+
+    let sphere = Sphere::new(Vector3::new(0.0, 0.0, 0.0), 1.0);
+    assert!(center.intersect_sphere(&sphere).is_some());
+    assert!(right.intersect_sphere(&sphere).is_none());
+}
+
+#[test]
+fn spheres() {
+    let points = [
+        Vector3::new(1.0, 2.0, 3.0),
+        Vector3::new(-1.0, 0.0, 3.0),
+        Vector3::new(0.0, 1.0, -1.0),
+    ];
+    let sphere = Sphere::from_points(&points).unwrap();
+    for &p in &points {
+        assert!(sphere.contains_point(p));
+    }
+    assert_eq!(Sphere::<f64>::from_points(&[]), None);
+
+    let a = Sphere::new(Vector3::new(0.0, 0.0, 0.0), 1.0);
+    assert!(a.intersects_sphere(&Sphere::new(Vector3::new(3.0, 0.0, 0.0), 2.0)));
+    assert!(!a.intersects_sphere(&Sphere::new(Vector3::new(3.0, 0.0, 0.0), 1.5)));
+
+    let aabb = Aabb::new(Vector3::new(1.5, -1.0, -1.0), Vector3::new(3.0, 1.0, 1.0));
+    assert!(!a.intersects_aabb(&aabb));
+    assert!(Sphere::new(Vector3::new(0.0, 0.0, 0.0), 1.6).intersects_aabb(&aabb));
+    // Near the corner the sphere misses even though it overlaps both slabs.
+    let corner = Aabb::new(Vector3::new(0.8, 0.8, 0.8), Vector3::new(2.0, 2.0, 2.0));
+    assert!(!a.intersects_aabb(&corner));
+}
+
+#[test]
+fn boxes() {
+    let aabb = Aabb::from_points(&[
+        Vector3::new(1.0, -2.0, 0.5),
+        Vector3::new(-1.0, 4.0, 0.0),
+        Vector3::new(0.0, 0.0, 2.5),
+    ]).unwrap();
+    assert_eq!(aabb.min, Vector3::new(-1.0, -2.0, 0.0));
+    assert_eq!(aabb.max, Vector3::new(1.0, 4.0, 2.5));
+    assert_eq!(aabb.center(), Vector3::new(0.0, 1.0, 1.25));
+    assert_eq!(aabb.size(), Vector3::new(2.0, 6.0, 2.5));
+    assert_eq!(aabb.half_extents(), Vector3::new(1.0, 3.0, 1.25));
+    assert_eq!(Aabb::<f64>::from_points(&[]), None);
+    for &corner in &aabb.corners() {
+        assert!(aabb.contains_point(corner));
+    }
+    assert!(!aabb.contains_point(Vector3::new(0.0, 0.0, 3.0)));
+
+    let other = Aabb::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(5.0, 5.0, 5.0));
+    assert!(aabb.intersects_aabb(&other));
+    assert!(!aabb.contains_aabb(&other));
+    assert!(aabb.union(&other).contains_aabb(&other));
+    assert!(aabb.union(&other).contains_aabb(&aabb));
+    assert_eq!(
+        aabb.intersection(&other),
+        Aabb::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 4.0, 2.5))
+    );
+    let far = Aabb::new(Vector3::new(10.0, 0.0, 0.0), Vector3::new(11.0, 1.0, 1.0));
+    assert!(!aabb.intersects_aabb(&far));
+    assert!(aabb.intersection(&far).is_empty());
+    assert!(!Aabb::new(Vector3::splat(1.0), Vector3::splat(1.0)).is_empty());
+}
+
+#[test]
+fn transformed_box_contains_transformed_corners() {
+    let aabb = Aabb::new(Vector3::new(-1.0, 0.0, 2.0), Vector3::new(3.0, 1.0, 4.0));
+    let m = Matrix4::translation(Vector3::new(5.0, -2.0, 1.0)) *
+        Matrix4::rotation(Vector3::new(0.3, 1.2, -0.7)) *
+        Matrix4::scaling(Vector3::new(2.0, 0.5, 1.0));
+    let transformed = aabb.transform(&m);
+    let corners: Vec<_> = aabb.corners().iter().map(|&c| m.transform(c)).collect();
+    let exact = Aabb::from_points(&corners).unwrap();
+    // The result is the tight box around the transformed corners.
+    assert_close(transformed.min, exact.min, EPSILON);
+    assert_close(transformed.max, exact.max, EPSILON);
+}
+
+#[test]
+fn frustum_from_perspective() {
+    let view = Matrix4::look_at(
+        Vector3::new(0.0, 0.0, 10.0),
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    );
+    let projection = Matrix4::perspective_fov(PI / 2.0, 1.0, 1.0, 20.0);
+    let frustum = Frustum::from_matrix(&(projection * view));
+
+    assert!(frustum.contains_point(Vector3::new(0.0, 0.0, 0.0)));
+    assert!(frustum.contains_point(Vector3::new(9.0, 0.0, 0.0)));
+    assert!(!frustum.contains_point(Vector3::new(11.0, 0.0, 0.0)));
+    // Between the eye and the near plane, and beyond the far plane.
+    assert!(!frustum.contains_point(Vector3::new(0.0, 0.0, 9.5)));
+    assert!(!frustum.contains_point(Vector3::new(0.0, 0.0, -10.5)));
+    // Behind the camera
+    assert!(!frustum.contains_point(Vector3::new(0.0, 0.0, 15.0)));
+
+    // The planes are normalized, so signed distances are real distances.
+    for plane in &frustum.planes {
+        assert_close(plane.normal.magnitude(), 1.0, EPSILON);
+    }
+    let far = frustum.planes[5];
+    assert_close(far.signed_distance(Vector3::new(0.0, 0.0, -5.0)), 5.0, EPSILON);
+
+    let inside = Sphere::new(Vector3::new(0.0, 0.0, 0.0), 1.0);
+    let straddling = Sphere::new(Vector3::new(10.0, 0.0, 0.0), 1.0);
+    let outside = Sphere::new(Vector3::new(0.0, 30.0, 0.0), 1.0);
+    assert!(frustum.contains_sphere(&inside));
+    assert!(frustum.intersects_sphere(&straddling));
+    assert!(!frustum.contains_sphere(&straddling));
+    assert!(!frustum.intersects_sphere(&outside));
+
+    assert!(frustum.contains_aabb(&unit_box()));
+    let big = Aabb::new(Vector3::splat(-50.0), Vector3::splat(50.0));
+    assert!(frustum.intersects_aabb(&big));
+    assert!(!frustum.contains_aabb(&big));
+    let behind = Aabb::new(Vector3::new(-1.0, -1.0, 12.0), Vector3::new(1.0, 1.0, 14.0));
+    assert!(!frustum.intersects_aabb(&behind));
+}
+
+#[test]
+fn frustum_from_orthographic() {
+    let frustum = Frustum::from_matrix(&Matrix4::orthographic(-2.0, 2.0, -1.0, 1.0, 0.5, 10.0));
+    assert!(frustum.contains_point(Vector3::new(1.9, 0.9, -0.6)));
+    assert!(!frustum.contains_point(Vector3::new(2.1, 0.0, -5.0)));
+    assert!(!frustum.contains_point(Vector3::new(0.0, 0.0, -10.5)));
+    assert!(!frustum.contains_point(Vector3::new(0.0, 0.0, 0.0)));
+}
+
+#[test]
+fn frustum_from_infinite_and_reverse_z_projections() {
+    let infinite = Frustum::from_matrix(&Matrix4::perspective_infinite(PI / 2.0, 1.0, 1.0));
+    assert!(infinite.contains_point(Vector3::new(0.0, 0.0, -1e9)));
+    assert!(!infinite.contains_point(Vector3::new(0.0, 0.0, -0.5)));
+
+    // Still correct for culling, if not as tight.
+    let reverse = Frustum::from_matrix(&Matrix4::perspective_reverse_z(PI / 2.0, 1.0, 1.0, 20.0));
+    assert!(reverse.contains_point(Vector3::new(0.0, 0.0, -1.5)));
+    assert!(reverse.contains_point(Vector3::new(0.0, 0.0, -19.5)));
+    assert!(!reverse.contains_point(Vector3::new(0.0, 0.0, -0.5)));
+    assert!(!reverse.contains_point(Vector3::new(3.0, 0.0, -2.0)));
+}
@@ -0,0 +1,307 @@
+extern crate rrasterizer;
+
+// Regression tests comparing rendered images against checked-in references.
+//
+// Reference images live in `tests/golden/<name>.png`.  When a comparison fails, the rendered image
+// and a diff image are written to `<target>/tmp/golden/` and the test panics.  To accept new
+// output as the reference, run the tests with `RRASTERIZER_BLESS=1`, e.g.
+//
+//     RRASTERIZER_BLESS=1 cargo test --test golden
+
+use std::env;
+use std::f32;
+use std::fs;
+use std::path::PathBuf;
+
+use rrasterizer::vec2::Vector2;
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::color::Color;
+use rrasterizer::image::Image;
+use rrasterizer::image_format;
+use rrasterizer::renderer::{Vertex, Triangle, Renderer};
+use rrasterizer::application::Application;
+use rrasterizer::mesh_format::obj;
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Tolerance {
+    /// Largest difference in any channel for a pixel to still count as matching.
+    channel: u8,
+    /// Number of pixels that may exceed `channel` before the comparison fails.
+    max_failing_pixels: usize,
+}
+
+impl Tolerance {
+    fn exact() -> Tolerance {
+        Tolerance {
+            channel: 0,
+            max_failing_pixels: 0,
+        }
+    }
+
+    /// Allows small rounding differences everywhere, and a handful of pixels along triangle
+    /// edges to flip between covered and uncovered.
+    fn rasterization() -> Tolerance {
+        Tolerance {
+            channel: 2,
+            max_failing_pixels: 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Comparison {
+    failing_pixels: usize,
+    max_difference: u8,
+}
+
+fn blessing() -> bool {
+    env::var("RRASTERIZER_BLESS").map(|v| !v.is_empty() && v != "0").unwrap_or(false)
+}
+
+fn reference_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(
+        format!("{}.png", name),
+    )
+}
+
+fn failure_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden")
+}
+
+fn compare(
+    actual: &Image<Color>,
+    expected: &Image<Color>,
+    tolerance: Tolerance,
+) -> (Comparison, Image<Color>) {
+    assert_eq!(actual.dimensions(), expected.dimensions());
+
+    let mut comparison = Comparison {
+        failing_pixels: 0,
+        max_difference: 0,
+    };
+    let mut diff = expected.clone();
+    for (d, (&a, &e)) in diff.pixels_mut().iter_mut().zip(
+        actual.pixels().iter().zip(expected.pixels()),
+    )
+    {
+        let difference = channel_difference(a, e);
+        comparison.max_difference = comparison.max_difference.max(difference);
+        *d = if difference > tolerance.channel {
+            comparison.failing_pixels += 1;
+            Color(255, 0, 255, 255)
+        } else {
+            // Matching pixels are drawn as a faded copy of the reference for orientation.
+            let Color(r, g, b, _) = e;
+            let v = ((r as u32 + g as u32 + b as u32) / 12) as u8;
+            Color(v, v, v, 255)
+        };
+    }
+    (comparison, diff)
+}
+
+/// Compares `actual` against the reference image called `name`, panicking if it does not match
+/// within `tolerance`.  When blessing, the reference is overwritten instead.
+fn assert_golden(name: &str, actual: &Image<Color>, tolerance: Tolerance) {
+    let reference = reference_path(name);
+    if blessing() {
+        fs::create_dir_all(reference.parent().unwrap()).unwrap();
+        image_format::save_image(&reference, actual).unwrap();
+        return;
+    }
+
+    let expected = match image_format::load_image(&reference) {
+        Ok(expected) => expected.into_rgba8(),
+        Err(err) => {
+            let actual_path = save_failure(name, "actual", actual);
+            panic!(
+                "could not load reference image {}: {}\nrendered image written to {}\nrun with \
+                 RRASTERIZER_BLESS=1 to create it",
+                reference.display(),
+                err,
+                actual_path.display()
+            );
+        }
+    };
+
+    if actual.dimensions() != expected.dimensions() {
+        let actual_path = save_failure(name, "actual", actual);
+        panic!(
+            "{}: rendered image is {:?} but the reference is {:?}\nrendered image written to {}",
+            name,
+            actual.dimensions(),
+            expected.dimensions(),
+            actual_path.display()
+        );
+    }
+
+    let (comparison, diff) = compare(actual, &expected, tolerance);
+    if comparison.failing_pixels > tolerance.max_failing_pixels {
+        let actual_path = save_failure(name, "actual", actual);
+        let diff_path = save_failure(name, "diff", &diff);
+        panic!(
+            "{}: {} pixels differ by more than {} (budget {}), largest difference {}\nrendered \
+             image written to {}\ndiff image written to {}",
+            name,
+            comparison.failing_pixels,
+            tolerance.channel,
+            tolerance.max_failing_pixels,
+            comparison.max_difference,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn save_failure(name: &str, kind: &str, image: &Image<Color>) -> PathBuf {
+    let dir = failure_dir();
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}-{}.png", name, kind));
+    image_format::save_image(&path, image).unwrap();
+    path
+}
+
+fn channel_difference(Color(r1, g1, b1, a1): Color, Color(r2, g2, b2, a2): Color) -> u8 {
+    r1.abs_diff(r2)
+        .max(g1.abs_diff(g2))
+        .max(b1.abs_diff(b2))
+        .max(a1.abs_diff(a2))
+}
+
+fn vertex(x: f32, y: f32, z: f32, r: f32, g: f32, b: f32) -> Vertex {
+    Vertex {
+        position: Vector3::new(x, y, z),
+        color: Vector4::new(r, g, b, 1.0),
+        normal: Vector3::splat(0.0),
+        tex_coord: Vector2::splat(0.0),
+    }
+}
+
+fn render(triangles: &[Triangle], transformation: Matrix4<f32>) -> Renderer {
+    let projection = Matrix4::perspective_fov(
+        f32::consts::PI / 3.0,
+        WIDTH as f32 / HEIGHT as f32,
+        0.1,
+        100.0,
+    );
+    let mut renderer = Renderer::new(WIDTH, HEIGHT, projection);
+    renderer.clear(Color(0, 0, 0, 255));
+    renderer.render(transformation, triangles);
+    renderer
+}
+
+#[test]
+fn vertex_color_triangle() {
+    let triangle = Triangle {
+        a: vertex(-1.0, -1.0, 0.0, 1.0, 0.0, 0.0),
+        b: vertex(1.0, -1.0, 0.0, 0.0, 1.0, 0.0),
+        c: vertex(0.0, 1.0, 0.0, 0.0, 0.0, 1.0),
+    };
+    let renderer = render(
+        &[triangle],
+        Matrix4::translation(Vector3::new(0.0, 0.0, -3.0)),
+    );
+    assert_golden(
+        "vertex_color_triangle",
+        renderer.framebuffer(),
+        Tolerance::rasterization(),
+    );
+}
+
+#[test]
+fn offscreen_triangle() {
+    // Extends well past every edge of the screen.
+    let triangle = Triangle {
+        a: vertex(-20.0, -10.0, 0.0, 1.0, 1.0, 0.0),
+        b: vertex(20.0, -10.0, 0.0, 0.0, 1.0, 1.0),
+        c: vertex(0.0, 20.0, 0.0, 1.0, 0.0, 1.0),
+    };
+    let renderer = render(
+        &[triangle],
+        Matrix4::translation(Vector3::new(0.0, 0.0, -3.0)),
+    );
+    assert_golden(
+        "offscreen_triangle",
+        renderer.framebuffer(),
+        Tolerance::rasterization(),
+    );
+}
+
+#[test]
+fn back_faces_are_culled() {
+    let triangle = Triangle {
+        a: vertex(-1.0, -1.0, 0.0, 1.0, 1.0, 1.0),
+        b: vertex(0.0, 1.0, 0.0, 1.0, 1.0, 1.0),
+        c: vertex(1.0, -1.0, 0.0, 1.0, 1.0, 1.0),
+    };
+    let renderer = render(
+        &[triangle],
+        Matrix4::translation(Vector3::new(0.0, 0.0, -3.0)),
+    );
+    assert!(renderer.framebuffer().pixels().iter().all(|&c| {
+        c == Color(0, 0, 0, 255)
+    }));
+}
+
+#[test]
+fn application_cube() {
+    let mut application = Application::new(WIDTH, HEIGHT);
+    application.render();
+    assert_golden(
+        "application_cube",
+        application.framebuffer(),
+        Tolerance::rasterization(),
+    );
+}
+
+#[test]
+fn obj_pyramid() {
+    let model = obj::parse_obj(
+        "
+        v -1 -1 0
+        v 1 -1 0
+        v 1 1 0
+        v -1 1 0
+        v 0 0 0.5
+        f 1 2 5
+        f 2 3 5
+        f 3 4 5
+        f 4 1 5
+        ",
+    ).unwrap();
+    let triangles = model.triangles();
+    let transformation = Matrix4::translation(Vector3::new(0.0, 0.0, -4.0)) *
+        Matrix4::rotation(Vector3::new(0.4, 0.6, 0.0));
+    let renderer = render(&triangles, transformation);
+    assert_golden("obj_pyramid", renderer.framebuffer(), Tolerance::rasterization());
+}
+
+#[test]
+fn comparison_respects_tolerance() {
+    let triangle = Triangle {
+        a: vertex(-1.0, -1.0, 0.0, 0.5, 0.5, 0.5),
+        b: vertex(1.0, -1.0, 0.0, 0.5, 0.5, 0.5),
+        c: vertex(0.0, 1.0, 0.0, 0.5, 0.5, 0.5),
+    };
+    let renderer = render(
+        &[triangle],
+        Matrix4::translation(Vector3::new(0.0, 0.0, -3.0)),
+    );
+    let expected = renderer.framebuffer();
+
+    let mut actual = expected.clone();
+    let Color(r, g, b, a) = actual.get_pixel(80, 60);
+    actual.set_pixel(80, 60, Color(r + 3, g, b, a));
+
+    let (comparison, diff) = compare(&actual, expected, Tolerance::exact());
+    assert_eq!(comparison.failing_pixels, 1);
+    assert_eq!(comparison.max_difference, 3);
+    assert_eq!(diff.get_pixel(80, 60), Color(255, 0, 255, 255));
+
+    let (comparison, _) = compare(&actual, expected, Tolerance::rasterization());
+    assert!(comparison.failing_pixels <= Tolerance::rasterization().max_failing_pixels);
+}
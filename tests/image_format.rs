@@ -0,0 +1,337 @@
+extern crate rrasterizer;
+
+use std::fs;
+
+use rrasterizer::color::{Color, Color16};
+use rrasterizer::image::Image;
+use rrasterizer::image_format::{DecodedImage, ImageError, ImageFormat, decode_image,
+                                depth_to_grayscale, encode_image, load_image, save_image};
+
+const FORMATS: [ImageFormat; 5] = [
+    ImageFormat::Ppm,
+    ImageFormat::Pam,
+    ImageFormat::Tga,
+    ImageFormat::Bmp,
+    ImageFormat::Png,
+];
+
+// A 5x3 image with every pixel different, some of them translucent.
+fn test_image() -> Image<Color> {
+    let mut image = Image::new(5, 3, Color(0, 0, 0, 255));
+    for y in 0..3 {
+        for x in 0..5 {
+            let (x8, y8) = (x as u8, y as u8);
+            image[(x, y)] = Color(x8 * 60, y8 * 120, 255 - x8 * 7, 255 - y8 * 64);
+        }
+    }
+    image
+}
+
+// A red and a green pixel on the bottom row, and a blue and a translucent white one above.
+fn small_image() -> Image<Color> {
+    Image::from_pixels(
+        2,
+        2,
+        vec![
+            Color(255, 0, 0, 255),
+            Color(0, 255, 0, 255),
+            Color(0, 0, 255, 255),
+            Color(255, 255, 255, 128),
+        ],
+    )
+}
+
+fn encode(image: &Image<Color>, format: ImageFormat) -> Vec<u8> {
+    let mut data = Vec::new();
+    encode_image(&mut data, image, format).unwrap();
+    data
+}
+
+fn decode(data: &[u8], format: ImageFormat) -> Image<Color> {
+    decode_image(data, format).unwrap().into_rgba8()
+}
+
+fn malformed(data: &[u8], format: ImageFormat) {
+    match decode_image(data, format) {
+        Err(ImageError::Malformed(_)) | Err(ImageError::Unsupported(_)) => {}
+        other => panic!("{:?}: expected an error, got {:?}", format, other.map(|_| ())),
+    }
+}
+
+fn le_u32(data: &[u8], offset: usize) -> u32 {
+    data[offset..offset + 4].iter().rev().fold(0, |v, &b| v << 8 | b as u32)
+}
+
+#[test]
+fn netpbm_files() {
+    // Both are written top row first.
+    let mut ppm = b"P6\n2 2\n255\n".to_vec();
+    ppm.extend_from_slice(&[0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0]);
+    assert_eq!(encode(&small_image(), ImageFormat::Ppm), ppm);
+
+    let mut pam = b"P7\nWIDTH 2\nHEIGHT 2\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n"
+        .to_vec();
+    pam.extend_from_slice(&[0, 0, 255, 255, 255, 255, 255, 128]);
+    pam.extend_from_slice(&[255, 0, 0, 255, 0, 255, 0, 255]);
+    assert_eq!(encode(&small_image(), ImageFormat::Pam), pam);
+}
+
+#[test]
+fn tga_files() {
+    let data = encode(&small_image(), ImageFormat::Tga);
+    assert_eq!(&data[..18], &[0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 2, 0, 32, 8]);
+    // Bottom row first, in BGRA order.
+    assert_eq!(
+        &data[18..],
+        &[0, 0, 255, 255, 0, 255, 0, 255, 255, 0, 0, 255, 255, 255, 255, 128][..]
+    );
+
+    let mut sink = Vec::new();
+    let wide = Image::new(0x10000, 1, Color(0, 0, 0, 255));
+    assert!(encode_image(&mut sink, &wide, ImageFormat::Tga).is_err());
+}
+
+#[test]
+fn bmp_files() {
+    let data = encode(&small_image(), ImageFormat::Bmp);
+    // Rows of two 24-bit pixels are padded from 6 to 8 bytes.
+    assert_eq!(data.len(), 54 + 16);
+    assert_eq!(&data[..2], b"BM");
+    assert_eq!(le_u32(&data, 2), 70);
+    assert_eq!(le_u32(&data, 10), 54);
+    assert_eq!((le_u32(&data, 18), le_u32(&data, 22)), (2, 2));
+    assert_eq!(&data[28..30], &[24, 0]);
+    // Bottom row first, in BGR order, without alpha.
+    assert_eq!(
+        &data[54..],
+        &[0, 0, 255, 0, 255, 0, 0, 0, 255, 0, 0, 255, 255, 255, 0, 0][..]
+    );
+}
+
+#[test]
+fn png_files() {
+    let data = encode(&test_image(), ImageFormat::Png);
+    assert_eq!(&data[..8], &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]);
+    // An 8-bit RGBA header.
+    assert_eq!(&data[8..16], &[0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+    assert_eq!(&data[16..29], &[0, 0, 0, 5, 0, 0, 0, 3, 8, 6, 0, 0, 0]);
+
+    let mut chunks = Vec::new();
+    let mut offset = 8;
+    while offset < data.len() {
+        let len = data[offset..offset + 4].iter().fold(0, |v, &b| v << 8 | b as usize);
+        chunks.push(data[offset + 4..offset + 8].to_vec());
+        offset += len + 12;
+    }
+    assert_eq!(offset, data.len());
+    assert_eq!(chunks.first().unwrap(), b"IHDR");
+    assert!(chunks.iter().any(|c| c == b"IDAT"));
+    assert_eq!(chunks.last().unwrap(), b"IEND");
+    assert_eq!(&data[data.len() - 4..], &[0xae, 0x42, 0x60, 0x82]);
+
+    let mut sink = Vec::new();
+    let empty = Image::new(0, 4, Color(0, 0, 0, 255));
+    assert!(encode_image(&mut sink, &empty, ImageFormat::Png).is_err());
+}
+
+#[test]
+fn saving_files() {
+    let dir = std::env::temp_dir().join(format!("rrasterizer-save-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let image = test_image();
+    let mut saved = Vec::new();
+    for &(extension, format) in &[
+        ("ppm", ImageFormat::Ppm),
+        ("PAM", ImageFormat::Pam),
+        ("tga", ImageFormat::Tga),
+        ("bmp", ImageFormat::Bmp),
+        ("png", ImageFormat::Png),
+    ]
+    {
+        let path = dir.join(format!("image.{}", extension));
+        save_image(&path, &image).unwrap();
+        saved.push((fs::read(&path).unwrap(), format));
+    }
+    let unknown = save_image(dir.join("image.gif"), &image);
+    let missing = save_image(dir.join("image"), &image);
+    fs::remove_dir_all(&dir).unwrap();
+
+    for (data, format) in saved {
+        assert_eq!(data, encode(&image, format), "{:?}", format);
+    }
+    assert!(unknown.is_err());
+    assert!(missing.is_err());
+}
+
+#[test]
+fn formats_from_paths() {
+    assert_eq!(ImageFormat::from_extension("PNG"), Some(ImageFormat::Png));
+    assert_eq!(ImageFormat::from_extension("jpg"), None);
+    assert_eq!(ImageFormat::from_path("a/b.tga"), Some(ImageFormat::Tga));
+    assert_eq!(ImageFormat::from_path("a.ppm/b"), None);
+    for &format in &FORMATS {
+        let extension = format!("{:?}", format);
+        assert_eq!(ImageFormat::from_extension(&extension), Some(format));
+    }
+}
+
+#[test]
+fn depth_as_grayscale() {
+    let depth = Image::from_pixels(4, 1, vec![0.5, 1.0, f32::INFINITY, 0.75]);
+    let gray: Vec<_> = depth_to_grayscale(&depth).pixels().iter().map(|c| c.0).collect();
+    // The nearest depth is white, the farthest black, and cleared entries black as well.
+    assert_eq!(gray, vec![255, 0, 0, 128]);
+    let flat = Image::new(2, 2, 3.0);
+    assert!(depth_to_grayscale(&flat).pixels().iter().all(|&c| c == Color(255, 255, 255, 255)));
+}
+
+#[test]
+fn round_trips() {
+    let image = test_image();
+    let opaque = image.map(|&Color(r, g, b, _)| Color(r, g, b, 255));
+    for &format in &FORMATS {
+        let decoded = decode(&encode(&image, format), format);
+        // Plain PPM files and 24-bit BMP files have no alpha channel.
+        let opaque_format = format == ImageFormat::Ppm || format == ImageFormat::Bmp;
+        let expected = if opaque_format { &opaque } else { &image };
+        assert_eq!(decoded, *expected, "{:?}", format);
+    }
+}
+
+#[test]
+fn loading_files() {
+    let dir = std::env::temp_dir().join(format!("rrasterizer-load-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let image = test_image();
+    let mut loaded = Vec::new();
+    for extension in &["pam", "tga", "png"] {
+        let path = dir.join(format!("image.{}", extension));
+        save_image(&path, &image).unwrap();
+        loaded.push(load_image(&path).map(|i| i.into_rgba8()));
+    }
+    let missing = load_image(dir.join("missing.png"));
+    fs::remove_dir_all(&dir).unwrap();
+
+    for result in loaded {
+        assert_eq!(result.unwrap(), image);
+    }
+    assert!(missing.is_err());
+}
+
+#[test]
+fn formats_from_magic() {
+    let image = test_image();
+    for &format in &[ImageFormat::Ppm, ImageFormat::Pam, ImageFormat::Bmp, ImageFormat::Png] {
+        assert_eq!(ImageFormat::from_magic(&encode(&image, format)), Some(format));
+    }
+    // TGA files have no signature.
+    assert_eq!(ImageFormat::from_magic(&encode(&image, ImageFormat::Tga)), None);
+    assert_eq!(ImageFormat::from_magic(b""), None);
+}
+
+// A 3x2 RGB image compressed by the reference zlib implementation at level 9, with its top row
+// using the Sub filter and its bottom row the Up filter.
+const ZLIB_PNG: [u8; 80] = [
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x08, 0x02, 0x00, 0x00, 0x00, 0x12, 0x16, 0xf1,
+    0x4d, 0x00, 0x00, 0x00, 0x17, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0xfc, 0xcf, 0xc0, 0xc0,
+    0x08, 0xc6, 0x4c, 0x0c, 0xff, 0xff, 0x37, 0x34, 0x36, 0x00, 0x99, 0x00, 0x43, 0xa2, 0x06, 0x83,
+    0x98, 0x7b, 0x77, 0x79, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+#[test]
+fn png_compressed_by_zlib() {
+    let image = decode(&ZLIB_PNG, ImageFormat::Png);
+    assert_eq!(image.dimensions(), (3, 2));
+    // Rows are stored bottom-up.
+    let expected = [
+        Color(255, 255, 255, 255),
+        Color(128, 128, 128, 255),
+        Color(0, 0, 0, 255),
+        Color(255, 0, 0, 255),
+        Color(0, 255, 0, 255),
+        Color(0, 0, 255, 255),
+    ];
+    assert_eq!(image.pixels(), &expected[..]);
+}
+
+#[test]
+fn sixteen_bit_channels() {
+    let data = b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 4\nMAXVAL 65535\nTUPLTYPE RGB_ALPHA\nENDHDR\n\
+                 \x12\x34\x00\x00\xff\xff\x80\x00";
+    match decode_image(data, ImageFormat::Pam).unwrap() {
+        DecodedImage::Rgba16(image) => {
+            assert_eq!(image[(0, 0)], Color16(0x1234, 0, 0xffff, 0x8000));
+        }
+        other => panic!("expected a 16-bit image, got {:?}", other),
+    }
+}
+
+#[test]
+fn truncated_files_are_errors() {
+    let image = test_image();
+    for &format in &FORMATS {
+        let data = encode(&image, format);
+        for len in 0..data.len() {
+            match decode_image(&data[..len], format) {
+                Err(_) => {}
+                // TGA files end in an optional footer.
+                Ok(ref decoded) if format == ImageFormat::Tga => {
+                    assert_eq!(decoded.clone().into_rgba8(), image)
+                }
+                Ok(_) => panic!("{:?} truncated to {} bytes decoded", format, len),
+            }
+        }
+    }
+}
+
+#[test]
+fn corrupt_files_do_not_panic() {
+    let image = test_image();
+    for &format in &FORMATS {
+        let data = encode(&image, format);
+        for i in 0..data.len() {
+            for &value in &[0x00, 0x7f, 0xff] {
+                let mut corrupt = data.clone();
+                corrupt[i] = value;
+                let _ = decode_image(&corrupt, format);
+            }
+        }
+    }
+}
+
+#[test]
+fn crafted_headers_are_errors() {
+    malformed(b"P6\n100000 100000\n255\n\x00\x00\x00", ImageFormat::Ppm);
+    malformed(b"P3\n4000000000 4000000000\n255\n0 0 0\n", ImageFormat::Ppm);
+    malformed(b"P4\n0 0\n", ImageFormat::Ppm);
+    malformed(
+        b"P7\nWIDTH 65536\nHEIGHT 65536\nDEPTH 4\nMAXVAL 65535\nENDHDR\n\x00",
+        ImageFormat::Pam,
+    );
+
+    // Uncompressed and run length encoded 65535x65535 images with a single pixel of data.
+    let mut tga = vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 32, 8];
+    tga.extend_from_slice(&[1, 2, 3, 4]);
+    malformed(&tga, ImageFormat::Tga);
+    tga[2] = 10;
+    malformed(&tga, ImageFormat::Tga);
+    // A 0x0 image.
+    let tga = [0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32, 8];
+    malformed(&tga, ImageFormat::Tga);
+
+    // A 100000x100000 RGBA PNG with ten bytes of image data.
+    let png = [
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x01, 0x86, 0xa0, 0x00, 0x01, 0x86, 0xa0, 0x08, 0x06, 0x00, 0x00, 0x00, 0xa8,
+        0x52, 0x0b, 0xc8, 0x00, 0x00, 0x00, 0x0b, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0x60,
+        0x80, 0x01, 0x00, 0x00, 0x0a, 0x00, 0x01, 0xec, 0x24, 0x03, 0xb9, 0x00, 0x00, 0x00, 0x00,
+        0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+    malformed(&png, ImageFormat::Png);
+
+    // A BMP claiming to be 2^31 - 1 pixels wide and high.
+    let mut bmp = encode(&test_image(), ImageFormat::Bmp);
+    bmp[18..26].copy_from_slice(&[0xff, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff, 0x7f]);
+    malformed(&bmp, ImageFormat::Bmp);
+}
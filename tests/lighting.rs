@@ -0,0 +1,252 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod light;
+    pub mod mesh;
+    pub mod render;
+}
+
+use std::f32;
+use std::rc::Rc;
+
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::color::Color;
+use rrasterizer::renderer::Renderer;
+use rrasterizer::lighting::{Attenuation, LightKind, PlacedLight, Material, ShadingMode, phong};
+use rrasterizer::scene::{Scene, Node, Model, Camera, Projection};
+
+use common::light::white_light;
+use common::mesh::square;
+use common::render::SIZE;
+
+fn directional(direction: Vector3<f32>) -> PlacedLight {
+    PlacedLight {
+        light: white_light(LightKind::Directional),
+        position: Vector3::splat(0.0),
+        direction: direction.normalize(),
+        shadow: None,
+    }
+}
+
+fn matte() -> Material {
+    Material {
+        ambient: Vector3::splat(1.0),
+        diffuse: Vector3::splat(0.5),
+        specular: Vector3::splat(0.0),
+        shininess: 1.0,
+    }
+}
+
+fn renderer(shading: ShadingMode, lights: Vec<PlacedLight>) -> Renderer {
+    let mut renderer = common::render::renderer();
+    renderer.set_shading(shading);
+    renderer.set_lights(lights);
+    renderer.set_ambient_light(Vector3::splat(0.1));
+    renderer.set_material(matte());
+    renderer
+}
+
+fn center_red(renderer: &Renderer) -> i32 {
+    renderer.get_pixel(SIZE / 2, SIZE / 2).0 as i32
+}
+
+fn assert_channel_near(actual: i32, expected: i32) {
+    assert!((actual - expected).abs() <= 1, "{} != {}", actual, expected);
+}
+
+#[test]
+fn attenuation() {
+    assert_eq!(Attenuation::none().factor(10.0), 1.0);
+    assert_eq!(Attenuation::inverse_square().factor(2.0), 0.25);
+    let a = Attenuation {
+        constant: 1.0,
+        linear: 0.5,
+        quadratic: 0.25,
+    };
+    assert_eq!(a.factor(2.0), 1.0 / 3.0);
+}
+
+#[test]
+fn phong_and_blinn_phong_highlights() {
+    let material = Material {
+        ambient: Vector3::splat(0.0),
+        diffuse: Vector3::splat(0.0),
+        specular: Vector3::splat(1.0),
+        shininess: 1.0,
+    };
+    // The light arrives 30 degrees off the normal and the viewer looks straight down it.
+    let angle = f32::consts::PI / 6.0;
+    let lights = [directional(-Vector3::new(angle.sin(), 0.0, angle.cos()))];
+    let shade = |blinn| {
+        phong(
+            &material,
+            &lights,
+            Vector3::splat(0.0),
+            Vector3::splat(1.0),
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            blinn,
+        ).x
+    };
+    assert!((shade(false) - angle.cos()).abs() < 1e-5);
+    assert!((shade(true) - (angle / 2.0).cos()).abs() < 1e-5);
+}
+
+#[test]
+fn surfaces_facing_away_only_get_ambient_light() {
+    let light = directional(Vector3::new(0.0, 0.0, -1.0));
+    let color = phong(
+        &matte(),
+        &[light],
+        Vector3::splat(0.25),
+        Vector3::new(1.0, 0.5, 0.0),
+        Vector3::new(0.0, 0.0, -1.0),
+        Vector3::new(0.0, 0.0, -1.0),
+        false,
+    );
+    assert_eq!(color, Vector3::new(0.25, 0.125, 0.0));
+}
+
+#[test]
+fn point_light_falloff() {
+    let light = PlacedLight {
+        light: white_light(LightKind::Point { attenuation: Attenuation::inverse_square() }),
+        position: Vector3::new(0.0, 0.0, 2.0),
+        direction: Vector3::new(0.0, 0.0, -1.0),
+        shadow: None,
+    };
+    let (l, radiance) = light.incoming(Vector3::splat(0.0));
+    assert_eq!(l, Vector3::new(0.0, 0.0, 1.0));
+    assert_eq!(radiance, Vector3::splat(0.25));
+}
+
+#[test]
+fn spot_light_cone() {
+    let degrees = f32::consts::PI / 180.0;
+    let light = PlacedLight {
+        light: white_light(LightKind::Spot {
+            attenuation: Attenuation::none(),
+            inner_angle: 10.0 * degrees,
+            outer_angle: 20.0 * degrees,
+        }),
+        position: Vector3::splat(0.0),
+        direction: Vector3::new(0.0, 0.0, -1.0),
+        shadow: None,
+    };
+    let at = |angle: f32| {
+        let angle = angle * degrees;
+        light.incoming(Vector3::new(angle.sin(), 0.0, -angle.cos()) * 3.0).1.x
+    };
+    assert!((at(0.0) - 1.0).abs() < 1e-5);
+    assert!((at(9.0) - 1.0).abs() < 1e-5);
+    let edge = at(15.0);
+    assert!(edge > 0.0 && edge < 1.0, "{}", edge);
+    assert!(at(14.0) > edge);
+    assert_eq!(at(21.0), 0.0);
+}
+
+#[test]
+fn shading_modes() {
+    let triangles = square(-3.0, Vector4::splat(1.0), Vector3::splat(0.0));
+    let lit = vec![directional(Vector3::new(0.0, 0.0, -1.0))];
+
+    let mut unlit = renderer(ShadingMode::Unlit, lit.clone());
+    unlit.render(Matrix4::identity(), &triangles);
+    assert_eq!(center_red(&unlit), 255);
+
+    // Ambient plus half the light, from the face normal since the vertices have none.
+    for &mode in &[ShadingMode::Gouraud, ShadingMode::Phong, ShadingMode::BlinnPhong] {
+        let mut r = renderer(mode, lit.clone());
+        r.render(Matrix4::identity(), &triangles);
+        assert_channel_near(center_red(&r), 153);
+
+        let mut r = renderer(mode, vec![directional(Vector3::new(0.0, 0.0, 1.0))]);
+        r.render(Matrix4::identity(), &triangles);
+        assert_channel_near(center_red(&r), 25);
+    }
+}
+
+#[test]
+fn per_pixel_lighting_catches_highlights_between_vertices() {
+    // A point light just in front of the center of the square lights the center far more than
+    // the corners.
+    let light = PlacedLight {
+        light: white_light(LightKind::Point { attenuation: Attenuation::none() }),
+        position: Vector3::new(0.0, 0.0, -2.5),
+        direction: Vector3::new(0.0, 0.0, -1.0),
+        shadow: None,
+    };
+    let triangles = square(-3.0, Vector4::splat(1.0), Vector3::new(0.0, 0.0, 1.0));
+
+    let mut gouraud = renderer(ShadingMode::Gouraud, vec![light.clone()]);
+    gouraud.set_ambient_light(Vector3::splat(0.0));
+    gouraud.render(Matrix4::identity(), &triangles);
+    let mut phong = renderer(ShadingMode::Phong, vec![light]);
+    phong.set_ambient_light(Vector3::splat(0.0));
+    phong.render(Matrix4::identity(), &triangles);
+
+    assert_channel_near(center_red(&gouraud), 42);
+    assert!(center_red(&phong) > 120, "{}", center_red(&phong));
+}
+
+#[test]
+fn normals_follow_the_normal_matrix() {
+    // Tilting the square by 45 degrees and squashing it vertically tilts its normals by 60
+    // degrees, halving the diffuse light.  Transformed like positions they would only tilt by 30.
+    let transformation = Matrix4::translation(Vector3::new(0.0, 0.0, -3.0)) *
+        Matrix4::scaling(Vector3::new(1.0, 1.0 / 3.0f32.sqrt(), 1.0)) *
+        Matrix4::rotation(Vector3::new(f32::consts::PI / 4.0, 0.0, 0.0));
+    let triangles = square(0.0, Vector4::splat(1.0), Vector3::new(0.0, 0.0, 1.0));
+
+    let mut r = renderer(ShadingMode::Phong, vec![directional(Vector3::new(0.0, 0.0, -1.0))]);
+    r.render(transformation, &triangles);
+    assert_channel_near(center_red(&r), 89);
+}
+
+#[test]
+fn scene_lights_are_placed_in_view_space() {
+    let quarter = f32::consts::PI / 2.0;
+    let mut scene = Scene::new();
+    // The camera looks down the negative x axis at a square facing it.
+    let camera = scene.add(
+        None,
+        Node::new("camera")
+            .with_transform(Matrix4::rotation(Vector3::new(0.0, quarter, 0.0)))
+            .with_camera(Camera {
+                projection: Projection::Perspective {
+                    fovy: f32::consts::PI / 3.0,
+                    near: 0.1,
+                    far: 100.0,
+                },
+            }),
+    );
+    let triangles = square(0.0, Vector4::splat(1.0), Vector3::splat(0.0));
+    let model = Model::new(triangles).with_material(matte());
+    scene.add(
+        None,
+        Node::new("square")
+            .with_transform(
+                Matrix4::translation(Vector3::new(-3.0, 0.0, 0.0)) *
+                    Matrix4::rotation(Vector3::new(0.0, quarter, 0.0)),
+            )
+            .with_model(Rc::new(model)),
+    );
+    let headlight = scene.add(
+        Some(camera),
+        Node::new("headlight").with_light(white_light(LightKind::Directional)),
+    );
+
+    let mut r = renderer(ShadingMode::Phong, Vec::new());
+    r.set_ambient_light(Vector3::splat(0.0));
+    scene.render(&mut r, camera);
+    assert_eq!(r.lights().len(), 1);
+    assert_channel_near(center_red(&r), 127);
+
+    // Shining along the world's negative z axis, the light grazes the square.
+    scene.set_parent(headlight, None);
+    r.clear(Color(0, 0, 0, 255));
+    scene.render(&mut r, camera);
+    assert_eq!(center_red(&r), 0);
+}
@@ -0,0 +1,399 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod approx;
+}
+
+use rrasterizer::vec2::Vector2;
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::mat2::Matrix2;
+use rrasterizer::mat3::Matrix3;
+use rrasterizer::mat4::Matrix4;
+
+use common::approx::assert_close;
+
+// A small xorshift generator, so that failures are reproducible without any extra dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next()
+    }
+
+    fn vector(&mut self, min: f64, max: f64) -> Vector3<f64> {
+        Vector3::new(
+            self.range(min, max),
+            self.range(min, max),
+            self.range(min, max),
+        )
+    }
+
+    fn matrix(&mut self) -> Matrix4<f64> {
+        let mut e = [0.0; 16];
+        for v in &mut e {
+            *v = self.range(-10.0, 10.0);
+        }
+        Matrix4::from_rows_array(e)
+    }
+
+    // A random combination of translation, rotation and non-uniform scaling.
+    fn affine(&mut self) -> Matrix4<f64> {
+        let mut scale = self.vector(0.1, 4.0);
+        if self.next() < 0.5 {
+            scale.x = -scale.x;
+        }
+        Matrix4::translation(self.vector(-100.0, 100.0)) *
+            Matrix4::rotation(self.vector(-3.2, 3.2)) * Matrix4::scaling(scale)
+    }
+}
+
+fn max_difference(a: Matrix4<f64>, b: Matrix4<f64>) -> f64 {
+    let mut max: f64 = 0.0;
+    for (x, y) in a.to_cols_array().iter().zip(b.to_cols_array().iter()) {
+        max = max.max((x - y).abs());
+    }
+    max
+}
+
+fn assert_near_identity(m: Matrix4<f64>, epsilon: f64) {
+    let difference = max_difference(m, Matrix4::identity());
+    assert!(
+        difference < epsilon,
+        "{:?} differs from identity by {}",
+        m,
+        difference
+    );
+}
+
+#[test]
+fn inverse_of_random_matrices() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..1000 {
+        let m = rng.matrix();
+        // Random matrices are almost never singular, but skip any that are nearly singular since
+        // their inverses are too imprecise to compare.
+        let det = m.determinant();
+        if det.abs() < 1e-3 {
+            continue;
+        }
+        let inverse = m.inverse().unwrap();
+        assert_near_identity(m * inverse, 1e-9);
+        assert_near_identity(inverse * m, 1e-9);
+    }
+}
+
+#[test]
+fn inverse_of_random_affine_matrices() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..1000 {
+        let m = rng.affine();
+        let inverse = m.inverse().unwrap();
+        let affine_inverse = m.affine_inverse().unwrap();
+        assert_near_identity(m * inverse, 1e-9);
+        assert_near_identity(m * affine_inverse, 1e-9);
+        assert!(max_difference(inverse, affine_inverse) < 1e-9);
+    }
+}
+
+#[test]
+fn inverse_in_single_precision() {
+    let m = Matrix4::translation(Vector3::new(1.0f32, -2.0, 3.0)) *
+        Matrix4::rotation(Vector3::new(0.3, -1.2, 2.0)) *
+        Matrix4::scaling(Vector3::new(2.0, 0.5, 3.0));
+    let p = Vector3::new(4.0, 5.0, -6.0);
+    let q = m.inverse().unwrap().transform(m.transform(p));
+    assert!((q - p).magnitude() < 1e-4);
+}
+
+#[test]
+fn determinant_properties() {
+    let mut rng = Rng(0xdead_beef_cafe_f00d);
+    for _ in 0..1000 {
+        let a = rng.matrix();
+        let b = rng.matrix();
+        let (da, db) = (a.determinant(), b.determinant());
+        let scale = da.abs().max(1.0) * db.abs().max(1.0);
+        assert!(((a * b).determinant() - da * db).abs() / scale < 1e-9);
+        assert!((a.transpose().determinant() - da).abs() / da.abs().max(1.0) < 1e-9);
+    }
+
+    assert_eq!(Matrix4::<f64>::identity().determinant(), 1.0);
+    let scale = Matrix4::scaling(Vector3::new(2.0, 3.0, 4.0));
+    assert_eq!(scale.determinant(), 24.0);
+    assert_eq!(Matrix4::translation(Vector3::new(5.0, 6.0, 7.0)).determinant(), 1.0);
+}
+
+#[test]
+fn singular_matrices_have_no_inverse() {
+    let flatten = Matrix4::scaling(Vector3::new(1.0, 0.0, 1.0));
+    assert_eq!(flatten.determinant(), 0.0);
+    assert_eq!(flatten.inverse(), None);
+    assert_eq!(flatten.affine_inverse(), None);
+    assert_eq!(flatten.normal_matrix(), None);
+
+    // Two equal rows
+    let m = Matrix4::new(
+        1.0, 2.0, 3.0, 4.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 1.0, 2.0, 3.0,
+    );
+    assert_eq!(m.inverse(), None);
+
+    let zero = Matrix4::scaling(Vector3::new(0.0f32, 0.0, 0.0));
+    assert_eq!(zero.inverse(), None);
+}
+
+#[test]
+fn normal_matrix_keeps_normals_perpendicular() {
+    let mut rng = Rng(0x0123_4567_89ab_cdef);
+    for _ in 0..1000 {
+        let m = rng.affine();
+        let normal_matrix = m.normal_matrix().unwrap();
+
+        // Two tangents of a random plane and its normal.
+        let t1 = rng.vector(-1.0, 1.0);
+        let t2 = rng.vector(-1.0, 1.0);
+        let n = t1.cross(t2);
+        if n.magnitude() < 1e-3 {
+            continue;
+        }
+
+        let n = normal_matrix.transform_vector(n).normalize();
+        let t1 = m.transform_vector(t1).normalize();
+        let t2 = m.transform_vector(t2).normalize();
+        assert!(n.dot(t1).abs() < 1e-9);
+        assert!(n.dot(t2).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn normal_matrix_ignores_translation() {
+    let rotation = Matrix4::rotation(Vector3::new(0.5, 1.0, 1.5));
+    let m = Matrix4::translation(Vector3::new(10.0, 20.0, 30.0)) * rotation;
+    // For a pure rotation the normal matrix is the rotation itself.
+    assert!(max_difference(m.normal_matrix().unwrap(), rotation) < 1e-12);
+}
+
+#[test]
+fn look_at_moves_eye_to_origin() {
+    let eye = Vector3::new(3.0, 4.0, 5.0);
+    let target = Vector3::new(-1.0, 2.0, 0.5);
+    let view = Matrix4::look_at(eye, target, Vector3::new(0.0, 1.0, 0.0));
+
+    assert_close(view.transform(eye), Vector3::new(0.0, 0.0, 0.0), 1e-9);
+    // The target ends up straight ahead, down the negative z axis.
+    let t = view.transform(target);
+    assert_close(t, Vector3::new(0.0, 0.0, -(target - eye).magnitude()), 1e-9);
+    // Up stays up
+    assert!(view.transform(eye + Vector3::new(0.0, 1.0, 0.0)).y > 0.0);
+    assert!(view.determinant() > 0.0);
+}
+
+#[test]
+fn perspective_maps_near_and_far_planes() {
+    let (near, far): (f64, f64) = (0.5, 50.0);
+    let m = Matrix4::perspective_fov(1.2, 1.5, near, far);
+    assert_close(m.transform(Vector3::new(0.0, 0.0, -near)).z, -1.0, 1e-9);
+    assert_close(m.transform(Vector3::new(0.0, 0.0, -far)).z, 1.0, 1e-9);
+
+    // The top edge of the field of view maps to y = 1.
+    let top = (0.6f64).tan() * 10.0;
+    assert_close(m.transform(Vector3::new(0.0, top, -10.0)).y, 1.0, 1e-9);
+    assert_close(m.transform(Vector3::new(top * 1.5, 0.0, -10.0)).x, 1.0, 1e-9);
+
+    let infinite = Matrix4::perspective_infinite(1.2, 1.5, near);
+    assert_close(infinite.transform(Vector3::new(0.0, 0.0, -near)).z, -1.0, 1e-9);
+    assert_close(infinite.transform(Vector3::new(0.0, 0.0, -1e12)).z, 1.0, 1e-9);
+}
+
+#[test]
+fn reverse_z_maps_near_to_one() {
+    let (near, far): (f64, f64) = (0.5, 50.0);
+    let m = Matrix4::perspective_reverse_z(1.2, 1.5, near, far);
+    assert_close(m.transform(Vector3::new(0.0, 0.0, -near)).z, 1.0, 1e-9);
+    assert_close(m.transform(Vector3::new(0.0, 0.0, -far)).z, 0.0, 1e-9);
+    let mid = m.transform(Vector3::new(0.0, 0.0, -5.0)).z;
+    assert!(mid > 0.0 && mid < 1.0);
+
+    let infinite = Matrix4::perspective_infinite_reverse_z(1.2, 1.5, near);
+    assert_close(infinite.transform(Vector3::new(0.0, 0.0, -near)).z, 1.0, 1e-9);
+    assert_close(infinite.transform(Vector3::new(0.0, 0.0, -1e12)).z, 0.0, 1e-9);
+}
+
+#[test]
+fn orthographic_maps_box_to_unit_cube() {
+    let m = Matrix4::orthographic(-4.0, 2.0, -1.0, 3.0, 1.0, 11.0);
+    let corner = m.transform(Vector3::new(-4.0, -1.0, -1.0));
+    assert_close(corner, Vector3::new(-1.0, -1.0, -1.0), 1e-9);
+    let corner = m.transform(Vector3::new(2.0, 3.0, -11.0));
+    assert_close(corner, Vector3::new(1.0, 1.0, 1.0), 1e-9);
+    let center = m.transform(Vector3::new(-1.0, 1.0, -6.0));
+    assert_close(center, Vector3::new(0.0, 0.0, 0.0), 1e-9);
+}
+
+fn matrix3(m: Matrix4<f64>) -> Matrix3<f64> {
+    Matrix3::from(m)
+}
+
+fn max_difference3(a: Matrix3<f64>, b: Matrix3<f64>) -> f64 {
+    max_difference(Matrix4::from(a), Matrix4::from(b))
+}
+
+#[test]
+fn matrix3_matches_upper_left_of_matrix4() {
+    let mut rng = Rng(0x5851_f42d_4c95_7f2d);
+    for _ in 0..1000 {
+        let (a, b) = (rng.matrix(), rng.matrix());
+        let (a3, b3) = (matrix3(a), matrix3(b));
+
+        // Upper-left blocks only multiply like the full matrices when the rest is zero.
+        let (a, b) = (Matrix4::from(a3), Matrix4::from(b3));
+        assert!(max_difference(Matrix4::from(a3 * b3), a * b) < 1e-9);
+        assert_eq!(a3.transpose(), matrix3(a.transpose()));
+        let det = a.determinant();
+        assert!((a3.determinant() - det).abs() / det.abs().max(1.0) < 1e-9);
+
+        if a3.determinant().abs() < 1e-3 {
+            continue;
+        }
+        let inverse = a3.inverse().unwrap();
+        assert!(max_difference3(a3 * inverse, Matrix3::identity()) < 1e-9);
+        assert!(max_difference(Matrix4::from(inverse), a.inverse().unwrap()) < 1e-9);
+    }
+}
+
+#[test]
+fn matrix3_transforms_2d_points() {
+    let m = Matrix3::translation(Vector2::new(3.0, -1.0)) *
+        Matrix3::rotation(std::f64::consts::PI / 2.0) *
+        Matrix3::scaling(Vector2::new(2.0, 0.5));
+    let p = m.transform(Vector2::new(1.0, 4.0));
+    assert!((p - Vector2::new(1.0, 1.0)).magnitude() < 1e-9, "{:?}", p);
+    let v = m.transform_vector(Vector2::new(1.0, 0.0));
+    assert!((v - Vector2::new(0.0, 2.0)).magnitude() < 1e-9, "{:?}", v);
+
+    let back = m.inverse().unwrap().transform(p);
+    assert!((back - Vector2::new(1.0, 4.0)).magnitude() < 1e-9);
+    assert_close(m.determinant(), 1.0, 1e-9);
+}
+
+#[test]
+fn matrix3_normal_matrix_matches_matrix4() {
+    let mut rng = Rng(0x1405_7b7e_f767_814f);
+    for _ in 0..100 {
+        let m = rng.affine();
+        let normal = matrix3(m).inverse().unwrap().transpose();
+        assert!(max_difference(Matrix4::from(normal), m.normal_matrix().unwrap()) < 1e-9);
+    }
+}
+
+#[test]
+fn matrix2_operations() {
+    let m = Matrix2::new(4.0, 7.0, 2.0, 6.0);
+    assert_eq!(m.determinant(), 10.0);
+    assert_eq!(m.transpose(), Matrix2::new(4.0, 2.0, 7.0, 6.0));
+    let inverse = m.inverse().unwrap();
+    let i = m * inverse;
+    assert!(max_difference(Matrix4::from(i), Matrix4::identity()) < 1e-12);
+    assert_eq!(Matrix2::new(1.0, 2.0, 2.0, 4.0).inverse(), None);
+
+    let mut r = Matrix2::rotation(0.5);
+    r *= Matrix2::rotation(0.25);
+    let v = r * Vector2::new(1.0, 0.0);
+    assert!((v - Vector2::new(0.75f64.cos(), 0.75f64.sin())).magnitude() < 1e-12);
+    assert_eq!(
+        Matrix2::scaling(Vector2::new(2.0, 3.0)) * Vector2::new(1.0, 1.0),
+        Vector2::new(2.0, 3.0)
+    );
+}
+
+#[test]
+fn block_conversions() {
+    let m = Matrix4::new(
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+    assert_eq!(
+        Matrix3::from(m),
+        Matrix3::new(1.0, 2.0, 3.0, 5.0, 6.0, 7.0, 9.0, 10.0, 11.0)
+    );
+    assert_eq!(Matrix2::from(m), Matrix2::new(1.0, 2.0, 5.0, 6.0));
+    assert_eq!(Matrix2::from(Matrix3::from(m)), Matrix2::from(m));
+
+    assert_eq!(
+        Matrix4::from(Matrix2::new(1.0, 2.0, 3.0, 4.0)),
+        Matrix4::new(
+            1.0, 2.0, 0.0, 0.0, 3.0, 4.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        )
+    );
+    assert_eq!(
+        Matrix3::from(Matrix2::new(1.0, 2.0, 3.0, 4.0)),
+        Matrix3::new(1.0, 2.0, 0.0, 3.0, 4.0, 0.0, 0.0, 0.0, 1.0)
+    );
+    assert_eq!(Matrix4::from(Matrix3::<f32>::identity()), Matrix4::identity());
+}
+
+#[test]
+fn element_access() {
+    let mut m = Matrix4::new(
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    );
+    assert_eq!(m[(0, 3)], 4.0);
+    assert_eq!(m[(3, 0)], 13.0);
+    assert_eq!(m.row(1), Vector4::new(5.0, 6.0, 7.0, 8.0));
+    assert_eq!(m.col(1), Vector4::new(2.0, 6.0, 10.0, 14.0));
+    for i in 0..4 {
+        let mut unit = Vector4::splat(0.0);
+        unit[i] = 1.0;
+        assert_eq!(m.col(i), m * unit);
+    }
+
+    m[(2, 1)] = -1.0;
+    assert_eq!(m.row(2), Vector4::new(9.0, -1.0, 11.0, 12.0));
+
+    // The translation lives in the last column.
+    let t = Matrix4::translation(Vector3::new(1.0, 2.0, 3.0));
+    assert_eq!(t.col(3), Vector4::new(1.0, 2.0, 3.0, 1.0));
+}
+
+#[test]
+#[should_panic(expected = "out of range")]
+fn element_access_out_of_range() {
+    let _ = Matrix4::<f32>::identity()[(4, 0)];
+}
+
+#[test]
+fn row_and_column_major_conversions() {
+    let rows = [
+        1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+    ];
+    let m = Matrix4::from_rows_array(rows);
+    assert_eq!(m.to_rows_array(), rows);
+    assert_eq!(
+        m.to_cols_array(),
+        [
+            1.0, 5.0, 9.0, 13.0, 2.0, 6.0, 10.0, 14.0, 3.0, 7.0, 11.0, 15.0, 4.0, 8.0, 12.0, 16.0,
+        ]
+    );
+    assert_eq!(Matrix4::from_cols_array(m.to_cols_array()), m);
+    assert_eq!(Matrix4::from_rows([m.row(0), m.row(1), m.row(2), m.row(3)]), m);
+    assert_eq!(Matrix4::from_cols([m.col(0), m.col(1), m.col(2), m.col(3)]), m);
+}
+
+#[test]
+fn display() {
+    let m = Matrix4::translation(Vector3::new(1.5, -20.0, 3.0));
+    assert_eq!(
+        format!("{}", m),
+        "[  1   0   0 1.5]\n[  0   1   0 -20]\n[  0   0   1   3]\n[  0   0   0   1]"
+    );
+    assert_eq!(
+        format!("{:.1}", Matrix4::<f32>::identity()),
+        "[1.0 0.0 0.0 0.0]\n[0.0 1.0 0.0 0.0]\n[0.0 0.0 1.0 0.0]\n[0.0 0.0 0.0 1.0]"
+    );
+}
@@ -0,0 +1,454 @@
+extern crate rrasterizer;
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+use rrasterizer::vec2::Vector2;
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::mesh_format::MeshError;
+use rrasterizer::mesh_format::obj::{load_obj, parse_obj};
+use rrasterizer::mesh_format::mtl::parse_mtl;
+use rrasterizer::mesh_format::gltf::{load_gltf, parse_gltf};
+use rrasterizer::mesh_format::{stl, ply};
+use rrasterizer::mesh_format::ply::PlyFormat;
+use rrasterizer::mesh::Mesh;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rrasterizer-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn malformed<T>(result: Result<T, MeshError>) -> String {
+    match result {
+        Err(MeshError::Malformed(message)) => message,
+        Err(other) => panic!("expected a malformed mesh, got {:?}", other),
+        Ok(_) => panic!("expected a malformed mesh"),
+    }
+}
+
+fn parse_error_line(result: Result<(), MeshError>) -> usize {
+    match result {
+        Err(MeshError::Parse { line, .. }) => line,
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+const OBJ: &str = "
+# A red quad and a blue triangle sharing its corners.
+mtllib quad.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0 1
+v 0 1 0 0.5 0.5 0.5
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+
+o quad
+usemtl red
+f 1/1/1 2/2/1 3/3/1 4/4/1
+g second part
+usemtl blue
+f -4 -3 \\
+  -2
+";
+
+const MTL: &str = "
+newmtl red
+Kd 1 0 0
+d 0.5
+map_Kd textures/red.png
+
+newmtl blue
+Kd 0 0 1
+Ns 32
+illum 1
+";
+
+#[test]
+fn obj_objects() {
+    let model = parse_obj(OBJ).unwrap();
+    assert_eq!(model.material_libraries, vec!["quad.mtl"]);
+    assert!(model.materials.is_empty());
+    assert_eq!(model.objects.len(), 2);
+
+    let quad = &model.objects[0];
+    assert_eq!((quad.object.as_str(), quad.group.as_str()), ("quad", ""));
+    assert_eq!(quad.material.as_deref(), Some("red"));
+    assert_eq!(quad.mesh.vertex_count(), 4);
+    assert_eq!(quad.mesh.triangle_count(), 2);
+    assert_eq!(quad.mesh.tex_coords[2], Vector2::new(1.0, 1.0));
+    assert_eq!(quad.mesh.normals, vec![Vector3::new(0.0, 0.0, 1.0); 4]);
+    // Only the last vertex has a color, the others are back filled with white.
+    assert_eq!(quad.mesh.colors[0], Vector4::new(1.0, 1.0, 1.0, 1.0));
+    assert_eq!(quad.mesh.colors[3], Vector4::new(0.5, 0.5, 0.5, 1.0));
+
+    let triangle = &model.objects[1];
+    assert_eq!((triangle.object.as_str(), triangle.group.as_str()), ("quad", "second part"));
+    assert_eq!(triangle.mesh.positions[0], Vector3::new(0.0, 0.0, 0.0));
+    assert_eq!(triangle.mesh.indices, vec![0, 1, 2]);
+    assert!(triangle.mesh.normals.is_empty());
+}
+
+#[test]
+fn mtl_materials() {
+    let materials = parse_mtl(MTL).unwrap();
+    assert_eq!(materials.len(), 2);
+    assert_eq!(materials[0].name, "red");
+    assert_eq!(materials[0].diffuse, Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(materials[0].dissolve, 0.5);
+    assert_eq!(materials[0].diffuse_map, Some(PathBuf::from("textures/red.png")));
+    assert_eq!(materials[1].shininess, 32.0);
+    assert_eq!(materials[1].illumination_model, 1);
+    assert_eq!(materials[1].dissolve, 1.0);
+}
+
+#[test]
+fn obj_with_material_library() {
+    let dir = temp_dir("obj");
+    fs::write(dir.join("quad.obj"), OBJ).unwrap();
+    fs::write(dir.join("quad.mtl"), MTL).unwrap();
+    let model = load_obj(dir.join("quad.obj"));
+    fs::remove_file(dir.join("quad.mtl")).unwrap();
+    let missing = load_obj(dir.join("quad.obj"));
+    fs::remove_dir_all(&dir).unwrap();
+
+    let model = model.unwrap();
+    assert_eq!(model.material("red").unwrap().diffuse_map, Some(dir.join("textures/red.png")));
+    let triangles = model.triangles();
+    assert_eq!(triangles.len(), 3);
+    // The quad has vertex colors, the triangle takes the diffuse color of its material.
+    let quad_colors: Vec<_> = triangles[..2]
+        .iter()
+        .flat_map(|t| vec![t.a.color, t.b.color, t.c.color])
+        .collect();
+    assert!(quad_colors.contains(&Vector4::new(0.5, 0.5, 0.5, 1.0)));
+    assert!(quad_colors.iter().all(|c| c.w == 1.0));
+    assert_eq!(triangles[2].a.color, Vector4::new(0.0, 0.0, 1.0, 1.0));
+    match missing {
+        Err(MeshError::Io(_)) => {}
+        other => panic!("expected an i/o error, got {:?}", other),
+    }
+}
+
+#[test]
+fn obj_errors() {
+    let error = |source: &str| parse_error_line(parse_obj(source).map(|_| ()));
+    assert_eq!(error("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n"), 4);
+    assert_eq!(error("v 0 0 0\nv 1 0 0\nv 0 1 0\n\nf 1 2 0\n"), 5);
+    assert_eq!(error("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1/1 2/1 3/1\n"), 4);
+    assert_eq!(error("v 0 0\n"), 1);
+    assert_eq!(error("v 0 0 0\nf 1 1\n"), 2);
+    assert_eq!(error("v 0 x 0\n"), 1);
+    assert_eq!(parse_error_line(parse_mtl("Kd 1 1 1\n").map(|_| ())), 1);
+    assert_eq!(parse_error_line(parse_mtl("newmtl a\nillum x\n").map(|_| ())), 2);
+}
+
+// A triangle's positions, its indices, and a sparse normal for its second vertex.
+fn gltf_buffer() -> Vec<u8> {
+    let mut data = Vec::new();
+    for &v in &[0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        data.extend_from_slice(&v.to_le_bytes());
+    }
+    data.extend_from_slice(&[0, 0, 1, 0, 2, 0, 0, 0]);
+    data.extend_from_slice(&[1, 0, 0, 0]);
+    for &v in &[0.0f32, 0.0, 1.0] {
+        data.extend_from_slice(&v.to_le_bytes());
+    }
+    data
+}
+
+// `gltf_buffer` encoded as base64.
+const GLTF_BASE64: &str = concat!(
+    "AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8",
+    "AAAAAAAABAAIAAAABAAAAAAAAAAAAAAAAAIA/"
+);
+
+// The normals have no buffer view, so they are zero apart from the sparse one.
+const GLTF: &str = r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [
+        { "name": "parent", "translation": [0, 0, -2], "children": [1] },
+        { "name": "triangle", "mesh": 0, "scale": [2, 2, 2] }
+    ],
+    "meshes": [{
+        "primitives": [{
+            "attributes": { "POSITION": 0, "NORMAL": 2 },
+            "indices": 1,
+            "material": 0
+        }]
+    }],
+    "materials": [{ "pbrMetallicRoughness": { "baseColorFactor": [1, 0, 0, 1] } }],
+    "accessors": [
+        { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" },
+        { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" },
+        {
+            "componentType": 5126, "count": 3, "type": "VEC3",
+            "sparse": {
+                "count": 1,
+                "indices": { "bufferView": 2, "componentType": 5121 },
+                "values": { "bufferView": 3 }
+            }
+        }
+    ],
+    "bufferViews": [
+        { "buffer": 0, "byteLength": 36 },
+        { "buffer": 0, "byteOffset": 36, "byteLength": 6 },
+        { "buffer": 0, "byteOffset": 44, "byteLength": 1 },
+        { "buffer": 0, "byteOffset": 48, "byteLength": 12 }
+    ],
+    "buffers": [{ URI "byteLength": 60 }]
+}"#;
+
+fn gltf_json(uri: &str) -> String {
+    GLTF.replace("URI", uri)
+}
+
+fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+    let chunk = |data: &[u8], kind: &[u8], padding: u8| {
+        let mut chunk = Vec::new();
+        let length = (data.len() + 3) & !3;
+        chunk.extend_from_slice(&(length as u32).to_le_bytes());
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        chunk.resize(8 + length, padding);
+        chunk
+    };
+    let chunks = [chunk(json.as_bytes(), b"JSON", b' '), chunk(bin, b"BIN\0", 0)].concat();
+    let mut data = b"glTF".to_vec();
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&(12 + chunks.len() as u32).to_le_bytes());
+    data.extend_from_slice(&chunks);
+    data
+}
+
+#[test]
+fn gltf_documents() {
+    let uri = format!("\"uri\": \"data:application/octet-stream;base64,{}\",", GLTF_BASE64);
+    let embedded = parse_gltf(gltf_json(&uri).as_bytes(), None).unwrap();
+    let binary = parse_gltf(&glb(&gltf_json(""), &gltf_buffer()), None).unwrap();
+    assert_eq!(embedded, binary);
+
+    let primitive = &embedded.meshes[0].primitives[0];
+    assert_eq!(primitive.mesh.positions[1], Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(primitive.mesh.indices, vec![0, 1, 2]);
+    assert_eq!(primitive.mesh.normals[0], Vector3::new(0.0, 0.0, 0.0));
+    assert_eq!(primitive.mesh.normals[1], Vector3::new(0.0, 0.0, 1.0));
+    assert_eq!(primitive.material, Some(0));
+    assert_eq!(embedded.nodes[1].name.as_deref(), Some("triangle"));
+
+    // The triangle is scaled by its node and translated by the parent.
+    let triangles = embedded.triangles();
+    assert_eq!(triangles.len(), 1);
+    assert_eq!(triangles[0].b.position, Vector3::new(2.0, 0.0, -2.0));
+    assert_eq!(triangles[0].a.color, Vector4::new(1.0, 0.0, 0.0, 1.0));
+}
+
+#[test]
+fn gltf_external_buffers() {
+    let dir = temp_dir("gltf");
+    fs::write(dir.join("triangle.bin"), gltf_buffer()).unwrap();
+    fs::write(dir.join("triangle.gltf"), gltf_json("\"uri\": \"triangle.bin\",")).unwrap();
+    fs::write(dir.join("triangle.glb"), glb(&gltf_json(""), &gltf_buffer())).unwrap();
+    let external = load_gltf(dir.join("triangle.gltf"));
+    let binary = load_gltf(dir.join("triangle.glb"));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(external.unwrap(), binary.unwrap());
+    // Without a directory external buffers cannot be found.
+    malformed(parse_gltf(gltf_json("\"uri\": \"triangle.bin\",").as_bytes(), None));
+}
+
+#[test]
+fn gltf_errors() {
+    let parse = |json: String| parse_gltf(&glb(&json, &gltf_buffer()), None);
+    malformed(parse(gltf_json("").replace("\"indices\": 1", "\"indices\": 7")));
+    malformed(parse(gltf_json("").replace("\"byteLength\": 36", "\"byteLength\": 70")));
+    malformed(parse(gltf_json("").replace("\"byteLength\": 60", "\"byteLength\": 64")));
+    malformed(parse(gltf_json("").replace("\"version\": \"2.0\"", "\"x\": 1")));
+    malformed(parse_gltf(b"glTF\x02\x00\x00\x00", None));
+    match parse(gltf_json("").replace("2.0", "1.0")) {
+        Err(MeshError::Unsupported(_)) => {}
+        other => panic!("expected an unsupported mesh, got {:?}", other),
+    }
+
+    // Accessor counts far beyond the data, with and without a buffer view.
+    let huge = |accessor: &str| {
+        let json = gltf_json("").replace(accessor, &accessor.replace(": 3", ": 4000000000"));
+        malformed(parse(json))
+    };
+    huge("\"bufferView\": 0, \"componentType\": 5126, \"count\": 3");
+    let message = huge("\"count\": 3, \"type\": \"VEC3\",");
+    assert!(message.contains("no buffer view"), "{}", message);
+}
+
+// A colored, textured unit square facing the z axis.
+fn square() -> Mesh {
+    let mut mesh = Mesh::new();
+    mesh.positions = vec![
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(1.0, 1.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    ];
+    mesh.normals = vec![Vector3::new(0.0, 0.0, 1.0); 4];
+    mesh.tex_coords = mesh.positions.iter().map(|p| Vector2::new(p.x, p.y)).collect();
+    mesh.colors = vec![
+        Vector4::new(1.0, 0.0, 0.0, 1.0),
+        Vector4::new(0.0, 1.0, 0.0, 1.0),
+        Vector4::new(0.0, 0.0, 1.0, 1.0),
+        Vector4::new(1.0, 1.0, 1.0, 0.0),
+    ];
+    mesh.indices = vec![0, 1, 2, 0, 2, 3];
+    mesh
+}
+
+const ASCII_STL: &str = "solid square
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid square
+";
+
+#[test]
+fn stl_meshes() {
+    // Shared corners are welded into single vertices.
+    let ascii = stl::decode(ASCII_STL.as_bytes()).unwrap();
+    assert_eq!(ascii.positions, square().positions);
+    assert_eq!(ascii.indices, square().indices);
+    assert!(ascii.normals.is_empty());
+
+    let mut binary = Vec::new();
+    stl::encode(&mut binary, &square()).unwrap();
+    assert_eq!(binary.len(), 84 + 2 * 50);
+    assert_eq!(stl::decode(&binary).unwrap(), ascii);
+    // Binary files may start with "solid" too.
+    binary[..5].copy_from_slice(b"solid");
+    assert_eq!(stl::decode(&binary).unwrap(), ascii);
+
+    let mut text = Vec::new();
+    stl::encode_ascii(&mut text, &square(), "square").unwrap();
+    assert_eq!(stl::decode(&text).unwrap(), ascii);
+}
+
+#[test]
+fn stl_errors() {
+    let mut binary = Vec::new();
+    stl::encode(&mut binary, &square()).unwrap();
+    binary[0] = b'x';
+    for len in 0..binary.len() {
+        malformed(stl::decode(&binary[..len]));
+    }
+    // A count of 2^32 - 1 triangles in a file with two.
+    binary[80..84].copy_from_slice(&[0xff; 4]);
+    malformed(stl::decode(&binary));
+
+    let error = |source: &str| parse_error_line(stl::decode(source.as_bytes()).map(|_| ()));
+    assert_eq!(error(&ASCII_STL.replace("vertex 1 0 0", "vertex 1 0")), 5);
+    assert_eq!(error(&ASCII_STL.replace("      vertex 0 1 0\n", "")), 13);
+}
+
+#[test]
+fn ply_round_trips() {
+    for &format in &[PlyFormat::Ascii, PlyFormat::BinaryLittleEndian, PlyFormat::BinaryBigEndian] {
+        let mut data = Vec::new();
+        ply::encode(&mut data, &square(), format).unwrap();
+        assert_eq!(ply::decode(&data).unwrap(), square(), "{:?}", format);
+    }
+}
+
+#[test]
+fn ply_files() {
+    let source = "ply
+format ascii 1.0
+comment a quad with a skipped element and extra properties
+element vertex 4
+property double x
+property double y
+property double z
+property ushort red
+property ushort green
+property ushort blue
+property float confidence
+element edge 1
+property int vertex1
+property int vertex2
+element face 1
+property uchar intensity
+property list uint short vertex_index
+end_header
+0 0 0 65535 0 0 0.5
+1 0 0 0 65535 0 0.5
+1 1 0 0 0 65535 0.5
+0 1 0 0 0 0 0.5
+0 1
+7 4 0 1 2 3
+";
+    let dir = temp_dir("ply");
+    let path = dir.join("quad.ply");
+    fs::File::create(&path).unwrap().write_all(source.as_bytes()).unwrap();
+    let mesh = ply::load_ply(&path);
+    fs::remove_dir_all(&dir).unwrap();
+
+    let mesh = mesh.unwrap();
+    assert_eq!(mesh.positions, square().positions);
+    assert_eq!(mesh.triangle_count(), 2);
+    assert_eq!(mesh.colors[1], Vector4::new(0.0, 1.0, 0.0, 1.0));
+    assert_eq!(mesh.colors[3], Vector4::new(0.0, 0.0, 0.0, 1.0));
+    assert!(mesh.normals.is_empty() && mesh.tex_coords.is_empty());
+}
+
+#[test]
+fn ply_errors() {
+    let header = |format: &str, elements: &str| {
+        format!("ply\nformat {} 1.0\n{}end_header\n", format, elements).into_bytes()
+    };
+    let vertices = "element vertex 3\nproperty float x\nproperty float y\nproperty float z\n";
+    let faces = |count: &str| {
+        format!("element face {}\nproperty list uchar int vertex_indices\n", count)
+    };
+
+    let mut ascii = header("ascii", &format!("{}{}", vertices, faces("1")));
+    ascii.extend_from_slice(b"0 0 0\n1 0 0\n0 1 0\n3 0 1 3\n");
+    assert!(malformed(ply::decode(&ascii)).contains("vertex 3"));
+    let truncated = &ascii[..ascii.len() - 4];
+    malformed(ply::decode(truncated));
+    assert_eq!(parse_error_line(ply::decode(b"ply\nformat text 1.0\n").map(|_| ())), 2);
+    malformed(ply::decode(b"ply\nformat ascii 1.0\nelement vertex 1\n"));
+
+    // Header counts far beyond the data.
+    let elements = format!("{}{}", vertices, faces("100000000000000"));
+    let mut huge = header("binary_little_endian", &elements);
+    for &v in &[0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        huge.extend_from_slice(&v.to_le_bytes());
+    }
+    huge.push(3);
+    huge.extend_from_slice(&[0; 12]);
+    malformed(ply::decode(&huge));
+    let list = "element face 1\nproperty list uint int vertex_indices\n";
+    let mut huge_list = header("binary_little_endian", list);
+    huge_list.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+    malformed(ply::decode(&huge_list));
+    // Elements without properties take no data, however many there are.
+    let empty = header("ascii", &format!("element nothing 100000000000000\n{}", vertices));
+    let mesh = ply::decode(&[&empty[..], b"0 0 0 1 0 0 0 1 0\n"].concat()).unwrap();
+    assert_eq!(mesh.positions.len(), 3);
+}
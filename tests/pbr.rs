@@ -0,0 +1,323 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod approx;
+    pub mod mesh;
+    pub mod render;
+}
+
+use std::f32;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use rrasterizer::vec2::Vector2;
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::color::Color;
+use rrasterizer::image::Image;
+use rrasterizer::image_format::save_image;
+use rrasterizer::renderer::Renderer;
+use rrasterizer::lighting::{Light, LightKind, PlacedLight, ShadingMode};
+use rrasterizer::pbr::{PbrMaterial, Surface, DIELECTRIC_F0, brdf, distribution_ggx,
+                       fresnel_schlick, visibility_smith_ggx};
+use rrasterizer::texture::{Texture, srgb_to_linear};
+use rrasterizer::scene_format::{SceneError, parse_scene};
+
+use common::approx::assert_close;
+use common::mesh::square;
+
+fn surface(base_color: Vector3<f32>, metallic: f32, roughness: f32) -> Surface {
+    Surface {
+        base_color: base_color.extend(1.0),
+        metallic,
+        roughness,
+        emissive: Vector3::splat(0.0),
+        occlusion: 1.0,
+    }
+}
+
+fn direction(theta: f32, phi: f32) -> Vector3<f32> {
+    Vector3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos())
+}
+
+/// Integrates `f` over the hemisphere around the z axis with the midpoint rule.
+fn integrate_hemisphere<F>(steps: usize, f: F) -> f32
+where
+    F: Fn(Vector3<f32>) -> f32,
+{
+    let d_theta = f32::consts::PI / 2.0 / steps as f32;
+    let d_phi = 2.0 * f32::consts::PI / (4 * steps) as f32;
+    let mut sum = 0.0;
+    for i in 0..steps {
+        let theta = (i as f32 + 0.5) * d_theta;
+        for j in 0..4 * steps {
+            let phi = (j as f32 + 0.5) * d_phi;
+            sum += f(direction(theta, phi)) * theta.sin() * d_theta * d_phi;
+        }
+    }
+    sum
+}
+
+#[test]
+fn ggx_distribution_reference_values() {
+    assert_close(distribution_ggx(1.0, 1.0), 1.0 / f32::consts::PI, 1e-6);
+    assert_close(distribution_ggx(1.0, 0.5), 16.0 / f32::consts::PI, 1e-4);
+    // With alpha = 0.25, at 45 degrees: 0.0625 / (pi * (0.5 * -0.9375 + 1)^2).
+    assert_close(distribution_ggx(0.5f32.sqrt(), 0.5), 0.070_490, 1e-5);
+}
+
+#[test]
+fn ggx_distribution_is_normalized() {
+    // The projected area of the microfacets equals the macroscopic surface.
+    for &roughness in &[0.3, 0.5, 0.8, 1.0] {
+        let area = integrate_hemisphere(200, |h| distribution_ggx(h.z, roughness) * h.z);
+        assert_close(area, 1.0, 1e-2);
+    }
+}
+
+#[test]
+fn smith_visibility_reference_values() {
+    // Head on there is no shadowing, leaving the 1 / (4 n·l n·v) normalization.
+    for &roughness in &[0.0, 0.5, 1.0] {
+        assert_close(visibility_smith_ggx(1.0, 1.0, roughness), 0.25, 1e-6);
+    }
+    // alpha = 1 makes the square roots 1, giving 0.5 / (n·l + n·v).
+    assert_close(visibility_smith_ggx(0.5, 0.25, 1.0), 2.0 / 3.0, 1e-6);
+    // Grazing angles shadow rough surfaces more than smooth ones.
+    assert!(visibility_smith_ggx(0.1, 0.1, 1.0) < visibility_smith_ggx(0.1, 0.1, 0.2));
+}
+
+#[test]
+fn schlick_fresnel_reference_values() {
+    let f0 = Vector3::new(0.04, 0.5, 1.0);
+    assert_eq!(fresnel_schlick(1.0, f0), f0);
+    assert_eq!(fresnel_schlick(0.0, f0), Vector3::splat(1.0));
+    let f = fresnel_schlick(0.5, Vector3::splat(DIELECTRIC_F0));
+    assert_close(f.x, 0.04 + 0.96 / 32.0, 1e-6);
+}
+
+#[test]
+fn brdf_reference_values() {
+    let n = Vector3::new(0.0, 0.0, 1.0);
+
+    // A rough white dielectric seen and lit head on: (1 - F) / pi diffuse plus F D V specular
+    // with F = 0.04, D = 1 / pi and V = 1 / 4.
+    let white = surface(Vector3::splat(1.0), 0.0, 1.0);
+    assert_close(brdf(&white, n, n, n).x, 0.97 / f32::consts::PI, 1e-6);
+
+    // A metal only reflects specularly, tinted by its base color: F D V with D = 16 / pi.
+    let gold = surface(Vector3::new(1.0, 0.75, 0.25), 1.0, 0.5);
+    let f = brdf(&gold, n, n, n);
+    let peak = 4.0 / f32::consts::PI;
+    assert_close(f.x, peak, 1e-4);
+    assert_close(f.y, 0.75 * peak, 1e-4);
+    assert_close(f.z, 0.25 * peak, 1e-4);
+
+    // Light from below the surface is not reflected.
+    assert_eq!(brdf(&white, n, n, -n), Vector3::splat(0.0));
+}
+
+#[test]
+fn brdf_does_not_create_energy() {
+    // The white furnace test: a white surface under uniform light reflects at most all of it.
+    // Single scattering GGX loses some energy at high roughness, but not most of it.
+    for &(metallic, roughness) in &[(1.0, 0.3), (1.0, 0.7), (0.0, 0.5), (0.0, 1.0)] {
+        let white = surface(Vector3::splat(1.0), metallic, roughness);
+        for &view_angle in &[0.0f32, 0.5, 1.0] {
+            let v = direction(view_angle, 0.0);
+            let n = Vector3::new(0.0, 0.0, 1.0);
+            let albedo = integrate_hemisphere(200, |l| brdf(&white, n, v, l).x * l.z);
+            assert!(albedo <= 1.01, "{} at {:?}", albedo, (metallic, roughness, view_angle));
+            assert!(albedo > 0.6, "{} at {:?}", albedo, (metallic, roughness, view_angle));
+        }
+    }
+}
+
+#[test]
+fn texture_sampling() {
+    let image = Image::from_pixels(
+        2,
+        2,
+        vec![
+            Vector4::new(0.0, 0.0, 0.0, 1.0),
+            Vector4::new(1.0, 0.0, 0.0, 1.0),
+            Vector4::new(0.0, 1.0, 0.0, 1.0),
+            Vector4::new(1.0, 1.0, 0.0, 1.0),
+        ],
+    );
+    let texture = Texture::new(image);
+    // Texel centers are exact, the bottom row comes first.
+    assert_eq!(texture.sample(Vector2::new(0.75, 0.25)), Vector4::new(1.0, 0.0, 0.0, 1.0));
+    assert_eq!(texture.sample(Vector2::new(0.25, 0.75)), Vector4::new(0.0, 1.0, 0.0, 1.0));
+    assert_eq!(texture.sample(Vector2::new(0.5, 0.5)), Vector4::new(0.5, 0.5, 0.0, 1.0));
+    // Coordinates wrap around.
+    assert_eq!(texture.sample(Vector2::new(1.75, -0.75)), Vector4::new(1.0, 0.0, 0.0, 1.0));
+
+    let srgb = Texture::from_colors(&Image::new(1, 1, Color(188, 0, 255, 128)), true);
+    let texel = srgb.sample(Vector2::splat(0.0));
+    assert_close(texel.x, 0.5, 5e-3);
+    assert_eq!(texel.y, 0.0);
+    assert_eq!(texel.z, 1.0);
+    assert_close(texel.w, 128.0 / 255.0, 1e-6);
+    assert_close(srgb_to_linear(0.02), 0.02 / 12.92, 1e-7);
+
+    assert!(Texture::try_new(Image::new(0, 4, Vector4::splat(0.0))).is_none());
+    assert!(Texture::try_from_colors(&Image::new(4, 0, Color(0, 0, 0, 0)), false).is_none());
+}
+
+#[test]
+fn material_inputs_combine_factors_and_textures() {
+    let metallic_roughness = Texture::new(Image::new(1, 1, Vector4::new(0.0, 0.5, 0.25, 1.0)));
+    let occlusion = Texture::new(Image::new(1, 1, Vector4::new(0.2, 0.0, 0.0, 1.0)));
+    let material = PbrMaterial {
+        base_color: Vector4::new(1.0, 0.5, 1.0, 1.0),
+        metallic: 1.0,
+        roughness: 0.8,
+        metallic_roughness_texture: Some(Rc::new(metallic_roughness)),
+        emissive: Vector3::new(2.0, 0.0, 0.0),
+        occlusion_texture: Some(Rc::new(occlusion)),
+        occlusion_strength: 0.5,
+        ..PbrMaterial::default()
+    };
+    let s = material.surface(Vector4::new(0.5, 1.0, 1.0, 1.0), Vector2::splat(0.5));
+    assert_eq!(s.base_color, Vector4::new(0.5, 0.5, 1.0, 1.0));
+    assert_eq!(s.metallic, 0.25);
+    assert_eq!(s.roughness, 0.4);
+    assert_eq!(s.emissive, Vector3::new(2.0, 0.0, 0.0));
+    assert_close(s.occlusion, 0.6, 1e-6);
+}
+
+fn renderer(material: PbrMaterial, lights: Vec<PlacedLight>) -> Renderer {
+    let mut renderer = common::render::renderer();
+    renderer.set_shading(ShadingMode::Pbr);
+    renderer.set_lights(lights);
+    renderer.set_pbr_material(material);
+    renderer
+}
+
+#[test]
+fn rendering_with_textures_and_lights() {
+    // Red on the left half and green on the right.
+    let red = Vector4::new(1.0, 0.0, 0.0, 1.0);
+    let green = Vector4::new(0.0, 1.0, 0.0, 1.0);
+    let texture = Texture::new(Image::from_pixels(4, 1, vec![red, red, green, green]));
+    // Rough enough for the white specular highlight to be negligible.
+    let material = PbrMaterial {
+        base_color_texture: Some(Rc::new(texture)),
+        roughness: 1.0,
+        ..PbrMaterial::default()
+    };
+    let triangles = square(-3.0, Vector4::splat(1.0), Vector3::new(0.0, 0.0, 1.0));
+
+    // Without any light only emission would show.
+    let mut r = renderer(material.clone(), Vec::new());
+    r.render(Matrix4::identity(), &triangles);
+    assert_eq!(r.get_pixel(20, 32), Color(0, 0, 0, 255));
+
+    let light = PlacedLight {
+        light: Light {
+            kind: LightKind::Directional,
+            color: Vector3::splat(1.0),
+            intensity: f32::consts::PI,
+        },
+        position: Vector3::splat(0.0),
+        direction: Vector3::new(0.0, 0.0, -1.0),
+        shadow: None,
+    };
+    let mut r = renderer(material, vec![light]);
+    r.render(Matrix4::identity(), &triangles);
+    let Color(red, green, blue, _) = r.get_pixel(20, 32);
+    assert!(red > 240 && green < 5 && blue < 5, "{:?}", r.get_pixel(20, 32));
+    let Color(red, green, blue, _) = r.get_pixel(44, 32);
+    assert!(red < 5 && green > 240 && blue < 5, "{:?}", r.get_pixel(44, 32));
+
+    let glowing = PbrMaterial {
+        emissive: Vector3::new(0.0, 0.0, 1.0),
+        ..PbrMaterial::default()
+    };
+    let mut r = renderer(glowing, Vec::new());
+    r.render(Matrix4::identity(), &triangles);
+    assert_eq!(r.get_pixel(32, 32), Color(0, 0, 255, 255));
+}
+
+#[test]
+fn scene_materials() {
+    let scene = parse_scene(
+        r#"
+        [render]
+        shading = "pbr"
+
+        [[material]]
+        name = "metal"
+        color = [0.9, 0.6, 0.2]
+        metallic = 1
+        roughness = 0.3
+        emissive = [0.1, 0, 0]
+        base_color_texture = "textures/metal.png"
+        "#,
+        None,
+    ).unwrap();
+    assert_eq!(scene.render.shading, ShadingMode::Pbr);
+    let metal = &scene.materials[0];
+    assert_eq!(metal.pbr.metallic, 1.0);
+    assert_eq!(metal.pbr.roughness, 0.3);
+    assert_eq!(metal.pbr.emissive, Vector3::new(0.1, 0.0, 0.0));
+    assert_eq!(metal.textures.base_color, Some("textures/metal.png".into()));
+    assert_eq!(metal.textures.occlusion, None);
+
+    match parse_scene("[[material]]\nname = \"a\"\nroughness = 2\n", None) {
+        Err(SceneError::Invalid(message)) => assert!(message.contains("roughness"), "{}", message),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn scene_textures_are_loaded() {
+    let dir = std::env::temp_dir().join(format!("rrasterizer-pbr-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    save_image(dir.join("albedo.png"), &Image::new(2, 2, Color(255, 0, 0, 255))).unwrap();
+    let source = r#"
+        [[material]]
+        name = "textured"
+        base_color_texture = "albedo.png"
+        occlusion_texture = "albedo.png"
+
+        [[node]]
+        name = "camera"
+        camera = {}
+
+        [[node]]
+        name = "a"
+        primitive = "cube"
+        material = "textured"
+        "#;
+    let loaded = parse_scene(source, Some(&dir)).unwrap().instantiate();
+    let missing = parse_scene(source, None).unwrap().instantiate();
+    // A TGA header for a 0x0 image.
+    let mut empty = vec![0u8; 18];
+    empty[2] = 2;
+    empty[16] = 32;
+    fs::write(dir.join("empty.tga"), empty).unwrap();
+    let empty = parse_scene(&source.replace("albedo.png", "empty.tga"), Some(&dir))
+        .unwrap()
+        .instantiate();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let loaded = loaded.unwrap();
+    let a = loaded.scene.node(loaded.scene.find("a").unwrap());
+    let a = &a.model.as_ref().unwrap().pbr_material;
+    let base_color = a.base_color_texture.as_ref().unwrap();
+    assert_eq!(base_color.sample(Vector2::splat(0.5)), Vector4::new(1.0, 0.0, 0.0, 1.0));
+    assert!(a.occlusion_texture.is_some());
+
+    match missing {
+        Err(SceneError::Image { path, .. }) => assert_eq!(path, Path::new("albedo.png")),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+    match empty {
+        Err(SceneError::Image { path, .. }) => assert!(path.ends_with("empty.tga")),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,157 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod approx;
+}
+
+use std::f64::consts::PI;
+
+use rrasterizer::vec3::Vector3;
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::quaternion::Quaternion;
+
+use common::approx::assert_close;
+
+const EPSILON: f64 = 1e-9;
+
+// Quaternions q and -q are the same rotation.
+fn assert_same_rotation(a: Quaternion<f64>, b: Quaternion<f64>) {
+    assert!(
+        (a.dot(b).abs() - 1.0).abs() < EPSILON,
+        "{:?} != {:?}",
+        a,
+        b
+    );
+}
+
+fn sample_angles() -> Vec<Vector3<f64>> {
+    let mut angles = Vec::new();
+    for &x in &[-2.5, -0.3, 0.0, 1.1, 3.0] {
+        for &y in &[-1.4, -0.2, 0.0, 0.7, 1.5] {
+            for &z in &[-3.0, -1.0, 0.0, 0.4, 2.2] {
+                angles.push(Vector3::new(x, y, z));
+            }
+        }
+    }
+    angles
+}
+
+#[test]
+fn euler_matches_matrix_rotation() {
+    for angles in sample_angles() {
+        let q = Quaternion::from_euler(angles);
+        assert_close(q.to_matrix(), Matrix4::rotation(angles), EPSILON);
+        assert_close(Matrix4::from(q), Matrix4::rotation(angles), EPSILON);
+
+        // Euler angles are not unique, so compare the rotations they produce.
+        let back = Quaternion::from_euler(q.to_euler());
+        assert_same_rotation(back, q);
+    }
+}
+
+#[test]
+fn euler_round_trip_in_principal_range() {
+    let angles = Vector3::new(0.3, -0.6, 1.2);
+    assert_close(Quaternion::from_euler(angles).to_euler(), angles, EPSILON);
+
+    // Gimbal lock
+    let locked = Quaternion::from_euler(Vector3::new(0.5, PI / 2.0, 0.25));
+    assert_same_rotation(Quaternion::from_euler(locked.to_euler()), locked);
+}
+
+#[test]
+fn matrix_round_trip() {
+    for angles in sample_angles() {
+        let q = Quaternion::from_euler(angles);
+        assert_same_rotation(Quaternion::from_matrix(&q.to_matrix()), q);
+    }
+    // Translation is ignored
+    let m = Matrix4::translation(Vector3::new(1.0, 2.0, 3.0)) *
+        Matrix4::rotation(Vector3::new(0.1, 0.2, 0.3));
+    assert_same_rotation(
+        Quaternion::from_matrix(&m),
+        Quaternion::from_euler(Vector3::new(0.1, 0.2, 0.3)),
+    );
+}
+
+#[test]
+fn axis_angle() {
+    let q = Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 2.0), PI / 2.0);
+    assert_close(q.rotate(Vector3::new(1.0, 0.0, 0.0)), Vector3::new(0.0, 1.0, 0.0), EPSILON);
+    assert_close(q * Vector3::new(0.0, 1.0, 0.0), Vector3::new(-1.0, 0.0, 0.0), EPSILON);
+
+    let axis = Vector3::new(1.0, -2.0, 0.5).normalize();
+    let (a, angle) = Quaternion::from_axis_angle(axis, 2.0).to_axis_angle();
+    assert_close(a, axis, EPSILON);
+    assert_close(angle, 2.0, EPSILON);
+
+    let (_, angle) = Quaternion::<f64>::identity().to_axis_angle();
+    assert_eq!(angle, 0.0);
+}
+
+#[test]
+fn rotation_matches_matrix() {
+    let v = Vector3::new(0.3, -4.0, 2.5);
+    for angles in sample_angles() {
+        let q = Quaternion::from_euler(angles);
+        assert_close(q.rotate(v), Matrix4::rotation(angles).transform(v), EPSILON);
+    }
+}
+
+#[test]
+fn multiplication_composes_rotations() {
+    let a = Quaternion::from_euler(Vector3::new(0.4, -1.0, 2.0));
+    let b = Quaternion::from_euler(Vector3::new(-2.0, 0.3, 0.1));
+    let v = Vector3::new(1.0, 2.0, 3.0);
+    assert_close((a * b).rotate(v), a.rotate(b.rotate(v)), EPSILON);
+    assert_close((a * b).to_matrix(), a.to_matrix() * b.to_matrix(), EPSILON);
+
+    let mut c = a;
+    c *= b;
+    assert_eq!(c, a * b);
+
+    assert_same_rotation(a * a.inverse(), Quaternion::identity());
+    assert_same_rotation(a * a.conjugate(), Quaternion::identity());
+    let scaled = a * 3.0;
+    assert_same_rotation(scaled * scaled.inverse(), Quaternion::identity());
+    assert_close(scaled.normalize().magnitude(), 1.0, EPSILON);
+}
+
+#[test]
+fn slerp_has_constant_angular_velocity() {
+    let axis = Vector3::new(1.0, 1.0, 0.0);
+    let a = Quaternion::from_axis_angle(axis, 0.2);
+    let b = Quaternion::from_axis_angle(axis, 2.2);
+
+    assert_same_rotation(a.slerp(b, 0.0), a);
+    assert_same_rotation(a.slerp(b, 1.0), b);
+    for i in 0..11 {
+        let t = i as f64 / 10.0;
+        let expected = Quaternion::from_axis_angle(axis, 0.2 + 2.0 * t);
+        assert_same_rotation(a.slerp(b, t), expected);
+    }
+
+    // nlerp follows the same path, but not at a constant speed.
+    let mid = a.nlerp(b, 0.5);
+    assert_same_rotation(mid, Quaternion::from_axis_angle(axis, 1.2));
+    let quarter = a.nlerp(b, 0.25);
+    let (_, angle) = quarter.to_axis_angle();
+    assert!((angle - 0.7).abs() > 1e-3);
+}
+
+#[test]
+fn slerp_takes_the_shortest_path() {
+    let a = Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), 0.1);
+    let b = -Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), 0.5);
+    let mid = a.slerp(b, 0.5);
+    assert_same_rotation(
+        mid,
+        Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), 0.3),
+    );
+
+    // Nearly identical rotations fall back to nlerp without dividing by zero.
+    let c = Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), 0.1 + 1e-12);
+    let q = a.slerp(c, 0.5);
+    assert!(q.magnitude().is_finite());
+    assert_same_rotation(q, a);
+}
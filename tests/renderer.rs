@@ -0,0 +1,280 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod mesh;
+    pub mod render;
+}
+
+use std::f32;
+use std::rc::Rc;
+
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::color::Color;
+use rrasterizer::renderer::{Renderer, DepthTest, Framebuffer, FragmentOutput};
+use rrasterizer::lighting::{Light, LightKind, PlacedLight, ShadingMode};
+use rrasterizer::pbr::PbrMaterial;
+use rrasterizer::application::Application;
+
+use common::mesh::square;
+use common::render::SIZE;
+
+const BLACK: Color = Color(0, 0, 0, 255);
+const RED: Color = Color(255, 0, 0, 255);
+const GREEN: Color = Color(0, 255, 0, 255);
+
+// A red square with a green one in front of it, which covers it on screen.
+fn draw_squares(renderer: &mut Renderer, near_first: bool) {
+    let far = square(-4.0, Vector4::new(1.0, 0.0, 0.0, 1.0), Vector3::splat(0.0));
+    let near = square(-2.0, Vector4::new(0.0, 1.0, 0.0, 1.0), Vector3::splat(0.0));
+    let order = if near_first { [&near, &far] } else { [&far, &near] };
+    for triangles in &order {
+        renderer.render(Matrix4::identity(), triangles);
+    }
+}
+
+fn center(renderer: &Renderer) -> Color {
+    renderer.get_pixel(SIZE / 2, SIZE / 2)
+}
+
+fn depth_is(renderer: &Renderer, depth: f32) -> bool {
+    renderer.depth_buffer().pixels().iter().all(|&d| d == depth)
+}
+
+// The columns and rows covered by the given color.
+fn extent(renderer: &Renderer, color: Color) -> (u32, u32) {
+    let (width, height) = renderer.dimensions();
+    let columns = (0..width)
+        .filter(|&x| (0..height).any(|y| renderer.get_pixel(x, y) == color))
+        .count();
+    let rows = (0..height)
+        .filter(|&y| (0..width).any(|x| renderer.get_pixel(x, y) == color))
+        .count();
+    (columns as u32, rows as u32)
+}
+
+fn quarter_view() -> Matrix4<f32> {
+    Matrix4::perspective_fov(f32::consts::PI / 2.0, 1.0, 0.1, 100.0)
+}
+
+#[test]
+fn nearer_fragments_are_drawn() {
+    for &near_first in &[false, true] {
+        let mut renderer = common::render::renderer();
+        renderer.clear(BLACK);
+        draw_squares(&mut renderer, near_first);
+        assert_eq!(center(&renderer), GREEN, "near first: {}", near_first);
+    }
+}
+
+#[test]
+fn depth_buffer_is_written_and_cleared() {
+    let mut renderer = common::render::renderer();
+    renderer.clear(BLACK);
+    assert_eq!(renderer.depth_buffer().dimensions(), (SIZE, SIZE));
+    assert!(depth_is(&renderer, f32::INFINITY));
+
+    let triangles = square(-2.0, Vector4::splat(1.0), Vector3::splat(0.0));
+    renderer.render(Matrix4::identity(), &triangles);
+    // Normalized device z, which the default projection maps into [-1, 1].
+    let depth = renderer.depth_buffer()[(SIZE / 2, SIZE / 2)];
+    assert!(depth > -1.0 && depth < 1.0, "{}", depth);
+    // The square does not reach the corners.
+    assert_eq!(renderer.depth_buffer()[(0, 0)], f32::INFINITY);
+
+    renderer.clear(BLACK);
+    assert!(depth_is(&renderer, f32::INFINITY));
+}
+
+#[test]
+fn reverse_z_keeps_greater_depths() {
+    let projection = Matrix4::perspective_reverse_z(f32::consts::PI / 3.0, 1.0, 0.1, 100.0);
+    let mut renderer = Renderer::new(SIZE, SIZE, projection);
+    assert_eq!(renderer.depth_test(), DepthTest::Less);
+    renderer.set_depth_test(DepthTest::Greater);
+    renderer.clear(BLACK);
+    assert!(depth_is(&renderer, f32::NEG_INFINITY));
+    for &near_first in &[false, true] {
+        draw_squares(&mut renderer, near_first);
+        assert_eq!(center(&renderer), GREEN, "near first: {}", near_first);
+    }
+}
+
+#[test]
+fn disabled_depth_test_draws_in_order() {
+    let mut renderer = common::render::renderer();
+    renderer.set_depth_test(DepthTest::Disabled);
+    renderer.clear(BLACK);
+    draw_squares(&mut renderer, true);
+    assert_eq!(center(&renderer), RED);
+    assert!(depth_is(&renderer, f32::INFINITY));
+}
+
+#[test]
+fn resize() {
+    let mut renderer = Renderer::new(40, 40, quarter_view());
+    let triangles = square(-4.0, Vector4::new(1.0, 0.0, 0.0, 1.0), Vector3::splat(0.0));
+    renderer.clear(BLACK);
+    renderer.render(Matrix4::identity(), &triangles);
+    assert_eq!(extent(&renderer, RED), (10, 10));
+
+    // Twice as wide, the square stays square and the same height.
+    renderer.resize(80, 40);
+    assert_eq!(renderer.dimensions(), (80, 40));
+    assert_eq!(renderer.framebuffer().dimensions(), (80, 40));
+    assert_eq!(renderer.depth_buffer().dimensions(), (80, 40));
+    assert!(renderer.framebuffer().pixels().iter().all(|&p| p == BLACK));
+    assert_eq!(
+        renderer.projection(),
+        Matrix4::perspective_fov(f32::consts::PI / 2.0, 2.0, 0.1, 100.0)
+    );
+    renderer.render(Matrix4::identity(), &triangles);
+    assert_eq!(extent(&renderer, RED), (10, 10));
+
+    renderer.resize(40, 80);
+    renderer.render(Matrix4::identity(), &triangles);
+    assert_eq!(extent(&renderer, RED), (20, 20));
+
+    // Resizing to the same size keeps the image.
+    renderer.resize(40, 80);
+    assert_eq!(extent(&renderer, RED), (20, 20));
+}
+
+#[test]
+fn resized_depth_buffer_is_cleared() {
+    let mut renderer = Renderer::new(20, 20, quarter_view());
+    renderer.set_depth_test(DepthTest::Greater);
+    renderer.clear(BLACK);
+    renderer.resize(30, 10);
+    assert!(depth_is(&renderer, f32::NEG_INFINITY));
+}
+
+#[test]
+fn resize_to_nothing() {
+    let triangles = square(-4.0, Vector4::new(1.0, 0.0, 0.0, 1.0), Vector3::splat(0.0));
+    let mut direct = Renderer::new(20, 20, quarter_view());
+    direct.resize(40, 20);
+    direct.render(Matrix4::identity(), &triangles);
+
+    let mut renderer = Renderer::new(20, 20, quarter_view());
+    // An empty framebuffer, as for a minimized window, renders nothing and keeps the projection.
+    renderer.resize(0, 20);
+    assert_eq!(renderer.dimensions(), (0, 20));
+    renderer.render(Matrix4::identity(), &triangles);
+    assert_eq!(renderer.projection(), quarter_view());
+
+    // Restored, the projection is fitted as if the framebuffer had never been empty.
+    renderer.resize(40, 20);
+    assert_eq!(renderer.projection(), direct.projection());
+    renderer.render(Matrix4::identity(), &triangles);
+    assert_eq!(extent(&renderer, RED), extent(&direct, RED));
+
+    // A renderer created empty takes its projection to suit its first dimensions.
+    let mut empty = Renderer::new(0, 0, quarter_view());
+    empty.render(Matrix4::identity(), &triangles);
+    empty.resize(40, 20);
+    assert_eq!(empty.projection(), quarter_view());
+}
+
+#[test]
+fn application_resize() {
+    let mut application = Application::new(80, 60);
+    application.render();
+    application.resize(120, 40);
+    assert_eq!(application.dimensions(), (120, 40));
+    application.render();
+    assert_eq!(application.framebuffer().dimensions(), (120, 40));
+    assert!(application.stats().objects_drawn > 0);
+}
+
+fn renderer() -> Renderer {
+    let mut renderer = common::render::renderer();
+    renderer.clear(BLACK);
+    renderer
+}
+
+#[test]
+fn render_to_texture() {
+    let mut renderer = renderer();
+    let screen = renderer.bind_framebuffer(Framebuffer::new(20, 20, &[FragmentOutput::Color]));
+    assert_eq!(renderer.dimensions(), (20, 20));
+    renderer.clear(Color(0, 0, 255, 255));
+    let triangles = square(-4.0, Vector4::new(1.0, 0.0, 0.0, 1.0), Vector3::splat(0.0));
+    renderer.render(Matrix4::identity(), &triangles);
+    let offscreen = renderer.bind_framebuffer(screen);
+
+    // The screen is untouched and the offscreen target holds the square.
+    assert_eq!(renderer.dimensions(), (SIZE, SIZE));
+    assert!(renderer.framebuffer().pixels().iter().all(|&p| p == BLACK));
+    assert_eq!(offscreen.color(0)[(10, 10)], RED);
+    assert_eq!(offscreen.color(0)[(0, 0)], Color(0, 0, 255, 255));
+    assert!(offscreen.depth()[(10, 10)] < 1.0);
+    assert_eq!(offscreen.depth()[(0, 0)], f32::INFINITY);
+
+    // Sampled as the emission of a black surface, the texture shows up unchanged.
+    let quad = square(-1.0, Vector4::splat(1.0), Vector3::splat(0.0));
+    renderer.set_shading(ShadingMode::Pbr);
+    renderer.set_pbr_material(PbrMaterial {
+        base_color: Vector4::new(0.0, 0.0, 0.0, 1.0),
+        emissive: Vector3::splat(1.0),
+        emissive_texture: Some(Rc::new(offscreen.texture(0))),
+        ..PbrMaterial::default()
+    });
+    renderer.render(Matrix4::identity(), &quad);
+    assert_eq!(center(&renderer), RED);
+    assert_eq!(renderer.get_pixel(0, 0), Color(0, 0, 255, 255));
+}
+
+#[test]
+fn multiple_render_targets() {
+    let outputs = [FragmentOutput::Color, FragmentOutput::Albedo, FragmentOutput::Normal];
+    let mut renderer = renderer();
+    renderer.bind_framebuffer(Framebuffer::new(20, 20, &outputs));
+    renderer.set_shading(ShadingMode::Phong);
+    renderer.set_lights(vec![
+        PlacedLight {
+            light: Light {
+                kind: LightKind::Directional,
+                color: Vector3::splat(1.0),
+                intensity: 0.5,
+            },
+            position: Vector3::splat(0.0),
+            direction: Vector3::new(0.0, 0.0, -1.0),
+            shadow: None,
+        },
+    ]);
+    renderer.clear(BLACK);
+    let triangles = square(-4.0, Vector4::new(0.0, 1.0, 0.0, 1.0), Vector3::splat(0.0));
+    renderer.render(Matrix4::identity(), &triangles);
+
+    let target = renderer.bound_framebuffer();
+    assert_eq!(target.outputs(), &outputs);
+    // The lit color, and the color and normal before lighting.
+    let Color(_, green, _, _) = target.color(0)[(10, 10)];
+    assert!(green > 100 && green < 250, "{}", green);
+    assert_eq!(target.color(1)[(10, 10)], Color(0, 255, 0, 255));
+    assert_eq!(target.color(2)[(10, 10)], Color(127, 127, 255, 255));
+    for i in 0..3 {
+        assert_eq!(target.color(i)[(0, 0)], BLACK);
+    }
+    assert_eq!(renderer.framebuffer(), target.color(0));
+
+    // Resizing keeps the attachments.
+    renderer.resize(10, 30);
+    assert_eq!(renderer.bound_framebuffer().outputs(), &outputs);
+    assert_eq!(renderer.bound_framebuffer().color(2).dimensions(), (10, 30));
+}
+
+#[test]
+fn depth_only_framebuffer() {
+    let mut renderer = renderer();
+    renderer.bind_framebuffer(Framebuffer::new(20, 20, &[]));
+    renderer.clear(BLACK);
+    let triangles = square(-4.0, Vector4::splat(1.0), Vector3::splat(0.0));
+    renderer.render(Matrix4::identity(), &triangles);
+    let depth = renderer.depth_buffer();
+    assert!(depth[(10, 10)] < 1.0);
+    assert_eq!(depth[(0, 0)], f32::INFINITY);
+    assert!(renderer.bound_framebuffer().outputs().is_empty());
+}
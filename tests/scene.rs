@@ -0,0 +1,192 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod approx;
+    pub mod mesh;
+}
+
+use std::f32;
+use std::rc::Rc;
+
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::color::Color;
+use rrasterizer::renderer::{Renderer, RenderStats, DepthTest};
+use rrasterizer::scene::{Scene, Node, Model, Camera, Projection};
+
+use common::approx::assert_close;
+
+const BLACK: Color = Color(0, 0, 0, 255);
+
+fn camera() -> Node {
+    Node::new("camera").with_camera(Camera {
+        projection: Projection::Perspective {
+            fovy: f32::consts::PI / 3.0,
+            near: 0.1,
+            far: 100.0,
+        },
+    })
+}
+
+fn square(color: Vector4<f32>) -> Rc<Model> {
+    Rc::new(Model::new(common::mesh::square(0.0, color, Vector3::splat(0.0))))
+}
+
+#[test]
+fn hierarchy() {
+    let mut scene = Scene::new();
+    let root = scene.add(None, Node::new("root"));
+    let a = scene.add(Some(root), Node::new("a"));
+    let b = scene.add(Some(a), Node::new("b"));
+    let c = scene.add(None, Node::new("c"));
+
+    assert_eq!(scene.len(), 4);
+    assert_eq!(scene.roots(), vec![root, c]);
+    assert_eq!(scene.node(root).children(), &[a]);
+    assert_eq!(scene.node(b).parent(), Some(a));
+    assert_eq!(scene.find("b"), Some(b));
+    assert_eq!(scene.find("missing"), None);
+
+    scene.set_parent(b, Some(c));
+    assert!(scene.node(a).children().is_empty());
+    assert_eq!(scene.node(c).children(), &[b]);
+    scene.set_parent(c, None);
+    assert_eq!(scene.roots(), vec![root, c]);
+
+    let names: Vec<_> = scene.nodes().map(|(_, n)| n.name.as_str()).collect();
+    assert_eq!(names, vec!["root", "a", "b", "c"]);
+}
+
+#[test]
+#[should_panic(expected = "ancestor")]
+fn cycles_are_rejected() {
+    let mut scene = Scene::new();
+    let a = scene.add(None, Node::new("a"));
+    let b = scene.add(Some(a), Node::new("b"));
+    scene.set_parent(a, Some(b));
+}
+
+#[test]
+fn world_transforms() {
+    let t = Matrix4::translation(Vector3::new(1.0, 2.0, 3.0));
+    let r = Matrix4::rotation(Vector3::new(0.0, f32::consts::PI / 2.0, 0.0));
+    let s = Matrix4::scaling(Vector3::splat(2.0));
+
+    let mut scene = Scene::new();
+    let a = scene.add(None, Node::new("a").with_transform(t));
+    let b = scene.add(Some(a), Node::new("b").with_transform(r));
+    let c = scene.add(Some(b), Node::new("c").with_transform(s));
+    scene.update();
+    assert_close(scene.world_transform(a), t, 1e-5);
+    assert_close(scene.world_transform(b), t * r, 1e-5);
+    assert_close(scene.world_transform(c), t * r * s, 1e-5);
+
+    // Changing a transform propagates to the descendants on the next update only.
+    scene.set_transform(a, Matrix4::identity());
+    assert_close(scene.world_transform(c), t * r * s, 1e-5);
+    scene.update();
+    assert_close(scene.world_transform(b), r, 1e-5);
+    assert_close(scene.world_transform(c), r * s, 1e-5);
+
+    // So does moving a node to another parent.
+    scene.set_parent(c, None);
+    scene.update();
+    assert_close(scene.world_transform(c), s, 1e-5);
+    let p = scene.world_transform(c).transform(Vector3::new(1.0, 0.0, 0.0));
+    assert!((p - Vector3::new(2.0, 0.0, 0.0)).magnitude() < 1e-5);
+}
+
+#[test]
+fn rendering_uses_the_camera_transform() {
+    let red = Vector4::new(1.0, 0.0, 0.0, 1.0);
+    let mut scene = Scene::new();
+    let rig = scene.add(None, Node::new("rig"));
+    let camera = scene.add(Some(rig), camera());
+    scene.add(
+        None,
+        Node::new("square")
+            .with_transform(Matrix4::translation(Vector3::new(0.0, 0.0, -5.0)))
+            .with_model(square(red)),
+    );
+
+    let mut renderer = Renderer::new(80, 60, Matrix4::identity());
+    renderer.clear(BLACK);
+    scene.render(&mut renderer, camera);
+    assert_eq!(renderer.get_pixel(40, 30), Color(255, 0, 0, 255));
+    assert_eq!(renderer.projection(), Matrix4::perspective_fov(
+        f32::consts::PI / 3.0,
+        80.0 / 60.0,
+        0.1,
+        100.0,
+    ));
+
+    // Turning the camera's parent around leaves the square behind it.
+    scene.set_transform(rig, Matrix4::rotation(Vector3::new(0.0, f32::consts::PI, 0.0)));
+    renderer.clear(BLACK);
+    scene.render(&mut renderer, camera);
+    assert!(renderer.framebuffer().pixels().iter().all(|&p| p == BLACK));
+    assert_eq!(
+        renderer.stats(),
+        RenderStats {
+            objects_drawn: 0,
+            objects_culled: 1,
+        }
+    );
+}
+
+#[test]
+fn nearer_objects_hide_further_ones() {
+    let red = square(Vector4::new(1.0, 0.0, 0.0, 1.0));
+    let green = square(Vector4::new(0.0, 1.0, 0.0, 1.0));
+
+    let mut scene = Scene::new();
+    let camera = scene.add(None, camera());
+    // The nearer square is added first, so it is drawn first.
+    scene.add(
+        None,
+        Node::new("near")
+            .with_transform(Matrix4::translation(Vector3::new(0.0, 0.0, -4.0)))
+            .with_model(red),
+    );
+    scene.add(
+        None,
+        Node::new("far")
+            .with_transform(Matrix4::translation(Vector3::new(0.5, 0.0, -6.0)))
+            .with_model(green),
+    );
+
+    let mut renderer = Renderer::new(80, 60, Matrix4::identity());
+    renderer.clear(BLACK);
+    scene.render(&mut renderer, camera);
+    assert_eq!(renderer.get_pixel(40, 30), Color(255, 0, 0, 255));
+    assert!(renderer.depth_buffer()[(40, 30)] < 1.0);
+    assert_eq!(renderer.depth_buffer()[(0, 0)], f32::INFINITY);
+
+    renderer.set_depth_test(DepthTest::Disabled);
+    renderer.clear(BLACK);
+    scene.render(&mut renderer, camera);
+    assert_eq!(renderer.get_pixel(40, 30), Color(0, 255, 0, 255));
+}
+
+#[test]
+fn shared_models() {
+    let model = square(Vector4::new(1.0, 1.0, 1.0, 1.0));
+    let mut scene = Scene::new();
+    let camera = scene.add(None, camera());
+    for i in 0..3 {
+        let position = Vector3::new(i as f32 * 3.0 - 3.0, 0.0, -8.0);
+        scene.add(
+            None,
+            Node::new(format!("square {}", i))
+                .with_transform(Matrix4::translation(position))
+                .with_model(model.clone()),
+        );
+    }
+    assert_eq!(Rc::strong_count(&model), 4);
+
+    let mut renderer = Renderer::new(80, 60, Matrix4::identity());
+    renderer.clear(BLACK);
+    scene.render(&mut renderer, camera);
+    assert_eq!(renderer.stats().objects_drawn, 3);
+}
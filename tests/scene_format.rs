@@ -0,0 +1,321 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod approx;
+}
+
+use std::f32;
+use std::path::{Path, PathBuf};
+
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::color::Color;
+use rrasterizer::renderer::DepthTest;
+use rrasterizer::scene::Projection;
+use rrasterizer::lighting::{Attenuation, LightKind, Material, ShadingMode};
+use rrasterizer::scene_format::{SceneError, MeshSource, parse_scene};
+
+use common::approx::assert_close;
+
+fn parse_error(source: &str) -> (usize, String) {
+    match parse_scene(source, None) {
+        Err(SceneError::Parse { line, message }) => (line, message),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+fn invalid(source: &str) -> String {
+    let result = parse_scene(source, None).and_then(|s| s.instantiate().map(|_| s));
+    match result {
+        Err(SceneError::Invalid(message)) => message,
+        other => panic!("expected an invalid scene, got {:?}", other),
+    }
+}
+
+#[test]
+fn full_scene() {
+    let scene = parse_scene(
+        r#"
+        # Settings
+        [render]
+        width = 320
+        height = 2_40
+        clear_color = [0.5, 0.25, 0, 0.5]
+        depth_test = "greater"
+        camera = "eye"
+
+        [[material]]
+        name = 'literal \ name'
+        color = [1, 0.5, 0.25]
+
+        [[node]]
+        name = "eye"
+        translation = [1, 2, 3]
+        rotation = [90, 0, -90]  # degrees
+        camera = { projection = "orthographic", height = 4.0, near = 1e-1, far = 50 }
+
+        [[node]]
+        name = "mesh \"quoted\""
+        parent = "eye"
+        scale = 2
+        spin = [
+            0,
+            180,   # half a turn per second
+            0,
+        ]
+        mesh = "models/thing.obj"
+        material = "literal \\ name"
+        light = { kind = "point", color = [1, 0, 0], intensity = 2.5 }
+        "#,
+        Some(Path::new("base")),
+    ).unwrap();
+
+    assert_eq!(scene.render.width, 320);
+    assert_eq!(scene.render.height, 240);
+    assert_eq!(scene.render.clear_color, Vector4::new(0.5, 0.25, 0.0, 0.5));
+    assert_eq!(scene.render.depth_test, DepthTest::Greater);
+    assert_eq!(scene.render.camera, Some("eye".to_string()));
+
+    assert_eq!(scene.materials.len(), 1);
+    assert_eq!(scene.materials[0].name, "literal \\ name");
+    assert_eq!(scene.materials[0].color, Vector4::new(1.0, 0.5, 0.25, 1.0));
+
+    let eye = &scene.nodes[0];
+    assert_eq!(eye.translation, Vector3::new(1.0, 2.0, 3.0));
+    let quarter = f32::consts::PI / 2.0;
+    assert_close(eye.rotation, Vector3::new(quarter, 0.0, -quarter), 1e-5);
+    assert_eq!(
+        eye.camera.unwrap().projection,
+        Projection::Orthographic {
+            height: 4.0,
+            near: 0.1,
+            far: 50.0,
+        }
+    );
+
+    let mesh = &scene.nodes[1];
+    assert_eq!(mesh.name, "mesh \"quoted\"");
+    assert_eq!(mesh.parent, Some("eye".to_string()));
+    assert_eq!(mesh.scale, Vector3::splat(2.0));
+    assert_close(mesh.spin, Vector3::new(0.0, f32::consts::PI, 0.0), 1e-5);
+    assert_eq!(
+        mesh.mesh,
+        Some(MeshSource::File(PathBuf::from("base").join("models/thing.obj")))
+    );
+    let light = mesh.light.unwrap();
+    assert_eq!(light.kind, LightKind::Point { attenuation: Attenuation::none() });
+    assert_eq!(light.color, Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(light.intensity, 2.5);
+}
+
+#[test]
+fn defaults() {
+    let scene = parse_scene("[[node]]\nname = \"camera\"\ncamera = {}\n", None).unwrap();
+    assert_eq!((scene.render.width, scene.render.height), (800, 600));
+    assert_eq!(scene.render.depth_test, DepthTest::Less);
+    assert_eq!(scene.nodes[0].scale, Vector3::splat(1.0));
+    match scene.nodes[0].camera.unwrap().projection {
+        Projection::Perspective { fovy, near, far } => {
+            assert!((fovy - f32::consts::PI / 3.0).abs() < 1e-6);
+            assert_eq!((near, far), (0.1, 100.0));
+        }
+        other => panic!("unexpected projection {:?}", other),
+    }
+}
+
+#[test]
+fn lighting() {
+    let scene = parse_scene(
+        r#"
+        [render]
+        shading = "blinn-phong"
+        ambient_light = [0.1, 0.2, 0.3]
+
+        [[material]]
+        name = "shiny"
+        diffuse = [0.5, 0.5, 0.5]
+        specular = [1, 1, 1]
+        shininess = 64
+
+        [[node]]
+        name = "lamp"
+        light = { kind = "spot", attenuation = [1, 0, 0.5], inner_angle = 15, outer_angle = 30 }
+        "#,
+        None,
+    ).unwrap();
+
+    assert_eq!(scene.render.shading, ShadingMode::BlinnPhong);
+    assert_eq!(scene.render.ambient_light, Vector3::new(0.1, 0.2, 0.3));
+    assert_eq!(
+        scene.materials[0].material,
+        Material {
+            diffuse: Vector3::splat(0.5),
+            specular: Vector3::splat(1.0),
+            shininess: 64.0,
+            ..Material::default()
+        }
+    );
+    match scene.nodes[0].light.unwrap().kind {
+        LightKind::Spot {
+            attenuation,
+            inner_angle,
+            outer_angle,
+        } => {
+            assert_eq!(attenuation.quadratic, 0.5);
+            assert!((inner_angle - f32::consts::PI / 12.0).abs() < 1e-6);
+            assert!((outer_angle - f32::consts::PI / 6.0).abs() < 1e-6);
+        }
+        other => panic!("unexpected light {:?}", other),
+    }
+
+    let light = |table: &str| invalid(&format!("[[node]]\nname = \"a\"\nlight = {}\n", table));
+    assert!(light("{ kind = \"directional\", attenuation = [1, 0, 0] }").contains("attenuation"));
+    assert!(light("{ kind = \"point\", outer_angle = 10 }").contains("spot"));
+    assert!(light("{ kind = \"spot\", inner_angle = 40 }").contains("inner angle"));
+    assert!(invalid("[render]\nshading = \"flat\"\n").contains("flat"));
+}
+
+#[test]
+fn syntax_errors() {
+    assert_eq!(parse_error("[render]\nwidth = \n").0, 2);
+    assert_eq!(parse_error("\n\n[render\n").0, 3);
+    assert_eq!(parse_error("a = 1\na = 2\n"), (2, "duplicate key 'a'".to_string()));
+    assert_eq!(parse_error("[render]\n[render]\n").0, 2);
+    assert_eq!(parse_error("a = \"unterminated\n").0, 1);
+    assert_eq!(parse_error("a = 1 b = 2\n").0, 1);
+    assert_eq!(parse_error("a = [1, 2\n").0, 2);
+    assert_eq!(parse_error("a = 1__0\n").0, 1);
+    assert!(parse_error("a.b = 1\n").1.contains("dotted"));
+    assert!(parse_error("[a.b]\n").1.contains("dotted"));
+}
+
+#[test]
+fn schema_errors() {
+    assert!(invalid("[render]\nwdth = 3\n").contains("unknown key 'wdth'"));
+    assert!(invalid("[render]\nwidth = \"wide\"\n").contains("'width'"));
+    assert!(invalid("[render]\nwidth = -1\n").contains("'width'"));
+    assert!(invalid("[render]\nwidth = 0\n").contains("from 1 to 16384"));
+    assert!(invalid("[render]\nheight = 16385\n").contains("'height'"));
+    assert!(invalid("[[node]]\nname = \"a\"\ntranslation = [1, 2]\n").contains("3 numbers"));
+    assert!(invalid("[[node]]\ntranslation = [1, 2, 3]\n").contains("'name'"));
+    assert!(invalid("[[node]]\nname = \"a\"\nprimitive = \"torus\"\n").contains("torus"));
+    assert!(invalid("[render]\ndepth_test = \"always\"\n").contains("always"));
+
+    let camera = "[[node]]\nname = \"camera\"\ncamera = {}\n";
+    assert!(invalid("").contains("no camera"));
+    assert!(
+        invalid(&format!("{}[[node]]\nname = \"camera\"\n", camera)).contains("more than one")
+    );
+    assert!(
+        invalid(&format!("{}[[node]]\nname = \"a\"\nparent = \"b\"\n", camera))
+            .contains("unknown parent")
+    );
+    assert!(
+        invalid(&format!(
+            "{}[[node]]\nname = \"a\"\nparent = \"b\"\n[[node]]\nname = \"b\"\nparent = \"a\"\n",
+            camera
+        )).contains("ancestor")
+    );
+    assert!(
+        invalid(&format!(
+            "{}[[node]]\nname = \"a\"\nprimitive = \"cube\"\nmaterial = \"gold\"\n",
+            camera
+        )).contains("unknown material 'gold'")
+    );
+    assert!(
+        invalid(&format!("{}[render]\ncamera = \"a\"\n", camera)).contains("unknown camera")
+    );
+}
+
+#[test]
+fn missing_meshes() {
+    let scene = parse_scene(
+        "[[node]]\nname = \"camera\"\ncamera = {}\n[[node]]\nname = \"a\"\nmesh = \"nope.obj\"\n",
+        Some(Path::new("/nonexistent")),
+    ).unwrap();
+    match scene.instantiate() {
+        Err(SceneError::Mesh { path, .. }) => assert_eq!(path, Path::new("/nonexistent/nope.obj")),
+        other => panic!("expected a mesh error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn instantiated_scenes() {
+    let mut loaded = parse_scene(
+        r#"
+        [render]
+        width = 40
+        height = 30
+        clear_color = [0, 0, 1]
+
+        [[node]]
+        name = "pivot"
+        translation = [0, 0, -6]
+        spin = [0, 90, 0]
+
+        [[node]]
+        name = "red cube"
+        parent = "pivot"
+        translation = [2, 0, 0]
+        primitive = "cube"
+        material = "red"
+
+        [[node]]
+        name = "red cube 2"
+        parent = "pivot"
+        translation = [-2, 0, 0]
+        primitive = "cube"
+        material = "red"
+
+        [[node]]
+        name = "camera"
+        camera = { fovy = 90 }
+
+        [[material]]
+        name = "red"
+        color = [1, 0, 0]
+        "#,
+        None,
+    ).unwrap()
+        .instantiate()
+        .unwrap();
+
+    let scene = &loaded.scene;
+    let pivot = scene.find("pivot").unwrap();
+    let cube = scene.find("red cube").unwrap();
+    let cube2 = scene.find("red cube 2").unwrap();
+    assert_eq!(loaded.camera, scene.find("camera").unwrap());
+    assert_eq!(scene.node(cube).parent(), Some(pivot));
+    // Nodes with the same mesh and material share a model.
+    let model = scene.node(cube).model.clone().unwrap();
+    assert!(::std::rc::Rc::ptr_eq(&model, scene.node(cube2).model.as_ref().unwrap()));
+    for t in &model.triangles {
+        assert_eq!(t.a.color.y, 0.0);
+        assert_eq!(t.a.color.z, 0.0);
+    }
+
+    let mut renderer = loaded.renderer();
+    assert_eq!(renderer.dimensions(), (40, 30));
+    loaded.render(&mut renderer);
+    assert_eq!(renderer.get_pixel(0, 0), Color(0, 0, 255, 255));
+    assert_eq!(renderer.stats().objects_drawn, 2);
+
+    // After a second the pivot has turned a quarter, moving a cube in front of the camera.
+    loaded.scene.update();
+    let p = loaded.scene.world_transform(cube).transform(Vector3::splat(0.0));
+    assert_close(p, Vector3::new(2.0, 0.0, -6.0), 1e-5);
+    loaded.set_time(1.0);
+    loaded.render(&mut renderer);
+    let p = loaded.scene.world_transform(cube).transform(Vector3::splat(0.0));
+    assert_close(p, Vector3::new(0.0, 0.0, -8.0), 1e-5);
+    let Color(_, g, b, _) = renderer.get_pixel(20, 15);
+    assert_eq!((g, b), (0, 0));
+}
+
+#[test]
+fn example_scene() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/example.toml");
+    let scene = rrasterizer::scene_format::load_scene(&path).unwrap();
+    assert_eq!(scene.nodes.len(), 4);
+    scene.instantiate().unwrap();
+}
@@ -0,0 +1,301 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod approx;
+    pub mod light;
+    pub mod render;
+}
+
+use std::f32;
+use std::rc::Rc;
+
+use rrasterizer::vec2::Vector2;
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::color::Color;
+use rrasterizer::image::Image;
+use rrasterizer::renderer::{Vertex, Triangle, Renderer};
+use rrasterizer::lighting::{Attenuation, LightKind, Material, ShadingMode};
+use rrasterizer::shadow::{ShadowCascade, ShadowMap, ShadowSettings, cascade_splits,
+                          frustum_slice};
+use rrasterizer::scene::{Scene, Node, NodeId, Model, Camera, Projection};
+use rrasterizer::scene_format::parse_scene;
+
+use common::approx::assert_close;
+use common::light::white_light;
+use common::render::SIZE;
+
+const DEGREES: f32 = f32::consts::PI / 180.0;
+
+/// A square facing up along the y axis, filling [-1, 1] on the x and z axes.
+fn floor_square() -> Model {
+    let vertex = |x, z| {
+        Vertex {
+            position: Vector3::new(x, 0.0, z),
+            color: Vector4::splat(1.0),
+            normal: Vector3::new(0.0, 1.0, 0.0),
+            tex_coord: Vector2::splat(0.0),
+        }
+    };
+    Model::new(vec![
+        Triangle {
+            a: vertex(-1.0, 1.0),
+            b: vertex(1.0, 1.0),
+            c: vertex(1.0, -1.0),
+        },
+        Triangle {
+            a: vertex(-1.0, 1.0),
+            b: vertex(1.0, -1.0),
+            c: vertex(-1.0, -1.0),
+        },
+    ]).with_material(Material {
+        ambient: Vector3::splat(1.0),
+        diffuse: Vector3::splat(0.5),
+        specular: Vector3::splat(0.0),
+        shininess: 1.0,
+    })
+}
+
+/// A large floor with a small square hovering above its center, seen from straight above.
+fn scene() -> (Scene, NodeId) {
+    let model = Rc::new(floor_square());
+    let mut scene = Scene::new();
+    let camera = scene.add(
+        None,
+        Node::new("camera")
+            .with_transform(
+                Matrix4::translation(Vector3::new(0.0, 10.0, 0.0)) *
+                    Matrix4::rotation(Vector3::new(-90.0 * DEGREES, 0.0, 0.0)),
+            )
+            .with_camera(Camera {
+                projection: Projection::Perspective {
+                    fovy: 60.0 * DEGREES,
+                    near: 0.1,
+                    far: 100.0,
+                },
+            }),
+    );
+    scene.add(
+        None,
+        Node::new("floor")
+            .with_transform(Matrix4::scaling(Vector3::splat(5.0)))
+            .with_model(model.clone()),
+    );
+    scene.add(
+        None,
+        Node::new("occluder")
+            .with_transform(
+                Matrix4::translation(Vector3::new(0.0, 2.0, 0.0)) *
+                    Matrix4::scaling(Vector3::splat(0.5)),
+            )
+            .with_model(model),
+    );
+    (scene, camera)
+}
+
+fn renderer() -> Renderer {
+    let mut renderer = common::render::renderer();
+    renderer.set_shading(ShadingMode::Phong);
+    renderer.set_ambient_light(Vector3::splat(0.1));
+    renderer
+}
+
+#[test]
+fn splits() {
+    assert_eq!(cascade_splits(1.0, 7.0, 3, 0.0), vec![1.0, 3.0, 5.0, 7.0]);
+    let logarithmic = cascade_splits(1.0, 1000.0, 3, 1.0);
+    for (split, expected) in logarithmic.iter().zip(&[1.0, 10.0, 100.0, 1000.0]) {
+        assert_close(*split, *expected, expected * 1e-5);
+    }
+    let mixed = cascade_splits(0.1, 50.0, 4, 0.5);
+    assert_eq!(mixed.len(), 5);
+    assert_eq!((mixed[0], mixed[4]), (0.1, 50.0));
+    assert!(mixed.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn frustum_slices() {
+    let projection = Matrix4::perspective_fov(90.0 * DEGREES, 2.0, 0.5, 100.0);
+    let corners = frustum_slice(&projection, 1.0, 4.0).unwrap();
+    for corner in &corners[..4] {
+        assert_close(corner.z, -1.0, 1e-4);
+        assert_close(corner.x.abs(), 2.0, 1e-4);
+        assert_close(corner.y.abs(), 1.0, 1e-4);
+    }
+    for corner in &corners[4..] {
+        assert_close(corner.z, -4.0, 1e-3);
+        assert_close(corner.x.abs(), 8.0, 1e-3);
+        assert_close(corner.y.abs(), 4.0, 1e-3);
+    }
+
+    let projection = Matrix4::orthographic(-3.0, 3.0, -1.0, 1.0, 0.0, 10.0);
+    let corners = frustum_slice(&projection, 2.0, 5.0).unwrap();
+    assert_close(corners[0].z, -2.0, 1e-5);
+    assert_close(corners[7].x, 3.0, 1e-5);
+    assert_close(corners[7].z, -5.0, 1e-5);
+}
+
+#[test]
+fn depth_comparison_and_filtering() {
+    // The left half of the map has an occluder at depth 0, the right half nothing.
+    let mut depth = Image::new(8, 8, 1.0);
+    for y in 0..8 {
+        for x in 0..4 {
+            depth[(x, y)] = 0.0;
+        }
+    }
+    let mut map = ShadowMap {
+        cascades: vec![
+            ShadowCascade {
+                depth,
+                // Looking down the negative z axis, from z = 0 to z = -2.
+                transform: Matrix4::orthographic(-1.0, 1.0, -1.0, 1.0, 0.0, 2.0),
+                near: 0.0,
+                far: 2.0,
+            },
+        ],
+        bias: 0.01,
+        slope_bias: 0.0,
+        pcf_radius: 0,
+    };
+    let up = Vector3::new(0.0, 0.0, 1.0);
+    let visibility = |map: &ShadowMap, x, z| map.visibility(Vector3::new(x, 0.1, z), up, up);
+
+    assert_eq!(visibility(&map, -0.5, -1.5), 0.0);
+    assert_eq!(visibility(&map, 0.5, -1.5), 1.0);
+    // In front of the occluder, within the bias of it, and beyond the last cascade.
+    assert_eq!(visibility(&map, -0.5, -0.5), 1.0);
+    assert_eq!(visibility(&map, -0.5, -1.005), 1.0);
+    assert_eq!(visibility(&map, -0.5, -2.5), 1.0);
+    // Outside the map.
+    assert_eq!(visibility(&map, -1.5, -1.5), 1.0);
+
+    // Percentage closer filtering softens the edge over the kernel.
+    map.pcf_radius = 1;
+    assert_close(visibility(&map, -0.1, -1.5), 1.0 / 3.0, 1e-6);
+    assert_close(visibility(&map, 0.1, -1.5), 2.0 / 3.0, 1e-6);
+    assert_eq!(visibility(&map, -0.6, -1.5), 0.0);
+
+    // Slope scaled bias keeps grazing surfaces from shadowing themselves.
+    map.pcf_radius = 0;
+    map.slope_bias = 0.01;
+    let grazing = Vector3::new(1.0, 0.0, 0.02).normalize();
+    let surface = Vector3::new(-0.5, 0.1, -1.05);
+    assert_eq!(map.visibility(surface, up, up), 0.0);
+    assert_eq!(map.visibility(surface, up, grazing), 1.0);
+}
+
+#[test]
+fn depth_only_passes() {
+    let (scene, _) = scene();
+    let model = scene.node(scene.find("floor").unwrap()).model.clone().unwrap();
+    let projection = Matrix4::perspective_fov(60.0 * DEGREES, 1.0, 0.1, 100.0);
+    let mut pass = Renderer::new(SIZE, SIZE, projection);
+    pass.clear(Color(1, 2, 3, 255));
+    pass.set_color_write(false);
+    pass.set_shading(ShadingMode::Phong);
+    let view = Matrix4::translation(Vector3::new(0.0, 0.0, -3.0)) *
+        Matrix4::rotation(Vector3::new(90.0 * DEGREES, 0.0, 0.0));
+    pass.render(view, &model.triangles);
+
+    assert!(pass.framebuffer().pixels().iter().all(|&c| c == Color(1, 2, 3, 255)));
+    let depth = pass.depth_buffer()[(SIZE / 2, SIZE / 2)];
+    assert!(depth > -1.0 && depth < 1.0, "{}", depth);
+}
+
+#[test]
+fn directional_shadows() {
+    let (mut scene, camera) = scene();
+    // Shining down at 45 degrees towards positive x, so the occluder's shadow falls around
+    // x = 2 on the floor.
+    let sun = scene.add(
+        None,
+        Node::new("sun")
+            .with_transform(Matrix4::rotation(Vector3::new(-90.0 * DEGREES, 0.0, 45.0 * DEGREES)))
+            .with_light(white_light(LightKind::Directional)),
+    );
+    let lit = |renderer: &Renderer, x| renderer.get_pixel(x, SIZE / 2).0;
+
+    let mut r = renderer();
+    scene.render(&mut r, camera);
+    assert_eq!(lit(&r, 43), lit(&r, 20));
+    assert!(lit(&r, 43) > 100, "{}", lit(&r, 43));
+
+    scene.node_mut(sun).shadow = Some(ShadowSettings::default());
+    r.clear(Color(0, 0, 0, 255));
+    scene.render(&mut r, camera);
+    let shadow = r.lights()[0].shadow.clone().unwrap();
+    assert_eq!(shadow.cascades.len(), 3);
+    assert!(lit(&r, 20) > 100, "{}", lit(&r, 20));
+    assert!(lit(&r, 43) < 30, "{}", lit(&r, 43));
+    // The occluder and the floor around the shadow are not shadowed by themselves.
+    assert!(lit(&r, 32) > 100, "{}", lit(&r, 32));
+    assert!(lit(&r, 55) > 100, "{}", lit(&r, 55));
+}
+
+#[test]
+fn spot_light_shadows() {
+    let (mut scene, camera) = scene();
+    let light = white_light(LightKind::Spot {
+        attenuation: Attenuation::none(),
+        inner_angle: 40.0 * DEGREES,
+        outer_angle: 60.0 * DEGREES,
+    });
+    scene.add(
+        None,
+        Node::new("spot")
+            .with_transform(
+                Matrix4::translation(Vector3::new(0.0, 6.0, 0.0)) *
+                    Matrix4::rotation(Vector3::new(-90.0 * DEGREES, 0.0, 0.0)),
+            )
+            .with_light(light)
+            .with_shadow(ShadowSettings {
+                resolution: 256,
+                ..ShadowSettings::default()
+            }),
+    );
+
+    let mut r = renderer();
+    scene.render(&mut r, camera);
+    let light = &r.lights()[0];
+    assert_eq!(light.shadow.as_ref().unwrap().cascades.len(), 1);
+    // In view space the floor below the occluder is straight ahead and faces the camera.
+    let up = Vector3::new(0.0, 0.0, 1.0);
+    assert_eq!(light.visibility(Vector3::new(0.0, 0.0, -10.0), up), 0.0);
+    assert_eq!(light.visibility(Vector3::new(2.0, 0.0, -10.0), up), 1.0);
+}
+
+#[test]
+fn scene_files() {
+    let scene = parse_scene(
+        r#"
+        [[node]]
+        name = "sun"
+        light = { kind = "directional" }
+        shadow = { resolution = 512, pcf_radius = 2, cascades = 4, bias = 0.001 }
+        "#,
+        None,
+    ).unwrap();
+    let shadow = scene.nodes[0].shadow.unwrap();
+    assert_eq!(shadow.resolution, 512);
+    assert_eq!(shadow.pcf_radius, 2);
+    assert_eq!(shadow.cascades, 4);
+    assert_eq!(shadow.bias, 0.001);
+    assert_eq!(shadow.slope_bias, ShadowSettings::default().slope_bias);
+
+    let error = |source: &str| parse_scene(source, None).unwrap_err().to_string();
+    assert!(error("[[node]]\nname = \"a\"\nshadow = {}\n").contains("no light"));
+    assert!(
+        error("[[node]]\nname = \"a\"\nlight = { kind = \"point\" }\nshadow = {}\n")
+            .contains("point lights")
+    );
+    let shadow = |settings: &str| {
+        error(&format!("[[node]]\nname = \"a\"\nlight = {{}}\nshadow = {{ {} }}\n", settings))
+    };
+    assert!(shadow("resolution = 0").contains("'resolution'"));
+    assert!(shadow("resolution = 16384").contains("from 1 to 8192"));
+    assert!(shadow("cascades = 0").contains("'cascades'"));
+    assert!(shadow("pcf_radius = 9").contains("'pcf_radius'"));
+}
+
@@ -0,0 +1,212 @@
+extern crate rrasterizer;
+
+mod common {
+    pub mod approx;
+}
+
+use std::f32;
+
+use rrasterizer::vec2::Vector2;
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+use rrasterizer::mat4::Matrix4;
+use rrasterizer::color::{Color, f32_to_half, half_to_f32};
+use rrasterizer::renderer::{Vertex, Triangle, Renderer, Framebuffer, FragmentOutput, ColorFormat,
+                            ColorBuffer};
+use rrasterizer::tone_mapping::{ToneMapping, ToneMapOperator};
+use rrasterizer::scene_format::parse_scene;
+use rrasterizer::application::Application;
+
+use common::approx::assert_close;
+
+// Covers the bottom left half of the view.
+fn triangle(color: Vector4<f32>) -> Vec<Triangle> {
+    let vertex = |x, y| {
+        Vertex {
+            position: Vector3::new(x, y, -1.0),
+            color,
+            normal: Vector3::splat(0.0),
+            tex_coord: Vector2::splat(0.0),
+        }
+    };
+    vec![
+        Triangle {
+            a: vertex(-1.0, -1.0),
+            b: vertex(1.0, -1.0),
+            c: vertex(-1.0, 1.0),
+        },
+    ]
+}
+
+fn tone_mapping(operator: ToneMapOperator) -> ToneMapping {
+    ToneMapping {
+        operator,
+        exposure: 0.0,
+    }
+}
+
+#[test]
+fn half_precision() {
+    assert_eq!(f32_to_half(0.0), 0);
+    assert_eq!(f32_to_half(-0.0), 0x8000);
+    assert_eq!(f32_to_half(1.0), 0x3c00);
+    assert_eq!(f32_to_half(-2.0), 0xc000);
+    assert_eq!(f32_to_half(65504.0), 0x7bff);
+    assert_eq!(f32_to_half(65520.0), 0x7c00);
+    assert_eq!(f32_to_half(f32::NEG_INFINITY), 0xfc00);
+    assert!(half_to_f32(f32_to_half(f32::NAN)).is_nan());
+    // Subnormals, down to the smallest.
+    assert_eq!(f32_to_half(2f32.powi(-24)), 1);
+    assert_eq!(f32_to_half(2f32.powi(-25)), 0);
+    assert_eq!(f32_to_half(3.0 * 2f32.powi(-25)), 2);
+    assert_eq!(f32_to_half(2f32.powi(-14)), 0x0400);
+    // Ties round to even.
+    assert_eq!(f32_to_half(1.0 + 2f32.powi(-11)), 0x3c00);
+    assert_eq!(f32_to_half(1.0 + 3.0 * 2f32.powi(-11)), 0x3c02);
+
+    assert_eq!(half_to_f32(0x3c00), 1.0);
+    assert_eq!(half_to_f32(0x7bff), 65504.0);
+    assert_eq!(half_to_f32(0xfc00), f32::NEG_INFINITY);
+    assert_eq!(half_to_f32(1), 2f32.powi(-24));
+    for &value in &[0.1, 0.5, 3.3, 1000.0, -7.25] {
+        let half = half_to_f32(f32_to_half(value));
+        assert_close(half, value, value.abs() / 1024.0);
+    }
+}
+
+#[test]
+fn operators() {
+    let clamp = tone_mapping(ToneMapOperator::Clamp);
+    assert_eq!(clamp.apply(Vector3::new(0.25, 2.0, -1.0)), Vector3::new(0.25, 1.0, 0.0));
+
+    let reinhard = tone_mapping(ToneMapOperator::Reinhard);
+    assert_eq!(reinhard.apply(Vector3::new(0.0, 1.0, 3.0)), Vector3::new(0.0, 0.5, 0.75));
+
+    let aces = tone_mapping(ToneMapOperator::AcesFilmic);
+    assert_eq!(aces.apply(Vector3::splat(0.0)), Vector3::splat(0.0));
+    assert_close(aces.apply(Vector3::splat(0.18)).x, 0.2669, 1e-4);
+    assert_eq!(aces.apply(Vector3::splat(100.0)), Vector3::splat(1.0));
+    let mut previous = 0.0;
+    for i in 1..100 {
+        let mapped = aces.apply(Vector3::splat(i as f32 * 0.1)).x;
+        assert!(mapped >= previous);
+        previous = mapped;
+    }
+
+    // Each stop of exposure doubles the color first.
+    let exposed = ToneMapping {
+        exposure: 2.0,
+        ..reinhard
+    };
+    assert_eq!(exposed.apply(Vector3::splat(0.25)), Vector3::splat(0.5));
+    assert_eq!(
+        exposed.to_color(Vector4::new(0.25, 0.0, 1.0, 0.5)),
+        Color(127, 0, 204, 127)
+    );
+}
+
+#[test]
+fn floating_point_targets() {
+    let bright = Vector4::new(4.0, 0.5, 0.0, 1.0);
+    let mut renderer = Renderer::new(8, 8, Matrix4::identity());
+    renderer.render(Matrix4::identity(), &triangle(bright));
+    assert_eq!(renderer.get_pixel(2, 2), Color(255, 127, 0, 255));
+    assert_eq!(renderer.resolve(), *renderer.framebuffer());
+
+    for &format in &[ColorFormat::Rgba16F, ColorFormat::Rgba32F] {
+        let attachments = [(FragmentOutput::Color, format), (FragmentOutput::Albedo, format)];
+        renderer.bind_framebuffer(Framebuffer::with_formats(8, 8, &attachments));
+        renderer.clear(Color(0, 0, 255, 255));
+        renderer.render(Matrix4::identity(), &triangle(bright));
+        let target = renderer.bound_framebuffer();
+        assert_eq!(target.attachment(0).format(), format);
+        assert_eq!(target.attachment(0).get_pixel(2, 2), bright);
+        assert_eq!(target.attachment(1).get_pixel(2, 2), bright);
+        assert_eq!(target.attachment(0).get_pixel(7, 7), Vector4::new(0.0, 0.0, 1.0, 1.0));
+        assert_eq!(target.texture(0).image()[(2, 2)], bright);
+
+        renderer.set_tone_mapping(tone_mapping(ToneMapOperator::Reinhard));
+        let resolved = renderer.resolve();
+        assert_eq!(resolved[(2, 2)], Color(204, 85, 0, 255));
+        assert_eq!(resolved[(7, 7)], Color(0, 0, 127, 255));
+    }
+
+    // Resizing keeps the formats.
+    renderer.resize(4, 4);
+    assert_eq!(renderer.bound_framebuffer().attachment(1).format(), ColorFormat::Rgba32F);
+    assert_eq!(renderer.bound_framebuffer().attachment(1).dimensions(), (4, 4));
+}
+
+#[test]
+fn half_precision_targets_round() {
+    let mut buffer = ColorBuffer::new(1, 1, ColorFormat::Rgba16F);
+    assert_eq!(buffer.get_pixel(0, 0), Vector4::new(0.0, 0.0, 0.0, 1.0));
+    buffer.set_pixel(0, 0, Vector4::new(0.1, 1e6, -2.0, 1.0));
+    let pixel = buffer.get_pixel(0, 0);
+    assert_close(pixel.x, 0.1, 1e-4);
+    assert_eq!(pixel.y, f32::INFINITY);
+    assert_eq!(pixel.z, -2.0);
+}
+
+#[test]
+#[should_panic]
+fn floating_point_targets_are_not_8_bit() {
+    let framebuffer =
+        Framebuffer::with_formats(1, 1, &[(FragmentOutput::Color, ColorFormat::Rgba16F)]);
+    framebuffer.color(0);
+}
+
+const HDR_SCENE: &str = r#"
+[render]
+width = 16
+height = 16
+format = "rgba16f"
+tone_mapping = "aces"
+exposure = -1
+
+[[material]]
+name = "bright"
+color = [8, 8, 8]
+
+[[node]]
+name = "camera"
+camera = { fovy = 60, near = 0.1, far = 100 }
+
+[[node]]
+name = "cube"
+primitive = "cube"
+material = "bright"
+translation = [0, 0, -3]
+"#;
+
+#[test]
+fn scene_files() {
+    let description = parse_scene(HDR_SCENE, None).unwrap();
+    assert_eq!(description.render.format, ColorFormat::Rgba16F);
+    assert_eq!(
+        description.render.tone_mapping,
+        ToneMapping {
+            operator: ToneMapOperator::AcesFilmic,
+            exposure: -1.0,
+        }
+    );
+    let defaults = parse_scene("", None).unwrap().render;
+    assert_eq!(defaults.format, ColorFormat::Rgba8);
+    assert_eq!(defaults.tone_mapping, ToneMapping::default());
+
+    let error = |source: &str| parse_scene(source, None).unwrap_err().to_string();
+    assert!(error("[render]\nformat = \"rgb565\"\n").contains("rgb565"));
+    assert!(error("[render]\ntone_mapping = \"filmic\"\n").contains("filmic"));
+
+    // The application shows the tone mapped frame.
+    let mut application = Application::with_scene(description.instantiate().unwrap());
+    application.render();
+    let Color(red, _, _, _) = application.framebuffer()[(8, 8)];
+    assert!(red > 200 && red < 255, "{}", red);
+    assert_eq!(application.get_pixel(8, 8).0, red);
+    application.resize(20, 10);
+    assert_eq!(application.framebuffer().dimensions(), (20, 10));
+    application.render();
+    let Color(red, _, _, _) = application.framebuffer()[(10, 5)];
+    assert!(red > 200 && red < 255, "{}", red);
+}
@@ -0,0 +1,173 @@
+extern crate rrasterizer;
+
+use rrasterizer::vec2::Vector2;
+use rrasterizer::vec3::Vector3;
+use rrasterizer::vec4::Vector4;
+
+const EPSILON: f64 = 1e-12;
+
+#[test]
+fn arithmetic() {
+    let a = Vector3::new(1.0, 2.0, 3.0);
+    let b = Vector3::new(4.0, -5.0, 6.0);
+    assert_eq!(a + b, Vector3::new(5.0, -3.0, 9.0));
+    assert_eq!(a - b, Vector3::new(-3.0, 7.0, -3.0));
+    assert_eq!(a * b, Vector3::new(4.0, -10.0, 18.0));
+    assert_eq!(a / Vector3::new(2.0, 4.0, 6.0), Vector3::splat(0.5));
+    assert_eq!(a * 2.0, 2.0 * a);
+    assert_eq!(a / 2.0, Vector3::new(0.5, 1.0, 1.5));
+    assert_eq!(-a, Vector3::new(-1.0, -2.0, -3.0));
+
+    let mut c = a;
+    c += b;
+    c -= a;
+    assert_eq!(c, b);
+    c *= 2.0;
+    c /= Vector3::new(2.0, 2.0, 2.0);
+    assert_eq!(c, b);
+    c *= a;
+    c /= 2.0;
+    assert_eq!(c, Vector3::new(2.0, -5.0, 9.0));
+
+    let d = Vector4::new(1.0f32, 2.0, 3.0, 4.0);
+    assert_eq!(2.0 * d - d, d);
+    assert_eq!(Vector2::new(1.0, 1.0) * 3.0f32, Vector2::splat(3.0));
+}
+
+#[test]
+fn products_and_lengths() {
+    let v = Vector4::new(1.0f64, 2.0, 2.0, 4.0);
+    assert_eq!(v.dot(Vector4::new(1.0, 0.0, 1.0, 1.0)), 7.0);
+    assert_eq!(v.magnitude_squared(), 25.0);
+    assert_eq!(v.magnitude(), 5.0);
+    assert!((v.normalize().magnitude() - 1.0).abs() < EPSILON);
+    assert_eq!(v.distance(Vector4::new(1.0, 2.0, 2.0, 0.0)), 4.0);
+    assert_eq!(v.distance_squared(Vector4::new(1.0, 2.0, 2.0, 0.0)), 16.0);
+
+    assert_eq!(Vector2::new(3.0, 4.0).magnitude(), 5.0);
+    assert_eq!(Vector2::new(1.0, 0.0).cross(Vector2::new(0.0, 1.0)), 1.0);
+    assert_eq!(
+        Vector3::new(1.0, 0.0, 0.0).cross(Vector3::new(0.0, 1.0, 0.0)),
+        Vector3::new(0.0, 0.0, 1.0)
+    );
+}
+
+#[test]
+fn component_wise_functions() {
+    let a = Vector3::new(-1.5, 2.25, 0.5);
+    let b = Vector3::new(1.0, -3.0, 0.5);
+    assert_eq!(a.min(b), Vector3::new(-1.5, -3.0, 0.5));
+    assert_eq!(a.max(b), Vector3::new(1.0, 2.25, 0.5));
+    assert_eq!(a.abs(), Vector3::new(1.5, 2.25, 0.5));
+    assert_eq!(a.floor(), Vector3::new(-2.0, 2.0, 0.0));
+    assert_eq!(a.ceil(), Vector3::new(-1.0, 3.0, 1.0));
+    assert_eq!(a.round(), Vector3::new(-2.0, 2.0, 1.0));
+    assert_eq!(
+        a.clamp(Vector3::splat(-1.0), Vector3::splat(1.0)),
+        Vector3::new(-1.0, 1.0, 0.5)
+    );
+
+    assert_eq!(a.lerp(b, 0.0), a);
+    assert_eq!(a.lerp(b, 1.0), b);
+    assert_eq!(
+        Vector2::new(0.0, 10.0).lerp(Vector2::new(4.0, 20.0), 0.25),
+        Vector2::new(1.0, 12.5)
+    );
+}
+
+#[test]
+fn reflect_and_refract() {
+    let normal = Vector3::new(0.0, 1.0, 0.0);
+    let incident = Vector3::new(1.0, -1.0, 0.0).normalize();
+    let reflected = incident.reflect(normal);
+    assert!((reflected - Vector3::new(1.0, 1.0, 0.0).normalize()).magnitude() < EPSILON);
+
+    // Without a change of medium the ray goes straight through.
+    let straight = incident.refract(normal, 1.0).unwrap();
+    assert!((straight - incident).magnitude() < EPSILON);
+
+    // Snell's law: sin(out) = eta * sin(in)
+    let eta = 1.0 / 1.5;
+    let refracted = incident.refract(normal, eta).unwrap();
+    assert!((refracted.magnitude() - 1.0).abs() < EPSILON);
+    assert!((refracted.x - eta * incident.x).abs() < EPSILON);
+    assert!(refracted.y < 0.0);
+
+    // Leaving a dense medium at a grazing angle reflects totally.
+    let grazing = Vector3::new(1.0, -0.1, 0.0).normalize();
+    assert_eq!(grazing.refract(normal, 1.5), None);
+
+    let flat = Vector2::new(1.0, -1.0).reflect(Vector2::new(0.0, 1.0));
+    assert_eq!(flat, Vector2::new(1.0, 1.0));
+}
+
+#[test]
+fn indexing() {
+    let mut v = Vector4::new(1, 2, 3, 4);
+    assert_eq!([v[0], v[1], v[2], v[3]], [1, 2, 3, 4]);
+    v[2] = 7;
+    assert_eq!(v.z, 7);
+
+    let mut u = Vector2::new(0.5, 1.5);
+    u[1] += 1.0;
+    assert_eq!(u[1], 2.5);
+}
+
+#[test]
+#[should_panic(expected = "out of range")]
+fn index_out_of_range() {
+    let v = Vector3::new(1, 2, 3);
+    let _ = v[3];
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(Vector2::from([1, 2]), Vector2::new(1, 2));
+    assert_eq!(Vector3::from([1, 2, 3]), Vector3::new(1, 2, 3));
+    assert_eq!(Vector4::from([1, 2, 3, 4]), Vector4::new(1, 2, 3, 4));
+    assert_eq!(Vector3::from((1, 2, 3)), Vector3::new(1, 2, 3));
+    assert_eq!(Vector4::from((1, 2, 3, 4)), Vector4::new(1, 2, 3, 4));
+
+    let array: [f32; 3] = Vector3::new(1.0, 2.0, 3.0).into();
+    assert_eq!(array, [1.0, 2.0, 3.0]);
+    let tuple: (i32, i32) = Vector2::new(5, 6).into();
+    assert_eq!(tuple, (5, 6));
+
+    assert_eq!(Vector2::new(1, 2).extend(3), Vector3::new(1, 2, 3));
+    assert_eq!(Vector3::new(1, 2, 3).extend(4), Vector4::new(1, 2, 3, 4));
+    assert_eq!(Vector4::new(1, 2, 3, 4).vec3().vec2(), Vector2::new(1, 2));
+}
+
+#[test]
+fn swizzles() {
+    let v = Vector4::new(1, 2, 3, 4);
+    assert_eq!(v.xy(), Vector2::new(1, 2));
+    assert_eq!(v.wz(), Vector2::new(4, 3));
+    assert_eq!(v.xyz(), Vector3::new(1, 2, 3));
+    assert_eq!(v.zyx(), Vector3::new(3, 2, 1));
+    assert_eq!(v.wzyx(), Vector4::new(4, 3, 2, 1));
+    assert_eq!(v.xxyy(), Vector4::new(1, 1, 2, 2));
+
+    let u = Vector3::new(1, 2, 3);
+    assert_eq!(u.zx(), Vector2::new(3, 1));
+    assert_eq!(u.xyzz(), Vector4::new(1, 2, 3, 3));
+    assert_eq!(Vector2::new(1, 2).yxy(), Vector3::new(2, 1, 2));
+}
+
+#[test]
+fn sum() {
+    let vectors = vec![
+        Vector3::new(1.0, 2.0, 3.0),
+        Vector3::new(4.0, 5.0, 6.0),
+        Vector3::new(-1.0, 0.0, 1.0),
+    ];
+    assert_eq!(vectors.iter().sum::<Vector3<f64>>(), Vector3::new(4.0, 7.0, 10.0));
+    assert_eq!(
+        vectors.into_iter().sum::<Vector3<f64>>(),
+        Vector3::new(4.0, 7.0, 10.0)
+    );
+    assert_eq!(
+        Vec::<Vector2<i32>>::new().into_iter().sum::<Vector2<i32>>(),
+        Vector2::new(0, 0)
+    );
+}